@@ -24,12 +24,21 @@ export interface CreateInstructionData {
   poolSeed: string;
   feeCollectionPeriod: number;
   feeRatio: number;
+  maxOrderLifetime: number;
+  timelockPeriod: number;
+  performanceFeeRatio: number;
+  redeemFeeRatio: number;
+  lockupPeriod: number;
+  maxPoolTokenSupply: number;
+  minDepositPoolTokens: number;
   depositAmounts: number[];
   markets: PublicKey[];
 }
 export interface DepositInstructionData {
   poolSeed: Buffer;
   poolTokenAmount: number;
+  minPoolTokensOut: number;
+  maxAmountsIn: number[];
 }
 
 export interface CreateOrderInstructionData {
@@ -63,7 +72,8 @@ export interface SettleFundsInstructionData {
 
 export interface RedeemInstructionData {
   poolSeed: string;
-  poolTokenAmount: OrderSide;
+  poolTokenAmount: number;
+  minAmountsOut: number[];
 }
 export interface CollectFeesInstructionData {
   poolSeed: string;
@@ -122,6 +132,37 @@ export function decodeInstruction(
       let feeRatio =
         new BN(buffer.slice(offset, offset + 2), 'le').toNumber() / 2 ** 16;
       offset += 2;
+      let maxOrderLifetime = new BN(
+        buffer.slice(offset, offset + 8),
+        'le',
+      ).toNumber();
+      offset += 8;
+      let timelockPeriod = new BN(
+        buffer.slice(offset, offset + 8),
+        'le',
+      ).toNumber();
+      offset += 8;
+      let performanceFeeRatio =
+        new BN(buffer.slice(offset, offset + 2), 'le').toNumber() / 2 ** 16;
+      offset += 2;
+      let redeemFeeRatio =
+        new BN(buffer.slice(offset, offset + 2), 'le').toNumber() / 2 ** 16;
+      offset += 2;
+      let lockupPeriod = new BN(
+        buffer.slice(offset, offset + 8),
+        'le',
+      ).toNumber();
+      offset += 8;
+      let maxPoolTokenSupply = new BN(
+        buffer.slice(offset, offset + 8),
+        'le',
+      ).toNumber();
+      offset += 8;
+      let minDepositPoolTokens = new BN(
+        buffer.slice(offset, offset + 8),
+        'le',
+      ).toNumber();
+      offset += 8;
       let markets: PublicKey[] = [];
       for (let i = 0; i < numberOfMarkets; i++) {
         markets.push(new PublicKey(buffer.slice(offset, offset + 32)));
@@ -139,6 +180,13 @@ export function decodeInstruction(
         poolSeed: bs58.encode(poolSeed),
         feeCollectionPeriod,
         feeRatio,
+        maxOrderLifetime,
+        timelockPeriod,
+        performanceFeeRatio,
+        redeemFeeRatio,
+        lockupPeriod,
+        maxPoolTokenSupply,
+        minDepositPoolTokens,
         depositAmounts,
         markets,
       };
@@ -148,9 +196,24 @@ export function decodeInstruction(
         buffer.slice(offset, offset + 8),
         'le',
       ).toNumber();
+      offset += 8;
+      let minPoolTokensOut = new BN(
+        buffer.slice(offset, offset + 8),
+        'le',
+      ).toNumber();
+      offset += 8;
+      let maxAmountsIn: number[] = [];
+      while (offset < buffer.length) {
+        maxAmountsIn.push(
+          new BN(buffer.slice(offset, offset + 8), 'le').toNumber(),
+        );
+        offset += 8;
+      }
       return {
         poolSeed: bs58.encode(poolSeed),
         poolTokenAmount,
+        minPoolTokensOut,
+        maxAmountsIn,
       };
     }
     case Instruction.CreateOrder: {
@@ -249,9 +312,17 @@ export function decodeInstruction(
         'le',
       ).toNumber();
       offset += 8;
+      let minAmountsOut: number[] = [];
+      while (offset < buffer.length) {
+        minAmountsOut.push(
+          new BN(buffer.slice(offset, offset + 8), 'le').toNumber(),
+        );
+        offset += 8;
+      }
       return {
         poolSeed: bs58.encode(poolSeed),
         poolTokenAmount,
+        minAmountsOut,
       };
     }
     case Instruction.CollectFees: {
@@ -337,10 +408,21 @@ export function createInstruction(
   sourceAssetKeys: Array<PublicKey>,
   serumProgramId: PublicKey,
   signalProviderKey: PublicKey,
+  systemProgramId: PublicKey,
+  rentSysvarKey: PublicKey,
+  payerKey: PublicKey,
+  registryKey: PublicKey,
   depositAmounts: Array<number>,
   markets: Array<PublicKey>,
   feeCollectionPeriod: Numberu64,
   feeRatio: Numberu16,
+  maxOrderLifetime: Numberu64,
+  timelockPeriod: Numberu64,
+  performanceFeeRatio: Numberu16,
+  redeemFeeRatio: Numberu16,
+  lockupPeriod: Numberu64,
+  maxPoolTokenSupply: Numberu64,
+  minDepositPoolTokens: Numberu64,
 ): TransactionInstruction {
   let buffers = [
     Buffer.from(Int8Array.from([1])),
@@ -349,6 +431,13 @@ export function createInstruction(
     new Numberu16(markets.length).toBuffer(),
     feeCollectionPeriod.toBuffer(),
     feeRatio.toBuffer(),
+    maxOrderLifetime.toBuffer(),
+    timelockPeriod.toBuffer(),
+    performanceFeeRatio.toBuffer(),
+    redeemFeeRatio.toBuffer(),
+    lockupPeriod.toBuffer(),
+    maxPoolTokenSupply.toBuffer(),
+    minDepositPoolTokens.toBuffer(),
   ];
   for (var market of markets) {
     // @ts-ignore
@@ -378,7 +467,7 @@ export function createInstruction(
     },
     {
       pubkey: signalProviderKey,
-      isSigner: false,
+      isSigner: true,
       isWritable: false,
     },
     {
@@ -416,6 +505,35 @@ export function createInstruction(
       isWritable: true,
     });
   }
+  for (var market of markets) {
+    keys.push({
+      pubkey: market,
+      isSigner: false,
+      isWritable: false,
+    });
+  }
+  keys.push(
+    {
+      pubkey: systemProgramId,
+      isSigner: false,
+      isWritable: false,
+    },
+    {
+      pubkey: rentSysvarKey,
+      isSigner: false,
+      isWritable: false,
+    },
+    {
+      pubkey: payerKey,
+      isSigner: true,
+      isWritable: true,
+    },
+    {
+      pubkey: registryKey,
+      isSigner: false,
+      isWritable: true,
+    },
+  );
 
   return new TransactionInstruction({
     keys,
@@ -428,23 +546,35 @@ export function depositInstruction(
   splTokenProgramId: PublicKey,
   bonfidaBotProgramId: PublicKey,
   sigProviderFeeReceiverKey: PublicKey,
-  bonfidaFeeReceiverKey: PublicKey,
-  bonfidaBuyAndBurnKey: PublicKey,
+  configKey: PublicKey,
+  feeRecipientKeys: Array<PublicKey>,
   mintKey: PublicKey,
   poolKey: PublicKey,
   poolAssetKeys: Array<PublicKey>,
   targetPoolTokenKey: PublicKey,
   sourceOwnerKey: PublicKey,
   sourceAssetKeys: Array<PublicKey>,
+  // The pool's whitelist account and, if it has a gating mint, the source owner's token account
+  // for this mint, only if the pool is private
+  allowListKeys: Array<PublicKey>,
+  // The system program, the rent and clock sysvars, the fee payer, the source owner's deposit
+  // receipt and the pool's escrow pooltoken account, only if the pool has a lockup period
+  lockupAccountKeys: Array<PublicKey>,
   poolSeed: Array<Buffer | Uint8Array>,
   poolTokenAmount: Numberu64,
+  minPoolTokensOut: Numberu64,
+  maxAmountsIn: Array<Numberu64>,
 ): TransactionInstruction {
   let buffers = [
     Buffer.from(Int8Array.from([2])),
     Buffer.concat(poolSeed),
     // @ts-ignore
     poolTokenAmount.toBuffer(),
+    minPoolTokensOut.toBuffer(),
   ];
+  for (var amount of maxAmountsIn) {
+    buffers.push(amount.toBuffer());
+  }
 
   const data = Buffer.concat(buffers);
   const keys = [
@@ -469,14 +599,9 @@ export function depositInstruction(
       isWritable: true,
     },
     {
-      pubkey: bonfidaFeeReceiverKey,
-      isSigner: false,
-      isWritable: true,
-    },
-    {
-      pubkey: bonfidaBuyAndBurnKey,
+      pubkey: configKey,
       isSigner: false,
-      isWritable: true,
+      isWritable: false,
     },
     {
       pubkey: poolKey,
@@ -503,6 +628,28 @@ export function depositInstruction(
       isWritable: true,
     });
   }
+  for (var feeRecipient of feeRecipientKeys) {
+    keys.push({
+      pubkey: feeRecipient,
+      isSigner: false,
+      isWritable: true,
+    });
+  }
+  for (var allowListKey of allowListKeys) {
+    keys.push({
+      pubkey: allowListKey,
+      isSigner: false,
+      isWritable: false,
+    });
+  }
+  for (var i = 0; i < lockupAccountKeys.length; i++) {
+    // The fee payer is the only signer, the program and sysvars are readonly
+    keys.push({
+      pubkey: lockupAccountKeys[i],
+      isSigner: i == 3,
+      isWritable: i >= 3,
+    });
+  }
 
   return new TransactionInstruction({
     keys,
@@ -529,6 +676,7 @@ export function createOrderInstruction(
   splTokenProgramId: PublicKey,
   dexProgramKey: PublicKey,
   rentProgramId: PublicKey,
+  clockSysvarKey: PublicKey,
   srmReferrerKey: PublicKey | null,
   poolSeed: Array<Buffer | Uint8Array>,
   side: OrderSide,
@@ -628,6 +776,11 @@ export function createOrderInstruction(
       isSigner: false,
       isWritable: false,
     },
+    {
+      pubkey: clockSysvarKey,
+      isSigner: false,
+      isWritable: false,
+    },
     {
       pubkey: dexProgramKey,
       isSigner: false,
@@ -659,6 +812,7 @@ export function cancelOrderInstruction(
   serumMarketAsks: PublicKey,
   poolKey: PublicKey,
   dexProgramKey: PublicKey,
+  clockSysvarKey: PublicKey,
   poolSeed: Array<Buffer | Uint8Array>,
   side: OrderSide,
   orderId: Numberu128,
@@ -688,17 +842,17 @@ export function cancelOrderInstruction(
       isWritable: true,
     },
     {
-      pubkey: serumEventQueue,
+      pubkey: serumMarketBids,
       isSigner: false,
       isWritable: true,
     },
     {
-      pubkey: serumMarketBids,
+      pubkey: serumMarketAsks,
       isSigner: false,
       isWritable: true,
     },
     {
-      pubkey: serumMarketAsks,
+      pubkey: serumEventQueue,
       isSigner: false,
       isWritable: true,
     },
@@ -712,6 +866,11 @@ export function cancelOrderInstruction(
       isSigner: false,
       isWritable: false,
     },
+    {
+      pubkey: clockSysvarKey,
+      isSigner: false,
+      isWritable: false,
+    },
   ];
 
   return new TransactionInstruction({
@@ -829,8 +988,15 @@ export function redeemInstruction(
   sourcePoolTokenOwnerKey: PublicKey,
   sourcePoolTokenKey: PublicKey,
   targetAssetKeys: Array<PublicKey>,
+  // The signal provider pooltoken account, the program config and the fee recipient pooltoken
+  // accounts, only if the pool charges a redeem fee
+  redeemFeeAccountKeys: Array<PublicKey>,
+  dexProgramKey: PublicKey,
+  // The 8 accounts needed for each pending OpenOrders account of the pool, if any
+  pendingOrderAccountKeys: Array<PublicKey>,
   poolSeed: Array<Buffer | Uint8Array>,
   poolTokenAmount: Numberu64,
+  minAmountsOut: Array<Numberu64>,
 ): TransactionInstruction {
   let buffers = [
     Buffer.from(Int8Array.from([6])),
@@ -838,6 +1004,9 @@ export function redeemInstruction(
     // @ts-ignore
     new Numberu64(poolTokenAmount).toBuffer(),
   ];
+  for (var amount of minAmountsOut) {
+    buffers.push(amount.toBuffer());
+  }
 
   const data = Buffer.concat(buffers);
   const keys = [
@@ -886,6 +1055,29 @@ export function redeemInstruction(
       isWritable: true,
     });
   }
+  for (var i = 0; i < redeemFeeAccountKeys.length; i++) {
+    // The program config is the only readonly account
+    keys.push({
+      pubkey: redeemFeeAccountKeys[i],
+      isSigner: false,
+      isWritable: i != 1,
+    });
+  }
+  if (pendingOrderAccountKeys.length > 0) {
+    keys.push({
+      pubkey: dexProgramKey,
+      isSigner: false,
+      isWritable: false,
+    });
+    for (var i = 0; i < pendingOrderAccountKeys.length; i++) {
+      // The vault signer is the only readonly account of each pending market
+      keys.push({
+        pubkey: pendingOrderAccountKeys[i],
+        isSigner: false,
+        isWritable: i % 8 != 7,
+      });
+    }
+  }
 
   return new TransactionInstruction({
     keys,
@@ -901,9 +1093,14 @@ export function collectFeesInstruction(
   poolKey: PublicKey,
   mintKey: PublicKey,
   signalProviderPoolTokenKey: PublicKey,
-  bonfidaFeePoolTokenKey: PublicKey,
-  bonfidaBnBPTKey: PublicKey,
+  configKey: PublicKey,
+  feeRecipientKeys: Array<PublicKey>,
   poolSeed: Array<Buffer | Uint8Array>,
+  // Only required when the pool charges a performance fee, empty otherwise
+  poolAssetKeys: Array<PublicKey>,
+  // The pool's first market followed by a (market, bids, asks) triplet for each asset
+  // which isn't the price currency
+  priceAccountKeys: Array<PublicKey>,
 ): TransactionInstruction {
   let buffers = [Buffer.from(Int8Array.from([7])), Buffer.concat(poolSeed)];
 
@@ -935,12 +1132,80 @@ export function collectFeesInstruction(
       isWritable: true,
     },
     {
-      pubkey: bonfidaFeePoolTokenKey,
+      pubkey: configKey,
+      isSigner: false,
+      isWritable: false,
+    },
+  ];
+  for (var feeRecipient of feeRecipientKeys) {
+    keys.push({
+      pubkey: feeRecipient,
+      isSigner: false,
+      isWritable: true,
+    });
+  }
+  for (var poolAsset of poolAssetKeys) {
+    keys.push({
+      pubkey: poolAsset,
+      isSigner: false,
+      isWritable: false,
+    });
+  }
+  for (var priceAccount of priceAccountKeys) {
+    keys.push({
+      pubkey: priceAccount,
+      isSigner: false,
+      isWritable: false,
+    });
+  }
+
+  return new TransactionInstruction({
+    keys,
+    programId: bonfidaBotProgramId,
+    data,
+  });
+}
+
+export function initOpenOrdersInstruction(
+  systemProgramId: PublicKey,
+  rentSysvarKey: PublicKey,
+  bonfidaBotProgramId: PublicKey,
+  payerKey: PublicKey,
+  poolKey: PublicKey,
+  openOrdersKey: PublicKey,
+  poolSeed: Array<Buffer | Uint8Array>,
+  marketIndex: Numberu16,
+): TransactionInstruction {
+  let buffers = [
+    Buffer.from(Int8Array.from([15])),
+    Buffer.concat(poolSeed),
+    marketIndex.toBuffer(),
+  ];
+
+  const data = Buffer.concat(buffers);
+  const keys = [
+    {
+      pubkey: systemProgramId,
+      isSigner: false,
+      isWritable: false,
+    },
+    {
+      pubkey: rentSysvarKey,
+      isSigner: false,
+      isWritable: false,
+    },
+    {
+      pubkey: payerKey,
+      isSigner: true,
+      isWritable: true,
+    },
+    {
+      pubkey: poolKey,
       isSigner: false,
       isWritable: true,
     },
     {
-      pubkey: bonfidaBnBPTKey,
+      pubkey: openOrdersKey,
       isSigner: false,
       isWritable: true,
     },
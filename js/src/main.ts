@@ -1,12 +1,10 @@
 import {
-  Account,
   PublicKey,
   SystemProgram,
   SYSVAR_RENT_PUBKEY,
   SYSVAR_CLOCK_PUBKEY,
   TransactionInstruction,
   Connection,
-  CreateAccountParams,
   InstructionType,
 } from '@solana/web3.js';
 import { TOKEN_PROGRAM_ID, AccountLayout } from '@solana/spl-token';
@@ -17,6 +15,7 @@ import {
   createOrderInstruction,
   depositInstruction,
   initInstruction,
+  initOpenOrdersInstruction,
   redeemInstruction,
   settleFundsInstruction,
 } from './instructions';
@@ -36,8 +35,16 @@ import {
   PoolHeader,
   SelfTradeBehavior,
   unpack_assets,
-  PUBKEY_LENGTH,
+  get_assets_end,
   unpack_markets,
+  CONFIG_SEED,
+  ProgramConfig,
+  OPEN_ORDERS_SEED,
+  DEPOSIT_RECEIPT_SEED,
+  WHITELIST_SEED,
+  REGISTRY_SEED,
+  Whitelist,
+  unpack_open_orders,
 } from './state';
 import bs58 from 'bs58';
 import * as crypto from 'crypto';
@@ -57,14 +64,6 @@ export const BONFIDABOT_PROGRAM_ID: PublicKey = new PublicKey(
   '63xyXHpA6EVF69kRmEbXAr8aBEkhgpaNUSRoTQyi5Rwr',
 );
 
-export const BONFIDA_FEE_KEY: PublicKey = new PublicKey(
-  '31LVSggbVz4VcwBSPdtK8HJ3Lt1cKTJUVQTRNNYMfqBq',
-);
-
-export const BONFIDA_BNB_KEY: PublicKey = new PublicKey(
-  '3oQzjfjzUkJ5qHsERk2JPEpAKo34dxAQjUriBqursfxU',
-);
-
 export const SERUM_PROGRAM_ID: PublicKey = new PublicKey(
   '9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin',
 );
@@ -79,11 +78,108 @@ export const PUBLIC_POOLS_SEEDS = [
 
 /////////////////////////////////
 
+/**
+ * Returns the address of the program config account and the fee split it defines
+ *
+ * @param connection The connection object to the rpc node
+ */
+export async function fetchProgramConfig(
+  connection: Connection,
+): Promise<[PublicKey, ProgramConfig]> {
+  let [configKey] = await PublicKey.findProgramAddress(
+    [CONFIG_SEED],
+    BONFIDABOT_PROGRAM_ID,
+  );
+  let configInfo = await connection.getAccountInfo(configKey);
+  if (!configInfo) {
+    throw 'Program config account is unavailable';
+  }
+  return [configKey, ProgramConfig.fromBuffer(configInfo.data)];
+}
+
+/**
+ * Returns the accounts allowing the source owner to buy into the pool, which are empty if the pool is public
+ *
+ * @param connection The connection object to the rpc node
+ * @param poolHeader The header of the pool that is deposited into
+ * @param poolSeed The seed of the pool that is deposited into
+ * @param sourceOwnerKey The address of the wallet that deposits into the pool
+ */
+export async function findAllowListKeys(
+  connection: Connection,
+  poolHeader: PoolHeader,
+  poolSeed: Array<Buffer | Uint8Array>,
+  sourceOwnerKey: PublicKey,
+): Promise<Array<PublicKey>> {
+  if (!poolHeader.isPrivate) {
+    return [];
+  }
+  let [whitelistKey] = await PublicKey.findProgramAddress(
+    [Buffer.concat(poolSeed), WHITELIST_SEED],
+    BONFIDABOT_PROGRAM_ID,
+  );
+  let whitelistInfo = await connection.getAccountInfo(whitelistKey);
+  if (!whitelistInfo) {
+    throw 'Pool whitelist account is unavailable';
+  }
+  let whitelist = Whitelist.fromBuffer(whitelistInfo.data);
+  let allowListKeys = [whitelistKey];
+  if (!whitelist.gatingMint.equals(new PublicKey(0))) {
+    allowListKeys.push(
+      await findAssociatedTokenAddress(sourceOwnerKey, whitelist.gatingMint),
+    );
+  }
+  return allowListKeys;
+}
+
+/**
+ * Returns the accounts needed to escrow a deposit into the pool, which are empty if the pool has no lockup period
+ *
+ * @param poolHeader The header of the pool that is deposited into
+ * @param poolSeed The seed of the pool that is deposited into
+ * @param sourceOwnerKey The address of the wallet that owns the deposit receipt
+ * @param payer The address of the account that should pay for the deposit receipt allocation
+ */
+export async function findLockupAccountKeys(
+  poolHeader: PoolHeader,
+  poolSeed: Array<Buffer | Uint8Array>,
+  sourceOwnerKey: PublicKey,
+  payer: PublicKey,
+): Promise<Array<PublicKey>> {
+  if (Number(poolHeader.lockupPeriod) == 0) {
+    return [];
+  }
+  let poolKey = await PublicKey.createProgramAddress(
+    poolSeed,
+    BONFIDABOT_PROGRAM_ID,
+  );
+  let array_one = new Uint8Array(1);
+  array_one[0] = 1;
+  let poolMintKey = await PublicKey.createProgramAddress(
+    poolSeed.concat(array_one),
+    BONFIDABOT_PROGRAM_ID,
+  );
+  let [depositReceiptKey] = await PublicKey.findProgramAddress(
+    [Buffer.concat(poolSeed), DEPOSIT_RECEIPT_SEED, sourceOwnerKey.toBuffer()],
+    BONFIDABOT_PROGRAM_ID,
+  );
+  let escrowKey = await findAssociatedTokenAddress(poolKey, poolMintKey);
+  return [
+    SystemProgram.programId,
+    SYSVAR_RENT_PUBKEY,
+    SYSVAR_CLOCK_PUBKEY,
+    payer,
+    depositReceiptKey,
+    escrowKey,
+  ];
+}
+
 /**
  * Returns the solana instructions to create a new pool by performing the first deposit of any number of different tokens
  * and setting the pubkey of the signal provider. The first deposit will fix the initial
  * value of 1 pooltoken (credited to the target) with respect to the deposited tokens.
- * (Signed by the sourceOwner account)
+ * The pool is listed in the signal provider's registry.
+ * (Signed by the sourceOwner, signalProvider and payer accounts)
  *
  * @param connection The connection object to the rpc node
  * @param sourceOwnerKey The address of the wallet that owns the tokens to be invested in the pool
@@ -95,6 +191,13 @@ export const PUBLIC_POOLS_SEEDS = [
  * @param payer The address of the account that should pay for the allocation fees
  * @param feeCollectionPeriod The smallest period in seconds after which the trading fees can be payed out again (minimum is 604800 s or 1 week)
  * @param feePercentage The percentage (a number from 0 to 100) of the pool assets that should be collected as fees
+ * @param maxOrderLifetime The amount of time in seconds after which anyone can cancel the pool's pending orders
+ * @param timelockPeriod The delay in seconds before a proposed change to the pool, such as a new signal provider, can take effect
+ * @param performanceFeePercentage The percentage (a number from 0 to 100) of the gains above the high-water mark that should be collected as fees
+ * @param redeemFeePercentage The percentage (a number from 0 to 100) of the redeemed pooltokens that should be collected as fees
+ * @param lockupPeriod The amount of time in seconds deposited pooltokens are escrowed by the pool, 0 for no lockup
+ * @param maxPoolTokenSupply The maximum supply of pooltokens deposits can bring the pool to, 0 for no cap
+ * @param minDepositPoolTokens The minimum amount of pooltokens a deposit has to buy, fees included
  */
 export async function createPool(
   connection: Connection,
@@ -107,6 +210,13 @@ export async function createPool(
   payer: PublicKey,
   feeCollectionPeriod: Numberu64,
   feePercentage: number,
+  maxOrderLifetime: Numberu64,
+  timelockPeriod: Numberu64,
+  performanceFeePercentage: number,
+  redeemFeePercentage: number,
+  lockupPeriod: Numberu64,
+  maxPoolTokenSupply: Numberu64,
+  minDepositPoolTokens: Numberu64,
 ): Promise<[Uint8Array, TransactionInstruction[]]> {
 
   // Find a valid pool seed
@@ -211,6 +321,15 @@ export async function createPool(
   // Create the pool
   // @ts-ignore
   let feeRatioU16 = new Numberu16(2**16 * feePercentage / 100);
+  // @ts-ignore
+  let performanceFeeRatioU16 = new Numberu16(2**16 * performanceFeePercentage / 100);
+  // @ts-ignore
+  let redeemFeeRatioU16 = new Numberu16(2**16 * redeemFeePercentage / 100);
+  // The pool is listed in the registry of its signal provider, created on first use
+  let [registryKey] = await PublicKey.findProgramAddress(
+    [signalProviderKey.toBuffer(), REGISTRY_SEED],
+    BONFIDABOT_PROGRAM_ID,
+  );
   let createTxInstruction = createInstruction(
     TOKEN_PROGRAM_ID,
     BONFIDABOT_PROGRAM_ID,
@@ -224,10 +343,21 @@ export async function createPool(
     sourceAssetKeys,
     SERUM_PROGRAM_ID,
     signalProviderKey,
+    SystemProgram.programId,
+    SYSVAR_RENT_PUBKEY,
+    payer,
+    registryKey,
     depositAmounts,
     markets,
     feeCollectionPeriod,
     feeRatioU16,
+    maxOrderLifetime,
+    timelockPeriod,
+    performanceFeeRatioU16,
+    redeemFeeRatioU16,
+    lockupPeriod,
+    maxPoolTokenSupply,
+    minDepositPoolTokens,
   );
   txInstructions = txInstructions.concat(assetTxInstructions);
   txInstructions.push(createTxInstruction);
@@ -241,8 +371,9 @@ export async function createPool(
  * a corresponding amount of pool-token in exchange. The program will try to
  * maximize the deposit sum with regards to the amounts given by the source and
  * the ratio of tokens present in the pool at that moment. Tokens can only be deposited
- * in the exact ratio of tokens that are present in the pool.
- * (Signed by the sourceOwnerKey)
+ * in the exact ratio of tokens that are present in the pool. If the pool has a lockup period, the pooltokens
+ * are escrowed by the pool until they are released to the source owner.
+ * (Signed by the sourceOwnerKey, and by the payer if the pool has a lockup period)
  *
  * @param connection The connection object to the rpc node
  * @param sourceOwnerKey The address of the wallet that owns the tokens to be invested in the pool
@@ -250,6 +381,8 @@ export async function createPool(
  * @param poolTokenAmount The amount of pooltokens that should be bought (ie the amount of tokens that should be invested)
  * @param poolSeed The seed of the pool that should be invested into
  * @param payer The address of the account that should pay for the allocation fees
+ * @param minPoolTokensOut The minimum amount of pooltokens the target should receive, after fees
+ * @param maxAmountsIn The maximum amount of each pool asset that should be taken from the source, in the order of the pool assets
  */
 export async function deposit(
   connection: Connection,
@@ -258,6 +391,8 @@ export async function deposit(
   poolTokenAmount: Numberu64,
   poolSeed: Array<Buffer | Uint8Array>,
   payer: PublicKey,
+  minPoolTokensOut: Numberu64,
+  maxAmountsIn: Array<Numberu64>,
 ): Promise<TransactionInstruction[]> {
 
   // Find the pool key and mint key
@@ -280,7 +415,8 @@ export async function deposit(
   let poolHeader = PoolHeader.fromBuffer(poolData.slice(0, PoolHeader.LEN));
   let poolAssets: Array<PoolAsset> = unpack_assets(
     poolData.slice(
-      PoolHeader.LEN + Number(poolHeader.numberOfMarkets) * PUBKEY_LENGTH,
+      Number(poolHeader.assetOffset),
+      get_assets_end(poolHeader),
     ),
   );
 
@@ -310,46 +446,62 @@ export async function deposit(
   );
   sigProvInstruction? createTargetsTxInstructions.push(sigProvInstruction) : null;
 
-  let [bonfidaFeeReceiverKey, bonfidaFeeInstruction] = await findAndCreateAssociatedAccount(
-    SystemProgram.programId,
+  let [configKey, config] = await fetchProgramConfig(connection);
+  let feeRecipientKeys: Array<PublicKey> = [];
+  for (let feeRecipient of config.feeRecipients) {
+    let [feeRecipientKey, feeRecipientInstruction] = await findAndCreateAssociatedAccount(
+      SystemProgram.programId,
+      connection,
+      feeRecipient.owner,
+      poolMintKey,
+      payer
+    );
+    feeRecipientInstruction? createTargetsTxInstructions.push(feeRecipientInstruction) : null;
+    feeRecipientKeys.push(feeRecipientKey);
+  }
+
+  // Private pools only accept deposits from the investors allowed by their whitelist
+  let allowListKeys = await findAllowListKeys(
     connection,
-    BONFIDA_FEE_KEY,
-    poolMintKey,
-    payer
+    poolHeader,
+    poolSeed,
+    sourceOwnerKey,
   );
-  bonfidaFeeInstruction? createTargetsTxInstructions.push(bonfidaFeeInstruction) : null;
 
-  let [bonfidaBuyAndBurnKey, bonfidaBNBInstruction] = await findAndCreateAssociatedAccount(
-    SystemProgram.programId,
-    connection,
-    BONFIDA_BNB_KEY,
-    poolMintKey,
-    payer
+  // Pools with a lockup period escrow the deposited pooltokens until they are released
+  let lockupAccountKeys = await findLockupAccountKeys(
+    poolHeader,
+    poolSeed,
+    sourceOwnerKey,
+    payer,
   );
-  bonfidaBNBInstruction? createTargetsTxInstructions.push(bonfidaBNBInstruction) : null;
 
   let depositTxInstruction = depositInstruction(
     TOKEN_PROGRAM_ID,
     BONFIDABOT_PROGRAM_ID,
     sigProviderFeeReceiverKey,
-    bonfidaFeeReceiverKey,
-    bonfidaBuyAndBurnKey,
+    configKey,
+    feeRecipientKeys,
     poolMintKey,
     poolKey,
     poolAssetKeys,
     targetPoolTokenKey,
     sourceOwnerKey,
     sourceAssetKeys,
+    allowListKeys,
+    lockupAccountKeys,
     poolSeed,
     poolTokenAmount,
+    minPoolTokensOut,
+    maxAmountsIn,
   );
   return createTargetsTxInstructions.concat(depositTxInstruction);
 }
 
 /**
  * Returns the solana instructions to create a new serum order for the pool.
- * (Signed by the SignalProvider account of the pool and the OpenOrder Account
- * returned by this function)
+ * (Signed by the SignalProvider account of the pool, and by the payer when the pool's
+ * OpenOrders account for this market still has to be created)
  *
  * @param connection The connection object to the rpc node
  * @param poolSeed The seed of the pool that should be traded on
@@ -357,7 +509,8 @@ export async function deposit(
  * @param side The side of the order (ask or bid)
  * @param limitPrice The limit price for the order
  * @param maxQuantityPercentage The percentage (a number from 0 to 100) of the pool assets that should be invested
- * @param orderType For now, all orders are to be set as type ImmediateOrCancel
+ * @param orderType The serum order type. Resting orders (Limit and PostOnly) can be cancelled by anyone once
+ *  the pool has had pending orders for longer than its maxOrderLifetime
  * @param clientId The serum clientId for the order, can be set as 0
  * @param selfTradeBehavior The serum self trade behaviour for the order
  * @param srmDiscountKey The address of the srm discount key for the order (optional)
@@ -379,7 +532,7 @@ export async function createOrder(
   srmDiscountKey: PublicKey | null,
   payerKey: PublicKey,
   amountToTrade?: number,
-): Promise<[Account, TransactionInstruction[]]> {
+): Promise<TransactionInstruction[]> {
 
   // Find the pool key
   let poolKey = await PublicKey.createProgramAddress(
@@ -409,8 +562,8 @@ export async function createOrder(
 
   let authorizedMarkets = unpack_markets(
     poolInfo.data.slice(
-      PoolHeader.LEN,
-      PoolHeader.LEN + Number(poolHeader.numberOfMarkets) * PUBKEY_LENGTH,
+      Number(poolHeader.marketOffset),
+      Number(poolHeader.openOrdersOffset),
     ),
     poolHeader.numberOfMarkets,
   );
@@ -422,7 +575,8 @@ export async function createOrder(
 
   let poolAssets = unpack_assets(
     poolInfo.data.slice(
-      PoolHeader.LEN + Number(poolHeader.numberOfMarkets) * PUBKEY_LENGTH,
+      Number(poolHeader.assetOffset),
+      get_assets_end(poolHeader),
     ),
   );
 
@@ -458,20 +612,33 @@ export async function createOrder(
     targetPoolAssetIndex = poolAssets.length;
   }
 
-  // Create the open order account with trhe serum specific size of 3228 bits
-  let rent = await connection.getMinimumBalanceForRentExemption(3228);
-  let openOrderAccount = new Account();
-  let openOrderKey = openOrderAccount.publicKey;
-  let createAccountParams: CreateAccountParams = {
-    fromPubkey: payerKey,
-    newAccountPubkey: openOrderKey,
-    lamports: rent,
-    space: 3228,
-    programId: SERUM_PROGRAM_ID,
-  };
-  let createOpenOrderAccountInstruction = SystemProgram.createAccount(
-    createAccountParams,
+  // The pool trades each market from a single OpenOrders account, which is created on first use
+  let poolOpenOrders = unpack_open_orders(
+    poolInfo.data.slice(
+      Number(poolHeader.openOrdersOffset),
+      Number(poolHeader.assetOffset),
+    ),
+    poolHeader.numberOfMarkets,
   );
+  // @ts-ignore
+  let marketIndexU16 = new Numberu16(marketIndex);
+  let [openOrderKey] = await PublicKey.findProgramAddress(
+    [poolSeed, OPEN_ORDERS_SEED, marketIndexU16.toBuffer()],
+    BONFIDABOT_PROGRAM_ID,
+  );
+  let initOpenOrdersTxInstruction = undefined;
+  if (poolOpenOrders[marketIndex].address.toString() != openOrderKey.toString()) {
+    initOpenOrdersTxInstruction = initOpenOrdersInstruction(
+      SystemProgram.programId,
+      SYSVAR_RENT_PUBKEY,
+      BONFIDABOT_PROGRAM_ID,
+      payerKey,
+      poolKey,
+      openOrderKey,
+      [poolSeed],
+      marketIndexU16,
+    );
+  }
   console.log('Open Order key: ', openOrderKey.toString());
 
 
@@ -501,12 +668,12 @@ export async function createOrder(
     TOKEN_PROGRAM_ID,
     SERUM_PROGRAM_ID,
     SYSVAR_RENT_PUBKEY,
+    SYSVAR_CLOCK_PUBKEY,
     srmDiscountKey,
     [poolSeed],
     side,
     limitPrice,
-    // @ts-ignore
-    new Numberu16(marketIndex),
+    marketIndexU16,
     marketData.coinLotSize,
     marketData.pcLotSize,
     targetMintKey,
@@ -518,14 +685,14 @@ export async function createOrder(
     new Numberu16((1<<16) - 1)
   );
 
-  let instructions = [
-    createOpenOrderAccountInstruction,
-    createOrderTxInstruction,
-  ];
+  let instructions = [createOrderTxInstruction];
+  if (!!initOpenOrdersTxInstruction) {
+    instructions.unshift(initOpenOrdersTxInstruction);
+  }
   if (!!createTargetAssetInstruction) {
     instructions.unshift(createTargetAssetInstruction);
   }
-  return [openOrderAccount, instructions];
+  return instructions;
 }
 
 
@@ -567,7 +734,8 @@ export async function settleFunds(
   );
   let poolAssets = unpack_assets(
     poolInfo.data.slice(
-      PoolHeader.LEN + Number(poolHeader.numberOfMarkets) * PUBKEY_LENGTH,
+      Number(poolHeader.assetOffset),
+      get_assets_end(poolHeader),
     ),
   );
 
@@ -625,12 +793,14 @@ export async function settleFunds(
 }
 
 /**
- * This method is obsolete for now as all orders can only be passed as ImmediateOrCancel
+ * Returns the solana instructions to cancel the first order of one of the pool's OpenOrders accounts.
+ * (Signed by the SignalProvider account of the pool, or by anyone once the pool's pending orders
+ * have outlived its maxOrderLifetime)
  *
- * @param connection
- * @param poolSeed
- * @param market
- * @param openOrdersKey
+ * @param connection The connection object to the rpc node
+ * @param poolSeed The seed of the pool
+ * @param market The address of the serum market on which the order is
+ * @param openOrdersKey The address of the serum openOrder account holding the order
  */
 export async function cancelOrder(
   connection: Connection,
@@ -680,6 +850,7 @@ export async function cancelOrder(
     marketData.asksKey,
     poolKey,
     SERUM_PROGRAM_ID,
+    SYSVAR_CLOCK_PUBKEY,
     [poolSeed],
     side,
     orderId,
@@ -691,9 +862,8 @@ export async function cancelOrder(
 
 /**
  * Returns the solana instructions to buy out of the pool by redeeming (burning) pooltokens.
- * This instruction needs to be executed after (and within the same transaction)
- * having settled on all possible open orders for the pool.
- * This is because as long as an order is open for the pool, redeeming is impossible.
+ * While the pool has pending orders, the redeemed share also covers the funds held by the
+ * pool's OpenOrders accounts, whose accounts are passed along by this function.
  * (Signed by the owner of the pooltokens)
  *
  * @param connection The connection object to the rpc node
@@ -702,6 +872,7 @@ export async function cancelOrder(
  * @param targetAssetKeys An array of addresses to which the pool asset tokens are payed out to
  * @param poolSeed The seed of the pool that should be redeemed from
  * @param poolTokenAmount The amount of pooltokens that should be used (ie the amount of tokens that should be bought back)
+ * @param minAmountsOut The minimum amount of each pool asset the targets should receive, in the order of the pool assets
  */
 export async function redeem(
   connection: Connection,
@@ -710,6 +881,7 @@ export async function redeem(
   targetAssetKeys: Array<PublicKey>,
   poolSeed: Array<Buffer | Uint8Array>,
   poolTokenAmount: Numberu64,
+  minAmountsOut: Array<Numberu64>,
 ): Promise<TransactionInstruction[]> {
 
   // Find the pool key and mint key
@@ -732,7 +904,8 @@ export async function redeem(
   let poolHeader = PoolHeader.fromBuffer(poolData.slice(0, PoolHeader.LEN));
   let poolAssets = unpack_assets(
     poolInfo.data.slice(
-      PoolHeader.LEN + Number(poolHeader.numberOfMarkets) * PUBKEY_LENGTH,
+      Number(poolHeader.assetOffset),
+      get_assets_end(poolHeader),
     ),
   );
   let poolAssetKeys: Array<PublicKey> = [];
@@ -741,6 +914,59 @@ export async function redeem(
     poolAssetKeys.push(assetKey);
   }
 
+  // Pools charging a redeem fee pay it out to the signal provider and the fee recipients
+  let redeemFeeAccountKeys: Array<PublicKey> = [];
+  if (Number(poolHeader.redeemFeeRatio) != 0) {
+    let [configKey, config] = await fetchProgramConfig(connection);
+    redeemFeeAccountKeys.push(
+      await findAssociatedTokenAddress(poolHeader.signalProvider, poolMintKey),
+      configKey,
+    );
+    for (let feeRecipient of config.feeRecipients) {
+      redeemFeeAccountKeys.push(
+        await findAssociatedTokenAddress(feeRecipient.owner, poolMintKey),
+      );
+    }
+  }
+
+  // The redeemed share of the funds held by pending OpenOrders accounts is paid out as well
+  let authorizedMarkets = unpack_markets(
+    poolData.slice(
+      Number(poolHeader.marketOffset),
+      Number(poolHeader.openOrdersOffset),
+    ),
+    poolHeader.numberOfMarkets,
+  );
+  let poolOpenOrders = unpack_open_orders(
+    poolData.slice(
+      Number(poolHeader.openOrdersOffset),
+      Number(poolHeader.assetOffset),
+    ),
+    poolHeader.numberOfMarkets,
+  );
+  let pendingOrderAccountKeys: Array<PublicKey> = [];
+  for (let i = 0; i < poolOpenOrders.length; i++) {
+    if (!poolOpenOrders[i].isPending) {
+      continue;
+    }
+    let market = authorizedMarkets[i];
+    let marketData = await getMarketData(connection, market);
+    let vaultSignerKey = await PublicKey.createProgramAddress(
+      [market.toBuffer(), marketData.vaultSignerNonce.toBuffer()],
+      SERUM_PROGRAM_ID,
+    );
+    pendingOrderAccountKeys.push(
+      market,
+      poolOpenOrders[i].address,
+      marketData.bidsKey,
+      marketData.asksKey,
+      marketData.eventQueueKey,
+      marketData.coinVaultKey,
+      marketData.pcVaultKey,
+      vaultSignerKey,
+    );
+  }
+
   let redeemTxInstruction = redeemInstruction(
     TOKEN_PROGRAM_ID,
     BONFIDABOT_PROGRAM_ID,
@@ -751,8 +977,12 @@ export async function redeem(
     sourcePoolTokenOwnerKey,
     sourcePoolTokenKey,
     targetAssetKeys,
+    redeemFeeAccountKeys,
+    SERUM_PROGRAM_ID,
+    pendingOrderAccountKeys,
     poolSeed,
     poolTokenAmount,
+    minAmountsOut,
   );
   return [redeemTxInstruction];
 }
@@ -760,7 +990,7 @@ export async function redeem(
 
  /**
   *  Returns the solana instructions to collect the fees from the pool.
-  *  See the readme for the payout destinations.
+  *  The fees are split between the signal provider and the fee recipients of the program config.
   * (Permissionless)
   *
   * @param connection The connection object to the rpc node
@@ -793,14 +1023,57 @@ export async function collectFees(
     poolHeader.signalProvider,
     poolMintKey,
   );
-  let bonfidaFeeReceiverKey = await findAssociatedTokenAddress(
-    BONFIDA_FEE_KEY,
-    poolMintKey,
-  );
-  let bonfidaBuyAndBurnKey = await findAssociatedTokenAddress(
-    BONFIDA_BNB_KEY,
-    poolMintKey,
-  );
+  let [configKey, config] = await fetchProgramConfig(connection);
+  let feeRecipientKeys: Array<PublicKey> = [];
+  for (let feeRecipient of config.feeRecipients) {
+    feeRecipientKeys.push(
+      await findAssociatedTokenAddress(feeRecipient.owner, poolMintKey),
+    );
+  }
+
+  // A performance fee requires valuing the pool in the price currency of its first market
+  let poolAssetKeys: Array<PublicKey> = [];
+  let priceAccountKeys: Array<PublicKey> = [];
+  if (Number(poolHeader.performanceFeeRatio) != 0) {
+    let authorizedMarkets = unpack_markets(
+      poolData.slice(
+        Number(poolHeader.marketOffset),
+        Number(poolHeader.openOrdersOffset),
+      ),
+      poolHeader.numberOfMarkets,
+    );
+    let marketsData = [];
+    for (let market of authorizedMarkets) {
+      marketsData.push(await getMarketData(connection, market));
+    }
+    let priceCurrency = marketsData[0].pcMintKey;
+    priceAccountKeys.push(authorizedMarkets[0]);
+    let poolAssets = unpack_assets(
+      poolData.slice(
+        Number(poolHeader.assetOffset),
+        get_assets_end(poolHeader),
+      ),
+    );
+    for (let asset of poolAssets) {
+      poolAssetKeys.push(
+        await findAssociatedTokenAddress(poolKey, asset.mintAddress),
+      );
+      if (asset.mintAddress.equals(priceCurrency)) {
+        continue;
+      }
+      let marketIndex = marketsData.findIndex(m =>
+        m.coinMintKey.equals(asset.mintAddress),
+      );
+      if (marketIndex == -1) {
+        throw 'No authorized market values a pool asset';
+      }
+      priceAccountKeys.push(
+        authorizedMarkets[marketIndex],
+        marketsData[marketIndex].bidsKey,
+        marketsData[marketIndex].asksKey,
+      );
+    }
+  }
 
   let collectFeesTxInstruction = collectFeesInstruction(
     TOKEN_PROGRAM_ID,
@@ -809,9 +1082,11 @@ export async function collectFees(
     poolKey,
     poolMintKey,
     sigProviderFeeReceiverKey,
-    bonfidaFeeReceiverKey,
-    bonfidaBuyAndBurnKey,
+    configKey,
+    feeRecipientKeys,
     poolSeed,
+    poolAssetKeys,
+    priceAccountKeys,
   );
   return [collectFeesTxInstruction];
 }
@@ -26,11 +26,13 @@ import {
   MarketData,
 } from './utils';
 import {
+  AccountType,
   OrderSide,
   OrderType,
+  POOL_VERSION,
   PoolHeader,
   PoolStatus,
-  PUBKEY_LENGTH,
+  get_assets_end,
   SelfTradeBehavior,
   unpack_assets,
   unpack_markets,
@@ -43,8 +45,9 @@ import {
 } from './types';
 import {
   BONFIDABOT_PROGRAM_ID,
-  BONFIDA_BNB_KEY,
-  BONFIDA_FEE_KEY,
+  fetchProgramConfig,
+  findAllowListKeys,
+  findLockupAccountKeys,
   createPool,
   SERUM_PROGRAM_ID,
   settleFunds,
@@ -102,8 +105,8 @@ export async function settlePool(
 
   let authorizedMarkets = unpack_markets(
     poolData.data.slice(
-      PoolHeader.LEN,
-      PoolHeader.LEN + Number(poolHeader.numberOfMarkets) * PUBKEY_LENGTH,
+      Number(poolHeader.marketOffset),
+      Number(poolHeader.openOrdersOffset),
     ),
     poolHeader.numberOfMarkets,
   );
@@ -173,14 +176,15 @@ export async function fetchPoolInfo(
   );
   let poolAssets = unpack_assets(
     poolData.data.slice(
-      PoolHeader.LEN + Number(poolHeader.numberOfMarkets) * PUBKEY_LENGTH,
+      Number(poolHeader.assetOffset),
+      get_assets_end(poolHeader),
     ),
   );
 
   let authorizedMarkets = unpack_markets(
     poolData.data.slice(
-      PoolHeader.LEN,
-      PoolHeader.LEN + Number(poolHeader.numberOfMarkets) * PUBKEY_LENGTH,
+      Number(poolHeader.marketOffset),
+      Number(poolHeader.openOrdersOffset),
     ),
     poolHeader.numberOfMarkets,
   );
@@ -230,7 +234,8 @@ export async function fetchPoolBalances(
   );
   let poolAssets = unpack_assets(
     poolData.data.slice(
-      PoolHeader.LEN + Number(poolHeader.numberOfMarkets) * PUBKEY_LENGTH,
+      Number(poolHeader.assetOffset),
+      get_assets_end(poolHeader),
     ),
   );
 
@@ -291,7 +296,8 @@ export async function singleTokenDeposit(
   );
   let poolAssets = unpack_assets(
     poolInfo.data.slice(
-      PoolHeader.LEN + Number(poolHeader.numberOfMarkets) * PUBKEY_LENGTH,
+      Number(poolHeader.assetOffset),
+      get_assets_end(poolHeader),
     ),
   );
 
@@ -566,32 +572,51 @@ export async function singleTokenDeposit(
   );
   sigProvInstruction ? instructions.push(sigProvInstruction) : null;
 
-  let [
-    bonfidaFeeReceiverKey,
-    bonfidaFeeInstruction,
-  ] = await findAndCreateAssociatedAccount(
-    SystemProgram.programId,
+  let [configKey, config] = await fetchProgramConfig(connection);
+  let feeRecipientKeys: Array<PublicKey> = [];
+  for (let feeRecipient of config.feeRecipients) {
+    let [
+      feeRecipientKey,
+      feeRecipientInstruction,
+    ] = await findAndCreateAssociatedAccount(
+      SystemProgram.programId,
+      connection,
+      feeRecipient.owner,
+      poolMintKey,
+      payer.publicKey,
+    );
+    feeRecipientInstruction ? instructions.push(feeRecipientInstruction) : null;
+    feeRecipientKeys.push(feeRecipientKey);
+  }
+
+  // @ts-ignore
+  console.log(poolTokenAmount, new Numberu64(1000000 * poolTokenAmount));
+
+  // The deposit can't take more than the bought assets
+  let maxAmountsIn: Array<Numberu64> = [];
+  for (let sourceAssetKey of sourceAssetKeys) {
+    maxAmountsIn.push(
+      new Numberu64(
+        (await connection.getTokenAccountBalance(sourceAssetKey)).value.amount,
+      ),
+    );
+  }
+
+  // Private pools only accept deposits from the investors allowed by their whitelist
+  let allowListKeys = await findAllowListKeys(
     connection,
-    BONFIDA_FEE_KEY,
-    poolMintKey,
-    payer.publicKey,
+    poolHeader,
+    [poolSeed],
+    sourceOwner.publicKey,
   );
-  bonfidaFeeInstruction ? instructions.push(bonfidaFeeInstruction) : null;
 
-  let [
-    bonfidaBuyAndBurnKey,
-    bonfidaBNBInstruction,
-  ] = await findAndCreateAssociatedAccount(
-    SystemProgram.programId,
-    connection,
-    BONFIDA_BNB_KEY,
-    poolMintKey,
+  // Pools with a lockup period escrow the deposited pooltokens until they are released
+  let lockupAccountKeys = await findLockupAccountKeys(
+    poolHeader,
+    [poolSeed],
+    sourceOwner.publicKey,
     payer.publicKey,
   );
-  bonfidaBNBInstruction ? instructions.push(bonfidaBNBInstruction) : null;
-
-  // @ts-ignore
-  console.log(poolTokenAmount, new Numberu64(1000000 * poolTokenAmount));
 
   // Do the effective deposit
   console.log('Execute Buy in');
@@ -599,17 +624,21 @@ export async function singleTokenDeposit(
     TOKEN_PROGRAM_ID,
     BONFIDABOT_PROGRAM_ID,
     sigProviderFeeReceiverKey,
-    bonfidaFeeReceiverKey,
-    bonfidaBuyAndBurnKey,
+    configKey,
+    feeRecipientKeys,
     poolMintKey,
     poolKey,
     poolAssetKeys,
     targetPoolTokenKey,
     sourceOwner.publicKey,
     sourceAssetKeys,
+    allowListKeys,
+    lockupAccountKeys,
     [poolSeed],
     // @ts-ignore
     new Numberu64(1000000 * poolTokenAmount),
+    new Numberu64(0),
+    maxAmountsIn,
   );
   instructions.push(depositTxInstruction);
   console.log(
@@ -649,14 +678,19 @@ export async function getPoolsSeedsBySigProvider(
   let poolSeeds: Buffer[] = [];
   for (var account of resp.result) {
     let data = Buffer.from(account['account']['data'][0], 'base64');
-    if (data.length < PoolHeader.LEN) {
+    if (
+      data.length < PoolHeader.LEN ||
+      data[0] != AccountType.Pool ||
+      data[1] != POOL_VERSION
+    ) {
       continue;
     }
+    let poolHeader = PoolHeader.fromBuffer(data.slice(0, PoolHeader.LEN));
     if (
       !signalProviderKey ||
-      new PublicKey(data.slice(64, 96)).equals(signalProviderKey)
+      poolHeader.signalProvider.equals(signalProviderKey)
     ) {
-      poolSeeds.push(data.slice(32, 64));
+      poolSeeds.push(Buffer.from(poolHeader.seed));
     }
   }
   return poolSeeds;
@@ -1,5 +1,5 @@
 import { PublicKey } from '@solana/web3.js';
-import { Numberu16, Numberu64 } from './utils';
+import { Numberu16, Numberu32, Numberu64 } from './utils';
 
 // Serum analog types
 export enum OrderSide {
@@ -18,6 +18,13 @@ export enum SelfTradeBehavior {
 }
 
 export const PUBKEY_LENGTH: number = 32;
+export const CONFIG_SEED: Buffer = Buffer.from('config');
+export const OPEN_ORDERS_SEED: Buffer = Buffer.from('open_orders');
+export const DEPOSIT_RECEIPT_SEED: Buffer = Buffer.from('deposit_receipt');
+export const WHITELIST_SEED: Buffer = Buffer.from('whitelist');
+export const REGISTRY_SEED: Buffer = Buffer.from('registry');
+// Size of a Serum OpenOrders account, including the 12 bytes of padding added by the dex
+export const OPEN_ORDERS_LEN: number = 3228;
 
 const STATUS_PENDING_ORDER_FLAG: number = 1 << 6;
 const STATUS_PENDING_ORDER_MASK: number = 0x3f;
@@ -34,8 +41,19 @@ export enum PoolStatusID {
 
 export type PoolStatus = [PoolStatusID, number];
 
+// Type of the account, stored in the first byte of the pool header
+export enum AccountType {
+  Uninitialized,
+  Pool,
+}
+
+// Current version of the pool account layout. Older pool accounts have to be migrated.
+export const POOL_VERSION: number = 1;
+
 export class PoolHeader {
-  static LEN = 117;
+  static LEN = 310;
+  accountType!: AccountType;
+  version!: number;
   serumProgramId!: PublicKey;
   seed!: Uint8Array;
   signalProvider!: PublicKey;
@@ -43,9 +61,31 @@ export class PoolHeader {
   numberOfMarkets!: Numberu16;
   feeRatio!: Numberu16;
   lastFeeCollectionTimestamp!: Numberu64;
-  feeCollectionPeriod!: Numberu64
+  feeCollectionPeriod!: Numberu64;
+  maxOrderLifetime!: Numberu64;
+  firstPendingOrderTimestamp!: Numberu64;
+  timelockPeriod!: Numberu64;
+  pendingSignalProvider!: PublicKey;
+  signalProviderProposalTimestamp!: Numberu64;
+  performanceFeeRatio!: Numberu16;
+  highWaterMark!: Numberu64;
+  redeemFeeRatio!: Numberu16;
+  lockupPeriod!: Numberu64;
+  maxPoolTokenSupply!: Numberu64;
+  minDepositPoolTokens!: Numberu64;
+  isPrivate!: boolean;
+  pendingMarketIndex!: Numberu16;
+  pendingMarket!: PublicKey;
+  marketProposalTimestamp!: Numberu64;
+  creator!: PublicKey;
+  maxNumberOfAssets!: Numberu32;
+  marketOffset!: Numberu32;
+  openOrdersOffset!: Numberu32;
+  assetOffset!: Numberu32;
 
   constructor(
+    accountType: AccountType,
+    version: number,
     serumProgramId: PublicKey,
     seed: Uint8Array,
     signalProvider: PublicKey,
@@ -53,8 +93,30 @@ export class PoolHeader {
     numberOfMarkets: Numberu16,
     feeRatio: Numberu16,
     lastFeeCollectionTimestamp: Numberu64,
-    feeCollectionPeriod: Numberu64
+    feeCollectionPeriod: Numberu64,
+    maxOrderLifetime: Numberu64,
+    firstPendingOrderTimestamp: Numberu64,
+    timelockPeriod: Numberu64,
+    pendingSignalProvider: PublicKey,
+    signalProviderProposalTimestamp: Numberu64,
+    performanceFeeRatio: Numberu16,
+    highWaterMark: Numberu64,
+    redeemFeeRatio: Numberu16,
+    lockupPeriod: Numberu64,
+    maxPoolTokenSupply: Numberu64,
+    minDepositPoolTokens: Numberu64,
+    isPrivate: boolean,
+    pendingMarketIndex: Numberu16,
+    pendingMarket: PublicKey,
+    marketProposalTimestamp: Numberu64,
+    creator: PublicKey,
+    maxNumberOfAssets: Numberu32,
+    marketOffset: Numberu32,
+    openOrdersOffset: Numberu32,
+    assetOffset: Numberu32,
   ) {
+    this.accountType = accountType;
+    this.version = version;
     this.serumProgramId = serumProgramId;
     this.seed = seed;
     this.signalProvider = signalProvider;
@@ -63,6 +125,26 @@ export class PoolHeader {
     this.feeRatio = feeRatio;
     this.lastFeeCollectionTimestamp = lastFeeCollectionTimestamp;
     this.feeCollectionPeriod = feeCollectionPeriod;
+    this.maxOrderLifetime = maxOrderLifetime;
+    this.firstPendingOrderTimestamp = firstPendingOrderTimestamp;
+    this.timelockPeriod = timelockPeriod;
+    this.pendingSignalProvider = pendingSignalProvider;
+    this.signalProviderProposalTimestamp = signalProviderProposalTimestamp;
+    this.performanceFeeRatio = performanceFeeRatio;
+    this.highWaterMark = highWaterMark;
+    this.redeemFeeRatio = redeemFeeRatio;
+    this.lockupPeriod = lockupPeriod;
+    this.maxPoolTokenSupply = maxPoolTokenSupply;
+    this.minDepositPoolTokens = minDepositPoolTokens;
+    this.isPrivate = isPrivate;
+    this.pendingMarketIndex = pendingMarketIndex;
+    this.pendingMarket = pendingMarket;
+    this.marketProposalTimestamp = marketProposalTimestamp;
+    this.creator = creator;
+    this.maxNumberOfAssets = maxNumberOfAssets;
+    this.marketOffset = marketOffset;
+    this.openOrdersOffset = openOrdersOffset;
+    this.assetOffset = assetOffset;
   }
 
   static match_status(status_byte: Buffer): PoolStatus {
@@ -91,16 +173,45 @@ export class PoolHeader {
   }
 
   static fromBuffer(buf: Buffer): PoolHeader {
-    const serumProgramId: PublicKey = new PublicKey(buf.slice(0, 32));
-    const seed: Uint8Array = buf.slice(32, 64);
-    const signalProvider: PublicKey = new PublicKey(buf.slice(64, 96));
-    const status: PoolStatus = PoolHeader.match_status(buf.slice(96, 97));
+    const accountType: AccountType = buf[0];
+    const version = buf[1];
+    if (accountType == AccountType.Pool && version != POOL_VERSION) {
+      throw 'Pool account should be migrated to the current layout version.';
+    }
+    const serumProgramId: PublicKey = new PublicKey(buf.slice(2, 34));
+    const seed: Uint8Array = buf.slice(34, 66);
+    const signalProvider: PublicKey = new PublicKey(buf.slice(66, 98));
+    const status: PoolStatus = PoolHeader.match_status(buf.slice(98, 99));
     // @ts-ignore
-    const numberOfMarkets = Numberu16.fromBuffer(buf.slice(97, 99));
-    const feeRatio = Numberu16.fromBuffer(buf.slice(99, 101));
-    const lastFeeCollectionTimestamp = Numberu64.fromBuffer(buf.slice(101, 109));
-    const feeCollectionPeriod = Numberu64.fromBuffer(buf.slice(109, 117));
+    const numberOfMarkets = Numberu16.fromBuffer(buf.slice(99, 101));
+    const feeRatio = Numberu16.fromBuffer(buf.slice(101, 103));
+    const lastFeeCollectionTimestamp = Numberu64.fromBuffer(buf.slice(103, 111));
+    const feeCollectionPeriod = Numberu64.fromBuffer(buf.slice(111, 119));
+    const maxOrderLifetime = Numberu64.fromBuffer(buf.slice(119, 127));
+    const firstPendingOrderTimestamp = Numberu64.fromBuffer(buf.slice(127, 135));
+    const timelockPeriod = Numberu64.fromBuffer(buf.slice(135, 143));
+    const pendingSignalProvider = new PublicKey(buf.slice(143, 175));
+    const signalProviderProposalTimestamp = Numberu64.fromBuffer(
+      buf.slice(175, 183),
+    );
+    const performanceFeeRatio = Numberu16.fromBuffer(buf.slice(183, 185));
+    const highWaterMark = Numberu64.fromBuffer(buf.slice(185, 193));
+    const redeemFeeRatio = Numberu16.fromBuffer(buf.slice(193, 195));
+    const lockupPeriod = Numberu64.fromBuffer(buf.slice(195, 203));
+    const maxPoolTokenSupply = Numberu64.fromBuffer(buf.slice(203, 211));
+    const minDepositPoolTokens = Numberu64.fromBuffer(buf.slice(211, 219));
+    const isPrivate = buf[219] == 1;
+    const pendingMarketIndex = Numberu16.fromBuffer(buf.slice(220, 222));
+    const pendingMarket = new PublicKey(buf.slice(222, 254));
+    const marketProposalTimestamp = Numberu64.fromBuffer(buf.slice(254, 262));
+    const creator = new PublicKey(buf.slice(262, 294));
+    const maxNumberOfAssets = Numberu32.fromBuffer(buf.slice(294, 298));
+    const marketOffset = Numberu32.fromBuffer(buf.slice(298, 302));
+    const openOrdersOffset = Numberu32.fromBuffer(buf.slice(302, 306));
+    const assetOffset = Numberu32.fromBuffer(buf.slice(306, 310));
     return new PoolHeader(
+      accountType,
+      version,
       serumProgramId,
       seed,
       signalProvider,
@@ -108,7 +219,27 @@ export class PoolHeader {
       numberOfMarkets,
       feeRatio,
       lastFeeCollectionTimestamp,
-      feeCollectionPeriod
+      feeCollectionPeriod,
+      maxOrderLifetime,
+      firstPendingOrderTimestamp,
+      timelockPeriod,
+      pendingSignalProvider,
+      signalProviderProposalTimestamp,
+      performanceFeeRatio,
+      highWaterMark,
+      redeemFeeRatio,
+      lockupPeriod,
+      maxPoolTokenSupply,
+      minDepositPoolTokens,
+      isPrivate,
+      pendingMarketIndex,
+      pendingMarket,
+      marketProposalTimestamp,
+      creator,
+      maxNumberOfAssets,
+      marketOffset,
+      openOrdersOffset,
+      assetOffset,
     );
   }
 }
@@ -131,6 +262,91 @@ export class PoolAsset {
   }
 }
 
+// Pool state is composed of PoolHeader, Array of PoolMarkets, array of PoolOpenOrders (one
+// per market) and array of poolassets
+export class PoolMarket {
+  static LEN = 112;
+  address!: PublicKey;
+  coinMint!: PublicKey;
+  pcMint!: PublicKey;
+  coinLotSize!: Numberu64;
+  pcLotSize!: Numberu64;
+
+  constructor(
+    address: PublicKey,
+    coinMint: PublicKey,
+    pcMint: PublicKey,
+    coinLotSize: Numberu64,
+    pcLotSize: Numberu64,
+  ) {
+    this.address = address;
+    this.coinMint = coinMint;
+    this.pcMint = pcMint;
+    this.coinLotSize = coinLotSize;
+    this.pcLotSize = pcLotSize;
+  }
+
+  static fromBuffer(buf: Buffer): PoolMarket {
+    const address: PublicKey = new PublicKey(buf.slice(0, 32));
+    const coinMint: PublicKey = new PublicKey(buf.slice(32, 64));
+    const pcMint: PublicKey = new PublicKey(buf.slice(64, 96));
+    const coinLotSize = Numberu64.fromBuffer(buf.slice(96, 104));
+    const pcLotSize = Numberu64.fromBuffer(buf.slice(104, 112));
+    return new PoolMarket(address, coinMint, pcMint, coinLotSize, pcLotSize);
+  }
+}
+
+export class PoolOpenOrders {
+  static LEN = 33;
+  address!: PublicKey;
+  isPending!: boolean;
+
+  constructor(address: PublicKey, isPending: boolean) {
+    this.address = address;
+    this.isPending = isPending;
+  }
+
+  static fromBuffer(buf: Buffer): PoolOpenOrders {
+    const address: PublicKey = new PublicKey(buf.slice(0, 32));
+    const isPending = buf[32] == 1;
+    return new PoolOpenOrders(address, isPending);
+  }
+}
+
+export function get_open_orders_offset(numberOfMarkets: Numberu16): number {
+  return PoolHeader.LEN + PoolMarket.LEN * Number(numberOfMarkets);
+}
+
+export function get_asset_offset(numberOfMarkets: Numberu16): number {
+  return (
+    get_open_orders_offset(numberOfMarkets) +
+    PoolOpenOrders.LEN * Number(numberOfMarkets)
+  );
+}
+
+// End of the asset region, which has room for the pool's maxNumberOfAssets
+export function get_assets_end(poolHeader: PoolHeader): number {
+  return (
+    Number(poolHeader.assetOffset) +
+    PoolAsset.LEN * Number(poolHeader.maxNumberOfAssets)
+  );
+}
+
+export function unpack_open_orders(
+  input: Buffer,
+  numberOfMarkets: Numberu16,
+): Array<PoolOpenOrders> {
+  let openOrders: Array<PoolOpenOrders> = new Array();
+  let offset = 0;
+  for (var i = 0; i < new Number(numberOfMarkets); i++) {
+    openOrders.push(
+      PoolOpenOrders.fromBuffer(input.slice(offset, offset + PoolOpenOrders.LEN)),
+    );
+    offset += PoolOpenOrders.LEN;
+  }
+  return openOrders;
+}
+
 export function unpack_assets(input: Buffer): Array<PoolAsset> {
   let numberOfAssets = input.length / PoolAsset.LEN;
   let output: Array<PoolAsset> = [];
@@ -156,11 +372,86 @@ export function unpack_markets(
   input: Buffer,
   numberOfMarkets: Numberu16,
 ): Array<PublicKey> {
-  let markets: Array<PublicKey> = new Array();
+  return unpack_pool_markets(input, numberOfMarkets).map(m => m.address);
+}
+
+export function unpack_pool_markets(
+  input: Buffer,
+  numberOfMarkets: Numberu16,
+): Array<PoolMarket> {
+  let markets: Array<PoolMarket> = new Array();
   let offset = 0;
   for (var i = 0; i < new Number(numberOfMarkets); i++) {
-    markets.push(new PublicKey(input.slice(offset, offset + 32)));
-    offset += 32;
+    markets.push(
+      PoolMarket.fromBuffer(input.slice(offset, offset + PoolMarket.LEN)),
+    );
+    offset += PoolMarket.LEN;
   }
   return markets;
 }
+
+export type FeeRecipient = {
+  owner: PublicKey;
+  share: Numberu16;
+};
+
+export class ProgramConfig {
+  isInitialized!: boolean;
+  signalProviderShare!: Numberu16;
+  feeRecipients!: Array<FeeRecipient>;
+
+  constructor(
+    isInitialized: boolean,
+    signalProviderShare: Numberu16,
+    feeRecipients: Array<FeeRecipient>,
+  ) {
+    this.isInitialized = isInitialized;
+    this.signalProviderShare = signalProviderShare;
+    this.feeRecipients = feeRecipients;
+  }
+
+  static fromBuffer(buf: Buffer): ProgramConfig {
+    const isInitialized = buf[0] == 1;
+    const signalProviderShare = Numberu16.fromBuffer(buf.slice(1, 3));
+    const numberOfRecipients = buf[3];
+    let feeRecipients: Array<FeeRecipient> = [];
+    let offset = 4;
+    for (let i = 0; i < numberOfRecipients; i++) {
+      feeRecipients.push({
+        owner: new PublicKey(buf.slice(offset, offset + 32)),
+        share: Numberu16.fromBuffer(buf.slice(offset + 32, offset + 34)),
+      });
+      offset += PUBKEY_LENGTH + 2;
+    }
+    return new ProgramConfig(isInitialized, signalProviderShare, feeRecipients);
+  }
+}
+
+export class Whitelist {
+  isInitialized!: boolean;
+  gatingMint!: PublicKey;
+  owners!: Array<PublicKey>;
+
+  constructor(
+    isInitialized: boolean,
+    gatingMint: PublicKey,
+    owners: Array<PublicKey>,
+  ) {
+    this.isInitialized = isInitialized;
+    this.gatingMint = gatingMint;
+    this.owners = owners;
+  }
+
+  static fromBuffer(buf: Buffer): Whitelist {
+    const isInitialized = buf[0] == 1;
+    const gatingMint = new PublicKey(buf.slice(1, 33));
+    const numberOfOwners = buf[33];
+    let owners: Array<PublicKey> = [];
+    let offset = 34;
+    for (let i = 0; i < numberOfOwners; i++) {
+      owners.push(new PublicKey(buf.slice(offset, offset + PUBKEY_LENGTH)));
+      offset += PUBKEY_LENGTH;
+    }
+    return new Whitelist(isInitialized, gatingMint, owners);
+  }
+}
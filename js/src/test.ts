@@ -155,7 +155,7 @@ const test = async (): Promise<void> => {
   // console.log("Deposited into Pool")
   // await sleep(5 * 1000);
 
-  // let [openOrderAccount, createPoolTxInstructions] = await createOrder(
+  // let createPoolTxInstructions = await createOrder(
   //   connection,
   //   poolInfo.seed,
   //   marketInfo.address,
@@ -174,7 +174,7 @@ const test = async (): Promise<void> => {
 
   // await signAndSendTransactionInstructions(
   //   connection,
-  //   [openOrderAccount, signalProviderAccount],
+  //   [signalProviderAccount],
   //   payerAccount,
   //   createPoolTxInstructions
   // );
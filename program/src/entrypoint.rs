@@ -30,9 +30,25 @@ impl PrintProgramError for BonfidaBotError {
         match self {
             BonfidaBotError::InvalidInstruction => msg!("Error: Invalid instruction!"),
             BonfidaBotError::Overflow => msg!("Error: Arithmetic operation overflow!"),
-            BonfidaBotError::LockedOperation => msg!("Error: Operation is locked in the current pool state!"),
-            BonfidaBotError::NotEnoughFIDA => msg!("Error: Pool must contain a minimum amount of FIDA tokens"),
-            BonfidaBotError::OperationTooSmall => msg!("Error: Operation was too small")
+            BonfidaBotError::LockedOperation => {
+                msg!("Error: Operation is locked in the current pool state!")
+            }
+            BonfidaBotError::NotEnoughFIDA => {
+                msg!("Error: Pool must contain a minimum amount of FIDA tokens")
+            }
+            BonfidaBotError::OperationTooSmall => msg!("Error: Operation was too small"),
+            BonfidaBotError::MaxAmountInExceeded => msg!("Error: Maximum amount in exceeded"),
+            BonfidaBotError::MinPoolTokensOutNotMet => {
+                msg!("Error: Minimum pool tokens out not met")
+            }
+            BonfidaBotError::MinAmountOutNotMet => msg!("Error: Minimum amount out not met"),
+            BonfidaBotError::OrderNotFilled => msg!("Error: Order was not entirely filled"),
+            BonfidaBotError::RedeemQueueFull => msg!("Error: Redeem queue is full"),
+            BonfidaBotError::DepositCapExceeded => msg!("Error: Pool deposit cap exceeded"),
+            BonfidaBotError::DepositTooSmall => msg!("Error: Deposit below the pool minimum"),
+            BonfidaBotError::DepositorNotAllowed => {
+                msg!("Error: Depositor not allowed in private pool")
+            }
         }
     }
 }
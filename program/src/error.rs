@@ -16,6 +16,24 @@ pub enum BonfidaBotError {
     NotEnoughFIDA,
     #[error("Operation too small.")]
     OperationTooSmall,
+    #[error("Deposit would take more than the maximum amount of an asset.")]
+    MaxAmountInExceeded,
+    #[error("Deposit would mint less than the minimum amount of pooltokens.")]
+    MinPoolTokensOutNotMet,
+    #[error("Redeem would pay out less than the minimum amount of an asset.")]
+    MinAmountOutNotMet,
+    #[error("An order could not be entirely filled.")]
+    OrderNotFilled,
+    #[error("The pool's redeem queue is full.")]
+    RedeemQueueFull,
+    #[error("Deposit would bring the pool above its maximum pooltoken supply.")]
+    DepositCapExceeded,
+    #[error("Deposit is below the pool's minimum deposit.")]
+    DepositTooSmall,
+    #[error("Depositor is not on the private pool's whitelist.")]
+    DepositorNotAllowed,
+    #[error("The market's bid-ask spread is too wide to value the pool.")]
+    SpreadTooWide,
 }
 
 impl From<BonfidaBotError> for ProgramError {
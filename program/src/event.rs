@@ -0,0 +1,203 @@
+use std::convert::TryInto;
+
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use solana_program::{msg, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::state::{pack_pool_status, unpack_pool_status, PoolStatus};
+
+/// Prefix of the program log lines carrying a base64 encoded PoolEvent
+pub const EVENT_LOG_PREFIX: &str = "Pool event: ";
+/// Version of the PoolEvent encoding, incremented on any change to the schema
+pub const EVENT_VERSION: u8 = 1;
+
+/// Instruction handler which emitted a PoolEvent
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, FromPrimitive, PartialEq)]
+pub enum EventKind {
+    Init = 0,
+    Create = 1,
+    Deposit = 2,
+    DepositSingleAsset = 3,
+    CreateOrder = 4,
+    SettleFunds = 5,
+    CancelOrder = 6,
+    CancelOrderByClientId = 7,
+    CancelAllOrders = 8,
+    Redeem = 9,
+    SettleAndRedeem = 10,
+    RequestRedeem = 11,
+    ProcessRedeemQueue = 12,
+    CancelRedeemRequest = 13,
+    ReleaseDeposit = 14,
+    RedeemToSingleAsset = 15,
+    CollectFees = 16,
+    Lock = 17,
+    Unlock = 18,
+    SetDepositLimits = 19,
+    SetWhitelist = 20,
+    RemoveWhitelist = 21,
+    ProposeSignalProvider = 22,
+    AcceptSignalProvider = 23,
+    ProposeMarketChange = 24,
+    ExecuteMarketChange = 25,
+    ClosePool = 26,
+    MigratePool = 27,
+    SetMetadata = 28,
+    InitOpenOrders = 29,
+    ResizePool = 30,
+}
+
+/// State change of a pool, logged by each of the pool's instructions. The encoding is:
+///
+///   0. The EVENT_VERSION
+///   1. The EventKind
+///   2..34. The pool key
+///   34..66. The key of the account which triggered the change
+///   66. The pool status after the change, packed as in the pool header
+///   67. 1 if the pooltoken supply follows, 0 otherwise
+///   68..76. The pooltoken supply before the change
+///   76..84. The pooltoken supply after the change
+///   84..86. The number N of amounts
+///   86..8N+86. The amounts
+#[derive(Clone, Debug, PartialEq)]
+pub struct PoolEvent {
+    pub kind: EventKind,
+    pub pool: Pubkey,
+    pub actor: Pubkey,
+    /// Token amounts moved by the instruction, in the order given by its handler: the pool's
+    /// assets for deposits and redemptions, the order's size for orders, the fee shares for
+    /// fee collections.
+    pub amounts: Vec<u64>,
+    /// Pooltoken supply before and after the instruction, None for the instructions which don't
+    /// take the pooltoken mint account.
+    pub pool_token_supply: Option<(u64, u64)>,
+    pub status: PoolStatus,
+}
+
+const EVENT_AMOUNTS_OFFSET: usize = 86;
+
+impl PoolEvent {
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(EVENT_AMOUNTS_OFFSET + 8 * self.amounts.len());
+        buf.push(EVENT_VERSION);
+        buf.push(self.kind as u8);
+        buf.extend_from_slice(&self.pool.to_bytes());
+        buf.extend_from_slice(&self.actor.to_bytes());
+        buf.push(pack_pool_status(&self.status));
+        let (supply_before, supply_after) = self.pool_token_supply.unwrap_or((0, 0));
+        buf.push(self.pool_token_supply.is_some() as u8);
+        buf.extend_from_slice(&supply_before.to_le_bytes());
+        buf.extend_from_slice(&supply_after.to_le_bytes());
+        buf.extend_from_slice(&(self.amounts.len() as u16).to_le_bytes());
+        for amount in self.amounts.iter() {
+            buf.extend_from_slice(&amount.to_le_bytes());
+        }
+        buf
+    }
+
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() < EVENT_AMOUNTS_OFFSET || input[0] != EVENT_VERSION {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let kind = EventKind::from_u8(input[1]).ok_or(ProgramError::InvalidArgument)?;
+        let pool = Pubkey::new(&input[2..34]);
+        let actor = Pubkey::new(&input[34..66]);
+        let status = unpack_pool_status(input[66])?;
+        let pool_token_supply = match input[67] {
+            0 => None,
+            1 => Some((
+                u64::from_le_bytes(input[68..76].try_into().unwrap()),
+                u64::from_le_bytes(input[76..84].try_into().unwrap()),
+            )),
+            _ => return Err(ProgramError::InvalidArgument),
+        };
+        let number_of_amounts = u16::from_le_bytes(input[84..86].try_into().unwrap()) as usize;
+        if input.len() != EVENT_AMOUNTS_OFFSET + 8 * number_of_amounts {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let amounts = input[EVENT_AMOUNTS_OFFSET..]
+            .chunks_exact(8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+        Ok(Self {
+            kind,
+            pool,
+            actor,
+            amounts,
+            pool_token_supply,
+            status,
+        })
+    }
+
+    pub fn emit(&self) {
+        msg!("{}{}", EVENT_LOG_PREFIX, base64::encode(self.pack()));
+    }
+
+    /// Decodes the event carried by a line of a transaction's log messages, None when the line
+    /// isn't a pool event
+    pub fn from_log(log: &str) -> Option<Self> {
+        let log = log.strip_prefix("Program log: ").unwrap_or(log);
+        let data = base64::decode(log.strip_prefix(EVENT_LOG_PREFIX)?).ok()?;
+        Self::unpack(&data).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU8;
+
+    use super::{EventKind, PoolEvent, EVENT_LOG_PREFIX};
+    use crate::state::PoolStatus;
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn test_event_packing() {
+        let event = PoolEvent {
+            kind: EventKind::Redeem,
+            pool: Pubkey::new_unique(),
+            actor: Pubkey::new_unique(),
+            amounts: vec![1_000, 0, u64::MAX],
+            pool_token_supply: Some((2_000_000, 1_500_000)),
+            status: PoolStatus::LockedPendingOrder(NonZeroU8::new(3).unwrap()),
+        };
+        let packed_event = event.pack();
+        assert_eq!(event, PoolEvent::unpack(&packed_event).unwrap());
+        assert!(PoolEvent::unpack(&packed_event[..packed_event.len() - 1]).is_err());
+
+        let event = PoolEvent {
+            kind: EventKind::Lock,
+            amounts: vec![],
+            pool_token_supply: None,
+            status: PoolStatus::Locked,
+            ..event
+        };
+        assert_eq!(event, PoolEvent::unpack(&event.pack()).unwrap());
+    }
+
+    #[test]
+    fn test_event_from_log() {
+        let event = PoolEvent {
+            kind: EventKind::Deposit,
+            pool: Pubkey::new_unique(),
+            actor: Pubkey::new_unique(),
+            amounts: vec![10, 20],
+            pool_token_supply: Some((1_000_000, 1_000_100)),
+            status: PoolStatus::Unlocked,
+        };
+        let log = format!(
+            "Program log: {}{}",
+            EVENT_LOG_PREFIX,
+            base64::encode(event.pack())
+        );
+        assert_eq!(Some(event), PoolEvent::from_log(&log));
+        assert_eq!(
+            None,
+            PoolEvent::from_log("Program log: Instruction: Deposit")
+        );
+        assert_eq!(
+            None,
+            PoolEvent::from_log("Program log: Pool event: not base64!")
+        );
+    }
+}
@@ -1,5 +1,9 @@
 use crate::error::BonfidaBotError;
-use crate::state::{BONFIDA_BNB, BONFIDA_FEE};
+use crate::state::FeeRecipient;
+use crate::utils::{
+    get_deposit_receipt_key, get_metadata_key, get_open_orders_key, get_redeem_queue_key,
+    get_registry_key, get_whitelist_key,
+};
 use serum_dex::{
     instruction::SelfTradeBehavior,
     matching::{OrderType, Side},
@@ -15,13 +19,13 @@ use std::{
     convert::TryInto,
     mem::size_of,
     num::{NonZeroU16, NonZeroU64},
-    str::FromStr,
 };
 
 #[repr(C)]
 #[derive(Clone, Debug, PartialEq)]
 pub enum PoolInstruction {
-    /// Initializes an empty pool account for the bonfida-bot program
+    /// Initializes an empty pool account for the bonfida-bot program. The fee payer is
+    /// recorded as the pool's creator. The pooltoken mint of a closed pool is reused.
     ///
     /// Accounts expected by this instruction:
     ///
@@ -51,19 +55,41 @@ pub enum PoolInstruction {
     ///   * Single owner
     ///   0. `[]` The spl-token program account
     ///   1. `[]` The clock sysvar account
-    ///   1. `[]` The serum program account
-    ///   1. `[]` The signal provider account
-    ///   2. `[writable]` The pooltoken mint account
-    ///   3. `[writable]` The target account that receives the pooltokens
-    ///   4. `[writable]` The pool account
-    ///   5..M+5. `[writable]` The M pool (associated) token assets accounts in the order of the
+    ///   2. `[]` The serum program account
+    ///   3. `[signer]` The signal provider account
+    ///   4. `[writable]` The pooltoken mint account
+    ///   5. `[writable]` The target account that receives the pooltokens
+    ///   6. `[writable]` The pool account
+    ///   7..M+7. `[writable]` The M pool (associated) token assets accounts in the order of the
     ///      corresponding PoolAssets in the pool account data.
-    ///   M+5. `[signer]` The source owner account
-    ///   M+6..2M+6. `[writable]` The M source token accounts in the same order as above
+    ///   M+7. `[signer]` The source owner account
+    ///   M+8..2M+8. `[writable]` The M source token accounts in the same order as above
+    ///   2M+8..2M+K+8. `[]` The K serum market accounts in the order of the given markets. Each
+    ///      market has to trade at least one of the deposited assets.
+    ///   2M+K+8. `[]` The system program account
+    ///   2M+K+9. `[]` The rent sysvar account
+    ///   2M+K+10. `[writable, signer]` The fee payer account, paying for the registry account
+    ///   2M+K+11. `[writable]` The signal provider's pool registry account, to which the pool is
+    ///      added. Created on first use.
     Create {
         pool_seed: [u8; 32],
         fee_collection_period: u64,
         fee_ratio: u16,
+        // The amount of time in seconds after which anyone can cancel the pool's pending orders
+        max_order_lifetime: u64,
+        // The delay in seconds before a proposed change to the pool can take effect
+        timelock_period: u64,
+        // The share of gains above the high-water mark collected as fees, out of 2**16
+        performance_fee_ratio: u16,
+        // The share of redeemed pooltokens collected as fees, out of 2**16
+        redeem_fee_ratio: u16,
+        // The amount of time in seconds deposited pooltokens are escrowed, zero for no lockup.
+        // At most MAX_LOCKUP_PERIOD.
+        lockup_period: u64,
+        // The maximum supply of pooltokens deposits can bring the pool to, zero for no cap
+        max_pool_token_supply: u64,
+        // The minimum amount of pooltokens a deposit has to buy, fees included
+        min_deposit_pool_tokens: u64,
         deposit_amounts: Vec<u64>,
         markets: Vec<Pubkey>,
     },
@@ -80,20 +106,41 @@ pub enum PoolInstruction {
     ///   1. `[writable]` The pooltoken mint account
     ///   2. `[writable]` The target account that receives the pooltokens
     ///   3. `[writable]` The signal provider account that receives the pooltoken fees
-    ///   4. `[writable]` The Bonfida fee account that receives the pooltoken fees
-    ///   5. `[writable]` The Bonfida buy and burn account that receives the pooltoken fees
-    ///   6. `[]` The pool account
-    ///   7..M+7. `[writable]` The M pool (associated) token assets accounts in the order of the
+    ///   4. `[]` The program config account
+    ///   5. `[]` The pool account
+    ///   6..M+6. `[writable]` The M pool (associated) token assets accounts in the order of the
     ///      corresponding PoolAssets in the pool account data.
-    ///   M+7. `[signer]` The source owner account
-    ///   M+8..2M+8. `[writable]` The M source token accounts in the same order as above
+    ///   M+6. `[signer]` The source owner account
+    ///   M+7..2M+7. `[writable]` The M source token accounts in the same order as above
+    ///   2M+7..2M+R+7. `[writable]` The R fee recipient (associated) pooltoken accounts in the
+    ///      order of the fee recipients in the program config.
+    ///   * Only if the pool is private:
+    ///     `[]` The pool's whitelist account, followed, if the whitelist has a gating mint, by
+    ///     `[]` the source owner's token account for this mint.
+    ///   * Only if the pool has a lockup period, in which case the pooltokens are escrowed by the
+    ///     pool instead of being credited to the target:
+    ///     `[]` The system program account, `[]` the rent sysvar account, `[]` the clock sysvar
+    ///     account, `[writable, signer]` the fee payer for the source owner's deposit receipt,
+    ///     `[writable]` the source owner's deposit receipt account and `[writable]` the pool's
+    ///     associated pooltoken account, used as escrow.
     Deposit {
         pool_seed: [u8; 32],
         // The amount of pool token the source wishes to buy
         pool_token_amount: u64,
+        // The minimum amount of pool token the target should receive, after fees
+        min_pool_tokens_out: u64,
+        // The maximum amount of each pool asset to take from the source, in the order of
+        // the PoolAssets in the pool account data
+        max_amounts_in: Vec<u64>,
     },
+    /// Deprecated, see CreateOrderV2. The given lot sizes and target mint have to match the
+    /// market's.
+    ///
     /// As a signal provider, create a new serum order for the pool.
     /// Amounts are translated into proportions of the pool between 0 and 2**16 - 1
+    /// Resting orders (Limit and PostOnly) are allowed, but once the pool has had pending
+    /// orders for longer than its max_order_lifetime, no new orders can be created until
+    /// all pending orders have been cancelled and settled.
     ///
     /// Accounts expected by this instruction:
     ///
@@ -101,7 +148,7 @@ pub enum PoolInstruction {
     ///    0. `[signer]` The signal provider account
     ///    1. `[writable]` The market account
     ///    2. `[writable]` The payer pool asset account
-    ///    3. `[writable]` The relevant OpenOrders account
+    ///    3. `[writable]` The pool's OpenOrders account for this market, see InitOpenOrders
     ///    4. `[writable]` The Serum event queue
     ///    5. `[writable]` The Serum request queue
     ///    6. `[writable]` The Serum market bids
@@ -111,8 +158,9 @@ pub enum PoolInstruction {
     ///   10. `[writable]` The price currency vault
     ///   11. `[]` The spl_token_program
     ///   12. `[]` The rent sysvar account
-    ///   13. `[]` The dex program account
-    ///   14. `[writable]` (optional) The (M)SRM discount account
+    ///   13. `[]` The clock sysvar account
+    ///   14. `[]` The dex program account
+    ///   15. `[writable]` (optional) The (M)SRM discount account
     CreateOrder {
         pool_seed: [u8; 32],
         side: Side,
@@ -129,19 +177,43 @@ pub enum PoolInstruction {
         target_mint: Pubkey,
         serum_limit: u16
     },
+    /// As a signal provider, create a new serum order for the pool. The lot sizes and the mint
+    /// of the target asset are read from the market account.
+    /// Amounts are translated into proportions of the pool between 0 and 2**16 - 1
+    /// Resting orders (Limit and PostOnly) are allowed, but once the pool has had pending
+    /// orders for longer than its max_order_lifetime, no new orders can be created until
+    /// all pending orders have been cancelled and settled.
+    ///
+    /// Accounts expected by this instruction: the same as CreateOrder
+    CreateOrderV2 {
+        pool_seed: [u8; 32],
+        side: Side,
+        limit_price: NonZeroU64,
+        ratio_of_pool_assets_to_trade: NonZeroU16,
+        order_type: OrderType,
+        client_id: u64,
+        self_trade_behavior: SelfTradeBehavior,
+        source_index: u64,
+        target_index: u64,
+        market_index: u16,
+        serum_limit: u16,
+    },
     /// As a signal provider, cancel a serum order for the pool.
+    /// Once the pool's pending orders have outlived its max_order_lifetime, anyone can
+    /// cancel them so that they can be settled and the pool freed for redemptions.
     ///
     /// Accounts expected by this instruction:
     ///
     ///   * Single owner
-    ///    0. `[signer]` The signal provider account
+    ///    0. `[signer]` The signal provider account, or any account once orders have expired
     ///    1. `[]` The market account
-    ///    2. `[writable]` The relevant OpenOrders account
+    ///    2. `[writable]` The pool's OpenOrders account for this market
     ///    3. `[writable]` The Serum market bids
     ///    4. `[writable]` The Serum market asks
     ///    5. `[writable]` The Serum event queue
     ///    6. `[]` The pool account
     ///    7. `[]` The dex program account
+    ///    8. `[]` The clock sysvar account
     CancelOrder {
         pool_seed: [u8; 32],
         side: Side,
@@ -168,9 +240,11 @@ pub enum PoolInstruction {
         pc_index: u64,
         coin_index: u64,
     },
-    /// Buy out of the pool by redeeming pooltokens.
-    /// This instruction needs to be executed after (and within the same transaction)
-    /// having settled on all possible open orders for the pool.
+    /// Buy out of the pool by redeeming pooltokens. When the pool charges a redeem fee, it is
+    /// transferred out of the redeemed pooltokens to the signal provider and the fee recipients.
+    /// While the pool has pending orders, the redeemed share also covers the funds held by the
+    /// pool's OpenOrders accounts. Their free funds are settled, and just enough of the pool's
+    /// resting orders are cancelled to pay out the locked part of the share.
     ///
     /// Accounts expected by this instruction:
     ///
@@ -180,28 +254,524 @@ pub enum PoolInstruction {
     ///   2. `[writable]` The pooltoken mint account
     ///   3. `[signer]` The pooltoken source account owner
     ///   4. `[writable]` The pooltoken source account
-    ///   5. `[]` The pool account
+    ///   5. `[writable]` The pool account
     ///   6..M+6. `[writable]` The M pool (associated) token assets accounts in the order of the
     ///      corresponding PoolAssets found in the pool account data.
     ///   M+7..2M+7. `[writable]` The M target token accounts in the same order as above
+    ///   * Only if the pool charges a redeem fee, the accounts receiving it:
+    ///      `[writable]` The signal provider (associated) pooltoken account, `[]` the program config
+    ///      account and `[writable]` the R fee recipient (associated) pooltoken accounts in the
+    ///      order of the fee recipients in the program config.
+    ///   * Only if the pool has pending orders, after the above
+    ///   `[]` The dex program account
+    ///   For each pending OpenOrders account of the pool, in market order, the 8 accounts:
+    ///      `[writable]` market, `[writable]` OpenOrders, `[writable]` bids, `[writable]` asks,
+    ///      `[writable]` event queue, `[writable]` coin vault, `[writable]` pc vault,
+    ///      `[]` vault signer
     Redeem {
         pool_seed: [u8; 32],
         // The amount of pool token the source wishes to redeem
         pool_token_amount: u64,
+        // The minimum amount of each pool asset the targets should receive, in the order of
+        // the PoolAssets in the pool account data
+        min_amounts_out: Vec<u64>,
     },
-    /// Trigger signal provider and Bonfida fee collection
+    /// Trigger signal provider and fee recipients fee collection
     ///
     /// Accounts expected by this instruction:
     ///
     ///   * Single owner
     ///   0. `[]` The spl-token program account
     ///   1. `[]` The clock sysvar account
-    ///   5. `[writable]` The pool account
-    ///   2. `[writable]` The pooltoken mint account
-    ///   3. `[writable]` The signal provider account that receives the pooltoken fees
-    ///   4. `[writable]` The Bonfida fee account that receives the pooltoken fees
-    ///   5. `[writable]` The Bonfida buy and burn account that receives the pooltoken fees
+    ///   2. `[writable]` The pool account
+    ///   3. `[writable]` The pooltoken mint account
+    ///   4. `[writable]` The signal provider account that receives the pooltoken fees
+    ///   5. `[]` The program config account
+    ///   6..R+6. `[writable]` The R fee recipient (associated) pooltoken accounts in the
+    ///      order of the fee recipients in the program config.
+    ///
+    ///   * When the pool charges a performance fee, the pool is valued in the price currency
+    ///     of its first market using the mid prices of its authorized markets
+    ///   R+6..M+R+6. `[]` The M pool (associated) token assets accounts in the order of the
+    ///      corresponding PoolAssets in the pool account data.
+    ///   M+R+6. `[]` The pool's first market account
+    ///   M+R+7.. `[]` For each pool asset which isn't the price currency, in the same order as
+    ///      above, an authorized market account with this asset as coin followed by its bids
+    ///      and asks accounts.
     CollectFees { pool_seed: [u8; 32] },
+    /// As a signal provider, lock the pool. A locked pool refuses buy-ins and redemptions
+    /// until it is unlocked.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[signer]` The signal provider account
+    ///   1. `[writable]` The pool account
+    Lock { pool_seed: [u8; 32] },
+    /// Unlock a locked pool. This instruction is permissionless so that anyone can always
+    /// regain access to their funds or buy in.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The pool account
+    Unlock { pool_seed: [u8; 32] },
+    /// As a signal provider, propose a new signal provider for the pool. The proposal
+    /// can only be accepted once the pool's timelock period has elapsed, leaving investors
+    /// time to redeem. Proposing the default pubkey cancels a pending proposal.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[signer]` The signal provider account
+    ///   1. `[]` The clock sysvar account
+    ///   2. `[writable]` The pool account
+    ProposeSignalProvider {
+        pool_seed: [u8; 32],
+        new_signal_provider: Pubkey,
+    },
+    /// As the proposed signal provider, take over the pool once the timelock period has elapsed.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[signer]` The proposed signal provider account
+    ///   1. `[]` The clock sysvar account
+    ///   2. `[writable]` The pool account
+    ///   3. `[]` The system program account
+    ///   4. `[]` The rent sysvar account
+    ///   5. `[writable, signer]` The fee payer account, paying for the new registry account
+    ///   6. `[writable]` The current signal provider's pool registry account
+    ///   7. `[writable]` The proposed signal provider's pool registry account, created on first
+    ///      use
+    AcceptSignalProvider { pool_seed: [u8; 32] },
+    /// Create the program config account which defines how pooltoken fees are split between
+    /// the signal provider and the fee recipients. Can only be executed by the program's
+    /// upgrade authority.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[]` The system program account
+    ///   1. `[]` The sysvar rent program account
+    ///   2. `[writable]` The program config account
+    ///   3. `[]` The program's ProgramData account
+    ///   4. `[signer]` The program's upgrade authority
+    ///   5. `[writable, signer]` The fee payer account
+    CreateConfig {
+        // The signal provider's share of the fees, relative to the sum of all shares
+        signal_provider_share: u16,
+        fee_recipients: Vec<FeeRecipient>,
+    },
+    /// Buy into the pool with a single token. The source tokens are swapped into each of the
+    /// other pool assets through the pool's authorized markets, using Immediate-or-Cancel bids
+    /// from the source owner's OpenOrders accounts which are settled in the same instruction.
+    /// The source token has to be the price currency of every market used. The instruction
+    /// fails if any of the orders isn't entirely filled.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The spl-token program account
+    ///   1. `[]` The rent sysvar account
+    ///   2. `[]` The dex program account
+    ///   3. `[writable]` The pooltoken mint account
+    ///   4. `[writable]` The target account that receives the pooltokens
+    ///   5. `[writable]` The signal provider account that receives the pooltoken fees
+    ///   6. `[]` The program config account
+    ///   7. `[]` The pool account
+    ///   8..M+8. `[writable]` The M pool (associated) token assets accounts in the order of the
+    ///      corresponding PoolAssets in the pool account data.
+    ///   M+8. `[signer]` The source owner account
+    ///   M+9. `[writable]` The source token account
+    ///   M+10.. `[writable]` For each pool asset which isn't the source token, in the same order
+    ///      as above, the 10 accounts needed to buy it:
+    ///        - The market account
+    ///        - The source owner's OpenOrders account for this market
+    ///        - The Serum request queue
+    ///        - The Serum event queue
+    ///        - The Serum market bids
+    ///        - The Serum market asks
+    ///        - The coin vault
+    ///        - The price currency vault
+    ///        - `[]` The vault signer
+    ///        - The source owner's token account which receives the bought asset
+    ///   followed by the R fee recipient (associated) pooltoken accounts in the order of the
+    ///   fee recipients in the program config.
+    ///   * Only if the pool is private:
+    ///     `[]` The pool's whitelist account, followed, if the whitelist has a gating mint, by
+    ///     `[]` the source owner's token account for this mint.
+    ///   * Only if the pool has a lockup period, in which case the pooltokens are escrowed by the
+    ///     pool instead of being credited to the target:
+    ///     `[]` The system program account, `[]` the rent sysvar account, `[]` the clock sysvar
+    ///     account, `[writable, signer]` the fee payer for the source owner's deposit receipt,
+    ///     `[writable]` the source owner's deposit receipt account and `[writable]` the pool's
+    ///     associated pooltoken account, used as escrow.
+    DepositSingleAsset {
+        pool_seed: [u8; 32],
+        // The amount of pool token the source wishes to buy
+        pool_token_amount: u64,
+        // The minimum amount of pool token the target should receive, after fees
+        min_pool_tokens_out: u64,
+        // The highest price to buy each pool asset at, in the order of the PoolAssets in the
+        // pool account data. The price for the source token is ignored.
+        limit_prices: Vec<u64>,
+    },
+    /// Buy out of the pool into a single token. The redeemer's share of each of the other pool
+    /// assets is sold through the pool's authorized markets, using Immediate-or-Cancel asks
+    /// from the redeemer's OpenOrders accounts which are settled in the same instruction.
+    /// The target token has to be the price currency of every market used. The instruction
+    /// fails if any of the orders isn't entirely filled. Less than a coin lot of each sold
+    /// asset can remain in the redeemer's token account for that asset.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The spl-token program account
+    ///   1. `[]` The clock sysvar account
+    ///   2. `[]` The rent sysvar account
+    ///   3. `[]` The dex program account
+    ///   4. `[writable]` The pooltoken mint account
+    ///   5. `[signer]` The pooltoken source account owner
+    ///   6. `[writable]` The pooltoken source account
+    ///   7. `[writable]` The pool account
+    ///   8..M+8. `[writable]` The M pool (associated) token assets accounts in the order of the
+    ///      corresponding PoolAssets in the pool account data.
+    ///   M+8. `[writable]` The target token account
+    ///   * Only if the pool charges a redeem fee, the accounts receiving it:
+    ///      `[writable]` The signal provider (associated) pooltoken account, `[]` the program config
+    ///      account and `[writable]` the R fee recipient (associated) pooltoken accounts in the
+    ///      order of the fee recipients in the program config.
+    ///   * Then, `[writable]` for each pool asset which isn't the target token, in the same order
+    ///      as above, the 10 accounts needed to sell it:
+    ///        - The market account
+    ///        - The redeemer's OpenOrders account for this market
+    ///        - The Serum request queue
+    ///        - The Serum event queue
+    ///        - The Serum market bids
+    ///        - The Serum market asks
+    ///        - The coin vault
+    ///        - The price currency vault
+    ///        - `[]` The vault signer
+    ///        - The redeemer's token account which receives the asset share before selling it
+    RedeemToSingleAsset {
+        pool_seed: [u8; 32],
+        // The amount of pool token the source wishes to redeem
+        pool_token_amount: u64,
+        // The minimum amount of target token that should be received
+        min_amount_out: u64,
+        // The lowest price to sell each pool asset at, in the order of the PoolAssets in the
+        // pool account data. The price for the target token is ignored.
+        limit_prices: Vec<u64>,
+    },
+    /// Create the pool's OpenOrders account for one of its markets and record it in the pool
+    /// account data. The account is derived from the pool seed, the OPEN_ORDERS_SEED and the
+    /// market index, and is the only one the pool can trade this market from. Anyone can pay
+    /// for its creation.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[]` The system program account
+    ///   1. `[]` The rent sysvar account
+    ///   2. `[writable, signer]` The fee payer account
+    ///   3. `[writable]` The pool account
+    ///   4. `[writable]` The OpenOrders account to create
+    InitOpenOrders {
+        pool_seed: [u8; 32],
+        market_index: u16,
+    },
+    /// As a signal provider, cancel a serum order for the pool using the client_id it was
+    /// created with. As for CancelOrder, anyone can cancel once the pool's pending orders have
+    /// outlived its max_order_lifetime.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///    0. `[signer]` The signal provider account, or any account once orders have expired
+    ///    1. `[]` The market account
+    ///    2. `[writable]` The pool's OpenOrders account for this market
+    ///    3. `[writable]` The Serum market bids
+    ///    4. `[writable]` The Serum market asks
+    ///    5. `[writable]` The Serum event queue
+    ///    6. `[]` The pool account
+    ///    7. `[]` The dex program account
+    ///    8. `[]` The clock sysvar account
+    CancelOrderByClientId { pool_seed: [u8; 32], client_id: u64 },
+    /// As a signal provider, cancel every order held by the pool's OpenOrders account for a
+    /// market. As for CancelOrder, anyone can cancel once the pool's pending orders have
+    /// outlived its max_order_lifetime.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///    0. `[signer]` The signal provider account, or any account once orders have expired
+    ///    1. `[]` The market account
+    ///    2. `[writable]` The pool's OpenOrders account for this market
+    ///    3. `[writable]` The Serum market bids
+    ///    4. `[writable]` The Serum market asks
+    ///    5. `[writable]` The Serum event queue
+    ///    6. `[]` The pool account
+    ///    7. `[]` The dex program account
+    ///    8. `[]` The clock sysvar account
+    CancelAllOrders { pool_seed: [u8; 32] },
+    /// Buy out of the pool after settling every pending order it holds, so that a redemption
+    /// doesn't need to be preceded by a separate SettleFunds for each market. As with Redeem, only
+    /// the resting orders needed to pay out the redeemed share are cancelled.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The spl-token program account
+    ///   1. `[]` The clock sysvar account
+    ///   2. `[]` The dex program account
+    ///   3. `[writable]` The pooltoken mint account
+    ///   4. `[signer]` The pooltoken source account owner
+    ///   5. `[writable]` The pooltoken source account
+    ///   6. `[writable]` The pool account
+    ///   7..M+7. `[writable]` The M pool (associated) token assets accounts in the order of the
+    ///      corresponding PoolAssets found in the pool account data.
+    ///   M+7..2M+7. `[writable]` The M target token accounts in the same order as above
+    ///   * Only if the pool charges a redeem fee, the accounts receiving it:
+    ///      `[writable]` The signal provider (associated) pooltoken account, `[]` the program config
+    ///      account and `[writable]` the R fee recipient (associated) pooltoken accounts in the
+    ///      order of the fee recipients in the program config.
+    ///   * Then, for each pending OpenOrders account of the pool, in market order, the 8 accounts:
+    ///      `[writable]` market, `[writable]` OpenOrders, `[writable]` bids, `[writable]` asks,
+    ///      `[writable]` event queue, `[writable]` coin vault, `[writable]` pc vault,
+    ///      `[]` vault signer
+    SettleAndRedeem {
+        pool_seed: [u8; 32],
+        // The amount of pool token the source wishes to redeem
+        pool_token_amount: u64,
+        // The minimum amount of each pool asset the targets should receive, in the order of
+        // the PoolAssets in the pool account data
+        min_amounts_out: Vec<u64>,
+    },
+    /// Queue a redemption to be executed once the pool is unlocked. The pooltokens are escrowed
+    /// by the pool until the request is processed or cancelled. The minimum amounts are stored
+    /// with the request, which can hold them for up to MAX_REDEEM_REQUEST_ASSETS pool assets.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The system program account
+    ///   1. `[]` The spl-token program account
+    ///   2. `[]` The rent sysvar account
+    ///   3. `[signer, writable]` The fee payer account, paying for the queue account
+    ///   4. `[]` The pool account
+    ///   5. `[writable]` The pool's redeem queue account, created on first use
+    ///   6. `[writable]` The pool's associated pooltoken account, used as escrow
+    ///   7. `[signer]` The pooltoken source account owner
+    ///   8. `[writable]` The pooltoken source account
+    RequestRedeem {
+        pool_seed: [u8; 32],
+        pool_token_amount: u64,
+        // The minimum amount of each pool asset the request should pay out, in the order of
+        // the PoolAssets in the pool account data
+        min_amounts_out: Vec<u64>,
+    },
+    /// Redeem the oldest queued requests of an unlocked pool. Anyone can process the queue,
+    /// each request being paid out to the associated token accounts of its owner. The pool's
+    /// redeem fee is taken out of each request. A request which can't be paid out because one
+    /// of these accounts is closed or frozen, or because it would pay out less than its minimum
+    /// amounts, is refunded to its owner's associated pooltoken account instead, which anyone
+    /// can create beforehand.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[]` The spl-token program account
+    ///   1. `[]` The clock sysvar account
+    ///   2. `[writable]` The pooltoken mint account
+    ///   3. `[writable]` The pool account
+    ///   4. `[writable]` The pool's redeem queue account
+    ///   5. `[writable]` The pool's associated pooltoken account, used as escrow
+    ///   6..M+6. `[writable]` The M pool (associated) token assets accounts in the order of the
+    ///      corresponding PoolAssets found in the pool account data.
+    ///   * Only if the pool charges a redeem fee, the accounts receiving it:
+    ///      `[writable]` The signal provider (associated) pooltoken account, `[]` the program config
+    ///      account and `[writable]` the R fee recipient (associated) pooltoken accounts in the
+    ///      order of the fee recipients in the program config.
+    ///   * Then, for each request to process, in queue order, the M associated token accounts of
+    ///      the request owner in the same order as above, followed by its associated pooltoken
+    ///      account
+    ProcessRedeemQueue { pool_seed: [u8; 32] },
+    /// Cancel a queued redeem request, giving back the escrowed pooltokens.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The spl-token program account
+    ///   1. `[]` The pool account
+    ///   2. `[writable]` The pool's redeem queue account
+    ///   3. `[writable]` The pool's associated pooltoken account, used as escrow
+    ///   4. `[signer]` The request owner
+    ///   5. `[writable]` The pooltoken target account
+    CancelRedeemRequest {
+        pool_seed: [u8; 32],
+        // The index of the request in the queue
+        request_index: u64,
+    },
+    /// Release the pooltokens escrowed for a depositor once the pool's lockup period has elapsed
+    /// since their latest deposit. The deposit receipt is closed, its rent going to the
+    /// depositor.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The spl-token program account
+    ///   1. `[]` The clock sysvar account
+    ///   2. `[]` The pool account
+    ///   3. `[writable]` The depositor's deposit receipt account
+    ///   4. `[writable]` The pool's associated pooltoken account, used as escrow
+    ///   5. `[writable, signer]` The depositor account
+    ///   6. `[writable]` The pooltoken target account
+    ReleaseDeposit { pool_seed: [u8; 32] },
+    /// As a signal provider, update the pool's deposit limits. The maximum supply cannot be set
+    /// below the current supply of pooltokens, nor below the minimum deposit.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[signer]` The signal provider account
+    ///   1. `[]` The pooltoken mint account
+    ///   2. `[writable]` The pool account
+    SetDepositLimits {
+        pool_seed: [u8; 32],
+        // The maximum supply of pooltokens deposits can bring the pool to, zero for no cap
+        max_pool_token_supply: u64,
+        // The minimum amount of pooltokens a deposit has to buy, fees included
+        min_deposit_pool_tokens: u64,
+    },
+    /// As a signal provider, make the pool private, or update the investors allowed to buy into
+    /// it. Deposits are then only accepted from the whitelisted owners, and from the holders of
+    /// the gating mint if there is one. Redemptions are not restricted.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[]` The system program account
+    ///   1. `[]` The rent sysvar account
+    ///   2. `[writable, signer]` The fee payer account, paying for the whitelist account
+    ///   3. `[signer]` The signal provider account
+    ///   4. `[writable]` The pool account
+    ///   5. `[writable]` The pool's whitelist account, created on first use
+    SetWhitelist {
+        pool_seed: [u8; 32],
+        // The mint whose holders are allowed to deposit, the default pubkey for none
+        gating_mint: Pubkey,
+        owners: Vec<Pubkey>,
+    },
+    /// As a signal provider, make a private pool public again, closing its whitelist account.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable, signer]` The signal provider account, which receives the rent
+    ///   1. `[writable]` The pool account
+    ///   2. `[writable]` The pool's whitelist account
+    RemoveWhitelist { pool_seed: [u8; 32] },
+    /// As a signal provider, propose to replace the market at the given index, or to authorize
+    /// a new market when the index is equal to the number of markets. The change can only be
+    /// executed once the pool's timelock period has elapsed, leaving investors time to redeem.
+    /// Proposing the default pubkey cancels a pending proposal.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[signer]` The signal provider account
+    ///   1. `[]` The clock sysvar account
+    ///   2. `[writable]` The pool account
+    ProposeMarketChange {
+        pool_seed: [u8; 32],
+        market_index: u16,
+        market: Pubkey,
+    },
+    /// As a signal provider, apply the pending market change once the timelock period has
+    /// elapsed. A new market takes its room from the unused asset slots of the pool account.
+    /// The OpenOrders account of a replaced market is closed, and has to be initialized again
+    /// with InitOpenOrders before trading on the new market.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable, signer]` The signal provider account, which receives the OpenOrders rent
+    ///   1. `[]` The clock sysvar account
+    ///   2. `[]` The serum program account
+    ///   3. `[writable]` The pool account
+    ///   4. `[]` The proposed market account, which has to trade at least one of the pool's assets
+    ///
+    ///   * Only if a market with an initialized OpenOrders account is replaced
+    ///   5. `[]` The replaced market account
+    ///   6. `[writable]` The pool's OpenOrders account for the replaced market
+    ExecuteMarketChange { pool_seed: [u8; 32] },
+    /// Close a created pool which has since been drained of its pooltokens, returning the rent
+    /// of its accounts to the pool's creator. The pool's redeem queue, whitelist and metadata
+    /// accounts are closed along with it. The pooltoken mint can't be closed and is reused if
+    /// the pool seed is initialized again.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[]` The spl-token program account
+    ///   1. `[]` The rent sysvar account
+    ///   2. `[]` The serum program account
+    ///   3. `[writable]` The pool account
+    ///   4. `[]` The pooltoken mint account
+    ///   5. `[writable]` The pool creator's account, which receives the rent
+    ///   6. `[writable]` The signal provider's pool registry account
+    ///   7. `[writable]` The pool's redeem queue account, closed if it exists
+    ///   8. `[writable]` The pool's whitelist account, closed if it exists
+    ///   9. `[writable]` The pool's metadata account, closed if it exists
+    ///   10..2N+10. For each of the N market indices, `[writable]` the pool's OpenOrders account
+    ///      for this index followed by `[]` the market account it was used on.
+    ///   2N+10... `[writable]` The pool's empty token accounts to close
+    ClosePool { pool_seed: [u8; 32] },
+    /// Grow the pool account so that it has room for more assets, the fee payer funding the rent
+    /// of the new asset slots. The account can grow by at most MAX_PERMITTED_DATA_INCREASE bytes
+    /// per instruction.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[]` The system program account
+    ///   1. `[]` The rent sysvar account
+    ///   2. `[writable, signer]` The fee payer account, paying for the new asset slots
+    ///   3. `[writable]` The pool account
+    ResizePool {
+        pool_seed: [u8; 32],
+        // Number of asset slots the pool account is grown to
+        max_number_of_assets: u32,
+    },
+    /// Upgrade a pool account written before the pool header was versioned to the current
+    /// layout. The account is grown to make room for the new header fields, the parameters of
+    /// the pool's markets and its OpenOrders registry, keeping the same number of asset slots.
+    /// The header fields which didn't exist take their default values, and the fee payer is
+    /// recorded as the pool's creator. A pool with pending orders can't be migrated.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[]` The system program account
+    ///   1. `[]` The rent sysvar account
+    ///   2. `[writable, signer]` The fee payer account, paying for the grown pool account. It has
+    ///      to be the signal provider of a created pool.
+    ///   3. `[writable]` The pool account
+    ///   4..N+4. `[]` The accounts of the pool's N markets, in order
+    MigratePool { pool_seed: [u8; 32] },
+    /// As a signal provider, describe the pool for explorers and dashboards. The metadata
+    /// account is created on first use, recording the creation timestamp, and overwritten by
+    /// later calls.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[]` The system program account
+    ///   1. `[]` The rent sysvar account
+    ///   2. `[]` The clock sysvar account
+    ///   3. `[writable, signer]` The fee payer account, paying for the metadata account
+    ///   4. `[signer]` The signal provider account
+    ///   5. `[]` The pool account
+    ///   6. `[writable]` The pool's metadata account, created on first use
+    SetMetadata {
+        pool_seed: [u8; 32],
+        name: String,
+        description: String,
+        // Link to the pool's strategy
+        uri: String,
+    },
 }
 
 impl PoolInstruction {
@@ -250,8 +820,43 @@ impl PoolInstruction {
                     .and_then(|slice| slice.try_into().ok())
                     .map(u16::from_le_bytes)
                     .ok_or(InvalidInstruction)?;
+                let max_order_lifetime = rest
+                    .get(44..52)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                let timelock_period = rest
+                    .get(52..60)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                let performance_fee_ratio = rest
+                    .get(60..62)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u16::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                let redeem_fee_ratio = rest
+                    .get(62..64)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u16::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                let lockup_period = rest
+                    .get(64..72)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                let max_pool_token_supply = rest
+                    .get(72..80)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                let min_deposit_pool_tokens = rest
+                    .get(80..88)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
                 let mut markets = Vec::with_capacity(number_of_markets as usize);
-                let mut offset = 44;
+                let mut offset = 88;
                 for _ in 0..number_of_markets {
                     markets.push(
                         rest.get(offset..offset + 32)
@@ -278,6 +883,13 @@ impl PoolInstruction {
                     deposit_amounts,
                     fee_collection_period,
                     fee_ratio,
+                    max_order_lifetime,
+                    timelock_period,
+                    performance_fee_ratio,
+                    redeem_fee_ratio,
+                    lockup_period,
+                    max_pool_token_supply,
+                    min_deposit_pool_tokens,
                 }
             }
             2 => {
@@ -290,12 +902,20 @@ impl PoolInstruction {
                     .and_then(|slice| slice.try_into().ok())
                     .map(u64::from_le_bytes)
                     .ok_or(InvalidInstruction)?;
+                let min_pool_tokens_out = rest
+                    .get(40..48)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                let max_amounts_in = unpack_amounts(&rest[48..])?;
                 Self::Deposit {
                     pool_seed,
                     pool_token_amount,
+                    min_pool_tokens_out,
+                    max_amounts_in,
                 }
             }
-            3 => {
+            3 | 28 => {
                 let pool_seed: [u8; 32] = rest
                     .get(..32)
                     .and_then(|slice| slice.try_into().ok())
@@ -352,6 +972,26 @@ impl PoolInstruction {
                     .and_then(|slice| slice.try_into().ok())
                     .map(u16::from_le_bytes)
                     .ok_or(InvalidInstruction)?;
+                if tag == 28 {
+                    let serum_limit = rest
+                        .get(71..73)
+                        .and_then(|slice| slice.try_into().ok())
+                        .map(u16::from_le_bytes)
+                        .ok_or(InvalidInstruction)?;
+                    return Ok(Self::CreateOrderV2 {
+                        pool_seed,
+                        side,
+                        limit_price,
+                        ratio_of_pool_assets_to_trade,
+                        order_type,
+                        client_id,
+                        self_trade_behavior,
+                        source_index,
+                        target_index,
+                        market_index,
+                        serum_limit,
+                    });
+                }
                 let coin_lot_size = rest
                     .get(71..79)
                     .and_then(|slice| slice.try_into().ok())
@@ -442,9 +1082,11 @@ impl PoolInstruction {
                     .and_then(|slice| slice.try_into().ok())
                     .map(u64::from_le_bytes)
                     .ok_or(InvalidInstruction)?;
+                let min_amounts_out = unpack_amounts(&rest[40..])?;
                 Self::Redeem {
                     pool_seed,
                     pool_token_amount,
+                    min_amounts_out,
                 }
             }
             7 => {
@@ -454,20 +1096,359 @@ impl PoolInstruction {
                     .unwrap();
                 Self::CollectFees { pool_seed }
             }
-            _ => {
-                msg!("Unsupported tag");
-                return Err(InvalidInstruction.into());
+            8 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                Self::Lock { pool_seed }
             }
-        })
-    }
-
-    pub fn pack(&self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(size_of::<Self>());
-        match self {
-            Self::Init {
-                pool_seed,
-                max_number_of_assets,
-                number_of_markets,
+            9 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                Self::Unlock { pool_seed }
+            }
+            10 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let new_signal_provider = rest
+                    .get(32..64)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(Pubkey::new)
+                    .ok_or(InvalidInstruction)?;
+                Self::ProposeSignalProvider {
+                    pool_seed,
+                    new_signal_provider,
+                }
+            }
+            11 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                Self::AcceptSignalProvider { pool_seed }
+            }
+            12 => {
+                let signal_provider_share = rest
+                    .get(..2)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u16::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                let mut fee_recipients = vec![];
+                let mut offset = 2;
+                while offset < rest.len() {
+                    let owner = rest
+                        .get(offset..offset + 32)
+                        .and_then(|slice| slice.try_into().ok())
+                        .map(Pubkey::new)
+                        .ok_or(InvalidInstruction)?;
+                    let share = rest
+                        .get(offset + 32..offset + 34)
+                        .and_then(|slice| slice.try_into().ok())
+                        .map(u16::from_le_bytes)
+                        .ok_or(InvalidInstruction)?;
+                    fee_recipients.push(FeeRecipient { owner, share });
+                    offset += 34;
+                }
+                Self::CreateConfig {
+                    signal_provider_share,
+                    fee_recipients,
+                }
+            }
+            13 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let pool_token_amount = rest
+                    .get(32..40)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                let min_pool_tokens_out = rest
+                    .get(40..48)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                let limit_prices = unpack_amounts(&rest[48..])?;
+                Self::DepositSingleAsset {
+                    pool_seed,
+                    pool_token_amount,
+                    min_pool_tokens_out,
+                    limit_prices,
+                }
+            }
+            14 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let pool_token_amount = rest
+                    .get(32..40)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                let min_amount_out = rest
+                    .get(40..48)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                let limit_prices = unpack_amounts(&rest[48..])?;
+                Self::RedeemToSingleAsset {
+                    pool_seed,
+                    pool_token_amount,
+                    min_amount_out,
+                    limit_prices,
+                }
+            }
+            15 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let market_index = rest
+                    .get(32..34)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u16::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                Self::InitOpenOrders {
+                    pool_seed,
+                    market_index,
+                }
+            }
+            16 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let client_id = rest
+                    .get(32..40)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                Self::CancelOrderByClientId {
+                    pool_seed,
+                    client_id,
+                }
+            }
+            17 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                Self::CancelAllOrders { pool_seed }
+            }
+            18 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let pool_token_amount = rest
+                    .get(32..40)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                let min_amounts_out = unpack_amounts(rest.get(40..).ok_or(InvalidInstruction)?)?;
+                Self::SettleAndRedeem {
+                    pool_seed,
+                    pool_token_amount,
+                    min_amounts_out,
+                }
+            }
+            19 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let pool_token_amount = rest
+                    .get(32..40)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                let min_amounts_out = unpack_amounts(rest.get(40..).ok_or(InvalidInstruction)?)?;
+                Self::RequestRedeem {
+                    pool_seed,
+                    pool_token_amount,
+                    min_amounts_out,
+                }
+            }
+            20 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                Self::ProcessRedeemQueue { pool_seed }
+            }
+            21 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let request_index = rest
+                    .get(32..40)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                Self::CancelRedeemRequest {
+                    pool_seed,
+                    request_index,
+                }
+            }
+            22 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                Self::ReleaseDeposit { pool_seed }
+            }
+            23 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let max_pool_token_supply = rest
+                    .get(32..40)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                let min_deposit_pool_tokens = rest
+                    .get(40..48)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                Self::SetDepositLimits {
+                    pool_seed,
+                    max_pool_token_supply,
+                    min_deposit_pool_tokens,
+                }
+            }
+            24 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let gating_mint = rest
+                    .get(32..64)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(Pubkey::new)
+                    .ok_or(InvalidInstruction)?;
+                let mut owners = vec![];
+                let mut offset = 64;
+                while offset < rest.len() {
+                    owners.push(
+                        rest.get(offset..offset + 32)
+                            .and_then(|slice| slice.try_into().ok())
+                            .map(Pubkey::new)
+                            .ok_or(InvalidInstruction)?,
+                    );
+                    offset += 32;
+                }
+                Self::SetWhitelist {
+                    pool_seed,
+                    gating_mint,
+                    owners,
+                }
+            }
+            25 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                Self::RemoveWhitelist { pool_seed }
+            }
+            26 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let market_index = rest
+                    .get(32..34)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u16::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                let market = rest
+                    .get(34..66)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(Pubkey::new)
+                    .ok_or(InvalidInstruction)?;
+                Self::ProposeMarketChange {
+                    pool_seed,
+                    market_index,
+                    market,
+                }
+            }
+            27 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                Self::ExecuteMarketChange { pool_seed }
+            }
+            29 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                Self::ClosePool { pool_seed }
+            }
+            30 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let max_number_of_assets: u32 = rest
+                    .get(32..36)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u32::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                Self::ResizePool {
+                    pool_seed,
+                    max_number_of_assets,
+                }
+            }
+            31 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                Self::MigratePool { pool_seed }
+            }
+            32 => {
+                let pool_seed: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let (name, rest) = unpack_string(&rest[32..])?;
+                let (description, rest) = unpack_string(rest)?;
+                let (uri, rest) = unpack_string(rest)?;
+                if !rest.is_empty() {
+                    return Err(InvalidInstruction.into());
+                }
+                Self::SetMetadata {
+                    pool_seed,
+                    name,
+                    description,
+                    uri,
+                }
+            }
+            _ => {
+                msg!("Unsupported tag");
+                return Err(InvalidInstruction.into());
+            }
+        })
+    }
+
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(size_of::<Self>());
+        match self {
+            Self::Init {
+                pool_seed,
+                max_number_of_assets,
+                number_of_markets,
             } => {
                 buf.push(0);
                 buf.extend_from_slice(pool_seed);
@@ -478,6 +1459,13 @@ impl PoolInstruction {
                 pool_seed,
                 fee_collection_period,
                 fee_ratio,
+                max_order_lifetime,
+                timelock_period,
+                performance_fee_ratio,
+                redeem_fee_ratio,
+                lockup_period,
+                max_pool_token_supply,
+                min_deposit_pool_tokens,
                 deposit_amounts,
                 markets,
             } => {
@@ -486,6 +1474,13 @@ impl PoolInstruction {
                 buf.extend_from_slice(&(markets.len() as u16).to_le_bytes());
                 buf.extend_from_slice(&fee_collection_period.to_le_bytes());
                 buf.extend_from_slice(&fee_ratio.to_le_bytes());
+                buf.extend_from_slice(&max_order_lifetime.to_le_bytes());
+                buf.extend_from_slice(&timelock_period.to_le_bytes());
+                buf.extend_from_slice(&performance_fee_ratio.to_le_bytes());
+                buf.extend_from_slice(&redeem_fee_ratio.to_le_bytes());
+                buf.extend_from_slice(&lockup_period.to_le_bytes());
+                buf.extend_from_slice(&max_pool_token_supply.to_le_bytes());
+                buf.extend_from_slice(&min_deposit_pool_tokens.to_le_bytes());
                 for market in markets {
                     buf.extend_from_slice(&market.to_bytes())
                 }
@@ -496,10 +1491,16 @@ impl PoolInstruction {
             Self::Deposit {
                 pool_seed,
                 pool_token_amount,
+                min_pool_tokens_out,
+                max_amounts_in,
             } => {
                 buf.push(2);
                 buf.extend_from_slice(pool_seed);
                 buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+                buf.extend_from_slice(&min_pool_tokens_out.to_le_bytes());
+                for amount in max_amounts_in.iter() {
+                    buf.extend_from_slice(&amount.to_le_bytes());
+                }
             }
             Self::CreateOrder {
                 pool_seed,
@@ -553,12 +1554,49 @@ impl PoolInstruction {
                 buf.extend_from_slice(&target_mint.to_bytes());
                 buf.extend_from_slice(&serum_limit.to_le_bytes())
             }
-            Self::CancelOrder {
+            Self::CreateOrderV2 {
                 pool_seed,
                 side,
-                order_id,
+                limit_price,
+                ratio_of_pool_assets_to_trade,
+                order_type,
+                client_id,
+                self_trade_behavior,
+                source_index,
+                target_index,
+                market_index,
+                serum_limit,
             } => {
-                buf.push(4);
+                buf.push(28);
+                buf.extend_from_slice(pool_seed);
+                buf.push(match side {
+                    Side::Bid => 0,
+                    Side::Ask => 1,
+                });
+                buf.extend_from_slice(&limit_price.get().to_le_bytes());
+                buf.extend_from_slice(&ratio_of_pool_assets_to_trade.get().to_le_bytes());
+                buf.push(match order_type {
+                    OrderType::Limit => 0,
+                    OrderType::ImmediateOrCancel => 1,
+                    OrderType::PostOnly => 2,
+                });
+                buf.extend_from_slice(&client_id.to_le_bytes());
+                buf.push(match self_trade_behavior {
+                    SelfTradeBehavior::DecrementTake => 0,
+                    SelfTradeBehavior::CancelProvide => 1,
+                    SelfTradeBehavior::AbortTransaction => 2,
+                });
+                buf.extend_from_slice(&source_index.to_le_bytes());
+                buf.extend_from_slice(&target_index.to_le_bytes());
+                buf.extend_from_slice(&market_index.to_le_bytes());
+                buf.extend_from_slice(&serum_limit.to_le_bytes());
+            }
+            Self::CancelOrder {
+                pool_seed,
+                side,
+                order_id,
+            } => {
+                buf.push(4);
                 buf.extend_from_slice(pool_seed);
                 buf.extend_from_slice(
                     &match side {
@@ -582,46 +1620,1205 @@ impl PoolInstruction {
             Self::Redeem {
                 pool_seed,
                 pool_token_amount,
+                min_amounts_out,
             } => {
                 buf.push(6);
                 buf.extend_from_slice(pool_seed);
                 buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+                for amount in min_amounts_out.iter() {
+                    buf.extend_from_slice(&amount.to_le_bytes());
+                }
             }
             Self::CollectFees { pool_seed } => {
                 buf.push(7);
                 buf.extend_from_slice(pool_seed);
             }
+            Self::Lock { pool_seed } => {
+                buf.push(8);
+                buf.extend_from_slice(pool_seed);
+            }
+            Self::Unlock { pool_seed } => {
+                buf.push(9);
+                buf.extend_from_slice(pool_seed);
+            }
+            Self::ProposeSignalProvider {
+                pool_seed,
+                new_signal_provider,
+            } => {
+                buf.push(10);
+                buf.extend_from_slice(pool_seed);
+                buf.extend_from_slice(&new_signal_provider.to_bytes());
+            }
+            Self::AcceptSignalProvider { pool_seed } => {
+                buf.push(11);
+                buf.extend_from_slice(pool_seed);
+            }
+            Self::CreateConfig {
+                signal_provider_share,
+                fee_recipients,
+            } => {
+                buf.push(12);
+                buf.extend_from_slice(&signal_provider_share.to_le_bytes());
+                for recipient in fee_recipients.iter() {
+                    buf.extend_from_slice(&recipient.owner.to_bytes());
+                    buf.extend_from_slice(&recipient.share.to_le_bytes());
+                }
+            }
+            Self::DepositSingleAsset {
+                pool_seed,
+                pool_token_amount,
+                min_pool_tokens_out,
+                limit_prices,
+            } => {
+                buf.push(13);
+                buf.extend_from_slice(pool_seed);
+                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+                buf.extend_from_slice(&min_pool_tokens_out.to_le_bytes());
+                for price in limit_prices.iter() {
+                    buf.extend_from_slice(&price.to_le_bytes());
+                }
+            }
+            Self::RedeemToSingleAsset {
+                pool_seed,
+                pool_token_amount,
+                min_amount_out,
+                limit_prices,
+            } => {
+                buf.push(14);
+                buf.extend_from_slice(pool_seed);
+                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+                buf.extend_from_slice(&min_amount_out.to_le_bytes());
+                for price in limit_prices.iter() {
+                    buf.extend_from_slice(&price.to_le_bytes());
+                }
+            }
+            Self::InitOpenOrders {
+                pool_seed,
+                market_index,
+            } => {
+                buf.push(15);
+                buf.extend_from_slice(pool_seed);
+                buf.extend_from_slice(&market_index.to_le_bytes());
+            }
+            Self::CancelOrderByClientId {
+                pool_seed,
+                client_id,
+            } => {
+                buf.push(16);
+                buf.extend_from_slice(pool_seed);
+                buf.extend_from_slice(&client_id.to_le_bytes());
+            }
+            Self::CancelAllOrders { pool_seed } => {
+                buf.push(17);
+                buf.extend_from_slice(pool_seed);
+            }
+            Self::SettleAndRedeem {
+                pool_seed,
+                pool_token_amount,
+                min_amounts_out,
+            } => {
+                buf.push(18);
+                buf.extend_from_slice(pool_seed);
+                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+                for amount in min_amounts_out.iter() {
+                    buf.extend_from_slice(&amount.to_le_bytes());
+                }
+            }
+            Self::RequestRedeem {
+                pool_seed,
+                pool_token_amount,
+                min_amounts_out,
+            } => {
+                buf.push(19);
+                buf.extend_from_slice(pool_seed);
+                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+                for amount in min_amounts_out.iter() {
+                    buf.extend_from_slice(&amount.to_le_bytes());
+                }
+            }
+            Self::ProcessRedeemQueue { pool_seed } => {
+                buf.push(20);
+                buf.extend_from_slice(pool_seed);
+            }
+            Self::CancelRedeemRequest {
+                pool_seed,
+                request_index,
+            } => {
+                buf.push(21);
+                buf.extend_from_slice(pool_seed);
+                buf.extend_from_slice(&request_index.to_le_bytes());
+            }
+            Self::ReleaseDeposit { pool_seed } => {
+                buf.push(22);
+                buf.extend_from_slice(pool_seed);
+            }
+            Self::SetDepositLimits {
+                pool_seed,
+                max_pool_token_supply,
+                min_deposit_pool_tokens,
+            } => {
+                buf.push(23);
+                buf.extend_from_slice(pool_seed);
+                buf.extend_from_slice(&max_pool_token_supply.to_le_bytes());
+                buf.extend_from_slice(&min_deposit_pool_tokens.to_le_bytes());
+            }
+            Self::SetWhitelist {
+                pool_seed,
+                gating_mint,
+                owners,
+            } => {
+                buf.push(24);
+                buf.extend_from_slice(pool_seed);
+                buf.extend_from_slice(&gating_mint.to_bytes());
+                for owner in owners.iter() {
+                    buf.extend_from_slice(&owner.to_bytes());
+                }
+            }
+            Self::RemoveWhitelist { pool_seed } => {
+                buf.push(25);
+                buf.extend_from_slice(pool_seed);
+            }
+            Self::ProposeMarketChange {
+                pool_seed,
+                market_index,
+                market,
+            } => {
+                buf.push(26);
+                buf.extend_from_slice(pool_seed);
+                buf.extend_from_slice(&market_index.to_le_bytes());
+                buf.extend_from_slice(&market.to_bytes());
+            }
+            Self::ExecuteMarketChange { pool_seed } => {
+                buf.push(27);
+                buf.extend_from_slice(pool_seed);
+            }
+            Self::ClosePool { pool_seed } => {
+                buf.push(29);
+                buf.extend_from_slice(pool_seed);
+            }
+            Self::ResizePool {
+                pool_seed,
+                max_number_of_assets,
+            } => {
+                buf.push(30);
+                buf.extend_from_slice(pool_seed);
+                buf.extend_from_slice(&max_number_of_assets.to_le_bytes());
+            }
+            Self::MigratePool { pool_seed } => {
+                buf.push(31);
+                buf.extend_from_slice(pool_seed);
+            }
+            Self::SetMetadata {
+                pool_seed,
+                name,
+                description,
+                uri,
+            } => {
+                buf.push(32);
+                buf.extend_from_slice(pool_seed);
+                for string in [name, description, uri].iter() {
+                    buf.push(string.len() as u8);
+                    buf.extend_from_slice(string.as_bytes());
+                }
+            }
         };
         buf
     }
 }
 
-// Creates a `Init` instruction
-pub fn init(
-    spl_token_program_id: &Pubkey,
+// Unpacks a list of u64 amounts taking up the rest of the instruction data
+fn unpack_amounts(input: &[u8]) -> Result<Vec<u64>, ProgramError> {
+    if input.len() % 8 != 0 {
+        return Err(BonfidaBotError::InvalidInstruction.into());
+    }
+    Ok(input
+        .chunks_exact(8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .collect())
+}
+
+// Unpacks a UTF-8 string prefixed by its length in bytes, returning the rest of the input
+fn unpack_string(input: &[u8]) -> Result<(String, &[u8]), ProgramError> {
+    let (&len, rest) = input
+        .split_first()
+        .ok_or(BonfidaBotError::InvalidInstruction)?;
+    let bytes = rest
+        .get(..len as usize)
+        .ok_or(BonfidaBotError::InvalidInstruction)?;
+    let string = String::from_utf8(bytes.to_vec())
+        .map_err(|_| ProgramError::from(BonfidaBotError::InvalidInstruction))?;
+    Ok((string, &rest[len as usize..]))
+}
+
+// Creates a `Init` instruction
+pub fn init(
+    spl_token_program_id: &Pubkey,
+    system_program_id: &Pubkey,
+    rent_program_id: &Pubkey,
+    bonfidabot_program_id: &Pubkey,
+    mint_key: &Pubkey,
+    payer_key: &Pubkey,
+    pool_key: &Pubkey,
+    pool_seed: [u8; 32],
+    max_number_of_assets: u32,
+    number_of_markets: u16,
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::Init {
+        pool_seed,
+        max_number_of_assets,
+        number_of_markets,
+    }
+    .pack();
+    let accounts = vec![
+        AccountMeta::new_readonly(*system_program_id, false),
+        AccountMeta::new_readonly(*rent_program_id, false),
+        AccountMeta::new_readonly(*spl_token_program_id, false),
+        AccountMeta::new(*pool_key, false),
+        AccountMeta::new(*mint_key, false),
+        AccountMeta::new(*payer_key, true),
+    ];
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `CreatePool` instruction
+pub fn create(
+    spl_token_program_id: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    bonfidabot_program_id: &Pubkey,
+    mint_key: &Pubkey,
+    pool_key: &Pubkey,
+    pool_seed: [u8; 32],
+    pool_asset_keys: &Vec<Pubkey>,
+    target_pool_token_key: &Pubkey,
+    source_owner_key: &Pubkey,
+    source_asset_keys: &Vec<Pubkey>,
+    serum_program_id: &Pubkey,
+    signal_provider_key: &Pubkey,
+    system_program_id: &Pubkey,
+    rent_sysvar_id: &Pubkey,
+    payer_key: &Pubkey,
+    fee_collection_period: u64,
+    fee_ratio: u16,
+    max_order_lifetime: u64,
+    timelock_period: u64,
+    performance_fee_ratio: u16,
+    redeem_fee_ratio: u16,
+    lockup_period: u64,
+    max_pool_token_supply: u64,
+    min_deposit_pool_tokens: u64,
+    deposit_amounts: Vec<u64>,
+    markets: Vec<Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let market_keys = markets.clone();
+    let data = PoolInstruction::Create {
+        pool_seed,
+        deposit_amounts,
+        markets,
+        fee_collection_period,
+        fee_ratio,
+        max_order_lifetime,
+        timelock_period,
+        performance_fee_ratio,
+        redeem_fee_ratio,
+        lockup_period,
+        max_pool_token_supply,
+        min_deposit_pool_tokens,
+    }
+    .pack();
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*spl_token_program_id, false),
+        AccountMeta::new_readonly(*clock_sysvar_id, false),
+        AccountMeta::new_readonly(*serum_program_id, false),
+        AccountMeta::new_readonly(*signal_provider_key, true),
+        AccountMeta::new(*mint_key, false),
+        AccountMeta::new(*target_pool_token_key, false),
+        AccountMeta::new(*pool_key, false),
+    ];
+    for pool_asset_key in pool_asset_keys.iter() {
+        accounts.push(AccountMeta::new(*pool_asset_key, false))
+    }
+    accounts.push(AccountMeta::new_readonly(*source_owner_key, true));
+    for source_asset_key in source_asset_keys.iter() {
+        accounts.push(AccountMeta::new(*source_asset_key, false))
+    }
+    for market_key in market_keys.iter() {
+        accounts.push(AccountMeta::new_readonly(*market_key, false))
+    }
+    let (registry_key, _) = get_registry_key(bonfidabot_program_id, signal_provider_key);
+    accounts.push(AccountMeta::new_readonly(*system_program_id, false));
+    accounts.push(AccountMeta::new_readonly(*rent_sysvar_id, false));
+    accounts.push(AccountMeta::new(*payer_key, true));
+    accounts.push(AccountMeta::new(registry_key, false));
+
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Appends the accounts needed to escrow a deposit into a pool with a lockup period
+fn push_lockup_accounts(
+    accounts: &mut Vec<AccountMeta>,
+    bonfidabot_program_id: &Pubkey,
+    mint_key: &Pubkey,
+    pool_key: &Pubkey,
+    source_owner: &Pubkey,
+    pool_seed: [u8; 32],
+    lockup_accounts: Option<(&Pubkey, &Pubkey, &Pubkey, &Pubkey)>,
+) {
+    if let Some((system_program_id, rent_sysvar_id, clock_sysvar_id, payer_key)) = lockup_accounts {
+        let (deposit_receipt_key, _) =
+            get_deposit_receipt_key(bonfidabot_program_id, &pool_seed, source_owner);
+        accounts.push(AccountMeta::new_readonly(*system_program_id, false));
+        accounts.push(AccountMeta::new_readonly(*rent_sysvar_id, false));
+        accounts.push(AccountMeta::new_readonly(*clock_sysvar_id, false));
+        accounts.push(AccountMeta::new(*payer_key, true));
+        accounts.push(AccountMeta::new(deposit_receipt_key, false));
+        accounts.push(AccountMeta::new(
+            get_associated_token_address(pool_key, mint_key),
+            false,
+        ));
+    }
+}
+
+// Creates a `Deposit` instruction
+pub fn deposit(
+    spl_token_program_id: &Pubkey,
+    bonfidabot_program_id: &Pubkey,
+    mint_key: &Pubkey,
+    pool_key: &Pubkey,
+    pool_asset_keys: &Vec<Pubkey>,
+    target_pool_token_key: &Pubkey,
+    signal_provider_pool_token_key: &Pubkey,
+    config_key: &Pubkey,
+    fee_recipients: &Vec<Pubkey>,
+    source_owner: &Pubkey,
+    // The pool's whitelist account and, if it has a gating mint, the source owner's token account
+    // for this mint, only if the pool is private
+    allow_list_keys: &Vec<Pubkey>,
+    // The system program, the rent and clock sysvars and the fee payer for the source owner's
+    // deposit receipt, only if the pool has a lockup period
+    lockup_accounts: Option<(&Pubkey, &Pubkey, &Pubkey, &Pubkey)>,
+    source_asset_keys: &Vec<Pubkey>,
+    pool_seed: [u8; 32],
+    pool_token_amount: u64,
+    min_pool_tokens_out: u64,
+    max_amounts_in: Vec<u64>,
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::Deposit {
+        pool_seed,
+        pool_token_amount,
+        min_pool_tokens_out,
+        max_amounts_in,
+    }
+    .pack();
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*spl_token_program_id, false),
+        AccountMeta::new(*mint_key, false),
+        AccountMeta::new(*target_pool_token_key, false),
+        AccountMeta::new(*signal_provider_pool_token_key, false),
+        AccountMeta::new_readonly(*config_key, false),
+        AccountMeta::new_readonly(*pool_key, false),
+    ];
+    for pool_asset_key in pool_asset_keys.iter() {
+        accounts.push(AccountMeta::new(*pool_asset_key, false))
+    }
+    accounts.push(AccountMeta::new_readonly(*source_owner, true));
+    for source_asset_key in source_asset_keys.iter() {
+        accounts.push(AccountMeta::new(*source_asset_key, false))
+    }
+    for fee_recipient in fee_recipients.iter() {
+        accounts.push(AccountMeta::new(
+            get_associated_token_address(fee_recipient, mint_key),
+            false,
+        ))
+    }
+    for allow_list_key in allow_list_keys.iter() {
+        accounts.push(AccountMeta::new_readonly(*allow_list_key, false))
+    }
+    push_lockup_accounts(
+        &mut accounts,
+        bonfidabot_program_id,
+        mint_key,
+        pool_key,
+        source_owner,
+        pool_seed,
+        lockup_accounts,
+    );
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Appends the accounts receiving the redeem fee of a pool which charges one
+fn push_redeem_fee_accounts(
+    accounts: &mut Vec<AccountMeta>,
+    mint_key: &Pubkey,
+    redeem_fee_accounts: Option<(&Pubkey, &Pubkey, &Vec<Pubkey>)>,
+) {
+    if let Some((signal_provider_pool_token_key, config_key, fee_recipients)) = redeem_fee_accounts
+    {
+        accounts.push(AccountMeta::new(*signal_provider_pool_token_key, false));
+        accounts.push(AccountMeta::new_readonly(*config_key, false));
+        for fee_recipient in fee_recipients.iter() {
+            accounts.push(AccountMeta::new(
+                get_associated_token_address(fee_recipient, mint_key),
+                false,
+            ))
+        }
+    }
+}
+
+// Creates a `Redeem` instruction
+pub fn redeem(
+    spl_token_program_id: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    bonfidabot_program_id: &Pubkey,
+    mint_key: &Pubkey,
+    pool_key: &Pubkey,
+    pool_asset_keys: &Vec<Pubkey>,
+    source_pool_token_owner_key: &Pubkey,
+    source_pool_token_key: &Pubkey,
+    target_asset_keys: &Vec<Pubkey>,
+    // The signal provider pooltoken account, the program config and the fee recipients, only
+    // if the pool charges a redeem fee
+    redeem_fee_accounts: Option<(&Pubkey, &Pubkey, &Vec<Pubkey>)>,
+    dex_program_id: &Pubkey,
+    // The 8 accounts needed for each pending OpenOrders account of the pool, if any
+    pending_order_account_keys: &Vec<Pubkey>,
+    pool_seed: [u8; 32],
+    pool_token_amount: u64,
+    min_amounts_out: Vec<u64>,
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::Redeem {
+        pool_seed,
+        pool_token_amount,
+        min_amounts_out,
+    }
+    .pack();
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*spl_token_program_id, false),
+        AccountMeta::new_readonly(*clock_sysvar_id, false),
+        AccountMeta::new(*mint_key, false),
+        AccountMeta::new_readonly(*source_pool_token_owner_key, true),
+        AccountMeta::new(*source_pool_token_key, false),
+        AccountMeta::new(*pool_key, false),
+    ];
+    for pool_asset_key in pool_asset_keys.iter() {
+        accounts.push(AccountMeta::new(*pool_asset_key, false))
+    }
+    for source_asset_key in target_asset_keys.iter() {
+        accounts.push(AccountMeta::new(*source_asset_key, false))
+    }
+    push_redeem_fee_accounts(&mut accounts, mint_key, redeem_fee_accounts);
+    if !pending_order_account_keys.is_empty() {
+        accounts.push(AccountMeta::new_readonly(*dex_program_id, false));
+        for (i, pending_order_account_key) in pending_order_account_keys.iter().enumerate() {
+            // The vault signer is the only readonly account of each pending market
+            if i % 8 == 7 {
+                accounts.push(AccountMeta::new_readonly(*pending_order_account_key, false))
+            } else {
+                accounts.push(AccountMeta::new(*pending_order_account_key, false))
+            }
+        }
+    }
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `CreateOrder` instruction
+pub fn create_order(
+    bonfidabot_program_id: &Pubkey,
+    signal_provider: &Pubkey,
+    market: &Pubkey,
+    payer_pool_asset_account: &Pubkey,
+    payer_pool_asset_index: u64,
+    target_pool_asset_index: u64,
+    openorders_account: &Pubkey,
+    serum_event_queue: &Pubkey,
+    serum_request_queue: &Pubkey,
+    serum_market_bids: &Pubkey,
+    serum_market_asks: &Pubkey,
+    pool_account: &Pubkey,
+    coin_vault: &Pubkey,
+    pc_vault: &Pubkey,
+    spl_token_program: &Pubkey,
+    dex_program: &Pubkey,
+    rent_sysvar: &Pubkey,
+    clock_sysvar: &Pubkey,
+    srm_discount_account: Option<&Pubkey>,
+    pool_seed: [u8; 32],
+    side: Side,
+    limit_price: NonZeroU64,
+    market_index: u16,
+    coin_lot_size: u64,
+    pc_lot_size: u64,
+    target_mint: &Pubkey,
+    ratio_of_pool_assets_to_trade: NonZeroU16,
+    order_type: OrderType,
+    client_id: u64,
+    self_trade_behavior: SelfTradeBehavior,
+    serum_limit: u16
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::CreateOrder {
+        pool_seed,
+        side,
+        limit_price,
+        ratio_of_pool_assets_to_trade,
+        order_type,
+        client_id,
+        self_trade_behavior,
+        source_index: payer_pool_asset_index,
+        target_index: target_pool_asset_index,
+        market_index,
+        coin_lot_size,
+        pc_lot_size,
+        target_mint: *target_mint,
+        serum_limit
+    }
+    .pack();
+    let accounts = create_order_accounts(
+        signal_provider,
+        market,
+        payer_pool_asset_account,
+        openorders_account,
+        serum_event_queue,
+        serum_request_queue,
+        serum_market_bids,
+        serum_market_asks,
+        pool_account,
+        coin_vault,
+        pc_vault,
+        spl_token_program,
+        dex_program,
+        rent_sysvar,
+        clock_sysvar,
+        srm_discount_account,
+    );
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `CreateOrderV2` instruction
+pub fn create_order_v2(
+    bonfidabot_program_id: &Pubkey,
+    signal_provider: &Pubkey,
+    market: &Pubkey,
+    payer_pool_asset_account: &Pubkey,
+    payer_pool_asset_index: u64,
+    target_pool_asset_index: u64,
+    openorders_account: &Pubkey,
+    serum_event_queue: &Pubkey,
+    serum_request_queue: &Pubkey,
+    serum_market_bids: &Pubkey,
+    serum_market_asks: &Pubkey,
+    pool_account: &Pubkey,
+    coin_vault: &Pubkey,
+    pc_vault: &Pubkey,
+    spl_token_program: &Pubkey,
+    dex_program: &Pubkey,
+    rent_sysvar: &Pubkey,
+    clock_sysvar: &Pubkey,
+    srm_discount_account: Option<&Pubkey>,
+    pool_seed: [u8; 32],
+    side: Side,
+    limit_price: NonZeroU64,
+    market_index: u16,
+    ratio_of_pool_assets_to_trade: NonZeroU16,
+    order_type: OrderType,
+    client_id: u64,
+    self_trade_behavior: SelfTradeBehavior,
+    serum_limit: u16,
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::CreateOrderV2 {
+        pool_seed,
+        side,
+        limit_price,
+        ratio_of_pool_assets_to_trade,
+        order_type,
+        client_id,
+        self_trade_behavior,
+        source_index: payer_pool_asset_index,
+        target_index: target_pool_asset_index,
+        market_index,
+        serum_limit,
+    }
+    .pack();
+    let accounts = create_order_accounts(
+        signal_provider,
+        market,
+        payer_pool_asset_account,
+        openorders_account,
+        serum_event_queue,
+        serum_request_queue,
+        serum_market_bids,
+        serum_market_asks,
+        pool_account,
+        coin_vault,
+        pc_vault,
+        spl_token_program,
+        dex_program,
+        rent_sysvar,
+        clock_sysvar,
+        srm_discount_account,
+    );
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Lists the accounts shared by both versions of the `CreateOrder` instruction
+fn create_order_accounts(
+    signal_provider: &Pubkey,
+    market: &Pubkey,
+    payer_pool_asset_account: &Pubkey,
+    openorders_account: &Pubkey,
+    serum_event_queue: &Pubkey,
+    serum_request_queue: &Pubkey,
+    serum_market_bids: &Pubkey,
+    serum_market_asks: &Pubkey,
+    pool_account: &Pubkey,
+    coin_vault: &Pubkey,
+    pc_vault: &Pubkey,
+    spl_token_program: &Pubkey,
+    dex_program: &Pubkey,
+    rent_sysvar: &Pubkey,
+    clock_sysvar: &Pubkey,
+    srm_discount_account: Option<&Pubkey>,
+) -> Vec<AccountMeta> {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*signal_provider, true),
+        AccountMeta::new(*market, false),
+        AccountMeta::new(*payer_pool_asset_account, false),
+        AccountMeta::new(*openorders_account, false),
+        AccountMeta::new(*serum_event_queue, false),
+        AccountMeta::new(*serum_request_queue, false),
+        AccountMeta::new(*serum_market_bids, false),
+        AccountMeta::new(*serum_market_asks, false),
+        AccountMeta::new(*pool_account, false),
+        AccountMeta::new(*coin_vault, false),
+        AccountMeta::new(*pc_vault, false),
+        AccountMeta::new_readonly(*spl_token_program, false),
+        AccountMeta::new_readonly(*rent_sysvar, false),
+        AccountMeta::new_readonly(*clock_sysvar, false),
+        AccountMeta::new_readonly(*dex_program, false),
+    ];
+    if let Some(key) = srm_discount_account {
+        accounts.push(AccountMeta::new(*key, false));
+    }
+    accounts
+}
+
+// Creates a `CancelOrder` instruction
+pub fn cancel_order(
+    bonfidabot_program_id: &Pubkey,
+    signal_provider: &Pubkey,
+    market: &Pubkey,
+    openorders_account: &Pubkey,
+    serum_market_bids: &Pubkey,
+    serum_market_asks: &Pubkey,
+    serum_event_queue: &Pubkey,
+    pool_account: &Pubkey,
+    dex_program: &Pubkey,
+    clock_sysvar: &Pubkey,
+    pool_seed: [u8; 32],
+    side: Side,
+    order_id: u128,
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::CancelOrder {
+        pool_seed,
+        side,
+        order_id,
+    }
+    .pack();
+    let accounts = vec![
+        AccountMeta::new_readonly(*signal_provider, true),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new(*openorders_account, false),
+        AccountMeta::new(*serum_market_bids, false),
+        AccountMeta::new(*serum_market_asks, false),
+        AccountMeta::new(*serum_event_queue, false),
+        AccountMeta::new_readonly(*pool_account, false),
+        AccountMeta::new_readonly(*dex_program, false),
+        AccountMeta::new_readonly(*clock_sysvar, false),
+    ];
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `CancelOrderByClientId` instruction
+pub fn cancel_order_by_client_id(
+    bonfidabot_program_id: &Pubkey,
+    signal_provider: &Pubkey,
+    market: &Pubkey,
+    openorders_account: &Pubkey,
+    serum_market_bids: &Pubkey,
+    serum_market_asks: &Pubkey,
+    serum_event_queue: &Pubkey,
+    pool_account: &Pubkey,
+    dex_program: &Pubkey,
+    clock_sysvar: &Pubkey,
+    pool_seed: [u8; 32],
+    client_id: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::CancelOrderByClientId {
+        pool_seed,
+        client_id,
+    }
+    .pack();
+    let accounts = vec![
+        AccountMeta::new_readonly(*signal_provider, true),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new(*openorders_account, false),
+        AccountMeta::new(*serum_market_bids, false),
+        AccountMeta::new(*serum_market_asks, false),
+        AccountMeta::new(*serum_event_queue, false),
+        AccountMeta::new_readonly(*pool_account, false),
+        AccountMeta::new_readonly(*dex_program, false),
+        AccountMeta::new_readonly(*clock_sysvar, false),
+    ];
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `CancelAllOrders` instruction
+pub fn cancel_all_orders(
+    bonfidabot_program_id: &Pubkey,
+    signal_provider: &Pubkey,
+    market: &Pubkey,
+    openorders_account: &Pubkey,
+    serum_market_bids: &Pubkey,
+    serum_market_asks: &Pubkey,
+    serum_event_queue: &Pubkey,
+    pool_account: &Pubkey,
+    dex_program: &Pubkey,
+    clock_sysvar: &Pubkey,
+    pool_seed: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::CancelAllOrders { pool_seed }.pack();
+    let accounts = vec![
+        AccountMeta::new_readonly(*signal_provider, true),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new(*openorders_account, false),
+        AccountMeta::new(*serum_market_bids, false),
+        AccountMeta::new(*serum_market_asks, false),
+        AccountMeta::new(*serum_event_queue, false),
+        AccountMeta::new_readonly(*pool_account, false),
+        AccountMeta::new_readonly(*dex_program, false),
+        AccountMeta::new_readonly(*clock_sysvar, false),
+    ];
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a settle funds
+pub fn settle_funds(
+    bonfidabot_program_id: &Pubkey,
+    market: &Pubkey,
+    openorders_account: &Pubkey,
+    pool_account: &Pubkey,
+    pool_token_mint: &Pubkey,
+    coin_vault: &Pubkey,
+    pc_vault: &Pubkey,
+    pool_coin_wallet: &Pubkey,
+    pool_pc_wallet: &Pubkey,
+    vault_signer: &Pubkey,
+    spl_token_program: &Pubkey,
+    dex_program: &Pubkey,
+    referrer_pc_account: Option<&Pubkey>,
+    pool_seed: [u8; 32],
+    pc_index: u64,
+    coin_index: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::SettleFunds {
+        pool_seed,
+        pc_index,
+        coin_index,
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*market, false),
+        AccountMeta::new(*openorders_account, false),
+        AccountMeta::new(*pool_account, false),
+        AccountMeta::new_readonly(*pool_token_mint, false),
+        AccountMeta::new(*coin_vault, false),
+        AccountMeta::new(*pc_vault, false),
+        AccountMeta::new(*pool_coin_wallet, false),
+        AccountMeta::new(*pool_pc_wallet, false),
+        AccountMeta::new_readonly(*vault_signer, false),
+        AccountMeta::new_readonly(*spl_token_program, false),
+        AccountMeta::new_readonly(*dex_program, false),
+    ];
+    if let Some(key) = referrer_pc_account {
+        accounts.push(AccountMeta::new(*key, false))
+    }
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `CollectFees` instruction
+pub fn collect_fees(
+    spl_token_program_id: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    bonfidabot_program_id: &Pubkey,
+    pool_key: &Pubkey,
+    pool_token_mint: &Pubkey,
+    signal_provider_pool_token_key: &Pubkey,
+    config_key: &Pubkey,
+    fee_recipients: &Vec<Pubkey>,
+    // Only required when the pool charges a performance fee, empty otherwise
+    pool_asset_keys: &Vec<Pubkey>,
+    // The pool's first market followed by a (market, bids, asks) triplet for each asset
+    // which isn't the price currency
+    price_account_keys: &Vec<Pubkey>,
+    pool_seed: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::CollectFees { pool_seed }.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*spl_token_program_id, false),
+        AccountMeta::new_readonly(*clock_sysvar_id, false),
+        AccountMeta::new(*pool_key, false),
+        AccountMeta::new(*pool_token_mint, false),
+        AccountMeta::new(*signal_provider_pool_token_key, false),
+        AccountMeta::new_readonly(*config_key, false),
+    ];
+    for fee_recipient in fee_recipients.iter() {
+        accounts.push(AccountMeta::new(
+            get_associated_token_address(fee_recipient, pool_token_mint),
+            false,
+        ))
+    }
+    for pool_asset_key in pool_asset_keys.iter() {
+        accounts.push(AccountMeta::new_readonly(*pool_asset_key, false))
+    }
+    for price_account_key in price_account_keys.iter() {
+        accounts.push(AccountMeta::new_readonly(*price_account_key, false))
+    }
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `Lock` instruction
+pub fn lock(
+    bonfidabot_program_id: &Pubkey,
+    signal_provider: &Pubkey,
+    pool_key: &Pubkey,
+    pool_seed: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::Lock { pool_seed }.pack();
+    let accounts = vec![
+        AccountMeta::new_readonly(*signal_provider, true),
+        AccountMeta::new(*pool_key, false),
+    ];
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates an `Unlock` instruction
+pub fn unlock(
+    bonfidabot_program_id: &Pubkey,
+    pool_key: &Pubkey,
+    pool_seed: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::Unlock { pool_seed }.pack();
+    let accounts = vec![AccountMeta::new(*pool_key, false)];
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `ProposeSignalProvider` instruction
+pub fn propose_signal_provider(
+    bonfidabot_program_id: &Pubkey,
+    signal_provider: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    pool_key: &Pubkey,
+    pool_seed: [u8; 32],
+    new_signal_provider: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::ProposeSignalProvider {
+        pool_seed,
+        new_signal_provider: *new_signal_provider,
+    }
+    .pack();
+    let accounts = vec![
+        AccountMeta::new_readonly(*signal_provider, true),
+        AccountMeta::new_readonly(*clock_sysvar_id, false),
+        AccountMeta::new(*pool_key, false),
+    ];
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates an `AcceptSignalProvider` instruction
+pub fn accept_signal_provider(
+    bonfidabot_program_id: &Pubkey,
+    new_signal_provider: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    system_program_id: &Pubkey,
+    rent_sysvar_id: &Pubkey,
+    payer_key: &Pubkey,
+    signal_provider: &Pubkey,
+    pool_key: &Pubkey,
+    pool_seed: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::AcceptSignalProvider { pool_seed }.pack();
+    let (previous_registry_key, _) = get_registry_key(bonfidabot_program_id, signal_provider);
+    let (registry_key, _) = get_registry_key(bonfidabot_program_id, new_signal_provider);
+    let accounts = vec![
+        AccountMeta::new_readonly(*new_signal_provider, true),
+        AccountMeta::new_readonly(*clock_sysvar_id, false),
+        AccountMeta::new(*pool_key, false),
+        AccountMeta::new_readonly(*system_program_id, false),
+        AccountMeta::new_readonly(*rent_sysvar_id, false),
+        AccountMeta::new(*payer_key, true),
+        AccountMeta::new(previous_registry_key, false),
+        AccountMeta::new(registry_key, false),
+    ];
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `CreateConfig` instruction
+pub fn create_config(
+    system_program_id: &Pubkey,
+    rent_program_id: &Pubkey,
+    bonfidabot_program_id: &Pubkey,
+    config_key: &Pubkey,
+    program_data_key: &Pubkey,
+    upgrade_authority_key: &Pubkey,
+    payer_key: &Pubkey,
+    signal_provider_share: u16,
+    fee_recipients: Vec<FeeRecipient>,
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::CreateConfig {
+        signal_provider_share,
+        fee_recipients,
+    }
+    .pack();
+    let accounts = vec![
+        AccountMeta::new_readonly(*system_program_id, false),
+        AccountMeta::new_readonly(*rent_program_id, false),
+        AccountMeta::new(*config_key, false),
+        AccountMeta::new_readonly(*program_data_key, false),
+        AccountMeta::new_readonly(*upgrade_authority_key, true),
+        AccountMeta::new(*payer_key, true),
+    ];
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `DepositSingleAsset` instruction
+pub fn deposit_single_asset(
+    spl_token_program_id: &Pubkey,
+    rent_sysvar_id: &Pubkey,
+    dex_program_id: &Pubkey,
+    bonfidabot_program_id: &Pubkey,
+    mint_key: &Pubkey,
+    pool_key: &Pubkey,
+    pool_asset_keys: &Vec<Pubkey>,
+    target_pool_token_key: &Pubkey,
+    signal_provider_pool_token_key: &Pubkey,
+    config_key: &Pubkey,
+    fee_recipients: &Vec<Pubkey>,
+    source_owner: &Pubkey,
+    source_key: &Pubkey,
+    // The pool's whitelist account and, if it has a gating mint, the source owner's token account
+    // for this mint, only if the pool is private
+    allow_list_keys: &Vec<Pubkey>,
+    // The system program, the rent and clock sysvars and the fee payer for the source owner's
+    // deposit receipt, only if the pool has a lockup period
+    lockup_accounts: Option<(&Pubkey, &Pubkey, &Pubkey, &Pubkey)>,
+    // The 10 accounts needed to buy each pool asset which isn't the source token
+    swap_account_keys: &Vec<Pubkey>,
+    pool_seed: [u8; 32],
+    pool_token_amount: u64,
+    min_pool_tokens_out: u64,
+    limit_prices: Vec<u64>,
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::DepositSingleAsset {
+        pool_seed,
+        pool_token_amount,
+        min_pool_tokens_out,
+        limit_prices,
+    }
+    .pack();
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*spl_token_program_id, false),
+        AccountMeta::new_readonly(*rent_sysvar_id, false),
+        AccountMeta::new_readonly(*dex_program_id, false),
+        AccountMeta::new(*mint_key, false),
+        AccountMeta::new(*target_pool_token_key, false),
+        AccountMeta::new(*signal_provider_pool_token_key, false),
+        AccountMeta::new_readonly(*config_key, false),
+        AccountMeta::new_readonly(*pool_key, false),
+    ];
+    for pool_asset_key in pool_asset_keys.iter() {
+        accounts.push(AccountMeta::new(*pool_asset_key, false))
+    }
+    accounts.push(AccountMeta::new_readonly(*source_owner, true));
+    accounts.push(AccountMeta::new(*source_key, false));
+    for (i, swap_account_key) in swap_account_keys.iter().enumerate() {
+        // The vault signer is the only readonly account of each swap
+        if i % 10 == 8 {
+            accounts.push(AccountMeta::new_readonly(*swap_account_key, false))
+        } else {
+            accounts.push(AccountMeta::new(*swap_account_key, false))
+        }
+    }
+    for fee_recipient in fee_recipients.iter() {
+        accounts.push(AccountMeta::new(
+            get_associated_token_address(fee_recipient, mint_key),
+            false,
+        ))
+    }
+    for allow_list_key in allow_list_keys.iter() {
+        accounts.push(AccountMeta::new_readonly(*allow_list_key, false))
+    }
+    push_lockup_accounts(
+        &mut accounts,
+        bonfidabot_program_id,
+        mint_key,
+        pool_key,
+        source_owner,
+        pool_seed,
+        lockup_accounts,
+    );
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `RedeemToSingleAsset` instruction
+pub fn redeem_to_single_asset(
+    spl_token_program_id: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    rent_sysvar_id: &Pubkey,
+    dex_program_id: &Pubkey,
+    bonfidabot_program_id: &Pubkey,
+    mint_key: &Pubkey,
+    pool_key: &Pubkey,
+    pool_asset_keys: &Vec<Pubkey>,
+    source_pool_token_owner_key: &Pubkey,
+    source_pool_token_key: &Pubkey,
+    target_key: &Pubkey,
+    // The signal provider pooltoken account, the program config and the fee recipients, only
+    // if the pool charges a redeem fee
+    redeem_fee_accounts: Option<(&Pubkey, &Pubkey, &Vec<Pubkey>)>,
+    // The 10 accounts needed to sell each pool asset which isn't the target token
+    swap_account_keys: &Vec<Pubkey>,
+    pool_seed: [u8; 32],
+    pool_token_amount: u64,
+    min_amount_out: u64,
+    limit_prices: Vec<u64>,
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::RedeemToSingleAsset {
+        pool_seed,
+        pool_token_amount,
+        min_amount_out,
+        limit_prices,
+    }
+    .pack();
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*spl_token_program_id, false),
+        AccountMeta::new_readonly(*clock_sysvar_id, false),
+        AccountMeta::new_readonly(*rent_sysvar_id, false),
+        AccountMeta::new_readonly(*dex_program_id, false),
+        AccountMeta::new(*mint_key, false),
+        AccountMeta::new_readonly(*source_pool_token_owner_key, true),
+        AccountMeta::new(*source_pool_token_key, false),
+        AccountMeta::new(*pool_key, false),
+    ];
+    for pool_asset_key in pool_asset_keys.iter() {
+        accounts.push(AccountMeta::new(*pool_asset_key, false))
+    }
+    accounts.push(AccountMeta::new(*target_key, false));
+    push_redeem_fee_accounts(&mut accounts, mint_key, redeem_fee_accounts);
+    for (i, swap_account_key) in swap_account_keys.iter().enumerate() {
+        // The vault signer is the only readonly account of each swap
+        if i % 10 == 8 {
+            accounts.push(AccountMeta::new_readonly(*swap_account_key, false))
+        } else {
+            accounts.push(AccountMeta::new(*swap_account_key, false))
+        }
+    }
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates an `InitOpenOrders` instruction
+pub fn init_open_orders(
     system_program_id: &Pubkey,
-    rent_program_id: &Pubkey,
+    rent_sysvar_id: &Pubkey,
     bonfidabot_program_id: &Pubkey,
-    mint_key: &Pubkey,
     payer_key: &Pubkey,
     pool_key: &Pubkey,
     pool_seed: [u8; 32],
-    max_number_of_assets: u32,
-    number_of_markets: u16,
+    market_index: u16,
 ) -> Result<Instruction, ProgramError> {
-    let data = PoolInstruction::Init {
+    let data = PoolInstruction::InitOpenOrders {
         pool_seed,
-        max_number_of_assets,
-        number_of_markets,
+        market_index,
     }
     .pack();
+    let (open_orders_key, _) = get_open_orders_key(bonfidabot_program_id, &pool_seed, market_index);
     let accounts = vec![
         AccountMeta::new_readonly(*system_program_id, false),
-        AccountMeta::new_readonly(*rent_program_id, false),
-        AccountMeta::new_readonly(*spl_token_program_id, false),
-        AccountMeta::new(*pool_key, false),
-        AccountMeta::new(*mint_key, false),
+        AccountMeta::new_readonly(*rent_sysvar_id, false),
         AccountMeta::new(*payer_key, true),
+        AccountMeta::new(*pool_key, false),
+        AccountMeta::new(open_orders_key, false),
     ];
     Ok(Instruction {
         program_id: *bonfidabot_program_id,
@@ -630,50 +2827,58 @@ pub fn init(
     })
 }
 
-// Creates a `CreatePool` instruction
-pub fn create(
+// Creates a `SettleAndRedeem` instruction
+pub fn settle_and_redeem(
     spl_token_program_id: &Pubkey,
     clock_sysvar_id: &Pubkey,
+    dex_program_id: &Pubkey,
     bonfidabot_program_id: &Pubkey,
     mint_key: &Pubkey,
     pool_key: &Pubkey,
-    pool_seed: [u8; 32],
     pool_asset_keys: &Vec<Pubkey>,
-    target_pool_token_key: &Pubkey,
-    source_owner_key: &Pubkey,
-    source_asset_keys: &Vec<Pubkey>,
-    serum_program_id: &Pubkey,
-    signal_provider_key: &Pubkey,
-    fee_collection_period: u64,
-    fee_ratio: u16,
-    deposit_amounts: Vec<u64>,
-    markets: Vec<Pubkey>,
+    source_pool_token_owner_key: &Pubkey,
+    source_pool_token_key: &Pubkey,
+    target_asset_keys: &Vec<Pubkey>,
+    // The signal provider pooltoken account, the program config and the fee recipients, only
+    // if the pool charges a redeem fee
+    redeem_fee_accounts: Option<(&Pubkey, &Pubkey, &Vec<Pubkey>)>,
+    // The 8 accounts needed to cancel the orders of and settle each pending OpenOrders account
+    // of the pool
+    settle_account_keys: &Vec<Pubkey>,
+    pool_seed: [u8; 32],
+    pool_token_amount: u64,
+    min_amounts_out: Vec<u64>,
 ) -> Result<Instruction, ProgramError> {
-    let data = PoolInstruction::Create {
+    let data = PoolInstruction::SettleAndRedeem {
         pool_seed,
-        deposit_amounts,
-        markets,
-        fee_collection_period,
-        fee_ratio,
+        pool_token_amount,
+        min_amounts_out,
     }
     .pack();
     let mut accounts = vec![
         AccountMeta::new_readonly(*spl_token_program_id, false),
         AccountMeta::new_readonly(*clock_sysvar_id, false),
-        AccountMeta::new_readonly(*serum_program_id, false),
-        AccountMeta::new_readonly(*signal_provider_key, false),
+        AccountMeta::new_readonly(*dex_program_id, false),
         AccountMeta::new(*mint_key, false),
-        AccountMeta::new(*target_pool_token_key, false),
+        AccountMeta::new_readonly(*source_pool_token_owner_key, true),
+        AccountMeta::new(*source_pool_token_key, false),
         AccountMeta::new(*pool_key, false),
     ];
     for pool_asset_key in pool_asset_keys.iter() {
         accounts.push(AccountMeta::new(*pool_asset_key, false))
     }
-    accounts.push(AccountMeta::new_readonly(*source_owner_key, true));
-    for source_asset_key in source_asset_keys.iter() {
-        accounts.push(AccountMeta::new(*source_asset_key, false))
+    for target_asset_key in target_asset_keys.iter() {
+        accounts.push(AccountMeta::new(*target_asset_key, false))
+    }
+    push_redeem_fee_accounts(&mut accounts, mint_key, redeem_fee_accounts);
+    for (i, settle_account_key) in settle_account_keys.iter().enumerate() {
+        // The vault signer is the only readonly account of each settlement
+        if i % 8 == 7 {
+            accounts.push(AccountMeta::new_readonly(*settle_account_key, false))
+        } else {
+            accounts.push(AccountMeta::new(*settle_account_key, false))
+        }
     }
-
     Ok(Instruction {
         program_id: *bonfidabot_program_id,
         accounts,
@@ -681,45 +2886,39 @@ pub fn create(
     })
 }
 
-// Creates a `Deposit` instruction
-pub fn deposit(
+// Creates a `RequestRedeem` instruction
+pub fn request_redeem(
+    system_program_id: &Pubkey,
     spl_token_program_id: &Pubkey,
+    rent_sysvar_id: &Pubkey,
     bonfidabot_program_id: &Pubkey,
+    payer_key: &Pubkey,
     mint_key: &Pubkey,
     pool_key: &Pubkey,
-    pool_asset_keys: &Vec<Pubkey>,
-    target_pool_token_key: &Pubkey,
-    signal_provider_pool_token_key: &Pubkey,
-    source_owner: &Pubkey,
-    source_asset_keys: &Vec<Pubkey>,
+    source_pool_token_owner_key: &Pubkey,
+    source_pool_token_key: &Pubkey,
     pool_seed: [u8; 32],
     pool_token_amount: u64,
+    min_amounts_out: Vec<u64>,
 ) -> Result<Instruction, ProgramError> {
-    let data = PoolInstruction::Deposit {
+    let data = PoolInstruction::RequestRedeem {
         pool_seed,
         pool_token_amount,
+        min_amounts_out,
     }
     .pack();
-    let bonfida_fee_pt_account =
-        get_associated_token_address(&Pubkey::from_str(BONFIDA_FEE).unwrap(), mint_key);
-    let bonfida_bnb_pt_account =
-        get_associated_token_address(&Pubkey::from_str(BONFIDA_BNB).unwrap(), mint_key);
-    let mut accounts = vec![
+    let (redeem_queue_key, _) = get_redeem_queue_key(bonfidabot_program_id, &pool_seed);
+    let accounts = vec![
+        AccountMeta::new_readonly(*system_program_id, false),
         AccountMeta::new_readonly(*spl_token_program_id, false),
-        AccountMeta::new(*mint_key, false),
-        AccountMeta::new(*target_pool_token_key, false),
-        AccountMeta::new(*signal_provider_pool_token_key, false),
-        AccountMeta::new(bonfida_fee_pt_account, false),
-        AccountMeta::new(bonfida_bnb_pt_account, false),
+        AccountMeta::new_readonly(*rent_sysvar_id, false),
+        AccountMeta::new(*payer_key, true),
         AccountMeta::new_readonly(*pool_key, false),
+        AccountMeta::new(redeem_queue_key, false),
+        AccountMeta::new(get_associated_token_address(pool_key, mint_key), false),
+        AccountMeta::new_readonly(*source_pool_token_owner_key, true),
+        AccountMeta::new(*source_pool_token_key, false),
     ];
-    for pool_asset_key in pool_asset_keys.iter() {
-        accounts.push(AccountMeta::new(*pool_asset_key, false))
-    }
-    accounts.push(AccountMeta::new_readonly(*source_owner, true));
-    for source_asset_key in source_asset_keys.iter() {
-        accounts.push(AccountMeta::new(*source_asset_key, false))
-    }
     Ok(Instruction {
         program_id: *bonfidabot_program_id,
         accounts,
@@ -727,38 +2926,38 @@ pub fn deposit(
     })
 }
 
-// Creates a `Redeem` instruction
-pub fn redeem(
+// Creates a `ProcessRedeemQueue` instruction
+pub fn process_redeem_queue(
     spl_token_program_id: &Pubkey,
     clock_sysvar_id: &Pubkey,
     bonfidabot_program_id: &Pubkey,
     mint_key: &Pubkey,
     pool_key: &Pubkey,
     pool_asset_keys: &Vec<Pubkey>,
-    source_pool_token_owner_key: &Pubkey,
-    source_pool_token_key: &Pubkey,
+    // The signal provider pooltoken account, the program config and the fee recipients, only
+    // if the pool charges a redeem fee
+    redeem_fee_accounts: Option<(&Pubkey, &Pubkey, &Vec<Pubkey>)>,
+    // The M target accounts of each processed request, followed by the pooltoken account its
+    // refund would go to
     target_asset_keys: &Vec<Pubkey>,
     pool_seed: [u8; 32],
-    pool_token_amount: u64,
 ) -> Result<Instruction, ProgramError> {
-    let data = PoolInstruction::Redeem {
-        pool_seed,
-        pool_token_amount,
-    }
-    .pack();
+    let data = PoolInstruction::ProcessRedeemQueue { pool_seed }.pack();
+    let (redeem_queue_key, _) = get_redeem_queue_key(bonfidabot_program_id, &pool_seed);
     let mut accounts = vec![
         AccountMeta::new_readonly(*spl_token_program_id, false),
         AccountMeta::new_readonly(*clock_sysvar_id, false),
         AccountMeta::new(*mint_key, false),
-        AccountMeta::new_readonly(*source_pool_token_owner_key, true),
-        AccountMeta::new(*source_pool_token_key, false),
         AccountMeta::new(*pool_key, false),
+        AccountMeta::new(redeem_queue_key, false),
+        AccountMeta::new(get_associated_token_address(pool_key, mint_key), false),
     ];
     for pool_asset_key in pool_asset_keys.iter() {
         accounts.push(AccountMeta::new(*pool_asset_key, false))
     }
-    for source_asset_key in target_asset_keys.iter() {
-        accounts.push(AccountMeta::new(*source_asset_key, false))
+    push_redeem_fee_accounts(&mut accounts, mint_key, redeem_fee_accounts);
+    for target_asset_key in target_asset_keys.iter() {
+        accounts.push(AccountMeta::new(*target_asset_key, false))
     }
     Ok(Instruction {
         program_id: *bonfidabot_program_id,
@@ -767,74 +2966,201 @@ pub fn redeem(
     })
 }
 
-// Creates a `CreateOrder` instruction
-pub fn create_order(
+// Creates a `CancelRedeemRequest` instruction
+pub fn cancel_redeem_request(
+    spl_token_program_id: &Pubkey,
     bonfidabot_program_id: &Pubkey,
-    signal_provider: &Pubkey,
-    market: &Pubkey,
-    payer_pool_asset_account: &Pubkey,
-    payer_pool_asset_index: u64,
-    target_pool_asset_index: u64,
-    openorders_account: &Pubkey,
-    serum_event_queue: &Pubkey,
-    serum_request_queue: &Pubkey,
-    serum_market_bids: &Pubkey,
-    serum_market_asks: &Pubkey,
-    pool_account: &Pubkey,
-    coin_vault: &Pubkey,
-    pc_vault: &Pubkey,
-    spl_token_program: &Pubkey,
-    dex_program: &Pubkey,
-    rent_sysvar: &Pubkey,
-    srm_discount_account: Option<&Pubkey>,
+    mint_key: &Pubkey,
+    pool_key: &Pubkey,
+    owner_key: &Pubkey,
+    target_pool_token_key: &Pubkey,
     pool_seed: [u8; 32],
-    side: Side,
-    limit_price: NonZeroU64,
-    market_index: u16,
-    coin_lot_size: u64,
-    pc_lot_size: u64,
-    target_mint: &Pubkey,
-    ratio_of_pool_assets_to_trade: NonZeroU16,
-    order_type: OrderType,
-    client_id: u64,
-    self_trade_behavior: SelfTradeBehavior,
-    serum_limit: u16
+    request_index: u64,
 ) -> Result<Instruction, ProgramError> {
-    let data = PoolInstruction::CreateOrder {
+    let data = PoolInstruction::CancelRedeemRequest {
+        pool_seed,
+        request_index,
+    }
+    .pack();
+    let (redeem_queue_key, _) = get_redeem_queue_key(bonfidabot_program_id, &pool_seed);
+    let accounts = vec![
+        AccountMeta::new_readonly(*spl_token_program_id, false),
+        AccountMeta::new_readonly(*pool_key, false),
+        AccountMeta::new(redeem_queue_key, false),
+        AccountMeta::new(get_associated_token_address(pool_key, mint_key), false),
+        AccountMeta::new_readonly(*owner_key, true),
+        AccountMeta::new(*target_pool_token_key, false),
+    ];
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `ReleaseDeposit` instruction
+pub fn release_deposit(
+    spl_token_program_id: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    bonfidabot_program_id: &Pubkey,
+    mint_key: &Pubkey,
+    pool_key: &Pubkey,
+    owner_key: &Pubkey,
+    target_pool_token_key: &Pubkey,
+    pool_seed: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::ReleaseDeposit { pool_seed }.pack();
+    let (deposit_receipt_key, _) =
+        get_deposit_receipt_key(bonfidabot_program_id, &pool_seed, owner_key);
+    let accounts = vec![
+        AccountMeta::new_readonly(*spl_token_program_id, false),
+        AccountMeta::new_readonly(*clock_sysvar_id, false),
+        AccountMeta::new_readonly(*pool_key, false),
+        AccountMeta::new(deposit_receipt_key, false),
+        AccountMeta::new(get_associated_token_address(pool_key, mint_key), false),
+        AccountMeta::new(*owner_key, true),
+        AccountMeta::new(*target_pool_token_key, false),
+    ];
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `SetDepositLimits` instruction
+pub fn set_deposit_limits(
+    bonfidabot_program_id: &Pubkey,
+    signal_provider_key: &Pubkey,
+    mint_key: &Pubkey,
+    pool_key: &Pubkey,
+    pool_seed: [u8; 32],
+    max_pool_token_supply: u64,
+    min_deposit_pool_tokens: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::SetDepositLimits {
+        pool_seed,
+        max_pool_token_supply,
+        min_deposit_pool_tokens,
+    }
+    .pack();
+    let accounts = vec![
+        AccountMeta::new_readonly(*signal_provider_key, true),
+        AccountMeta::new_readonly(*mint_key, false),
+        AccountMeta::new(*pool_key, false),
+    ];
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `SetWhitelist` instruction
+pub fn set_whitelist(
+    system_program_id: &Pubkey,
+    rent_sysvar_id: &Pubkey,
+    bonfidabot_program_id: &Pubkey,
+    payer_key: &Pubkey,
+    signal_provider_key: &Pubkey,
+    pool_key: &Pubkey,
+    pool_seed: [u8; 32],
+    gating_mint: Pubkey,
+    owners: Vec<Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::SetWhitelist {
+        pool_seed,
+        gating_mint,
+        owners,
+    }
+    .pack();
+    let (whitelist_key, _) = get_whitelist_key(bonfidabot_program_id, &pool_seed);
+    let accounts = vec![
+        AccountMeta::new_readonly(*system_program_id, false),
+        AccountMeta::new_readonly(*rent_sysvar_id, false),
+        AccountMeta::new(*payer_key, true),
+        AccountMeta::new_readonly(*signal_provider_key, true),
+        AccountMeta::new(*pool_key, false),
+        AccountMeta::new(whitelist_key, false),
+    ];
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `RemoveWhitelist` instruction
+pub fn remove_whitelist(
+    bonfidabot_program_id: &Pubkey,
+    signal_provider_key: &Pubkey,
+    pool_key: &Pubkey,
+    pool_seed: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::RemoveWhitelist { pool_seed }.pack();
+    let (whitelist_key, _) = get_whitelist_key(bonfidabot_program_id, &pool_seed);
+    let accounts = vec![
+        AccountMeta::new(*signal_provider_key, true),
+        AccountMeta::new(*pool_key, false),
+        AccountMeta::new(whitelist_key, false),
+    ];
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `ProposeMarketChange` instruction
+pub fn propose_market_change(
+    bonfidabot_program_id: &Pubkey,
+    signal_provider_key: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    pool_key: &Pubkey,
+    pool_seed: [u8; 32],
+    market_index: u16,
+    market: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::ProposeMarketChange {
         pool_seed,
-        side,
-        limit_price,
-        ratio_of_pool_assets_to_trade,
-        order_type,
-        client_id,
-        self_trade_behavior,
-        source_index: payer_pool_asset_index,
-        target_index: target_pool_asset_index,
         market_index,
-        coin_lot_size,
-        pc_lot_size,
-        target_mint: *target_mint,
-        serum_limit
+        market: *market,
     }
     .pack();
+    let accounts = vec![
+        AccountMeta::new_readonly(*signal_provider_key, true),
+        AccountMeta::new_readonly(*clock_sysvar_id, false),
+        AccountMeta::new(*pool_key, false),
+    ];
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates an `ExecuteMarketChange` instruction
+pub fn execute_market_change(
+    bonfidabot_program_id: &Pubkey,
+    signal_provider_key: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    serum_program_id: &Pubkey,
+    pool_key: &Pubkey,
+    pool_seed: [u8; 32],
+    market_key: &Pubkey,
+    replaced_open_orders: Option<(&Pubkey, &Pubkey)>,
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::ExecuteMarketChange { pool_seed }.pack();
     let mut accounts = vec![
-        AccountMeta::new_readonly(*signal_provider, true),
-        AccountMeta::new(*market, false),
-        AccountMeta::new(*payer_pool_asset_account, false),
-        AccountMeta::new(*openorders_account, false),
-        AccountMeta::new(*serum_event_queue, false),
-        AccountMeta::new(*serum_request_queue, false),
-        AccountMeta::new(*serum_market_bids, false),
-        AccountMeta::new(*serum_market_asks, false),
-        AccountMeta::new(*pool_account, false),
-        AccountMeta::new(*coin_vault, false),
-        AccountMeta::new(*pc_vault, false),
-        AccountMeta::new_readonly(*spl_token_program, false),
-        AccountMeta::new_readonly(*rent_sysvar, false),
-        AccountMeta::new_readonly(*dex_program, false),
+        AccountMeta::new(*signal_provider_key, true),
+        AccountMeta::new_readonly(*clock_sysvar_id, false),
+        AccountMeta::new_readonly(*serum_program_id, false),
+        AccountMeta::new(*pool_key, false),
+        AccountMeta::new_readonly(*market_key, false),
     ];
-    if let Some(key) = srm_discount_account {
-        accounts.push(AccountMeta::new(*key, false));
+    if let Some((replaced_market_key, openorders_key)) = replaced_open_orders {
+        accounts.push(AccountMeta::new_readonly(*replaced_market_key, false));
+        accounts.push(AccountMeta::new(*openorders_key, false));
     }
     Ok(Instruction {
         program_id: *bonfidabot_program_id,
@@ -843,37 +3169,52 @@ pub fn create_order(
     })
 }
 
-// Creates a `CancelOrder` instruction
-pub fn cancel_order(
+// Creates a `ClosePool` instruction
+pub fn close_pool(
+    spl_token_program_id: &Pubkey,
+    rent_sysvar_id: &Pubkey,
+    serum_program_id: &Pubkey,
     bonfidabot_program_id: &Pubkey,
-    signal_provider: &Pubkey,
-    market: &Pubkey,
-    openorders_account: &Pubkey,
-    serum_market_bids: &Pubkey,
-    serum_market_asks: &Pubkey,
-    serum_event_queue: &Pubkey,
-    pool_account: &Pubkey,
-    dex_program: &Pubkey,
+    pool_key: &Pubkey,
+    mint_key: &Pubkey,
+    creator_key: &Pubkey,
+    signal_provider_key: &Pubkey,
     pool_seed: [u8; 32],
-    side: Side,
-    order_id: u128,
+    // The market each of the pool's OpenOrders accounts was used on, by market index
+    market_keys: &Vec<Pubkey>,
+    token_account_keys: &Vec<Pubkey>,
 ) -> Result<Instruction, ProgramError> {
-    let data = PoolInstruction::CancelOrder {
-        pool_seed,
-        side,
-        order_id,
-    }
-    .pack();
-    let accounts = vec![
-        AccountMeta::new_readonly(*signal_provider, true),
-        AccountMeta::new_readonly(*market, false),
-        AccountMeta::new(*openorders_account, false),
-        AccountMeta::new(*serum_market_bids, false),
-        AccountMeta::new(*serum_market_asks, false),
-        AccountMeta::new(*serum_event_queue, false),
-        AccountMeta::new_readonly(*pool_account, false),
-        AccountMeta::new_readonly(*dex_program, false),
+    let data = PoolInstruction::ClosePool { pool_seed }.pack();
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*spl_token_program_id, false),
+        AccountMeta::new_readonly(*rent_sysvar_id, false),
+        AccountMeta::new_readonly(*serum_program_id, false),
+        AccountMeta::new(*pool_key, false),
+        AccountMeta::new_readonly(*mint_key, false),
+        AccountMeta::new(*creator_key, false),
+        AccountMeta::new(
+            get_registry_key(bonfidabot_program_id, signal_provider_key).0,
+            false,
+        ),
+        AccountMeta::new(
+            get_redeem_queue_key(bonfidabot_program_id, &pool_seed).0,
+            false,
+        ),
+        AccountMeta::new(
+            get_whitelist_key(bonfidabot_program_id, &pool_seed).0,
+            false,
+        ),
+        AccountMeta::new(get_metadata_key(bonfidabot_program_id, &pool_seed).0, false),
     ];
+    for (market_index, market_key) in market_keys.iter().enumerate() {
+        let (open_orders_key, _) =
+            get_open_orders_key(bonfidabot_program_id, &pool_seed, market_index as u16);
+        accounts.push(AccountMeta::new(open_orders_key, false));
+        accounts.push(AccountMeta::new_readonly(*market_key, false));
+    }
+    for token_account_key in token_account_keys.iter() {
+        accounts.push(AccountMeta::new(*token_account_key, false));
+    }
     Ok(Instruction {
         program_id: *bonfidabot_program_id,
         accounts,
@@ -881,47 +3222,53 @@ pub fn cancel_order(
     })
 }
 
-// Creates a settle funds
-pub fn settle_funds(
+// Creates a `ResizePool` instruction
+pub fn resize_pool(
+    system_program_id: &Pubkey,
+    rent_sysvar_id: &Pubkey,
     bonfidabot_program_id: &Pubkey,
-    market: &Pubkey,
-    openorders_account: &Pubkey,
-    pool_account: &Pubkey,
-    pool_token_mint: &Pubkey,
-    coin_vault: &Pubkey,
-    pc_vault: &Pubkey,
-    pool_coin_wallet: &Pubkey,
-    pool_pc_wallet: &Pubkey,
-    vault_signer: &Pubkey,
-    spl_token_program: &Pubkey,
-    dex_program: &Pubkey,
-    referrer_pc_account: Option<&Pubkey>,
+    payer_key: &Pubkey,
+    pool_key: &Pubkey,
     pool_seed: [u8; 32],
-    pc_index: u64,
-    coin_index: u64,
+    max_number_of_assets: u32,
 ) -> Result<Instruction, ProgramError> {
-    let data = PoolInstruction::SettleFunds {
+    let data = PoolInstruction::ResizePool {
         pool_seed,
-        pc_index,
-        coin_index,
+        max_number_of_assets,
     }
     .pack();
+    let accounts = vec![
+        AccountMeta::new_readonly(*system_program_id, false),
+        AccountMeta::new_readonly(*rent_sysvar_id, false),
+        AccountMeta::new(*payer_key, true),
+        AccountMeta::new(*pool_key, false),
+    ];
+    Ok(Instruction {
+        program_id: *bonfidabot_program_id,
+        accounts,
+        data,
+    })
+}
 
+// Creates a `MigratePool` instruction
+pub fn migrate_pool(
+    system_program_id: &Pubkey,
+    rent_sysvar_id: &Pubkey,
+    bonfidabot_program_id: &Pubkey,
+    payer_key: &Pubkey,
+    pool_key: &Pubkey,
+    pool_seed: [u8; 32],
+    market_keys: &Vec<Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let data = PoolInstruction::MigratePool { pool_seed }.pack();
     let mut accounts = vec![
-        AccountMeta::new(*market, false),
-        AccountMeta::new(*openorders_account, false),
-        AccountMeta::new(*pool_account, false),
-        AccountMeta::new_readonly(*pool_token_mint, false),
-        AccountMeta::new(*coin_vault, false),
-        AccountMeta::new(*pc_vault, false),
-        AccountMeta::new(*pool_coin_wallet, false),
-        AccountMeta::new(*pool_pc_wallet, false),
-        AccountMeta::new_readonly(*vault_signer, false),
-        AccountMeta::new_readonly(*spl_token_program, false),
-        AccountMeta::new_readonly(*dex_program, false),
+        AccountMeta::new_readonly(*system_program_id, false),
+        AccountMeta::new_readonly(*rent_sysvar_id, false),
+        AccountMeta::new(*payer_key, true),
+        AccountMeta::new(*pool_key, false),
     ];
-    if let Some(key) = referrer_pc_account {
-        accounts.push(AccountMeta::new(*key, false))
+    for market_key in market_keys.iter() {
+        accounts.push(AccountMeta::new_readonly(*market_key, false));
     }
     Ok(Instruction {
         program_id: *bonfidabot_program_id,
@@ -930,29 +3277,36 @@ pub fn settle_funds(
     })
 }
 
-pub fn collect_fees(
-    spl_token_program_id: &Pubkey,
+// Creates a `SetMetadata` instruction
+pub fn set_metadata(
+    system_program_id: &Pubkey,
+    rent_sysvar_id: &Pubkey,
     clock_sysvar_id: &Pubkey,
     bonfidabot_program_id: &Pubkey,
+    payer_key: &Pubkey,
+    signal_provider_key: &Pubkey,
     pool_key: &Pubkey,
-    pool_token_mint: &Pubkey,
-    signal_provider_pool_token_key: &Pubkey,
     pool_seed: [u8; 32],
+    name: String,
+    description: String,
+    uri: String,
 ) -> Result<Instruction, ProgramError> {
-    let data = PoolInstruction::CollectFees { pool_seed }.pack();
-
-    let bonfida_fee_pt_account =
-        get_associated_token_address(&Pubkey::from_str(BONFIDA_FEE).unwrap(), pool_token_mint);
-    let bonfida_bnb_pt_account =
-        get_associated_token_address(&Pubkey::from_str(BONFIDA_BNB).unwrap(), pool_token_mint);
+    let data = PoolInstruction::SetMetadata {
+        pool_seed,
+        name,
+        description,
+        uri,
+    }
+    .pack();
+    let (metadata_key, _) = get_metadata_key(bonfidabot_program_id, &pool_seed);
     let accounts = vec![
-        AccountMeta::new_readonly(*spl_token_program_id, false),
+        AccountMeta::new_readonly(*system_program_id, false),
+        AccountMeta::new_readonly(*rent_sysvar_id, false),
         AccountMeta::new_readonly(*clock_sysvar_id, false),
-        AccountMeta::new(*pool_key, false),
-        AccountMeta::new(*pool_token_mint, false),
-        AccountMeta::new(*signal_provider_pool_token_key, false),
-        AccountMeta::new(bonfida_fee_pt_account, false),
-        AccountMeta::new(bonfida_bnb_pt_account, false),
+        AccountMeta::new(*payer_key, true),
+        AccountMeta::new_readonly(*signal_provider_key, true),
+        AccountMeta::new_readonly(*pool_key, false),
+        AccountMeta::new(metadata_key, false),
     ];
     Ok(Instruction {
         program_id: *bonfidabot_program_id,
@@ -971,6 +3325,8 @@ mod test {
     };
     use solana_program::pubkey::Pubkey;
 
+    use crate::state::FeeRecipient;
+
     use super::PoolInstruction;
 
     #[test]
@@ -996,6 +3352,13 @@ mod test {
             ],
             fee_collection_period: 10_000,
             fee_ratio: 15,
+            max_order_lifetime: 86_400,
+            timelock_period: 604_800,
+            performance_fee_ratio: 1 << 13,
+            redeem_fee_ratio: 1 << 10,
+            lockup_period: 86_400,
+            max_pool_token_supply: 10_000_000,
+            min_deposit_pool_tokens: 500,
         };
         let packed_create = original_create.pack();
         let unpacked_create = PoolInstruction::unpack(&packed_create).unwrap();
@@ -1004,6 +3367,8 @@ mod test {
         let original_deposit = PoolInstruction::Deposit {
             pool_seed: [50u8; 32],
             pool_token_amount: 24 as u64,
+            min_pool_tokens_out: 20,
+            max_amounts_in: vec![15, 0, u64::MAX],
         };
         let packed_deposit = original_deposit.pack();
         let unpacked_deposit = PoolInstruction::unpack(&packed_deposit).unwrap();
@@ -1028,6 +3393,23 @@ mod test {
         let packed_create_order = original_create_order.pack();
         let unpacked_create_order = PoolInstruction::unpack(&packed_create_order).unwrap();
         assert_eq!(original_create_order, unpacked_create_order);
+
+        let original_create_order_v2 = PoolInstruction::CreateOrderV2 {
+            pool_seed: [50u8; 32],
+            side: Side::Bid,
+            limit_price: NonZeroU64::new(23).unwrap(),
+            ratio_of_pool_assets_to_trade: NonZeroU16::new(500).unwrap(),
+            order_type: OrderType::PostOnly,
+            client_id: 0xff44,
+            self_trade_behavior: SelfTradeBehavior::AbortTransaction,
+            source_index: 42,
+            target_index: 78,
+            market_index: 41,
+            serum_limit: 5000,
+        };
+        let packed_create_order_v2 = original_create_order_v2.pack();
+        let unpacked_create_order_v2 = PoolInstruction::unpack(&packed_create_order_v2).unwrap();
+        assert_eq!(original_create_order_v2, unpacked_create_order_v2);
         assert_eq!(original_deposit, unpacked_deposit);
 
         let original_settle_order = PoolInstruction::SettleFunds {
@@ -1042,6 +3424,7 @@ mod test {
         let original_redeem = PoolInstruction::Redeem {
             pool_seed: [50u8; 32],
             pool_token_amount: 24 as u64,
+            min_amounts_out: vec![3, 4],
         };
         let packed_redeem = original_redeem.pack();
         let unpacked_redeem = PoolInstruction::unpack(&packed_redeem).unwrap();
@@ -1062,5 +3445,240 @@ mod test {
         let packed_collect_fees = original_collect_fees.pack();
         let unpacked_collect_fees = PoolInstruction::unpack(&packed_collect_fees).unwrap();
         assert_eq!(original_collect_fees, unpacked_collect_fees);
+
+        let original_lock = PoolInstruction::Lock {
+            pool_seed: [50u8; 32],
+        };
+        let packed_lock = original_lock.pack();
+        let unpacked_lock = PoolInstruction::unpack(&packed_lock).unwrap();
+        assert_eq!(original_lock, unpacked_lock);
+
+        let original_unlock = PoolInstruction::Unlock {
+            pool_seed: [50u8; 32],
+        };
+        let packed_unlock = original_unlock.pack();
+        let unpacked_unlock = PoolInstruction::unpack(&packed_unlock).unwrap();
+        assert_eq!(original_unlock, unpacked_unlock);
+
+        let original_propose_signal_provider = PoolInstruction::ProposeSignalProvider {
+            pool_seed: [50u8; 32],
+            new_signal_provider: Pubkey::new_unique(),
+        };
+        let packed_propose_signal_provider = original_propose_signal_provider.pack();
+        let unpacked_propose_signal_provider =
+            PoolInstruction::unpack(&packed_propose_signal_provider).unwrap();
+        assert_eq!(
+            original_propose_signal_provider,
+            unpacked_propose_signal_provider
+        );
+
+        let original_accept_signal_provider = PoolInstruction::AcceptSignalProvider {
+            pool_seed: [50u8; 32],
+        };
+        let packed_accept_signal_provider = original_accept_signal_provider.pack();
+        let unpacked_accept_signal_provider =
+            PoolInstruction::unpack(&packed_accept_signal_provider).unwrap();
+        assert_eq!(
+            original_accept_signal_provider,
+            unpacked_accept_signal_provider
+        );
+
+        let original_create_config = PoolInstruction::CreateConfig {
+            signal_provider_share: 2,
+            fee_recipients: vec![
+                FeeRecipient {
+                    owner: Pubkey::new_unique(),
+                    share: 1,
+                },
+                FeeRecipient {
+                    owner: Pubkey::new_unique(),
+                    share: 1,
+                },
+            ],
+        };
+        let packed_create_config = original_create_config.pack();
+        let unpacked_create_config = PoolInstruction::unpack(&packed_create_config).unwrap();
+        assert_eq!(original_create_config, unpacked_create_config);
+
+        let original_deposit_single_asset = PoolInstruction::DepositSingleAsset {
+            pool_seed: [50u8; 32],
+            pool_token_amount: 10_000,
+            min_pool_tokens_out: 9_900,
+            limit_prices: vec![0, 300],
+        };
+        let packed_deposit_single_asset = original_deposit_single_asset.pack();
+        let unpacked_deposit_single_asset =
+            PoolInstruction::unpack(&packed_deposit_single_asset).unwrap();
+        assert_eq!(original_deposit_single_asset, unpacked_deposit_single_asset);
+
+        let original_redeem_to_single_asset = PoolInstruction::RedeemToSingleAsset {
+            pool_seed: [50u8; 32],
+            pool_token_amount: 10_000,
+            min_amount_out: 15_000,
+            limit_prices: vec![0, 250],
+        };
+        let packed_redeem_to_single_asset = original_redeem_to_single_asset.pack();
+        let unpacked_redeem_to_single_asset =
+            PoolInstruction::unpack(&packed_redeem_to_single_asset).unwrap();
+        assert_eq!(
+            original_redeem_to_single_asset,
+            unpacked_redeem_to_single_asset
+        );
+
+        let original_init_open_orders = PoolInstruction::InitOpenOrders {
+            pool_seed: [50u8; 32],
+            market_index: 3,
+        };
+        let packed_init_open_orders = original_init_open_orders.pack();
+        let unpacked_init_open_orders = PoolInstruction::unpack(&packed_init_open_orders).unwrap();
+        assert_eq!(original_init_open_orders, unpacked_init_open_orders);
+
+        let original_cancel_by_client_id = PoolInstruction::CancelOrderByClientId {
+            pool_seed: [50u8; 32],
+            client_id: 0xdeadbeef,
+        };
+        let packed_cancel_by_client_id = original_cancel_by_client_id.pack();
+        let unpacked_cancel_by_client_id =
+            PoolInstruction::unpack(&packed_cancel_by_client_id).unwrap();
+        assert_eq!(original_cancel_by_client_id, unpacked_cancel_by_client_id);
+
+        let original_cancel_all = PoolInstruction::CancelAllOrders {
+            pool_seed: [50u8; 32],
+        };
+        let packed_cancel_all = original_cancel_all.pack();
+        let unpacked_cancel_all = PoolInstruction::unpack(&packed_cancel_all).unwrap();
+        assert_eq!(original_cancel_all, unpacked_cancel_all);
+
+        let original_settle_and_redeem = PoolInstruction::SettleAndRedeem {
+            pool_seed: [50u8; 32],
+            pool_token_amount: 24 as u64,
+            min_amounts_out: vec![3, 4],
+        };
+        let packed_settle_and_redeem = original_settle_and_redeem.pack();
+        let unpacked_settle_and_redeem =
+            PoolInstruction::unpack(&packed_settle_and_redeem).unwrap();
+        assert_eq!(original_settle_and_redeem, unpacked_settle_and_redeem);
+
+        let original_request_redeem = PoolInstruction::RequestRedeem {
+            pool_seed: [50u8; 32],
+            pool_token_amount: 24,
+            min_amounts_out: vec![3, 4],
+        };
+        let packed_request_redeem = original_request_redeem.pack();
+        let unpacked_request_redeem = PoolInstruction::unpack(&packed_request_redeem).unwrap();
+        assert_eq!(original_request_redeem, unpacked_request_redeem);
+
+        let original_process_redeem_queue = PoolInstruction::ProcessRedeemQueue {
+            pool_seed: [50u8; 32],
+        };
+        let packed_process_redeem_queue = original_process_redeem_queue.pack();
+        let unpacked_process_redeem_queue =
+            PoolInstruction::unpack(&packed_process_redeem_queue).unwrap();
+        assert_eq!(original_process_redeem_queue, unpacked_process_redeem_queue);
+
+        let original_cancel_redeem_request = PoolInstruction::CancelRedeemRequest {
+            pool_seed: [50u8; 32],
+            request_index: 70,
+        };
+        let packed_cancel_redeem_request = original_cancel_redeem_request.pack();
+        let unpacked_cancel_redeem_request =
+            PoolInstruction::unpack(&packed_cancel_redeem_request).unwrap();
+        assert_eq!(
+            original_cancel_redeem_request,
+            unpacked_cancel_redeem_request
+        );
+
+        let original_release_deposit = PoolInstruction::ReleaseDeposit {
+            pool_seed: [50u8; 32],
+        };
+        let packed_release_deposit = original_release_deposit.pack();
+        let unpacked_release_deposit = PoolInstruction::unpack(&packed_release_deposit).unwrap();
+        assert_eq!(original_release_deposit, unpacked_release_deposit);
+
+        let original_set_deposit_limits = PoolInstruction::SetDepositLimits {
+            pool_seed: [50u8; 32],
+            max_pool_token_supply: 10_000_000,
+            min_deposit_pool_tokens: 1_000,
+        };
+        let packed_set_deposit_limits = original_set_deposit_limits.pack();
+        let unpacked_set_deposit_limits =
+            PoolInstruction::unpack(&packed_set_deposit_limits).unwrap();
+        assert_eq!(original_set_deposit_limits, unpacked_set_deposit_limits);
+
+        let original_set_whitelist = PoolInstruction::SetWhitelist {
+            pool_seed: [50u8; 32],
+            gating_mint: Pubkey::new_unique(),
+            owners: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        };
+        let packed_set_whitelist = original_set_whitelist.pack();
+        let unpacked_set_whitelist = PoolInstruction::unpack(&packed_set_whitelist).unwrap();
+        assert_eq!(original_set_whitelist, unpacked_set_whitelist);
+
+        let original_remove_whitelist = PoolInstruction::RemoveWhitelist {
+            pool_seed: [50u8; 32],
+        };
+        let packed_remove_whitelist = original_remove_whitelist.pack();
+        let unpacked_remove_whitelist = PoolInstruction::unpack(&packed_remove_whitelist).unwrap();
+        assert_eq!(original_remove_whitelist, unpacked_remove_whitelist);
+
+        let original_propose_market_change = PoolInstruction::ProposeMarketChange {
+            pool_seed: [50u8; 32],
+            market_index: 3,
+            market: Pubkey::new_unique(),
+        };
+        let packed_propose_market_change = original_propose_market_change.pack();
+        let unpacked_propose_market_change =
+            PoolInstruction::unpack(&packed_propose_market_change).unwrap();
+        assert_eq!(
+            original_propose_market_change,
+            unpacked_propose_market_change
+        );
+
+        let original_execute_market_change = PoolInstruction::ExecuteMarketChange {
+            pool_seed: [50u8; 32],
+        };
+        let packed_execute_market_change = original_execute_market_change.pack();
+        let unpacked_execute_market_change =
+            PoolInstruction::unpack(&packed_execute_market_change).unwrap();
+        assert_eq!(
+            original_execute_market_change,
+            unpacked_execute_market_change
+        );
+
+        let original_close_pool = PoolInstruction::ClosePool {
+            pool_seed: [50u8; 32],
+        };
+        let packed_close_pool = original_close_pool.pack();
+        let unpacked_close_pool = PoolInstruction::unpack(&packed_close_pool).unwrap();
+        assert_eq!(original_close_pool, unpacked_close_pool);
+
+        let original_resize_pool = PoolInstruction::ResizePool {
+            pool_seed: [50u8; 32],
+            max_number_of_assets: 200,
+        };
+        let packed_resize_pool = original_resize_pool.pack();
+        let unpacked_resize_pool = PoolInstruction::unpack(&packed_resize_pool).unwrap();
+        assert_eq!(original_resize_pool, unpacked_resize_pool);
+
+        let original_migrate_pool = PoolInstruction::MigratePool {
+            pool_seed: [50u8; 32],
+        };
+        let packed_migrate_pool = original_migrate_pool.pack();
+        let unpacked_migrate_pool = PoolInstruction::unpack(&packed_migrate_pool).unwrap();
+        assert_eq!(original_migrate_pool, unpacked_migrate_pool);
+
+        let original_set_metadata = PoolInstruction::SetMetadata {
+            pool_seed: [50u8; 32],
+            name: String::from("Bonfida Momentum"),
+            description: String::from(""),
+            uri: String::from("https://bonfida.com/bots"),
+        };
+        let packed_set_metadata = original_set_metadata.pack();
+        let unpacked_set_metadata = PoolInstruction::unpack(&packed_set_metadata).unwrap();
+        assert_eq!(original_set_metadata, unpacked_set_metadata);
+        // Truncated strings are rejected
+        assert!(
+            PoolInstruction::unpack(&packed_set_metadata[..packed_set_metadata.len() - 1]).is_err()
+        );
     }
 }
@@ -1,15 +1,44 @@
-use std::{cmp::min, convert::TryInto, mem::zeroed, num::{NonZeroU16, NonZeroU64, NonZeroU8}, str::FromStr};
+use std::{
+    cmp::min,
+    convert::TryInto,
+    mem::zeroed,
+    num::{NonZeroU16, NonZeroU64, NonZeroU8},
+    slice::Iter,
+};
 
 use crate::{
     error::BonfidaBotError,
+    event::{EventKind, PoolEvent},
     instruction::PoolInstruction,
     state::{
-        get_asset_slice, pack_markets, unpack_assets, unpack_market, unpack_unchecked_asset,
-        PoolAsset, PoolHeader, PoolStatus, BONFIDA_BNB, BONFIDA_FEE, PUBKEY_LENGTH,
+        get_asset_offset, get_asset_slice, get_assets_end, get_open_orders_offset,
+        get_open_orders_slice, get_pool_len, get_redeem_request_slice, migrate_pool_v0,
+        pack_markets, push_market, set_regions, unpack_assets, unpack_market, unpack_open_orders,
+        unpack_pool_market, unpack_pool_status, unpack_pool_v0_layout, unpack_redeem_request,
+        unpack_unchecked_asset, AccountType, DepositReceipt, FeeRecipient, PoolAsset, PoolHeader,
+        PoolMarket, PoolMetadata, PoolOpenOrders, PoolRegistry, PoolStatus, ProgramConfig,
+        RedeemQueueHeader, RedeemRequest, Whitelist, CONFIG_SEED, DEPOSIT_RECEIPT_SEED,
+        MAX_FEE_RECIPIENTS, MAX_LOCKUP_PERIOD, MAX_METADATA_DESCRIPTION_LEN, MAX_METADATA_NAME_LEN,
+        MAX_METADATA_URI_LEN, MAX_REDEEM_REQUEST_ASSETS, MAX_REGISTRY_POOLS, MAX_WHITELIST_OWNERS,
+        METADATA_SEED, OPEN_ORDERS_LEN, OPEN_ORDERS_SEED, POOL_VERSION, REDEEM_QUEUE_CAPACITY,
+        REDEEM_QUEUE_LEN, REDEEM_QUEUE_SEED, REGISTRY_SEED, WHITELIST_SEED,
+    },
+    utils::{
+        check_config_key, check_deposit_limits, check_pool_key, check_signal_provider,
+        check_upgrade_authority, compute_deposit_amount, compute_performance_fee,
+        create_pda_account, fill_slice, get_config_key, get_deposit_receipt_key, get_metadata_key,
+        get_open_orders_ids, get_open_orders_key, get_redeem_queue_key, get_registry_key,
+        get_whitelist_key, grow_program_account, pending_orders_expired, pow_fixedpoint_u16,
+        split_fees, MarketState, OpenOrdersBalances,
+    },
+};
+use serum_dex::{
+    instruction::{
+        self, cancel_order, cancel_order_by_client_order_id, close_open_orders, new_order,
+        settle_funds, SelfTradeBehavior,
     },
-    utils::{check_pool_key, check_signal_provider, fill_slice, pow_fixedpoint_u16},
+    matching::{OrderType, Side},
 };
-use serum_dex::{instruction::{self, SelfTradeBehavior, cancel_order, new_order, settle_funds}, matching::{OrderType, Side}};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     clock::Clock,
@@ -17,6 +46,7 @@ use solana_program::{
     msg,
     program::{invoke, invoke_signed},
     program_error::ProgramError,
+    program_option::COption,
     program_pack::{IsInitialized, Pack},
     pubkey::Pubkey,
     rent::Rent,
@@ -25,8 +55,9 @@ use solana_program::{
 };
 use spl_associated_token_account::get_associated_token_address;
 use spl_token::{
-    instruction::{burn, initialize_mint, mint_to, transfer},
+    instruction::{burn, close_account, initialize_mint, mint_to, transfer},
     state::Account,
+    state::AccountState,
     state::Mint,
 };
 
@@ -75,9 +106,8 @@ impl Processor {
             return Err(ProgramError::InvalidArgument);
         }
 
-        let state_size = PoolHeader::LEN
-            + PUBKEY_LENGTH * (number_of_markets as usize)
-            + max_number_of_assets as usize * PoolAsset::LEN;
+        let state_size =
+            get_asset_offset(number_of_markets) + max_number_of_assets as usize * PoolAsset::LEN;
 
         let create_pool_account = create_account(
             &payer_account.key,
@@ -113,20 +143,47 @@ impl Processor {
             &[&[&pool_seed]],
         )?;
 
-        invoke_signed(
-            &create_mint_account,
-            &[
-                system_program_account.clone(),
-                payer_account.clone(),
-                mint_account.clone(),
-            ],
-            &[&[&pool_seed, &[1]]],
-        )?;
+        // The mint of a closed pool can't be closed, in which case it is reused as is
+        if mint_account.data_len() == 0 {
+            invoke_signed(
+                &create_mint_account,
+                &[
+                    system_program_account.clone(),
+                    payer_account.clone(),
+                    mint_account.clone(),
+                ],
+                &[&[&pool_seed, &[1]]],
+            )?;
 
-        invoke(
-            &init_mint,
-            &[mint_account.clone(), rent_sysvar_account.clone()],
-        )?;
+            invoke(
+                &init_mint,
+                &[mint_account.clone(), rent_sysvar_account.clone()],
+            )?;
+        } else {
+            let mint = Mint::unpack(&mint_account.data.borrow())?;
+            if mint.mint_authority != COption::Some(pool_key) || mint.supply != 0 {
+                msg!("The existing pooltoken mint cannot be reused.");
+                return Err(ProgramError::InvalidArgument);
+            }
+        }
+
+        // Record the creator so that ClosePool can return the rent
+        let mut pool_header = PoolHeader::unpack_from_slice(&[0u8; PoolHeader::LEN])?;
+        pool_header.account_type = AccountType::Pool;
+        pool_header.version = POOL_VERSION;
+        pool_header.creator = *payer_account.key;
+        set_regions(&mut pool_header, number_of_markets, state_size);
+        pool_header.pack_into_slice(&mut pool_account.data.borrow_mut()[..PoolHeader::LEN]);
+
+        PoolEvent {
+            kind: EventKind::Init,
+            pool: *pool_account.key,
+            actor: *payer_account.key,
+            amounts: vec![],
+            pool_token_supply: Some((0, 0)),
+            status: PoolStatus::Uninitialized,
+        }
+        .emit();
 
         Ok(())
     }
@@ -139,6 +196,13 @@ impl Processor {
         markets: Vec<Pubkey>,
         fee_collection_period: u64,
         fee_ratio: u16,
+        max_order_lifetime: u64,
+        timelock_period: u64,
+        performance_fee_ratio: u16,
+        redeem_fee_ratio: u16,
+        lockup_period: u64,
+        max_pool_token_supply: u64,
+        min_deposit_pool_tokens: u64,
     ) -> ProgramResult {
         let number_of_assets = deposit_amounts.len();
         let accounts_iter = &mut accounts.iter();
@@ -166,6 +230,23 @@ impl Processor {
         for _ in 0..number_of_assets {
             source_assets_accounts.push(next_account_info(accounts_iter)?)
         }
+        let mut pool_markets: Vec<PoolMarket> = Vec::with_capacity(markets.len());
+        for market in markets.iter() {
+            let market_account = next_account_info(accounts_iter)?;
+            if market_account.key != market {
+                msg!("Provided market accounts should be in the order of the given markets.");
+                return Err(ProgramError::InvalidArgument);
+            }
+            pool_markets.push(Self::unpack_authorized_market(
+                serum_program_account.key,
+                market_account,
+            )?);
+        }
+
+        let system_program_account = next_account_info(accounts_iter)?;
+        let rent_sysvar_account = next_account_info(accounts_iter)?;
+        let payer_account = next_account_info(accounts_iter)?;
+        let registry_account = next_account_info(accounts_iter)?;
 
         let current_timestamp =
             Clock::from_account_info(&clock_sysvar_account)?.unix_timestamp as u64;
@@ -182,14 +263,16 @@ impl Processor {
             return Err(ProgramError::InvalidArgument);
         }
         // Verifying that no pool was already created with this seed
-        let pool_status =
-            PoolHeader::unpack_from_slice(&pool_account.try_borrow_data()?[..PoolHeader::LEN])
-                .unwrap()
-                .status;
-        if pool_status != PoolStatus::Uninitialized {
+        let previous_header =
+            PoolHeader::unpack_from_slice(&pool_account.try_borrow_data()?[..PoolHeader::LEN])?;
+        if previous_header.status != PoolStatus::Uninitialized {
             msg!("Cannot overwrite an existing pool.");
             return Err(ProgramError::InvalidArgument);
         }
+        if previous_header.account_type != AccountType::Pool {
+            msg!("The pool account should be initialized first.");
+            return Err(ProgramError::UninitializedAccount);
+        }
         if *pool_account.owner != *program_id {
             msg!("Program should own pool account");
             return Err(ProgramError::InvalidArgument);
@@ -198,14 +281,64 @@ impl Processor {
             msg!("Source token account owner should be a signer.");
             return Err(ProgramError::InvalidArgument);
         }
+        // The pool is listed in the signal provider's registry, which could otherwise be filled
+        // up by anyone
+        if !signal_provider_account.is_signer {
+            msg!("The signal provider should sign the pool's creation.");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
         if markets.len() >> 16 != 0 {
             msg!("Number of given markets is too high.");
             return Err(ProgramError::InvalidArgument);
         }
+        // The assets take the room left in the account after the markets
+        let max_number_of_assets = pool_account
+            .data_len()
+            .saturating_sub(get_asset_offset(markets.len() as u16))
+            / PoolAsset::LEN;
+        if number_of_assets > max_number_of_assets {
+            msg!("The pool account has no room for this many assets.");
+            return Err(ProgramError::AccountDataTooSmall);
+        }
         if fee_collection_period < 604800 {
             msg!("Fee collection period should be longer than a week.");
             return Err(ProgramError::InvalidArgument);
         }
+        if timelock_period < 86400 {
+            msg!("Timelock period should be longer than a day.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if lockup_period > MAX_LOCKUP_PERIOD {
+            msg!("Lockup period should be shorter than a year.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if performance_fee_ratio != 0 && markets.is_empty() {
+            msg!("A market is needed to value a pool which charges a performance fee.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        check_deposit_limits(max_pool_token_supply, min_deposit_pool_tokens, 1_000_000)?;
+
+        // A drained pool keeps its OpenOrders accounts, which Serum binds to the market they were
+        // used on. They are carried over if the pool is created again with the same markets.
+        let mut previous_open_orders: Vec<PoolOpenOrders> = vec![];
+        for market_index in 0..previous_header.number_of_markets {
+            let data = pool_account.data.borrow();
+            let pool_open_orders = unpack_open_orders(
+                &data[previous_header.open_orders_offset as usize..],
+                market_index,
+            )?;
+            if pool_open_orders.is_initialized()
+                && markets.get(market_index as usize)
+                    != Some(&unpack_market(
+                        &data[previous_header.market_offset as usize..],
+                        market_index,
+                    ))
+            {
+                msg!("The drained pool's OpenOrders accounts are bound to its markets. Close the pool to change them.");
+                return Err(ProgramError::InvalidArgument);
+            }
+            previous_open_orders.push(pool_open_orders);
+        }
 
         let mut pool_assets: Vec<PoolAsset> = vec![];
         for i in 0..number_of_assets {
@@ -251,6 +384,9 @@ impl Processor {
                 mint_address: mint_asset_key,
             });
         }
+        for pool_market in pool_markets.iter() {
+            Self::check_market_assets(pool_market, &pool_assets)?;
+        }
 
         // Mint the first pooltoken to the target
         let instruction = mint_to(
@@ -273,8 +409,20 @@ impl Processor {
             &[&[&pool_seed]],
         )?;
 
+        Self::register_pool(
+            program_id,
+            system_program_account,
+            rent_sysvar_account,
+            payer_account,
+            signal_provider_account.key,
+            registry_account,
+            &pool_seed,
+        )?;
+
         // Write state header into data
         let state_header = PoolHeader {
+            account_type: AccountType::Pool,
+            version: POOL_VERSION,
             serum_program_id: *serum_program_account.key,
             seed: pool_seed,
             signal_provider: *signal_provider_account.key,
@@ -283,20 +431,62 @@ impl Processor {
             last_fee_collection_timestamp: current_timestamp,
             fee_collection_period,
             fee_ratio,
+            max_order_lifetime,
+            first_pending_order_timestamp: 0,
+            timelock_period,
+            pending_signal_provider: Pubkey::default(),
+            signal_provider_proposal_timestamp: 0,
+            performance_fee_ratio,
+            high_water_mark: 0,
+            redeem_fee_ratio,
+            lockup_period,
+            max_pool_token_supply,
+            min_deposit_pool_tokens,
+            is_private: false,
+            pending_market_index: 0,
+            pending_market: Pubkey::default(),
+            market_proposal_timestamp: 0,
+            creator: previous_header.creator,
+            max_number_of_assets: max_number_of_assets as u32,
+            market_offset: PoolHeader::LEN as u32,
+            open_orders_offset: get_open_orders_offset(markets.len() as u16) as u32,
+            asset_offset: get_asset_offset(markets.len() as u16) as u32,
         };
         let mut data = pool_account.data.borrow_mut();
+        fill_slice(&mut data[PoolHeader::LEN..], 0u8);
         state_header.pack_into_slice(&mut data);
 
         // Write the authorized markets to the account data
-        pack_markets(&mut data[PoolHeader::LEN..], &markets)?;
+        pack_markets(
+            &mut data[state_header.market_offset as usize..],
+            &pool_markets,
+        )?;
+        for (market_index, pool_open_orders) in
+            previous_open_orders.iter().take(markets.len()).enumerate()
+        {
+            pool_open_orders.pack_into_slice(get_open_orders_slice(
+                &mut data[state_header.open_orders_offset as usize..],
+                market_index as u16,
+            )?);
+        }
 
         // Write the assets into the account data
-        let mut offset = PoolHeader::LEN + PUBKEY_LENGTH * markets.len();
+        let mut offset = state_header.asset_offset as usize;
         for asset in pool_assets.iter() {
             asset.pack_into_slice(&mut data[offset..]);
             offset += PoolAsset::LEN;
         }
 
+        PoolEvent {
+            kind: EventKind::Create,
+            pool: *pool_account.key,
+            actor: *source_owner_account.key,
+            amounts: deposit_amounts,
+            pool_token_supply: Some((0, 1_000_000)),
+            status: state_header.status,
+        }
+        .emit();
+
         Ok(())
     }
 
@@ -306,6 +496,8 @@ impl Processor {
         pool_seed: [u8; 32],
         // The amount of pooltokens wished to be bought
         pool_token_amount: u64,
+        min_pool_tokens_out: u64,
+        max_amounts_in: Vec<u64>,
     ) -> ProgramResult {
         let accounts_iter = &mut accounts.iter();
 
@@ -318,14 +510,20 @@ impl Processor {
 
         let target_pool_token_account = next_account_info(accounts_iter)?;
         let signal_provider_pt_account = next_account_info(accounts_iter)?;
-        let bonfida_fee_pt_account = next_account_info(accounts_iter)?;
-        let bonfida_bnb_pt_account = next_account_info(accounts_iter)?;
+        let config_account = next_account_info(accounts_iter)?;
 
         let pool_account = next_account_info(accounts_iter)?;
 
         let pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
-        let asset_offset = PoolHeader::LEN + PUBKEY_LENGTH * pool_header.number_of_markets as usize;
-        let pool_assets = unpack_assets(&pool_account.data.borrow()[asset_offset..])?;
+        let asset_offset = pool_header.asset_offset as usize;
+        let assets_end = get_assets_end(&pool_header);
+        let pool_assets = unpack_assets(
+            pool_account
+                .data
+                .borrow()
+                .get(asset_offset..assets_end)
+                .ok_or(ProgramError::InvalidAccountData)?,
+        )?;
         let nb_assets = pool_assets.len();
 
         let mut pool_assets_accounts: Vec<&AccountInfo> = vec![];
@@ -338,16 +536,27 @@ impl Processor {
             source_assets_accounts.push(next_account_info(accounts_iter)?)
         }
 
+        if max_amounts_in.len() != nb_assets {
+            msg!("A maximum amount should be given for each pool asset.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
         let pool_key = Pubkey::create_program_address(&[&pool_seed], &program_id).unwrap();
         let pool_mint_key =
             Pubkey::create_program_address(&[&pool_seed, &[1]], &program_id).unwrap();
 
+        let (config, fee_recipient_pt_accounts) =
+            Self::unpack_fee_recipients(program_id, config_account, &pool_mint_key, accounts_iter)?;
+        Self::check_depositor_allowed(
+            program_id,
+            &pool_header,
+            &pool_seed,
+            source_owner_account,
+            accounts_iter,
+        )?;
+
         let signal_provider_pt_key =
             get_associated_token_address(&pool_header.signal_provider, &pool_mint_key);
-        let bonfida_fee_pt_key =
-            get_associated_token_address(&Pubkey::from_str(BONFIDA_FEE).unwrap(), &pool_mint_key);
-        let bonfida_bnb_pt_key =
-            get_associated_token_address(&Pubkey::from_str(BONFIDA_BNB).unwrap(), &pool_mint_key);
 
         // Safety verifications
         if pool_key != *pool_account.key {
@@ -372,16 +581,6 @@ impl Processor {
             return Err(ProgramError::InvalidArgument);
         }
 
-        if bonfida_fee_pt_account.key != &bonfida_fee_pt_key {
-            msg!("The provided bonfida fee pool token account is invalid.");
-            return Err(ProgramError::InvalidArgument);
-        }
-
-        if bonfida_bnb_pt_account.key != &bonfida_bnb_pt_key {
-            msg!("The provided bonfida buy and burn pool token account is invalid.");
-            return Err(ProgramError::InvalidArgument);
-        }
-
         // Doing a match on all cases here would be more idiomatic
         match pool_header.status {
             PoolStatus::Unlocked => (),
@@ -415,8 +614,10 @@ impl Processor {
             );
         }
         pool_token_effective_amount = min(pool_token_amount, pool_token_effective_amount);
+        Self::check_deposit_amount(&pool_header, total_pooltokens, pool_token_effective_amount)?;
 
         // Execute buy in
+        let mut amounts_in = vec![0; nb_assets];
         let mut amounts_all_zero = true;
         for i in 0..nb_assets {
             let pool_asset_key =
@@ -427,8 +628,16 @@ impl Processor {
                 return Err(ProgramError::InvalidArgument);
             }
 
-            let amount = ((pool_token_effective_amount as u128) * (pool_asset_amounts[i] as u128))
-                / (total_pooltokens as u128);
+            let amount = compute_deposit_amount(
+                pool_token_effective_amount,
+                pool_asset_amounts[i],
+                total_pooltokens,
+            )?;
+            if amount > max_amounts_in[i] {
+                msg!("The deposit would take more than the maximum amount of a pool asset.");
+                return Err(BonfidaBotError::MaxAmountInExceeded.into());
+            }
+            amounts_in[i] = amount;
             if amount == 0 {
                 continue;
             } else {
@@ -441,7 +650,7 @@ impl Processor {
                 pool_assets_accounts[i].key,
                 source_owner_account.key,
                 &[],
-                amount as u64,
+                amount,
             )?;
             invoke(
                 &instruction,
@@ -464,11 +673,30 @@ impl Processor {
 
         let pool_token_amount_after_fee = pool_token_effective_amount - pool_token_fee;
 
+        if pool_token_amount_after_fee < min_pool_tokens_out {
+            msg!("The deposit would mint less than the minimum amount of pooltokens.");
+            return Err(BonfidaBotError::MinPoolTokensOutNotMet.into());
+        }
+
+        // Pools with a lockup period escrow the deposited pooltokens until they are released
+        let pool_token_target_account = if pool_header.lockup_period != 0 {
+            Self::lock_up_deposit(
+                program_id,
+                &pool_seed,
+                pool_account,
+                source_owner_account,
+                accounts_iter,
+                pool_token_amount_after_fee,
+            )?
+        } else {
+            target_pool_token_account
+        };
+
         // Mint the effective amount of pooltokens to the target
         let instruction = mint_to(
             spl_token_account.key,
             &pool_mint_key,
-            target_pool_token_account.key,
+            pool_token_target_account.key,
             &pool_key,
             &[],
             pool_token_amount_after_fee,
@@ -479,64 +707,281 @@ impl Processor {
             &[
                 spl_token_account.clone(),
                 mint_account.clone(),
-                target_pool_token_account.clone(),
+                pool_token_target_account.clone(),
                 pool_account.clone(),
             ],
             &[&[&pool_seed]],
         )?;
 
-        // Mint the effective amount of pooltokens to the target
-        let signal_provider_fee = pool_token_fee / 2;
-        let instruction = mint_to(
-            spl_token_account.key,
-            &pool_mint_key,
-            signal_provider_pt_account.key,
-            &pool_key,
-            &[],
-            signal_provider_fee,
+        // Mint the fees to the signal provider and the fee recipients
+        Self::mint_fees(
+            spl_token_account,
+            mint_account,
+            pool_account,
+            signal_provider_pt_account,
+            &fee_recipient_pt_accounts,
+            &config,
+            pool_seed,
+            pool_token_fee,
         )?;
 
-        invoke_signed(
-            &instruction,
-            &[
-                spl_token_account.clone(),
-                mint_account.clone(),
-                signal_provider_pt_account.clone(),
-                pool_account.clone(),
-            ],
-            &[&[&pool_seed]],
-        )?;
+        PoolEvent {
+            kind: EventKind::Deposit,
+            pool: *pool_account.key,
+            actor: *source_owner_account.key,
+            amounts: amounts_in,
+            pool_token_supply: Some((
+                total_pooltokens,
+                Mint::unpack(&mint_account.data.borrow())?.supply,
+            )),
+            status: pool_header.status,
+        }
+        .emit();
 
-        // Mint the effective amount of pooltokens to the target
-        let bonfida_fee = pool_token_fee / 4;
-        let instruction = mint_to(
-            spl_token_account.key,
-            &pool_mint_key,
-            bonfida_fee_pt_account.key,
-            &pool_key,
-            &[],
-            bonfida_fee,
+        Ok(())
+    }
+
+    pub fn process_deposit_single_asset(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+        pool_token_amount: u64,
+        min_pool_tokens_out: u64,
+        limit_prices: Vec<u64>,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let spl_token_account = next_account_info(accounts_iter)?;
+        if spl_token_account.key != &spl_token::id() {
+            msg!("Incorrect spl token program provided");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let rent_sysvar_account = next_account_info(accounts_iter)?;
+        let dex_program = next_account_info(accounts_iter)?;
+        let mint_account = next_account_info(accounts_iter)?;
+        let target_pool_token_account = next_account_info(accounts_iter)?;
+        let signal_provider_pt_account = next_account_info(accounts_iter)?;
+        let config_account = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
+
+        check_pool_key(program_id, pool_account.key, &pool_seed)?;
+        if *pool_account.owner != *program_id {
+            msg!("Program should own pool account.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+        let markets = (0..pool_header.number_of_markets)
+            .map(|i| {
+                unpack_pool_market(
+                    &pool_account.data.borrow()[pool_header.market_offset as usize..],
+                    i,
+                )
+            })
+            .collect::<Result<Vec<PoolMarket>, ProgramError>>()?;
+        let asset_offset = pool_header.asset_offset as usize;
+        let assets_end = get_assets_end(&pool_header);
+        let pool_assets = unpack_assets(
+            pool_account
+                .data
+                .borrow()
+                .get(asset_offset..assets_end)
+                .ok_or(ProgramError::InvalidAccountData)?,
         )?;
+        let nb_assets = pool_assets.len();
 
-        invoke_signed(
-            &instruction,
-            &[
-                spl_token_account.clone(),
-                mint_account.clone(),
-                bonfida_fee_pt_account.clone(),
-                pool_account.clone(),
-            ],
-            &[&[&pool_seed]],
+        let mut pool_assets_accounts: Vec<&AccountInfo> = vec![];
+        for _ in 0..nb_assets {
+            pool_assets_accounts.push(next_account_info(accounts_iter)?)
+        }
+        let source_owner_account = next_account_info(accounts_iter)?;
+        let source_account = next_account_info(accounts_iter)?;
+
+        let pool_mint_key =
+            Pubkey::create_program_address(&[&pool_seed, &[1]], &program_id).unwrap();
+
+        // Safety verifications
+        if pool_mint_key != *mint_account.key {
+            msg!("Provided mint account is invalid.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if !source_owner_account.is_signer {
+            msg!("Source token account owner should be a signer.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if &pool_header.serum_program_id != dex_program.key {
+            msg!("The provided serum program account is invalid for this pool.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if limit_prices.len() != nb_assets {
+            msg!("A limit price should be given for each pool asset.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if pool_token_amount == 0 {
+            msg!("Operation too small");
+            return Err(BonfidaBotError::OperationTooSmall.into());
+        }
+
+        match pool_header.status {
+            PoolStatus::Unlocked => (),
+            PoolStatus::Locked | PoolStatus::LockedPendingOrder(_) => {
+                msg!("The signal provider has currently locked the pool. No buy-ins are possible for now.");
+                return Err(BonfidaBotError::LockedOperation.into());
+            }
+            PoolStatus::PendingOrder(_) => {
+                msg!("The pool has one or more pending orders. No buy-ins are possible for now. Try again later.");
+                return Err(BonfidaBotError::LockedOperation.into());
+            }
+            PoolStatus::Uninitialized => unreachable!(),
+        };
+
+        let source_mint = Account::unpack(&source_account.data.borrow())?.mint;
+        let total_pooltokens = Mint::unpack(&mint_account.data.borrow())?.supply;
+        Self::check_deposit_amount(&pool_header, total_pooltokens, pool_token_amount)?;
+
+        let mut amounts_in = vec![0; nb_assets];
+        for i in 0..nb_assets {
+            let pool_asset_key =
+                get_associated_token_address(pool_account.key, &pool_assets[i].mint_address);
+            if pool_asset_key != *pool_assets_accounts[i].key {
+                msg!("Provided pool asset account is invalid");
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            let pool_asset_amount = Account::unpack(&pool_assets_accounts[i].data.borrow())?.amount;
+            let amount =
+                compute_deposit_amount(pool_token_amount, pool_asset_amount, total_pooltokens)?;
+
+            // The source token is transferred as is, other assets are bought with it first
+            let asset_source_account = if pool_assets[i].mint_address == source_mint {
+                source_account
+            } else {
+                let mut swap_accounts: Vec<&AccountInfo> = vec![];
+                for _ in 0..9 {
+                    swap_accounts.push(next_account_info(accounts_iter)?)
+                }
+                let coin_target_account = next_account_info(accounts_iter)?;
+
+                let pool_market = match markets
+                    .iter()
+                    .find(|pool_market| &pool_market.address == swap_accounts[0].key)
+                {
+                    Some(pool_market) => pool_market,
+                    None => {
+                        msg!("The given market account is not authorized.");
+                        return Err(ProgramError::InvalidArgument);
+                    }
+                };
+                if pool_market.coin_mint != pool_assets[i].mint_address
+                    || pool_market.pc_mint != source_mint
+                {
+                    msg!("The given market cannot be used to buy this pool asset with the source token.");
+                    return Err(ProgramError::InvalidArgument);
+                }
+                if amount == 0 {
+                    continue;
+                }
+
+                let lots_to_buy = (amount - 1) / pool_market.coin_lot_size + 1;
+                let coin_balance_before =
+                    Account::unpack(&coin_target_account.data.borrow())?.amount;
+                let source_balance = Account::unpack(&source_account.data.borrow())?.amount;
+
+                Self::swap_immediate(
+                    spl_token_account,
+                    rent_sysvar_account,
+                    dex_program,
+                    source_owner_account,
+                    &swap_accounts,
+                    coin_target_account,
+                    source_account,
+                    Side::Bid,
+                    limit_prices[i],
+                    lots_to_buy,
+                    source_balance,
+                )?;
+
+                let coin_received = Account::unpack(&coin_target_account.data.borrow())?
+                    .amount
+                    .saturating_sub(coin_balance_before);
+                if coin_received < amount {
+                    msg!("The order to buy a pool asset wasn't entirely filled.");
+                    return Err(BonfidaBotError::OrderNotFilled.into());
+                }
+                coin_target_account
+            };
+
+            amounts_in[i] = amount;
+            if amount == 0 {
+                continue;
+            }
+            let instruction = transfer(
+                spl_token_account.key,
+                asset_source_account.key,
+                pool_assets_accounts[i].key,
+                source_owner_account.key,
+                &[],
+                amount,
+            )?;
+            invoke(
+                &instruction,
+                &[
+                    asset_source_account.clone(),
+                    pool_assets_accounts[i].clone(),
+                    spl_token_account.clone(),
+                    source_owner_account.clone(),
+                ],
+            )?;
+        }
+
+        let (config, fee_recipient_pt_accounts) =
+            Self::unpack_fee_recipients(program_id, config_account, &pool_mint_key, accounts_iter)?;
+        Self::check_depositor_allowed(
+            program_id,
+            &pool_header,
+            &pool_seed,
+            source_owner_account,
+            accounts_iter,
         )?;
 
-        // Mint the effective amount of pooltokens to the target
+        let signal_provider_pt_key =
+            get_associated_token_address(&pool_header.signal_provider, &pool_mint_key);
+        if signal_provider_pt_account.key != &signal_provider_pt_key {
+            msg!("The provided signal provider pool token account is invalid.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let pool_token_fee =
+            ((pool_header.fee_ratio as u128 * pool_token_amount as u128) >> 16) as u64;
+        let pool_token_amount_after_fee = pool_token_amount - pool_token_fee;
+
+        if pool_token_amount_after_fee < min_pool_tokens_out {
+            msg!("The deposit would mint less than the minimum amount of pooltokens.");
+            return Err(BonfidaBotError::MinPoolTokensOutNotMet.into());
+        }
+
+        // Pools with a lockup period escrow the deposited pooltokens until they are released
+        let pool_token_target_account = if pool_header.lockup_period != 0 {
+            Self::lock_up_deposit(
+                program_id,
+                &pool_seed,
+                pool_account,
+                source_owner_account,
+                accounts_iter,
+                pool_token_amount_after_fee,
+            )?
+        } else {
+            target_pool_token_account
+        };
+
+        // Mint the pooltokens to the target
         let instruction = mint_to(
             spl_token_account.key,
             &pool_mint_key,
-            bonfida_bnb_pt_account.key,
-            &pool_key,
+            pool_token_target_account.key,
+            pool_account.key,
             &[],
-            pool_token_fee - bonfida_fee - signal_provider_fee,
+            pool_token_amount_after_fee,
         )?;
 
         invoke_signed(
@@ -544,12 +989,37 @@ impl Processor {
             &[
                 spl_token_account.clone(),
                 mint_account.clone(),
-                bonfida_bnb_pt_account.clone(),
+                pool_token_target_account.clone(),
                 pool_account.clone(),
             ],
             &[&[&pool_seed]],
         )?;
 
+        // Mint the fees to the signal provider and the fee recipients
+        Self::mint_fees(
+            spl_token_account,
+            mint_account,
+            pool_account,
+            signal_provider_pt_account,
+            &fee_recipient_pt_accounts,
+            &config,
+            pool_seed,
+            pool_token_fee,
+        )?;
+
+        PoolEvent {
+            kind: EventKind::DepositSingleAsset,
+            pool: *pool_account.key,
+            actor: *source_owner_account.key,
+            amounts: amounts_in,
+            pool_token_supply: Some((
+                total_pooltokens,
+                Mint::unpack(&mint_account.data.borrow())?.supply,
+            )),
+            status: pool_header.status,
+        }
+        .emit();
+
         Ok(())
     }
 
@@ -562,9 +1032,8 @@ impl Processor {
         max_ratio_of_pool_to_sell_to_another_fellow_trader: NonZeroU16,
         order_type: OrderType,
         market_index: u16,
-        coin_lot_size: u64,
-        pc_lot_size: u64,
-        target_mint: Pubkey,
+        // The lot sizes and target mint given to the deprecated version of the instruction
+        order_parameters: Option<(u64, u64, Pubkey)>,
         client_id: u64,
         self_trade_behavior: SelfTradeBehavior,
         source_index: usize,
@@ -592,6 +1061,7 @@ impl Processor {
             return Err(ProgramError::IncorrectProgramId)
         }
         let rent_sysvar_account = next_account_info(account_iter)?;
+        let clock_sysvar_account = next_account_info(account_iter)?;
         let dex_program = next_account_info(account_iter)?;
         let discount_account = next_account_info(account_iter).ok();
 
@@ -609,11 +1079,6 @@ impl Processor {
             msg!("Source token account should be associated to the pool account");
             return Err(ProgramError::InvalidArgument);
         }
-        if order_type != OrderType::ImmediateOrCancel {
-            msg!("Order needs to be of type ImmediateOrCancel");
-            return Err(ProgramError::InvalidArgument);
-        }
-
         let mut pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
         if &pool_header.serum_program_id != dex_program.key {
             msg!("The provided serum program account is invalid for this pool.");
@@ -627,38 +1092,63 @@ impl Processor {
             msg!("A wrong signal provider account was provided.");
             return Err(ProgramError::MissingRequiredSignature);
         }
-        if market.key
-            != &unpack_market(&pool_account.data.borrow()[PoolHeader::LEN..], market_index)
-        {
+        let pool_market = unpack_pool_market(
+            &pool_account.data.borrow()[pool_header.market_offset as usize..],
+            market_index,
+        )?;
+        if market.key != &pool_market.address {
             msg!("The given market account is not authorized.");
             return Err(ProgramError::MissingRequiredSignature);
         }
+        let (source_mint, target_mint) = match side {
+            Side::Bid => (pool_market.pc_mint, pool_market.coin_mint),
+            Side::Ask => (pool_market.coin_mint, pool_market.pc_mint),
+        };
+        if source_account.mint != source_mint {
+            msg!("The source asset does not match the market.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if let Some((coin_lot_size, pc_lot_size, given_target_mint)) = order_parameters {
+            if coin_lot_size != pool_market.coin_lot_size
+                || pc_lot_size != pool_market.pc_lot_size
+                || given_target_mint != target_mint
+            {
+                msg!("The given lot sizes and target mint do not match the market's.");
+                return Err(ProgramError::InvalidArgument);
+            }
+        }
 
-        
-        let openorders_total_pc = openorders_account
-            .data
-            .borrow()
-            .get(101..109)
-            .and_then(|slice| slice.try_into().ok())
-            .map(u64::from_le_bytes)
-            .ok_or(ProgramError::InvalidAccountData)?;
+        let current_timestamp =
+            Clock::from_account_info(clock_sysvar_account)?.unix_timestamp as u64;
+        if pending_orders_expired(&pool_header, current_timestamp) {
+            msg!("The pool's pending orders have expired. Cancel and settle them before creating new orders.");
+            return Err(BonfidaBotError::LockedOperation.into());
+        }
 
-        let openorders_total_coin = openorders_account
-            .data
-            .borrow()
-            .get(85..93)
-            .and_then(|slice| slice.try_into().ok())
-            .map(u64::from_le_bytes)
-            .ok_or(ProgramError::InvalidAccountData)?;
-        
-        let new_open_order = (openorders_total_coin == 0) && (openorders_total_pc == 0);
+        let open_orders_offset = pool_header.open_orders_offset as usize;
+        let mut pool_open_orders = unpack_open_orders(
+            &pool_account.data.borrow()[open_orders_offset..],
+            market_index,
+        )?;
+        if !pool_open_orders.is_initialized() {
+            msg!("The pool's OpenOrders account for this market should be initialized first.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if openorders_account.key != &pool_open_orders.address {
+            msg!("The given OpenOrders account is not the pool's account for this market.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let new_open_order = !pool_open_orders.is_pending;
         match (&pool_header.status, new_open_order) {
             (PoolStatus::Uninitialized, _) => return Err(ProgramError::UninitializedAccount),
             (PoolStatus::Unlocked, _) => {
-                pool_header.status = PoolStatus::PendingOrder(NonZeroU8::new(1).unwrap())
+                pool_header.status = PoolStatus::PendingOrder(NonZeroU8::new(1).unwrap());
+                pool_header.first_pending_order_timestamp = current_timestamp;
             }
             (PoolStatus::Locked, _) => {
-                pool_header.status = PoolStatus::LockedPendingOrder(NonZeroU8::new(1).unwrap())
+                pool_header.status = PoolStatus::LockedPendingOrder(NonZeroU8::new(1).unwrap());
+                pool_header.first_pending_order_timestamp = current_timestamp;
             }
             (PoolStatus::PendingOrder(n), true) | (PoolStatus::LockedPendingOrder(n), true) => {
                 if n.get() == 64 {
@@ -679,12 +1169,30 @@ impl Processor {
             _ => {} // This happens in the case when the openorder account is already counted in the pending orders.
         };
         pool_header.pack_into_slice(&mut pool_account.data.borrow_mut()[..PoolHeader::LEN]);
+        pool_open_orders.is_pending = true;
+        pool_open_orders.pack_into_slice(get_open_orders_slice(
+            &mut pool_account.data.borrow_mut()[open_orders_offset..],
+            market_index,
+        )?);
 
-        let asset_offset = PoolHeader::LEN + PUBKEY_LENGTH * pool_header.number_of_markets as usize;
-        let source_asset =
-            unpack_unchecked_asset(&pool_account.data.borrow()[asset_offset..], source_index)?;
-        let mut target_asset =
-            unpack_unchecked_asset(&pool_account.data.borrow()[asset_offset..], target_index)?;
+        let asset_offset = pool_header.asset_offset as usize;
+        let assets_end = get_assets_end(&pool_header);
+        let source_asset = unpack_unchecked_asset(
+            pool_account
+                .data
+                .borrow()
+                .get(asset_offset..assets_end)
+                .ok_or(ProgramError::InvalidAccountData)?,
+            source_index,
+        )?;
+        let mut target_asset = unpack_unchecked_asset(
+            pool_account
+                .data
+                .borrow()
+                .get(asset_offset..assets_end)
+                .ok_or(ProgramError::InvalidAccountData)?,
+            target_index,
+        )?;
 
         if !source_asset.is_initialized() {
             msg!("The pool has no account at the specificed source index");
@@ -709,7 +1217,11 @@ impl Processor {
         } else {
             target_asset.mint_address = target_mint;
             &target_asset.pack_into_slice(get_asset_slice(
-                &mut pool_account.data.borrow_mut()[asset_offset..],
+                pool_account
+                    .data
+                    .borrow_mut()
+                    .get_mut(asset_offset..assets_end)
+                    .ok_or(ProgramError::InvalidAccountData)?,
                 target_index,
             )?);
         }
@@ -722,8 +1234,8 @@ impl Processor {
 
         let lots_to_trade = amount_to_trade
             .checked_div(match side {
-                Side::Bid => pc_lot_size,
-                Side::Ask => coin_lot_size,
+                Side::Bid => pool_market.pc_lot_size,
+                Side::Ask => pool_market.coin_lot_size,
             })
             .ok_or(BonfidaBotError::Overflow)?;
 
@@ -732,7 +1244,11 @@ impl Processor {
             
             fill_slice(
                 get_asset_slice(
-                    &mut pool_account.data.borrow_mut()[asset_offset..],
+                    pool_account
+                        .data
+                        .borrow_mut()
+                        .get_mut(asset_offset..assets_end)
+                        .ok_or(ProgramError::InvalidAccountData)?,
                     source_index,
                 )?,
                 0u8,
@@ -797,6 +1313,16 @@ impl Processor {
 
         invoke_signed(&new_order_instruction, &account_infos, &[&[&pool_seed]])?;
 
+        PoolEvent {
+            kind: EventKind::CreateOrder,
+            pool: *pool_account.key,
+            actor: *signal_provider_account.key,
+            amounts: vec![amount_to_trade],
+            pool_token_supply: None,
+            status: pool_header.status,
+        }
+        .emit();
+
         Ok(())
     }
 
@@ -854,12 +1380,36 @@ impl Processor {
         let pool_pc_account = Account::unpack(&pool_pc_wallet.data.borrow())?;
 
         let mut pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+        let market_index = Self::get_open_orders_index(
+            pool_account,
+            &pool_header,
+            market.key,
+            openorders_account.key,
+        )?;
+        let open_orders_offset = pool_header.open_orders_offset as usize;
+        let mut pool_open_orders = unpack_open_orders(
+            &pool_account.data.borrow()[open_orders_offset..],
+            market_index,
+        )?;
 
-        let asset_offset = PoolHeader::LEN + PUBKEY_LENGTH * pool_header.number_of_markets as usize;
-        let mut pool_coin_asset =
-            unpack_unchecked_asset(&pool_account.data.borrow()[asset_offset..], coin_index)?;
-        let mut pool_pc_asset =
-            unpack_unchecked_asset(&pool_account.data.borrow()[asset_offset..], pc_index)?;
+        let asset_offset = pool_header.asset_offset as usize;
+        let assets_end = get_assets_end(&pool_header);
+        let mut pool_coin_asset = unpack_unchecked_asset(
+            pool_account
+                .data
+                .borrow()
+                .get(asset_offset..assets_end)
+                .ok_or(ProgramError::InvalidAccountData)?,
+            coin_index,
+        )?;
+        let mut pool_pc_asset = unpack_unchecked_asset(
+            pool_account
+                .data
+                .borrow()
+                .get(asset_offset..assets_end)
+                .ok_or(ProgramError::InvalidAccountData)?,
+            pc_index,
+        )?;
 
         if &pool_coin_account.owner != pool_account.key {
             msg!("Pool should own the provided coin account");
@@ -922,10 +1472,18 @@ impl Processor {
             .map(u64::from_le_bytes)
             .ok_or(ProgramError::InvalidAccountData)?;
 
-        if (openorders_free_pc == openorders_total_pc)
+        let mut settled_pending_order = false;
+        if pool_open_orders.is_pending
+            && (openorders_free_pc == openorders_total_pc)
             && (openorders_free_coin == openorders_total_coin)
         {
             // This means the order can be entirely settled.
+            pool_open_orders.is_pending = false;
+            settled_pending_order = true;
+            pool_open_orders.pack_into_slice(get_open_orders_slice(
+                &mut pool_account.data.borrow_mut()[open_orders_offset..],
+                market_index,
+            )?);
             pool_header.status = match pool_header.status {
                 PoolStatus::PendingOrder(n) | PoolStatus::LockedPendingOrder(n) => {
                     if n.get() == 1 {
@@ -957,52 +1515,77 @@ impl Processor {
         }
         pool_header.pack_into_slice(&mut pool_account.data.borrow_mut()[..PoolHeader::LEN]);
 
-        if (openorders_free_pc == 0) & (openorders_free_coin == 0) {
+        // An order which was never filled, like an unmatched IOC order, leaves nothing to
+        // settle but still has to stop being pending
+        let has_free_funds = (openorders_free_pc != 0) | (openorders_free_coin != 0);
+        if !has_free_funds && !settled_pending_order {
             msg!("No funds to settle.");
             return Err(BonfidaBotError::LockedOperation.into());
         }
 
         &pool_coin_asset.pack_into_slice(get_asset_slice(
-            &mut pool_account.data.borrow_mut()[asset_offset..],
+            pool_account
+                .data
+                .borrow_mut()
+                .get_mut(asset_offset..assets_end)
+                .ok_or(ProgramError::InvalidAccountData)?,
             coin_index,
         )?);
         &pool_pc_asset.pack_into_slice(get_asset_slice(
-            &mut pool_account.data.borrow_mut()[asset_offset..],
+            pool_account
+                .data
+                .borrow_mut()
+                .get_mut(asset_offset..assets_end)
+                .ok_or(ProgramError::InvalidAccountData)?,
             pc_index,
         )?);
 
-        let instruction = settle_funds(
-            dex_program.key,
-            market.key,
-            spl_token_program.key,
-            openorders_account.key,
-            pool_account.key,
-            coin_vault.key,
-            pool_coin_wallet.key,
-            pc_vault.key,
-            pool_pc_wallet.key,
-            referrer_account.map(|a| a.key),
-            vault_signer.key,
-        )?;
+        if has_free_funds {
+            let instruction = settle_funds(
+                dex_program.key,
+                market.key,
+                spl_token_program.key,
+                openorders_account.key,
+                pool_account.key,
+                coin_vault.key,
+                pool_coin_wallet.key,
+                pc_vault.key,
+                pool_pc_wallet.key,
+                referrer_account.map(|a| a.key),
+                vault_signer.key,
+            )?;
 
-        let mut accounts = vec![
-            dex_program.clone(),
-            market.clone(),
-            openorders_account.clone(),
-            pool_account.clone(),
-            coin_vault.clone(),
-            pc_vault.clone(),
-            pool_coin_wallet.clone(),
-            pool_pc_wallet.clone(),
-            vault_signer.clone(),
-            spl_token_program.clone(),
-        ];
+            let mut accounts = vec![
+                dex_program.clone(),
+                market.clone(),
+                openorders_account.clone(),
+                pool_account.clone(),
+                coin_vault.clone(),
+                pc_vault.clone(),
+                pool_coin_wallet.clone(),
+                pool_pc_wallet.clone(),
+                vault_signer.clone(),
+                spl_token_program.clone(),
+            ];
+
+            if let Some(a) = referrer_account {
+                accounts.push(a.clone())
+            }
 
-        if let Some(a) = referrer_account {
-            accounts.push(a.clone())
+            invoke_signed(&instruction, &accounts, &[&[&pool_seed]])?;
         }
 
-        invoke_signed(&instruction, &accounts, &[&[&pool_seed]])?;
+        let pool_token_supply = Mint::unpack(&pool_token_mint.data.borrow())?.supply;
+        // Anyone can settle the pool's funds, the pool stands for the actor
+        PoolEvent {
+            kind: EventKind::SettleFunds,
+            pool: *pool_account.key,
+            actor: *pool_account.key,
+            amounts: vec![openorders_free_coin, openorders_free_pc],
+            pool_token_supply: Some((pool_token_supply, pool_token_supply)),
+            status: pool_header.status,
+        }
+        .emit();
 
         Ok(())
     }
@@ -1024,11 +1607,18 @@ impl Processor {
         let event_queue = next_account_info(accounts_iter)?;
         let pool_account = next_account_info(accounts_iter)?;
         let dex_program = next_account_info(accounts_iter)?;
+        let clock_sysvar_account = next_account_info(accounts_iter)?;
 
-        check_pool_key(program_id, pool_account.key, &pool_seed)?;
-
-        let pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
-        check_signal_provider(&pool_header, signal_provider, true)?;
+        Self::check_cancel_accounts(
+            program_id,
+            &pool_seed,
+            signal_provider,
+            market,
+            openorders_account,
+            pool_account,
+            dex_program,
+            clock_sysvar_account,
+        )?;
 
         let instruction = cancel_order(
             &dex_program.key,
@@ -1056,6 +1646,145 @@ impl Processor {
             &[&[&pool_seed]],
         )?;
 
+        PoolEvent {
+            kind: EventKind::CancelOrder,
+            pool: *pool_account.key,
+            actor: *signal_provider.key,
+            amounts: vec![],
+            pool_token_supply: None,
+            status: PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?.status,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    pub fn process_cancel_by_client_id(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+        client_id: u64,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let signal_provider = next_account_info(accounts_iter)?;
+        let market = next_account_info(accounts_iter)?;
+        let openorders_account = next_account_info(accounts_iter)?;
+        let serum_market_bids = next_account_info(accounts_iter)?;
+        let serum_market_asks = next_account_info(accounts_iter)?;
+        let event_queue = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
+        let dex_program = next_account_info(accounts_iter)?;
+        let clock_sysvar_account = next_account_info(accounts_iter)?;
+
+        Self::check_cancel_accounts(
+            program_id,
+            &pool_seed,
+            signal_provider,
+            market,
+            openorders_account,
+            pool_account,
+            dex_program,
+            clock_sysvar_account,
+        )?;
+
+        let instruction = cancel_order_by_client_order_id(
+            &dex_program.key,
+            market.key,
+            serum_market_bids.key,
+            serum_market_asks.key,
+            openorders_account.key,
+            pool_account.key,
+            event_queue.key,
+            client_id,
+        )?;
+
+        invoke_signed(
+            &instruction,
+            &vec![
+                dex_program.clone(),
+                market.clone(),
+                serum_market_bids.clone(),
+                serum_market_asks.clone(),
+                openorders_account.clone(),
+                pool_account.clone(),
+                event_queue.clone(),
+            ],
+            &[&[&pool_seed]],
+        )?;
+
+        PoolEvent {
+            kind: EventKind::CancelOrderByClientId,
+            pool: *pool_account.key,
+            actor: *signal_provider.key,
+            amounts: vec![],
+            pool_token_supply: None,
+            status: PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?.status,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    pub fn process_cancel_all(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let signal_provider = next_account_info(accounts_iter)?;
+        let market = next_account_info(accounts_iter)?;
+        let openorders_account = next_account_info(accounts_iter)?;
+        let serum_market_bids = next_account_info(accounts_iter)?;
+        let serum_market_asks = next_account_info(accounts_iter)?;
+        let event_queue = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
+        let dex_program = next_account_info(accounts_iter)?;
+        let clock_sysvar_account = next_account_info(accounts_iter)?;
+
+        Self::check_cancel_accounts(
+            program_id,
+            &pool_seed,
+            signal_provider,
+            market,
+            openorders_account,
+            pool_account,
+            dex_program,
+            clock_sysvar_account,
+        )?;
+
+        let orders = get_open_orders_ids(&openorders_account.data.borrow())?;
+        if orders.is_empty() {
+            msg!("The pool has no orders on this market.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        for (side, order_id) in orders {
+            Self::cancel_pool_order(
+                dex_program,
+                market,
+                serum_market_bids,
+                serum_market_asks,
+                openorders_account,
+                pool_account,
+                event_queue,
+                side,
+                order_id,
+                pool_seed,
+            )?;
+        }
+
+        PoolEvent {
+            kind: EventKind::CancelAllOrders,
+            pool: *pool_account.key,
+            actor: *signal_provider.key,
+            amounts: vec![],
+            pool_token_supply: None,
+            status: PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?.status,
+        }
+        .emit();
+
         Ok(())
     }
 
@@ -1065,6 +1794,7 @@ impl Processor {
         pool_seed: [u8; 32],
         // The amount of pooltokens wished to be redeemed
         pool_token_amount: u64,
+        min_amounts_out: Vec<u64>,
     ) -> ProgramResult {
         let accounts_iter = &mut accounts.iter();
 
@@ -1081,8 +1811,15 @@ impl Processor {
         let pool_account = next_account_info(accounts_iter)?;
 
         let mut pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
-        let asset_offset = PoolHeader::LEN + PUBKEY_LENGTH * pool_header.number_of_markets as usize;
-        let pool_assets = unpack_assets(&pool_account.data.borrow()[asset_offset..])?;
+        let asset_offset = pool_header.asset_offset as usize;
+        let assets_end = get_assets_end(&pool_header);
+        let pool_assets = unpack_assets(
+            pool_account
+                .data
+                .borrow()
+                .get(asset_offset..assets_end)
+                .ok_or(ProgramError::InvalidAccountData)?,
+        )?;
         let nb_assets = pool_assets.len();
 
         let mut pool_assets_accounts: Vec<&AccountInfo> = vec![];
@@ -1094,6 +1831,11 @@ impl Processor {
             target_assets_accounts.push(next_account_info(accounts_iter)?)
         }
 
+        if min_amounts_out.len() != nb_assets {
+            msg!("A minimum amount should be given for each pool asset.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
         // Safety verifications
         check_pool_key(&program_id, &pool_account.key, &pool_seed)?;
         let pool_mint_key =
@@ -1110,12 +1852,13 @@ impl Processor {
             msg!("Program should own pool account");
             return Err(ProgramError::InvalidArgument);
         }
-        match pool_header.status {
-            PoolStatus::PendingOrder(_) | PoolStatus::LockedPendingOrder(_) => {
-                msg!("The pool has one or more pending orders. No buy-outs are possible for now. Try again later.");
+        let pending_orders = match pool_header.status {
+            PoolStatus::Locked | PoolStatus::LockedPendingOrder(_) => {
+                msg!("The signal provider has currently locked the pool. Unlock the pool before redeeming.");
                 return Err(BonfidaBotError::LockedOperation.into());
             }
-            _ => (),
+            PoolStatus::PendingOrder(n) => n.get(),
+            _ => 0,
         };
 
         let current_timestamp =
@@ -1127,240 +1870,3377 @@ impl Processor {
             return Err(BonfidaBotError::LockedOperation.into());
         }
 
-        let total_pooltokens = Mint::unpack(&mint_account.data.borrow())?.supply;
-        let total_user_pooltokens = Account::unpack(&source_pool_token_account.data.borrow())?.amount;
-
-        if total_user_pooltokens < pool_token_amount {
-            msg!("Insufficient pool token funds");
-            return Err(ProgramError::InsufficientFunds)
-        } 
-
-        // Execute buy out
-        for i in 0..nb_assets {
-            let pool_asset_key =
-                get_associated_token_address(&pool_account.key, &pool_assets[i].mint_address);
+        let redeem_fee_accounts = Self::unpack_redeem_fee_accounts(
+            program_id,
+            &pool_header,
+            &pool_mint_key,
+            accounts_iter,
+        )?;
+        let pool_token_amount = Self::transfer_redeem_fees(
+            spl_token_account,
+            source_pool_token_owner_account,
+            source_pool_token_account,
+            &pool_header,
+            &redeem_fee_accounts,
+            pool_seed,
+            pool_token_amount,
+        )?;
 
-            if pool_asset_key != *pool_assets_accounts[i].key {
-                msg!("Provided pool asset account is invalid");
+        // The funds held by the pool's OpenOrders accounts are part of the redeemed share
+        let mut locked_amounts = vec![0u64; nb_assets];
+        if pending_orders != 0 {
+            let dex_program = next_account_info(accounts_iter)?;
+            if &pool_header.serum_program_id != dex_program.key {
+                msg!("The provided serum program account is invalid for this pool.");
                 return Err(ProgramError::InvalidArgument);
             }
-
-            let pool_asset_amount = Account::unpack(&pool_assets_accounts[i].data.borrow())?.amount;
-
-            let amount: u64 = (((pool_token_amount as u128) * (pool_asset_amount as u128))
-                / (total_pooltokens as u128))
-                .try_into()
-                .map_err(|_| BonfidaBotError::Overflow)?;
-
-            if amount == 0 {
-                continue;
-            }
-            let instruction = transfer(
-                spl_token_account.key,
-                pool_assets_accounts[i].key,
-                target_assets_accounts[i].key,
-                pool_account.key,
-                &[],
-                amount,
-            )?;
-            invoke_signed(
-                &instruction,
-                &[
-                    spl_token_account.clone(),
-                    pool_assets_accounts[i].clone(),
-                    target_assets_accounts[i].clone(),
-                    pool_account.clone(),
-                ],
-                &[&[&pool_seed]],
+            // The settled funds are sent to the pool asset accounts, so they are checked beforehand
+            Self::check_pool_asset_accounts(pool_account, &pool_assets, &pool_assets_accounts)?;
+
+            locked_amounts = Self::free_redeemed_share(
+                spl_token_account,
+                dex_program,
+                mint_account,
+                pool_account,
+                &mut pool_header,
+                &pool_assets,
+                &pool_assets_accounts,
+                accounts_iter,
+                pool_seed,
+                pool_token_amount,
+                pending_orders,
             )?;
         }
 
-        // Burn the redeemed pooltokens
-        let instruction = burn(
-            spl_token_account.key,
-            &source_pool_token_account.key,
-            mint_account.key,
-            &source_pool_token_owner_account.key,
-            &[],
+        Self::pay_out_redeem(
+            spl_token_account,
+            mint_account,
+            source_pool_token_owner_account,
+            source_pool_token_account,
+            pool_account,
+            &mut pool_header,
+            &pool_assets,
+            &pool_assets_accounts,
+            &target_assets_accounts,
+            &locked_amounts,
+            pool_seed,
             pool_token_amount,
-        )?;
-
-        invoke(
-            &instruction,
-            &[
-                spl_token_account.clone(),
-                source_pool_token_account.clone(),
-                mint_account.clone(),
-                source_pool_token_owner_account.clone(),
-            ],
-        )?;
-
-        if pool_token_amount == total_pooltokens {
-            // Reset the pool data, keeping the pool header mostly intact to preserve pool seeds
-            fill_slice(&mut pool_account.data.borrow_mut()[PoolHeader::LEN..], 0u8);
-            pool_header.status = PoolStatus::Uninitialized;
-            pool_header.pack_into_slice(&mut pool_account.data.borrow_mut()[..PoolHeader::LEN]);
-        }
-
-        Ok(())
+            &min_amounts_out,
+            EventKind::Redeem,
+            source_pool_token_owner_account.key,
+        )
     }
 
-    pub fn process_collect_fees(
+    pub fn process_settle_and_redeem(
         program_id: &Pubkey,
         accounts: &[AccountInfo],
         pool_seed: [u8; 32],
+        // The amount of pooltokens wished to be redeemed
+        pool_token_amount: u64,
+        min_amounts_out: Vec<u64>,
     ) -> ProgramResult {
         let accounts_iter = &mut accounts.iter();
+
         let spl_token_account = next_account_info(accounts_iter)?;
         if spl_token_account.key != &spl_token::id() {
             msg!("Incorrect spl token program provided");
-            return Err(ProgramError::IncorrectProgramId)
+            return Err(ProgramError::IncorrectProgramId);
         }
         let clock_sysvar_account = next_account_info(accounts_iter)?;
-        let pool_account = next_account_info(accounts_iter)?;
+        let dex_program = next_account_info(accounts_iter)?;
 
         let mint_account = next_account_info(accounts_iter)?;
-        let signal_provider_pt_account = next_account_info(accounts_iter)?;
-        let bonfida_fee_pt_account = next_account_info(accounts_iter)?;
-        let bonfida_bnb_pt_account = next_account_info(accounts_iter)?;
+        let source_pool_token_owner_account = next_account_info(accounts_iter)?;
+        let source_pool_token_account = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
 
-        check_pool_key(program_id, pool_account.key, &pool_seed)?;
+        let mut pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+        let asset_offset = pool_header.asset_offset as usize;
+        let assets_end = get_assets_end(&pool_header);
+        let pool_assets = unpack_assets(
+            pool_account
+                .data
+                .borrow()
+                .get(asset_offset..assets_end)
+                .ok_or(ProgramError::InvalidAccountData)?,
+        )?;
+        let nb_assets = pool_assets.len();
 
-        let pool_mint_key =
-            Pubkey::create_program_address(&[&pool_seed, &[1]], &program_id).unwrap();
-        if pool_mint_key != *mint_account.key {
-            msg!("Provided mint account is invalid.");
-            return Err(ProgramError::InvalidArgument);
+        let mut pool_assets_accounts: Vec<&AccountInfo> = vec![];
+        let mut target_assets_accounts: Vec<&AccountInfo> = vec![];
+        for _ in 0..nb_assets {
+            pool_assets_accounts.push(next_account_info(accounts_iter)?)
+        }
+        for _ in 0..nb_assets {
+            target_assets_accounts.push(next_account_info(accounts_iter)?)
         }
 
-        let mut pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+        if min_amounts_out.len() != nb_assets {
+            msg!("A minimum amount should be given for each pool asset.");
+            return Err(ProgramError::InvalidArgument);
+        }
 
-        let signal_provider_pt_key =
-            get_associated_token_address(&pool_header.signal_provider, &pool_mint_key);
-        let bonfida_fee_pt_key =
-            get_associated_token_address(&Pubkey::from_str(BONFIDA_FEE).unwrap(), &pool_mint_key);
-        let bonfida_bnb_pt_key =
-            get_associated_token_address(&Pubkey::from_str(BONFIDA_BNB).unwrap(), &pool_mint_key);
+        // Safety verifications
+        check_pool_key(&program_id, &pool_account.key, &pool_seed)?;
+        let pool_mint_key =
+            Pubkey::create_program_address(&[&pool_seed, &[1]], &program_id).unwrap();
+        if pool_mint_key != *mint_account.key {
+            msg!("Provided mint account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if !source_pool_token_owner_account.is_signer {
+            msg!("Source pooltoken account owner should be a signer.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if *pool_account.owner != *program_id {
+            msg!("Program should own pool account");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if &pool_header.serum_program_id != dex_program.key {
+            msg!("The provided serum program account is invalid for this pool.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let redeem_fee_accounts = Self::unpack_redeem_fee_accounts(
+            program_id,
+            &pool_header,
+            &pool_mint_key,
+            accounts_iter,
+        )?;
+        // The settled funds are sent to the pool asset accounts, so they are checked beforehand
+        Self::check_pool_asset_accounts(pool_account, &pool_assets, &pool_assets_accounts)?;
+        let pending_orders = match pool_header.status {
+            PoolStatus::Locked | PoolStatus::LockedPendingOrder(_) => {
+                msg!("The signal provider has currently locked the pool. Unlock the pool before redeeming.");
+                return Err(BonfidaBotError::LockedOperation.into());
+            }
+            PoolStatus::PendingOrder(n) => n.get(),
+            _ => 0,
+        };
 
-        if signal_provider_pt_account.key != &signal_provider_pt_key {
-            msg!("The provided signal provider pool token account is invalid.");
+        let current_timestamp =
+            Clock::from_account_info(clock_sysvar_account)?.unix_timestamp as u64;
+        if current_timestamp - pool_header.last_fee_collection_timestamp
+            > pool_header.fee_collection_period
+        {
+            msg!("Fees should be collected before redeeming.");
+            return Err(BonfidaBotError::LockedOperation.into());
+        }
+
+        let pool_token_amount = Self::transfer_redeem_fees(
+            spl_token_account,
+            source_pool_token_owner_account,
+            source_pool_token_account,
+            &pool_header,
+            &redeem_fee_accounts,
+            pool_seed,
+            pool_token_amount,
+        )?;
+
+        // Settle every pending OpenOrders account of the pool into the pool asset accounts. Only
+        // the orders needed to pay out the redeemed share are cancelled, the others keep resting.
+        let locked_amounts = if pending_orders != 0 {
+            Self::free_redeemed_share(
+                spl_token_account,
+                dex_program,
+                mint_account,
+                pool_account,
+                &mut pool_header,
+                &pool_assets,
+                &pool_assets_accounts,
+                accounts_iter,
+                pool_seed,
+                pool_token_amount,
+                pending_orders,
+            )?
+        } else {
+            vec![0; nb_assets]
+        };
+
+        Self::pay_out_redeem(
+            spl_token_account,
+            mint_account,
+            source_pool_token_owner_account,
+            source_pool_token_account,
+            pool_account,
+            &mut pool_header,
+            &pool_assets,
+            &pool_assets_accounts,
+            &target_assets_accounts,
+            &locked_amounts,
+            pool_seed,
+            pool_token_amount,
+            &min_amounts_out,
+            EventKind::SettleAndRedeem,
+            source_pool_token_owner_account.key,
+        )
+    }
+
+    pub fn process_request_redeem(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+        pool_token_amount: u64,
+        min_amounts_out: Vec<u64>,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let system_program_account = next_account_info(accounts_iter)?;
+        let spl_token_account = next_account_info(accounts_iter)?;
+        if spl_token_account.key != &spl_token::id() {
+            msg!("Incorrect spl token program provided");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let rent_sysvar_account = next_account_info(accounts_iter)?;
+        let payer_account = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
+        let redeem_queue_account = next_account_info(accounts_iter)?;
+        let escrow_account = next_account_info(accounts_iter)?;
+        let source_pool_token_owner_account = next_account_info(accounts_iter)?;
+        let source_pool_token_account = next_account_info(accounts_iter)?;
+
+        check_pool_key(program_id, pool_account.key, &pool_seed)?;
+        if *pool_account.owner != *program_id {
+            msg!("Program should own pool account");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+        if pool_header.status == PoolStatus::Uninitialized {
+            msg!("The pool is not initialized.");
+            return Err(ProgramError::UninitializedAccount);
+        }
+        if pool_token_amount == 0 {
+            msg!("Redeem requests should be for a non-zero amount of pooltokens.");
+            return Err(BonfidaBotError::OperationTooSmall.into());
+        }
+        let nb_assets = unpack_assets(
+            pool_account
+                .data
+                .borrow()
+                .get(pool_header.asset_offset as usize..get_assets_end(&pool_header))
+                .ok_or(ProgramError::InvalidAccountData)?,
+        )?
+        .len();
+        if min_amounts_out.len() != nb_assets {
+            msg!("A minimum amount should be given for each pool asset.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if nb_assets > MAX_REDEEM_REQUEST_ASSETS {
+            msg!("Redeem requests can't be queued for pools holding this many assets.");
             return Err(ProgramError::InvalidArgument);
         }
+        Self::check_escrow_key(program_id, &pool_seed, pool_account, escrow_account)?;
 
-        if bonfida_fee_pt_account.key != &bonfida_fee_pt_key {
-            msg!("The provided bonfida fee pool token account is invalid.");
+        let (redeem_queue_key, bump) = get_redeem_queue_key(program_id, &pool_seed);
+        if redeem_queue_account.key != &redeem_queue_key {
+            msg!("Provided redeem queue account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if redeem_queue_account.data_len() == 0 {
+            create_pda_account(
+                system_program_account,
+                payer_account,
+                redeem_queue_account,
+                &Rent::from_account_info(rent_sysvar_account)?,
+                REDEEM_QUEUE_LEN,
+                program_id,
+                &[&pool_seed, REDEEM_QUEUE_SEED, &[bump]],
+            )?;
+        } else if redeem_queue_account.owner != program_id {
+            msg!("Program should own the redeem queue account");
             return Err(ProgramError::InvalidArgument);
         }
 
-        if bonfida_bnb_pt_account.key != &bonfida_bnb_pt_key {
-            msg!("The provided bonfida buy and burn pool token account is invalid.");
+        let mut queue_header = RedeemQueueHeader::unpack_from_slice(
+            &redeem_queue_account.data.borrow()[..RedeemQueueHeader::LEN],
+        )?;
+        if queue_header.tail - queue_header.head == REDEEM_QUEUE_CAPACITY as u64 {
+            msg!("The pool's redeem queue is full, try again once it has been processed.");
+            return Err(BonfidaBotError::RedeemQueueFull.into());
+        }
+        RedeemRequest {
+            owner: *source_pool_token_owner_account.key,
+            pool_token_amount,
+            min_amounts_out,
+        }
+        .pack_into_slice(get_redeem_request_slice(
+            &mut redeem_queue_account.data.borrow_mut(),
+            queue_header.tail,
+        )?);
+        queue_header.tail += 1;
+        queue_header
+            .pack_into_slice(&mut redeem_queue_account.data.borrow_mut()[..RedeemQueueHeader::LEN]);
+
+        let instruction = transfer(
+            spl_token_account.key,
+            source_pool_token_account.key,
+            escrow_account.key,
+            source_pool_token_owner_account.key,
+            &[],
+            pool_token_amount,
+        )?;
+        invoke(
+            &instruction,
+            &[
+                spl_token_account.clone(),
+                source_pool_token_account.clone(),
+                escrow_account.clone(),
+                source_pool_token_owner_account.clone(),
+            ],
+        )?;
+
+        PoolEvent {
+            kind: EventKind::RequestRedeem,
+            pool: *pool_account.key,
+            actor: *source_pool_token_owner_account.key,
+            amounts: vec![pool_token_amount],
+            pool_token_supply: None,
+            status: pool_header.status,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    pub fn process_redeem_queue(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let spl_token_account = next_account_info(accounts_iter)?;
+        if spl_token_account.key != &spl_token::id() {
+            msg!("Incorrect spl token program provided");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let clock_sysvar_account = next_account_info(accounts_iter)?;
+        let mint_account = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
+        let redeem_queue_account = next_account_info(accounts_iter)?;
+        let escrow_account = next_account_info(accounts_iter)?;
+
+        let mut pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+        let asset_offset = pool_header.asset_offset as usize;
+        let assets_end = get_assets_end(&pool_header);
+        let pool_assets = unpack_assets(
+            pool_account
+                .data
+                .borrow()
+                .get(asset_offset..assets_end)
+                .ok_or(ProgramError::InvalidAccountData)?,
+        )?;
+        let nb_assets = pool_assets.len();
+
+        let mut pool_assets_accounts: Vec<&AccountInfo> = vec![];
+        for _ in 0..nb_assets {
+            pool_assets_accounts.push(next_account_info(accounts_iter)?)
+        }
+
+        // Safety verifications
+        check_pool_key(&program_id, &pool_account.key, &pool_seed)?;
+        if *pool_account.owner != *program_id {
+            msg!("Program should own pool account");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let pool_mint_key =
+            Pubkey::create_program_address(&[&pool_seed, &[1]], &program_id).unwrap();
+        if pool_mint_key != *mint_account.key {
+            msg!("Provided mint account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if &get_redeem_queue_key(program_id, &pool_seed).0 != redeem_queue_account.key
+            || redeem_queue_account.owner != program_id
+        {
+            msg!("Provided redeem queue account is invalid");
             return Err(ProgramError::InvalidArgument);
         }
+        Self::check_escrow_key(program_id, &pool_seed, pool_account, escrow_account)?;
+        // The pool asset balances decide whether a request's minimum amounts can be met
+        Self::check_pool_asset_accounts(pool_account, &pool_assets, &pool_assets_accounts)?;
+        let redeem_fee_accounts = Self::unpack_redeem_fee_accounts(
+            program_id,
+            &pool_header,
+            &pool_mint_key,
+            accounts_iter,
+        )?;
+        if pool_header.status != PoolStatus::Unlocked {
+            msg!("Queued redeem requests can only be processed once the pool is unlocked.");
+            return Err(BonfidaBotError::LockedOperation.into());
+        }
 
         let current_timestamp =
             Clock::from_account_info(clock_sysvar_account)?.unix_timestamp as u64;
-        let fee_cycles_to_collect = (current_timestamp - pool_header.last_fee_collection_timestamp)
-            / pool_header.fee_collection_period;
-
-        if fee_cycles_to_collect == 0 {
-            msg!("There are currently no fees to collect");
+        if current_timestamp - pool_header.last_fee_collection_timestamp
+            > pool_header.fee_collection_period
+        {
+            msg!("Fees should be collected before redeeming.");
             return Err(BonfidaBotError::LockedOperation.into());
         }
 
-        // 2**-16 = 1.52587890625e-5_f32
-        // let feeless_ratio_u16 = (((!pool_header.fee_ratio) as f32 * 1.52587890625e-5_f32).powi(
-        //     fee_cycles_to_collect
-        //         .try_into()
-        //         .map_err(|_| BonfidaBotError::Overflow)?,
-        // ) * 65536.) as u16;
-        let feeless_ratio_u16 =
-            pow_fixedpoint_u16(!pool_header.fee_ratio as u32, fee_cycles_to_collect) as u16;
-        let collect_ratio = (!feeless_ratio_u16) as u128;
-        let feeless_ratio = feeless_ratio_u16 as u128;
-        pool_header.last_fee_collection_timestamp +=
-            fee_cycles_to_collect * pool_header.fee_collection_period;
+        let mut queue_header = RedeemQueueHeader::unpack_from_slice(
+            &redeem_queue_account.data.borrow()[..RedeemQueueHeader::LEN],
+        )?;
+        let initial_head = queue_header.head;
+        // Requests are processed in order, for as long as their target accounts are provided
+        while queue_header.head < queue_header.tail {
+            let request =
+                unpack_redeem_request(&redeem_queue_account.data.borrow(), queue_header.head)?;
+            if request.pool_token_amount != 0 {
+                let mut target_accounts: Vec<&AccountInfo> = vec![];
+                for mint_address in pool_assets
+                    .iter()
+                    .map(|pool_asset| &pool_asset.mint_address)
+                    .chain(std::iter::once(mint_account.key))
+                {
+                    let target_account = match next_account_info(accounts_iter) {
+                        Ok(account) => account,
+                        Err(_) => break,
+                    };
+                    if target_account.key
+                        != &get_associated_token_address(&request.owner, mint_address)
+                    {
+                        msg!("Redeem requests are paid out to the associated token accounts of their owner.");
+                        return Err(ProgramError::InvalidArgument);
+                    }
+                    target_accounts.push(target_account);
+                }
+                if target_accounts.len() <= nb_assets {
+                    break;
+                }
+                let target_pool_token_account = target_accounts.pop().unwrap();
+
+                // A request which can't be paid out, because one of its owner's accounts has been
+                // closed or frozen or because its minimum amounts can't be met, is refunded so that
+                // it doesn't block the rest of the queue
+                if !target_accounts
+                    .iter()
+                    .all(|account| Self::can_receive_tokens(account))
+                    || !Self::meets_min_amounts_out(
+                        mint_account,
+                        &pool_header,
+                        &pool_assets_accounts,
+                        request.pool_token_amount,
+                        &request.min_amounts_out,
+                    )?
+                {
+                    msg!("Refunding a redeem request which can't be paid out.");
+                    let instruction = transfer(
+                        spl_token_account.key,
+                        escrow_account.key,
+                        target_pool_token_account.key,
+                        pool_account.key,
+                        &[],
+                        request.pool_token_amount,
+                    )?;
+                    invoke_signed(
+                        &instruction,
+                        &[
+                            spl_token_account.clone(),
+                            escrow_account.clone(),
+                            target_pool_token_account.clone(),
+                            pool_account.clone(),
+                        ],
+                        &[&[&pool_seed]],
+                    )?;
+                    PoolEvent {
+                        kind: EventKind::CancelRedeemRequest,
+                        pool: *pool_account.key,
+                        actor: request.owner,
+                        amounts: vec![request.pool_token_amount],
+                        pool_token_supply: None,
+                        status: pool_header.status,
+                    }
+                    .emit();
+                    queue_header.head += 1;
+                    continue;
+                }
 
-        let total_pooltokens = Mint::unpack(&mint_account.data.borrow())?.supply as u128;
+                let pool_token_amount = Self::transfer_redeem_fees(
+                    spl_token_account,
+                    pool_account,
+                    escrow_account,
+                    &pool_header,
+                    &redeem_fee_accounts,
+                    pool_seed,
+                    request.pool_token_amount,
+                )?;
+                // Pool assets added since the request was queued have no minimum amount
+                let mut min_amounts_out = request.min_amounts_out;
+                min_amounts_out.resize(nb_assets, 0);
+                Self::pay_out_redeem(
+                    spl_token_account,
+                    mint_account,
+                    pool_account,
+                    escrow_account,
+                    pool_account,
+                    &mut pool_header,
+                    &pool_assets,
+                    &pool_assets_accounts,
+                    &target_accounts,
+                    &vec![0; nb_assets],
+                    pool_seed,
+                    pool_token_amount,
+                    &min_amounts_out,
+                    EventKind::ProcessRedeemQueue,
+                    &request.owner,
+                )?;
+            }
+            queue_header.head += 1;
+        }
+        if queue_header.head == initial_head {
+            msg!("No queued redeem request could be processed.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        queue_header
+            .pack_into_slice(&mut redeem_queue_account.data.borrow_mut()[..RedeemQueueHeader::LEN]);
+
+        Ok(())
+    }
+
+    pub fn process_cancel_redeem_request(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+        request_index: u64,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let spl_token_account = next_account_info(accounts_iter)?;
+        if spl_token_account.key != &spl_token::id() {
+            msg!("Incorrect spl token program provided");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let pool_account = next_account_info(accounts_iter)?;
+        let redeem_queue_account = next_account_info(accounts_iter)?;
+        let escrow_account = next_account_info(accounts_iter)?;
+        let owner_account = next_account_info(accounts_iter)?;
+        let target_pool_token_account = next_account_info(accounts_iter)?;
+
+        check_pool_key(program_id, pool_account.key, &pool_seed)?;
+        if &get_redeem_queue_key(program_id, &pool_seed).0 != redeem_queue_account.key
+            || redeem_queue_account.owner != program_id
+        {
+            msg!("Provided redeem queue account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        Self::check_escrow_key(program_id, &pool_seed, pool_account, escrow_account)?;
+        if !owner_account.is_signer {
+            msg!("The request owner should be a signer.");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let mut queue_header = RedeemQueueHeader::unpack_from_slice(
+            &redeem_queue_account.data.borrow()[..RedeemQueueHeader::LEN],
+        )?;
+        if (request_index < queue_header.head) | (request_index >= queue_header.tail) {
+            msg!("The request is not in the queue.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let mut request =
+            unpack_redeem_request(&redeem_queue_account.data.borrow(), request_index)?;
+        if &request.owner != owner_account.key {
+            msg!("Only the request owner can cancel it.");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if request.pool_token_amount == 0 {
+            msg!("The request has already been cancelled.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let pool_token_amount = request.pool_token_amount;
+        request.pool_token_amount = 0;
+        request.pack_into_slice(get_redeem_request_slice(
+            &mut redeem_queue_account.data.borrow_mut(),
+            request_index,
+        )?);
+
+        // Free up the cancelled requests at the front of the queue
+        while queue_header.head < queue_header.tail
+            && unpack_redeem_request(&redeem_queue_account.data.borrow(), queue_header.head)?
+                .pool_token_amount
+                == 0
+        {
+            queue_header.head += 1;
+        }
+        queue_header
+            .pack_into_slice(&mut redeem_queue_account.data.borrow_mut()[..RedeemQueueHeader::LEN]);
+
+        let instruction = transfer(
+            spl_token_account.key,
+            escrow_account.key,
+            target_pool_token_account.key,
+            pool_account.key,
+            &[],
+            pool_token_amount,
+        )?;
+        invoke_signed(
+            &instruction,
+            &[
+                spl_token_account.clone(),
+                escrow_account.clone(),
+                target_pool_token_account.clone(),
+                pool_account.clone(),
+            ],
+            &[&[&pool_seed]],
+        )?;
+
+        PoolEvent {
+            kind: EventKind::CancelRedeemRequest,
+            pool: *pool_account.key,
+            actor: *owner_account.key,
+            amounts: vec![pool_token_amount],
+            pool_token_supply: None,
+            status: PoolHeader::unpack_unchecked(&pool_account.data.borrow()[..PoolHeader::LEN])?
+                .status,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    pub fn process_release_deposit(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let spl_token_account = next_account_info(accounts_iter)?;
+        if spl_token_account.key != &spl_token::id() {
+            msg!("Incorrect spl token program provided");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let clock_sysvar_account = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
+        let deposit_receipt_account = next_account_info(accounts_iter)?;
+        let escrow_account = next_account_info(accounts_iter)?;
+        let owner_account = next_account_info(accounts_iter)?;
+        let target_pool_token_account = next_account_info(accounts_iter)?;
+
+        check_pool_key(program_id, pool_account.key, &pool_seed)?;
+        if *pool_account.owner != *program_id {
+            msg!("Program should own pool account");
+            return Err(ProgramError::InvalidArgument);
+        }
+        Self::check_escrow_key(program_id, &pool_seed, pool_account, escrow_account)?;
+        if !owner_account.is_signer {
+            msg!("The deposit receipt owner should be a signer.");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if &get_deposit_receipt_key(program_id, &pool_seed, owner_account.key).0
+            != deposit_receipt_account.key
+            || deposit_receipt_account.owner != program_id
+        {
+            msg!("Provided deposit receipt account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+        let receipt = DepositReceipt::unpack(&deposit_receipt_account.data.borrow())?;
+        if receipt.pool_token_amount == 0 {
+            msg!("No pooltokens are locked up for this depositor.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let current_timestamp =
+            Clock::from_account_info(clock_sysvar_account)?.unix_timestamp as u64;
+        let release_timestamp = receipt
+            .deposit_timestamp
+            .saturating_add(pool_header.lockup_period);
+        if current_timestamp < release_timestamp {
+            msg!("The deposited pooltokens are still locked up.");
+            return Err(BonfidaBotError::LockedOperation.into());
+        }
+
+        let instruction = transfer(
+            spl_token_account.key,
+            escrow_account.key,
+            target_pool_token_account.key,
+            pool_account.key,
+            &[],
+            receipt.pool_token_amount,
+        )?;
+        invoke_signed(
+            &instruction,
+            &[
+                spl_token_account.clone(),
+                escrow_account.clone(),
+                target_pool_token_account.clone(),
+                pool_account.clone(),
+            ],
+            &[&[&pool_seed]],
+        )?;
+
+        PoolEvent {
+            kind: EventKind::ReleaseDeposit,
+            pool: *pool_account.key,
+            actor: *owner_account.key,
+            amounts: vec![receipt.pool_token_amount],
+            pool_token_supply: None,
+            status: pool_header.status,
+        }
+        .emit();
+
+        // The receipt is created again by the depositor's next locked up deposit
+        Self::close_program_account(deposit_receipt_account, owner_account)?;
+
+        Ok(())
+    }
+
+    pub fn process_redeem_to_single_asset(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+        // The amount of pooltokens wished to be redeemed
+        pool_token_amount: u64,
+        min_amount_out: u64,
+        limit_prices: Vec<u64>,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let spl_token_account = next_account_info(accounts_iter)?;
+        if spl_token_account.key != &spl_token::id() {
+            msg!("Incorrect spl token program provided");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let clock_sysvar_account = next_account_info(accounts_iter)?;
+        let rent_sysvar_account = next_account_info(accounts_iter)?;
+        let dex_program = next_account_info(accounts_iter)?;
+
+        let mint_account = next_account_info(accounts_iter)?;
+        let source_pool_token_owner_account = next_account_info(accounts_iter)?;
+        let source_pool_token_account = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
+
+        check_pool_key(&program_id, &pool_account.key, &pool_seed)?;
+        if *pool_account.owner != *program_id {
+            msg!("Program should own pool account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+        let markets = (0..pool_header.number_of_markets)
+            .map(|i| {
+                unpack_pool_market(
+                    &pool_account.data.borrow()[pool_header.market_offset as usize..],
+                    i,
+                )
+            })
+            .collect::<Result<Vec<PoolMarket>, ProgramError>>()?;
+        let asset_offset = pool_header.asset_offset as usize;
+        let assets_end = get_assets_end(&pool_header);
+        let pool_assets = unpack_assets(
+            pool_account
+                .data
+                .borrow()
+                .get(asset_offset..assets_end)
+                .ok_or(ProgramError::InvalidAccountData)?,
+        )?;
+        let nb_assets = pool_assets.len();
+
+        let mut pool_assets_accounts: Vec<&AccountInfo> = vec![];
+        for _ in 0..nb_assets {
+            pool_assets_accounts.push(next_account_info(accounts_iter)?)
+        }
+        let target_account = next_account_info(accounts_iter)?;
+
+        if limit_prices.len() != nb_assets {
+            msg!("A limit price should be given for each pool asset.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Safety verifications
+        let pool_mint_key =
+            Pubkey::create_program_address(&[&pool_seed, &[1]], &program_id).unwrap();
+        if pool_mint_key != *mint_account.key {
+            msg!("Provided mint account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if !source_pool_token_owner_account.is_signer {
+            msg!("Source pooltoken account owner should be a signer.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if &pool_header.serum_program_id != dex_program.key {
+            msg!("The provided serum program account is invalid for this pool.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let redeem_fee_accounts = Self::unpack_redeem_fee_accounts(
+            program_id,
+            &pool_header,
+            &pool_mint_key,
+            accounts_iter,
+        )?;
+        match pool_header.status {
+            PoolStatus::Locked => {
+                msg!("The signal provider has currently locked the pool. Unlock the pool before redeeming.");
+                return Err(BonfidaBotError::LockedOperation.into());
+            }
+            PoolStatus::PendingOrder(_) | PoolStatus::LockedPendingOrder(_) => {
+                msg!("The pool has one or more pending orders. No buy-outs are possible for now. Try again later.");
+                return Err(BonfidaBotError::LockedOperation.into());
+            }
+            _ => (),
+        };
+
+        let current_timestamp =
+            Clock::from_account_info(clock_sysvar_account)?.unix_timestamp as u64;
+        if current_timestamp - pool_header.last_fee_collection_timestamp
+            > pool_header.fee_collection_period
+        {
+            msg!("Fees should be collected before redeeming.");
+            return Err(BonfidaBotError::LockedOperation.into());
+        }
+
+        let pool_token_amount = Self::transfer_redeem_fees(
+            spl_token_account,
+            source_pool_token_owner_account,
+            source_pool_token_account,
+            &pool_header,
+            &redeem_fee_accounts,
+            pool_seed,
+            pool_token_amount,
+        )?;
+
+        let total_pooltokens = Mint::unpack(&mint_account.data.borrow())?.supply;
+        let total_user_pooltokens =
+            Account::unpack(&source_pool_token_account.data.borrow())?.amount;
+
+        if total_user_pooltokens < pool_token_amount {
+            msg!("Insufficient pool token funds");
+            return Err(ProgramError::InsufficientFunds);
+        }
+
+        let target_mint = Account::unpack(&target_account.data.borrow())?.mint;
+        let target_balance_before = Account::unpack(&target_account.data.borrow())?.amount;
+
+        // Execute buy out
+        for i in 0..nb_assets {
+            let pool_asset_key =
+                get_associated_token_address(&pool_account.key, &pool_assets[i].mint_address);
+
+            if pool_asset_key != *pool_assets_accounts[i].key {
+                msg!("Provided pool asset account is invalid");
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            let pool_asset_amount = Account::unpack(&pool_assets_accounts[i].data.borrow())?.amount;
+
+            let amount: u64 = (((pool_token_amount as u128) * (pool_asset_amount as u128))
+                / (total_pooltokens as u128))
+                .try_into()
+                .map_err(|_| BonfidaBotError::Overflow)?;
+
+            // The target token is paid out as is, other assets are sold into it afterwards
+            let (asset_target_account, swap) = if pool_assets[i].mint_address == target_mint {
+                (target_account, None)
+            } else {
+                let mut swap_accounts: Vec<&AccountInfo> = vec![];
+                for _ in 0..9 {
+                    swap_accounts.push(next_account_info(accounts_iter)?)
+                }
+                let coin_source_account = next_account_info(accounts_iter)?;
+
+                let pool_market = match markets
+                    .iter()
+                    .find(|pool_market| &pool_market.address == swap_accounts[0].key)
+                {
+                    Some(pool_market) => pool_market,
+                    None => {
+                        msg!("The given market account is not authorized.");
+                        return Err(ProgramError::InvalidArgument);
+                    }
+                };
+                if pool_market.coin_mint != pool_assets[i].mint_address
+                    || pool_market.pc_mint != target_mint
+                {
+                    msg!("The given market cannot be used to sell this pool asset for the target token.");
+                    return Err(ProgramError::InvalidArgument);
+                }
+                (coin_source_account, Some((swap_accounts, pool_market)))
+            };
+
+            if amount == 0 {
+                continue;
+            }
+            let instruction = transfer(
+                spl_token_account.key,
+                pool_assets_accounts[i].key,
+                asset_target_account.key,
+                pool_account.key,
+                &[],
+                amount,
+            )?;
+            invoke_signed(
+                &instruction,
+                &[
+                    spl_token_account.clone(),
+                    pool_assets_accounts[i].clone(),
+                    asset_target_account.clone(),
+                    pool_account.clone(),
+                ],
+                &[&[&pool_seed]],
+            )?;
+
+            if let Some((swap_accounts, pool_market)) = swap {
+                let lots_to_sell = amount / pool_market.coin_lot_size;
+                if lots_to_sell == 0 {
+                    continue;
+                }
+                let coin_balance_before =
+                    Account::unpack(&asset_target_account.data.borrow())?.amount;
+
+                Self::swap_immediate(
+                    spl_token_account,
+                    rent_sysvar_account,
+                    dex_program,
+                    source_pool_token_owner_account,
+                    &swap_accounts,
+                    asset_target_account,
+                    target_account,
+                    Side::Ask,
+                    limit_prices[i],
+                    lots_to_sell,
+                    // Only bids are bounded by this quantity
+                    u64::MAX,
+                )?;
+
+                let coin_sold = coin_balance_before
+                    .saturating_sub(Account::unpack(&asset_target_account.data.borrow())?.amount);
+                if coin_sold < lots_to_sell * pool_market.coin_lot_size {
+                    msg!("The order to sell a pool asset wasn't entirely filled.");
+                    return Err(BonfidaBotError::OrderNotFilled.into());
+                }
+            }
+        }
+
+        let amount_out = Account::unpack(&target_account.data.borrow())?
+            .amount
+            .saturating_sub(target_balance_before);
+        if amount_out < min_amount_out {
+            msg!("The redeem would pay out less than the minimum amount of target token.");
+            return Err(BonfidaBotError::MinAmountOutNotMet.into());
+        }
+
+        // Burn the redeemed pooltokens
+        let instruction = burn(
+            spl_token_account.key,
+            &source_pool_token_account.key,
+            mint_account.key,
+            &source_pool_token_owner_account.key,
+            &[],
+            pool_token_amount,
+        )?;
+
+        invoke(
+            &instruction,
+            &[
+                spl_token_account.clone(),
+                source_pool_token_account.clone(),
+                mint_account.clone(),
+                source_pool_token_owner_account.clone(),
+            ],
+        )?;
+
+        if pool_token_amount == total_pooltokens {
+            Self::reset_drained_pool(pool_account, &mut pool_header)?;
+        }
+
+        PoolEvent {
+            kind: EventKind::RedeemToSingleAsset,
+            pool: *pool_account.key,
+            actor: *source_pool_token_owner_account.key,
+            amounts: vec![pool_token_amount, amount_out],
+            pool_token_supply: Some((
+                total_pooltokens,
+                Mint::unpack(&mint_account.data.borrow())?.supply,
+            )),
+            status: pool_header.status,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    pub fn process_collect_fees(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let spl_token_account = next_account_info(accounts_iter)?;
+        if spl_token_account.key != &spl_token::id() {
+            msg!("Incorrect spl token program provided");
+            return Err(ProgramError::IncorrectProgramId)
+        }
+        let clock_sysvar_account = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
+
+        let mint_account = next_account_info(accounts_iter)?;
+        let signal_provider_pt_account = next_account_info(accounts_iter)?;
+        let config_account = next_account_info(accounts_iter)?;
+
+        check_pool_key(program_id, pool_account.key, &pool_seed)?;
+
+        let pool_mint_key =
+            Pubkey::create_program_address(&[&pool_seed, &[1]], &program_id).unwrap();
+        if pool_mint_key != *mint_account.key {
+            msg!("Provided mint account is invalid.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+
+        let (config, fee_recipient_pt_accounts) =
+            Self::unpack_fee_recipients(program_id, config_account, &pool_mint_key, accounts_iter)?;
+
+        let signal_provider_pt_key =
+            get_associated_token_address(&pool_header.signal_provider, &pool_mint_key);
+
+        if signal_provider_pt_account.key != &signal_provider_pt_key {
+            msg!("The provided signal provider pool token account is invalid.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let current_timestamp =
+            Clock::from_account_info(clock_sysvar_account)?.unix_timestamp as u64;
+        let fee_cycles_to_collect = (current_timestamp - pool_header.last_fee_collection_timestamp)
+            / pool_header.fee_collection_period;
+
+        if fee_cycles_to_collect == 0 {
+            msg!("There are currently no fees to collect");
+            return Err(BonfidaBotError::LockedOperation.into());
+        }
+
+        // 2**-16 = 1.52587890625e-5_f32
+        // let feeless_ratio_u16 = (((!pool_header.fee_ratio) as f32 * 1.52587890625e-5_f32).powi(
+        //     fee_cycles_to_collect
+        //         .try_into()
+        //         .map_err(|_| BonfidaBotError::Overflow)?,
+        // ) * 65536.) as u16;
+        let feeless_ratio_u16 =
+            pow_fixedpoint_u16(!pool_header.fee_ratio as u32, fee_cycles_to_collect) as u16;
+        let collect_ratio = (!feeless_ratio_u16) as u128;
+        let feeless_ratio = feeless_ratio_u16 as u128;
+        pool_header.last_fee_collection_timestamp +=
+            fee_cycles_to_collect * pool_header.fee_collection_period;
+
+        let total_pooltokens = Mint::unpack(&mint_account.data.borrow())?.supply as u128;
+
+        let mut tokens_to_mint = (collect_ratio * total_pooltokens / feeless_ratio) as u64;
+
+        if pool_header.performance_fee_ratio != 0 {
+            let total_value = Self::get_pool_value(pool_account, &pool_header, accounts_iter)?;
+            let (performance_fee, high_water_mark) = compute_performance_fee(
+                total_value,
+                (total_pooltokens as u64)
+                    .checked_add(tokens_to_mint)
+                    .ok_or(BonfidaBotError::Overflow)?,
+                pool_header.high_water_mark,
+                pool_header.performance_fee_ratio,
+            )?;
+            tokens_to_mint = tokens_to_mint
+                .checked_add(performance_fee)
+                .ok_or(BonfidaBotError::Overflow)?;
+            pool_header.high_water_mark = high_water_mark;
+        }
+
+        // Mint the required amount of pooltokens to the signal provider and the fee recipients
+        Self::mint_fees(
+            spl_token_account,
+            mint_account,
+            pool_account,
+            signal_provider_pt_account,
+            &fee_recipient_pt_accounts,
+            &config,
+            pool_seed,
+            tokens_to_mint,
+        )?;
+
+        let status = pool_header.status;
+        PoolHeader::pack(
+            pool_header,
+            &mut pool_account.data.borrow_mut()[..PoolHeader::LEN],
+        )?;
+
+        // Anyone can collect the fees, the pool stands for the actor
+        PoolEvent {
+            kind: EventKind::CollectFees,
+            pool: *pool_account.key,
+            actor: *pool_account.key,
+            amounts: vec![tokens_to_mint],
+            pool_token_supply: Some((
+                total_pooltokens as u64,
+                Mint::unpack(&mint_account.data.borrow())?.supply,
+            )),
+            status,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    pub fn process_lock(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let signal_provider_account = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
+
+        check_pool_key(program_id, pool_account.key, &pool_seed)?;
+
+        let mut pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+        check_signal_provider(&pool_header, signal_provider_account, true)?;
+
+        pool_header.status = match pool_header.status {
+            PoolStatus::Unlocked => PoolStatus::Locked,
+            PoolStatus::PendingOrder(n) => PoolStatus::LockedPendingOrder(n),
+            PoolStatus::Locked | PoolStatus::LockedPendingOrder(_) => {
+                msg!("The pool is already locked.");
+                return Err(ProgramError::InvalidArgument);
+            }
+            PoolStatus::Uninitialized => unreachable!(),
+        };
+        pool_header.pack_into_slice(&mut pool_account.data.borrow_mut()[..PoolHeader::LEN]);
+
+        PoolEvent {
+            kind: EventKind::Lock,
+            pool: *pool_account.key,
+            actor: *signal_provider_account.key,
+            amounts: vec![],
+            pool_token_supply: None,
+            status: pool_header.status,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    pub fn process_unlock(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let pool_account = next_account_info(accounts_iter)?;
+
+        check_pool_key(program_id, pool_account.key, &pool_seed)?;
+
+        let mut pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+
+        // Anyone can unlock the pool, pending orders are kept track of
+        pool_header.status = match pool_header.status {
+            PoolStatus::Locked => PoolStatus::Unlocked,
+            PoolStatus::LockedPendingOrder(n) => PoolStatus::PendingOrder(n),
+            PoolStatus::Unlocked | PoolStatus::PendingOrder(_) => {
+                msg!("The pool is not locked.");
+                return Err(ProgramError::InvalidArgument);
+            }
+            PoolStatus::Uninitialized => unreachable!(),
+        };
+        pool_header.pack_into_slice(&mut pool_account.data.borrow_mut()[..PoolHeader::LEN]);
+
+        // The instruction takes no signer, the pool stands for the actor
+        PoolEvent {
+            kind: EventKind::Unlock,
+            pool: *pool_account.key,
+            actor: *pool_account.key,
+            amounts: vec![],
+            pool_token_supply: None,
+            status: pool_header.status,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    pub fn process_set_deposit_limits(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+        max_pool_token_supply: u64,
+        min_deposit_pool_tokens: u64,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let signal_provider_account = next_account_info(accounts_iter)?;
+        let mint_account = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
+
+        check_pool_key(program_id, pool_account.key, &pool_seed)?;
+        if *pool_account.owner != *program_id {
+            msg!("Program should own pool account");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let pool_mint_key =
+            Pubkey::create_program_address(&[&pool_seed, &[1]], &program_id).unwrap();
+        if pool_mint_key != *mint_account.key {
+            msg!("Provided mint account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+        check_signal_provider(&pool_header, signal_provider_account, true)?;
+
+        let pool_token_supply = Mint::unpack(&mint_account.data.borrow())?.supply;
+        check_deposit_limits(
+            max_pool_token_supply,
+            min_deposit_pool_tokens,
+            pool_token_supply,
+        )?;
+
+        pool_header.max_pool_token_supply = max_pool_token_supply;
+        pool_header.min_deposit_pool_tokens = min_deposit_pool_tokens;
+        pool_header.pack_into_slice(&mut pool_account.data.borrow_mut()[..PoolHeader::LEN]);
+
+        PoolEvent {
+            kind: EventKind::SetDepositLimits,
+            pool: *pool_account.key,
+            actor: *signal_provider_account.key,
+            amounts: vec![max_pool_token_supply, min_deposit_pool_tokens],
+            pool_token_supply: Some((pool_token_supply, pool_token_supply)),
+            status: pool_header.status,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    pub fn process_set_whitelist(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+        gating_mint: Pubkey,
+        owners: Vec<Pubkey>,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let system_program_account = next_account_info(accounts_iter)?;
+        let rent_sysvar_account = next_account_info(accounts_iter)?;
+        let payer_account = next_account_info(accounts_iter)?;
+        let signal_provider_account = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
+        let whitelist_account = next_account_info(accounts_iter)?;
+
+        check_pool_key(program_id, pool_account.key, &pool_seed)?;
+        if *pool_account.owner != *program_id {
+            msg!("Program should own pool account");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let mut pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+        check_signal_provider(&pool_header, signal_provider_account, true)?;
+        if owners.len() > MAX_WHITELIST_OWNERS {
+            msg!("Too many owners for the pool's whitelist.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if owners.is_empty() && gating_mint == Pubkey::default() {
+            msg!("A whitelist should allow at least one owner or the holders of a gating mint.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let (whitelist_key, bump) = get_whitelist_key(program_id, &pool_seed);
+        if whitelist_account.key != &whitelist_key {
+            msg!("Provided whitelist account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if whitelist_account.data_len() == 0 {
+            create_pda_account(
+                system_program_account,
+                payer_account,
+                whitelist_account,
+                &Rent::from_account_info(rent_sysvar_account)?,
+                Whitelist::LEN,
+                program_id,
+                &[&pool_seed, WHITELIST_SEED, &[bump]],
+            )?;
+        } else if whitelist_account.owner != program_id {
+            msg!("Program should own the whitelist account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        Whitelist {
+            is_initialized: true,
+            gating_mint,
+            owners,
+        }
+        .pack_into_slice(&mut whitelist_account.data.borrow_mut());
+
+        pool_header.is_private = true;
+        pool_header.pack_into_slice(&mut pool_account.data.borrow_mut()[..PoolHeader::LEN]);
+
+        PoolEvent {
+            kind: EventKind::SetWhitelist,
+            pool: *pool_account.key,
+            actor: *signal_provider_account.key,
+            amounts: vec![],
+            pool_token_supply: None,
+            status: pool_header.status,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    pub fn process_set_metadata(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+        name: String,
+        description: String,
+        uri: String,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let system_program_account = next_account_info(accounts_iter)?;
+        let rent_sysvar_account = next_account_info(accounts_iter)?;
+        let clock_sysvar_account = next_account_info(accounts_iter)?;
+        let payer_account = next_account_info(accounts_iter)?;
+        let signal_provider_account = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
+        let metadata_account = next_account_info(accounts_iter)?;
+
+        check_pool_key(program_id, pool_account.key, &pool_seed)?;
+        if *pool_account.owner != *program_id {
+            msg!("Program should own pool account");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+        check_signal_provider(&pool_header, signal_provider_account, true)?;
+        if name.len() > MAX_METADATA_NAME_LEN
+            || description.len() > MAX_METADATA_DESCRIPTION_LEN
+            || uri.len() > MAX_METADATA_URI_LEN
+        {
+            msg!("The pool's metadata is too long.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let (metadata_key, bump) = get_metadata_key(program_id, &pool_seed);
+        if metadata_account.key != &metadata_key {
+            msg!("Provided metadata account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let creation_timestamp = if metadata_account.data_len() == 0 {
+            create_pda_account(
+                system_program_account,
+                payer_account,
+                metadata_account,
+                &Rent::from_account_info(rent_sysvar_account)?,
+                PoolMetadata::LEN,
+                program_id,
+                &[&pool_seed, METADATA_SEED, &[bump]],
+            )?;
+            Clock::from_account_info(clock_sysvar_account)?.unix_timestamp as u64
+        } else if metadata_account.owner != program_id {
+            msg!("Program should own the metadata account");
+            return Err(ProgramError::InvalidArgument);
+        } else {
+            PoolMetadata::unpack(&metadata_account.data.borrow())?.creation_timestamp
+        };
+
+        PoolMetadata {
+            is_initialized: true,
+            creation_timestamp,
+            name,
+            description,
+            uri,
+        }
+        .pack_into_slice(&mut metadata_account.data.borrow_mut());
+
+        PoolEvent {
+            kind: EventKind::SetMetadata,
+            pool: *pool_account.key,
+            actor: *signal_provider_account.key,
+            amounts: vec![],
+            pool_token_supply: None,
+            status: pool_header.status,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    pub fn process_remove_whitelist(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let signal_provider_account = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
+        let whitelist_account = next_account_info(accounts_iter)?;
+
+        check_pool_key(program_id, pool_account.key, &pool_seed)?;
+        if *pool_account.owner != *program_id {
+            msg!("Program should own pool account");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let mut pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+        check_signal_provider(&pool_header, signal_provider_account, true)?;
+        if &get_whitelist_key(program_id, &pool_seed).0 != whitelist_account.key
+            || whitelist_account.owner != program_id
+        {
+            msg!("Provided whitelist account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // The whitelist account is closed, its rent going back to the signal provider
+        let whitelist_lamports = whitelist_account.lamports();
+        **whitelist_account.lamports.borrow_mut() = 0;
+        **signal_provider_account.lamports.borrow_mut() = signal_provider_account
+            .lamports()
+            .checked_add(whitelist_lamports)
+            .ok_or(BonfidaBotError::Overflow)?;
+        fill_slice(&mut whitelist_account.data.borrow_mut(), 0u8);
+
+        pool_header.is_private = false;
+        pool_header.pack_into_slice(&mut pool_account.data.borrow_mut()[..PoolHeader::LEN]);
+
+        PoolEvent {
+            kind: EventKind::RemoveWhitelist,
+            pool: *pool_account.key,
+            actor: *signal_provider_account.key,
+            amounts: vec![],
+            pool_token_supply: None,
+            status: pool_header.status,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    pub fn process_propose_signal_provider(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+        new_signal_provider: Pubkey,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let signal_provider_account = next_account_info(accounts_iter)?;
+        let clock_sysvar_account = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
+
+        check_pool_key(program_id, pool_account.key, &pool_seed)?;
+
+        let mut pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+        check_signal_provider(&pool_header, signal_provider_account, true)?;
+
+        let current_timestamp =
+            Clock::from_account_info(clock_sysvar_account)?.unix_timestamp as u64;
+
+        // Proposing the default pubkey cancels the pending proposal
+        pool_header.pending_signal_provider = new_signal_provider;
+        pool_header.signal_provider_proposal_timestamp = current_timestamp;
+        pool_header.pack_into_slice(&mut pool_account.data.borrow_mut()[..PoolHeader::LEN]);
+
+        PoolEvent {
+            kind: EventKind::ProposeSignalProvider,
+            pool: *pool_account.key,
+            actor: *signal_provider_account.key,
+            amounts: vec![],
+            pool_token_supply: None,
+            status: pool_header.status,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    pub fn process_accept_signal_provider(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let new_signal_provider_account = next_account_info(accounts_iter)?;
+        let clock_sysvar_account = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
+        let system_program_account = next_account_info(accounts_iter)?;
+        let rent_sysvar_account = next_account_info(accounts_iter)?;
+        let payer_account = next_account_info(accounts_iter)?;
+        let previous_registry_account = next_account_info(accounts_iter)?;
+        let registry_account = next_account_info(accounts_iter)?;
+
+        check_pool_key(program_id, pool_account.key, &pool_seed)?;
+
+        let mut pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+
+        if pool_header.pending_signal_provider == Pubkey::default() {
+            msg!("There is no pending signal provider proposal.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if &pool_header.pending_signal_provider != new_signal_provider_account.key {
+            msg!("A wrong signal provider account was provided.");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if !new_signal_provider_account.is_signer {
+            msg!("The new signal provider's signature is required.");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let current_timestamp =
+            Clock::from_account_info(clock_sysvar_account)?.unix_timestamp as u64;
+        if current_timestamp - pool_header.signal_provider_proposal_timestamp
+            < pool_header.timelock_period
+        {
+            msg!("The signal provider proposal is still timelocked.");
+            return Err(BonfidaBotError::LockedOperation.into());
+        }
+
+        // The pool moves to the new signal provider's registry
+        Self::unregister_pool(
+            program_id,
+            &pool_header.signal_provider,
+            previous_registry_account,
+            &pool_seed,
+        )?;
+        Self::register_pool(
+            program_id,
+            system_program_account,
+            rent_sysvar_account,
+            payer_account,
+            new_signal_provider_account.key,
+            registry_account,
+            &pool_seed,
+        )?;
+
+        pool_header.signal_provider = pool_header.pending_signal_provider;
+        pool_header.pending_signal_provider = Pubkey::default();
+        pool_header.signal_provider_proposal_timestamp = 0;
+        pool_header.pack_into_slice(&mut pool_account.data.borrow_mut()[..PoolHeader::LEN]);
+
+        PoolEvent {
+            kind: EventKind::AcceptSignalProvider,
+            pool: *pool_account.key,
+            actor: *new_signal_provider_account.key,
+            amounts: vec![],
+            pool_token_supply: None,
+            status: pool_header.status,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    pub fn process_propose_market_change(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+        market_index: u16,
+        market: Pubkey,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let signal_provider_account = next_account_info(accounts_iter)?;
+        let clock_sysvar_account = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
+
+        check_pool_key(program_id, pool_account.key, &pool_seed)?;
+
+        let mut pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+        check_signal_provider(&pool_header, signal_provider_account, true)?;
+
+        // Proposing the default pubkey cancels the pending proposal
+        if market != Pubkey::default() {
+            if market_index > pool_header.number_of_markets {
+                msg!("The market index should be at most the number of markets.");
+                return Err(ProgramError::InvalidArgument);
+            }
+            let pool_data = pool_account.data.borrow();
+            for i in 0..pool_header.number_of_markets {
+                if unpack_market(&pool_data[pool_header.market_offset as usize..], i) == market {
+                    msg!("The market is already authorized for this pool.");
+                    return Err(ProgramError::InvalidArgument);
+                }
+            }
+        }
+
+        let current_timestamp =
+            Clock::from_account_info(clock_sysvar_account)?.unix_timestamp as u64;
+
+        pool_header.pending_market_index = market_index;
+        pool_header.pending_market = market;
+        pool_header.market_proposal_timestamp = current_timestamp;
+        pool_header.pack_into_slice(&mut pool_account.data.borrow_mut()[..PoolHeader::LEN]);
+
+        PoolEvent {
+            kind: EventKind::ProposeMarketChange,
+            pool: *pool_account.key,
+            actor: *signal_provider_account.key,
+            amounts: vec![],
+            pool_token_supply: None,
+            status: pool_header.status,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    pub fn process_execute_market_change(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let signal_provider_account = next_account_info(accounts_iter)?;
+        let clock_sysvar_account = next_account_info(accounts_iter)?;
+        let serum_program_account = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
+        let market_account = next_account_info(accounts_iter)?;
+
+        check_pool_key(program_id, pool_account.key, &pool_seed)?;
+
+        let mut pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+        check_signal_provider(&pool_header, signal_provider_account, true)?;
+
+        if pool_header.pending_market == Pubkey::default() {
+            msg!("There is no pending market change proposal.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if serum_program_account.key != &pool_header.serum_program_id {
+            msg!("Provided serum program account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if market_account.key != &pool_header.pending_market {
+            msg!("Provided market account is not the proposed market.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // The timelock leaves investors time to redeem before the pool can trade on a new market
+        let current_timestamp =
+            Clock::from_account_info(clock_sysvar_account)?.unix_timestamp as u64;
+        if current_timestamp - pool_header.market_proposal_timestamp < pool_header.timelock_period {
+            msg!("The market change proposal is still timelocked.");
+            return Err(BonfidaBotError::LockedOperation.into());
+        }
+
+        let pool_market =
+            Self::unpack_authorized_market(&pool_header.serum_program_id, market_account)?;
+        let asset_offset = pool_header.asset_offset as usize;
+        let assets_end = get_assets_end(&pool_header);
+        Self::check_market_assets(
+            &pool_market,
+            &unpack_assets(
+                pool_account
+                    .data
+                    .borrow()
+                    .get(asset_offset..assets_end)
+                    .ok_or(ProgramError::InvalidAccountData)?,
+            )?,
+        )?;
+
+        let market_index = pool_header.pending_market_index;
+        if market_index == pool_header.number_of_markets {
+            push_market(
+                &mut pool_account.data.borrow_mut(),
+                &mut pool_header,
+                &pool_market,
+            )
+            .map_err(|e| {
+                msg!("The pool account has no room left for another market.");
+                e
+            })?;
+        } else if market_index < pool_header.number_of_markets {
+            let open_orders_offset = pool_header.open_orders_offset as usize;
+            let pool_open_orders = unpack_open_orders(
+                &pool_account.data.borrow()[open_orders_offset..],
+                market_index,
+            )?;
+            if pool_open_orders.is_initialized() {
+                let replaced_market_account = next_account_info(accounts_iter)?;
+                let openorders_account = next_account_info(accounts_iter)?;
+
+                if replaced_market_account.key
+                    != &unpack_market(
+                        &pool_account.data.borrow()[pool_header.market_offset as usize..],
+                        market_index,
+                    )
+                {
+                    msg!("Provided market account is not the replaced market.");
+                    return Err(ProgramError::InvalidArgument);
+                }
+                if openorders_account.key != &pool_open_orders.address {
+                    msg!("Provided OpenOrders account is invalid");
+                    return Err(ProgramError::InvalidArgument);
+                }
+                if pool_open_orders.is_pending {
+                    msg!("The replaced market's OpenOrders account should be settled first.");
+                    return Err(BonfidaBotError::LockedOperation.into());
+                }
+
+                // An OpenOrders account is bound to its market once it has been used. It is then
+                // closed so that InitOpenOrders can create a fresh one for the new market.
+                let is_used = openorders_account
+                    .data
+                    .borrow()
+                    .get(5..13)
+                    .map_or(false, |flags| flags.iter().any(|b| *b != 0));
+                if is_used {
+                    let instruction = close_open_orders(
+                        serum_program_account.key,
+                        openorders_account.key,
+                        pool_account.key,
+                        signal_provider_account.key,
+                        replaced_market_account.key,
+                    )?;
+                    invoke_signed(
+                        &instruction,
+                        &[
+                            serum_program_account.clone(),
+                            openorders_account.clone(),
+                            pool_account.clone(),
+                            signal_provider_account.clone(),
+                            replaced_market_account.clone(),
+                        ],
+                        &[&[&pool_seed]],
+                    )?;
+                }
+                PoolOpenOrders {
+                    address: Pubkey::default(),
+                    is_pending: false,
+                }
+                .pack_into_slice(get_open_orders_slice(
+                    &mut pool_account.data.borrow_mut()[open_orders_offset..],
+                    market_index,
+                )?);
+            }
+            let market_offset =
+                pool_header.market_offset as usize + PoolMarket::LEN * market_index as usize;
+            pool_market.pack_into_slice(&mut pool_account.data.borrow_mut()[market_offset..]);
+        } else {
+            msg!("The pool has no market at the given index.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        pool_header.pending_market_index = 0;
+        pool_header.pending_market = Pubkey::default();
+        pool_header.market_proposal_timestamp = 0;
+        pool_header.pack_into_slice(&mut pool_account.data.borrow_mut()[..PoolHeader::LEN]);
+
+        PoolEvent {
+            kind: EventKind::ExecuteMarketChange,
+            pool: *pool_account.key,
+            actor: *signal_provider_account.key,
+            amounts: vec![],
+            pool_token_supply: None,
+            status: pool_header.status,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    pub fn process_close_pool(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let spl_token_account = next_account_info(accounts_iter)?;
+        let rent_sysvar_account = next_account_info(accounts_iter)?;
+        let serum_program_account = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
+        let mint_account = next_account_info(accounts_iter)?;
+        let creator_account = next_account_info(accounts_iter)?;
+        let registry_account = next_account_info(accounts_iter)?;
+        let redeem_queue_account = next_account_info(accounts_iter)?;
+        let whitelist_account = next_account_info(accounts_iter)?;
+        let metadata_account = next_account_info(accounts_iter)?;
+
+        if spl_token_account.key != &spl_token::id() {
+            msg!("Incorrect spl token program provided");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        check_pool_key(program_id, pool_account.key, &pool_seed)?;
+        let mint_key = Pubkey::create_program_address(&[&pool_seed, &[1]], &program_id)?;
+        if &mint_key != mint_account.key {
+            msg!("Provided mint account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // A drained pool keeps its header, with an uninitialized status. The seed is only written
+        // by Create, so that a pool which was initialized but never created can't be closed.
+        let pool_header =
+            PoolHeader::unpack_unchecked(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+        if pool_header.status != PoolStatus::Uninitialized
+            || pool_header.seed != pool_seed
+            || Mint::unpack(&mint_account.data.borrow())?.supply != 0
+        {
+            msg!("Only a pool which has been created and then drained can be closed.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if &pool_header.creator != creator_account.key {
+            msg!("Provided creator account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        Self::unregister_pool(
+            program_id,
+            &pool_header.signal_provider,
+            registry_account,
+            &pool_seed,
+        )?;
+
+        // The pool's other program derived accounts would otherwise be inherited by a pool
+        // initialized again with the same seed. Without pooltokens, the redeem queue holds no
+        // request and every deposit receipt has been released and closed.
+        for (account, expected_key) in [
+            (
+                redeem_queue_account,
+                get_redeem_queue_key(program_id, &pool_seed).0,
+            ),
+            (
+                whitelist_account,
+                get_whitelist_key(program_id, &pool_seed).0,
+            ),
+            (metadata_account, get_metadata_key(program_id, &pool_seed).0),
+        ]
+        .iter()
+        {
+            if account.key != expected_key {
+                msg!("Provided pool derived account is invalid");
+                return Err(ProgramError::InvalidArgument);
+            }
+            if account.owner == program_id {
+                Self::close_program_account(account, creator_account)?;
+            }
+        }
+
+        // The OpenOrders accounts are closed through the market they were used on, which Serum
+        // checks.
+        for market_index in 0..pool_header.number_of_markets {
+            let openorders_account = next_account_info(accounts_iter)?;
+            let market_account = next_account_info(accounts_iter)?;
+            let (open_orders_key, _) = get_open_orders_key(program_id, &pool_seed, market_index);
+            if openorders_account.key != &open_orders_key {
+                msg!("Provided OpenOrders account is invalid");
+                return Err(ProgramError::InvalidArgument);
+            }
+            if openorders_account.data_len() == 0 {
+                continue;
+            }
+            if serum_program_account.key != &pool_header.serum_program_id {
+                msg!("Provided serum program account is invalid");
+                return Err(ProgramError::InvalidArgument);
+            }
+            let is_used = openorders_account
+                .data
+                .borrow()
+                .get(5..13)
+                .map_or(false, |flags| flags.iter().any(|b| *b != 0));
+            // Serum can only close OpenOrders accounts it has initialized
+            if !is_used {
+                let instruction = instruction::init_open_orders(
+                    serum_program_account.key,
+                    openorders_account.key,
+                    pool_account.key,
+                    market_account.key,
+                    None,
+                )?;
+                invoke_signed(
+                    &instruction,
+                    &[
+                        serum_program_account.clone(),
+                        openorders_account.clone(),
+                        pool_account.clone(),
+                        market_account.clone(),
+                        rent_sysvar_account.clone(),
+                    ],
+                    &[&[&pool_seed]],
+                )?;
+            }
+            let instruction = close_open_orders(
+                serum_program_account.key,
+                openorders_account.key,
+                pool_account.key,
+                creator_account.key,
+                market_account.key,
+            )?;
+            invoke_signed(
+                &instruction,
+                &[
+                    serum_program_account.clone(),
+                    openorders_account.clone(),
+                    pool_account.clone(),
+                    creator_account.clone(),
+                    market_account.clone(),
+                ],
+                &[&[&pool_seed]],
+            )?;
+        }
+
+        // The remaining accounts are the pool's empty token accounts
+        for token_account in accounts_iter {
+            if &Account::unpack(&token_account.data.borrow())?.owner != pool_account.key {
+                msg!("Only the pool's token accounts can be closed.");
+                return Err(ProgramError::InvalidArgument);
+            }
+            let instruction = close_account(
+                spl_token_account.key,
+                token_account.key,
+                creator_account.key,
+                pool_account.key,
+                &[],
+            )?;
+            invoke_signed(
+                &instruction,
+                &[
+                    spl_token_account.clone(),
+                    token_account.clone(),
+                    creator_account.clone(),
+                    pool_account.clone(),
+                ],
+                &[&[&pool_seed]],
+            )?;
+        }
+
+        // Closing the pool account lets the seed be initialized again
+        Self::close_program_account(pool_account, creator_account)?;
+
+        PoolEvent {
+            kind: EventKind::ClosePool,
+            pool: *pool_account.key,
+            actor: *creator_account.key,
+            amounts: vec![],
+            pool_token_supply: Some((0, 0)),
+            status: PoolStatus::Uninitialized,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    pub fn process_resize_pool(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+        max_number_of_assets: u32,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let system_program_account = next_account_info(accounts_iter)?;
+        let rent_sysvar_account = next_account_info(accounts_iter)?;
+        let payer_account = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
+
+        check_pool_key(program_id, pool_account.key, &pool_seed)?;
+        if pool_account.owner != program_id {
+            msg!("Program should own pool account");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if !payer_account.is_signer {
+            msg!("The fee payer account should be a signer.");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let mut pool_header =
+            PoolHeader::unpack_unchecked(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+        if pool_header.account_type != AccountType::Pool {
+            msg!("The pool account has to be initialized.");
+            return Err(ProgramError::UninitializedAccount);
+        }
+        if max_number_of_assets <= pool_header.max_number_of_assets {
+            msg!("The pool account can only be grown.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        // The asset region is the last one of the pool account
+        let rent = Rent::from_account_info(rent_sysvar_account)?;
+        grow_program_account(
+            system_program_account,
+            payer_account,
+            pool_account,
+            &rent,
+            pool_header.asset_offset as usize + PoolAsset::LEN * max_number_of_assets as usize,
+        )?;
+
+        pool_header.max_number_of_assets = max_number_of_assets;
+        pool_header.pack_into_slice(&mut pool_account.data.borrow_mut()[..PoolHeader::LEN]);
+
+        PoolEvent {
+            kind: EventKind::ResizePool,
+            pool: *pool_account.key,
+            actor: *payer_account.key,
+            amounts: vec![max_number_of_assets as u64],
+            pool_token_supply: None,
+            status: pool_header.status,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    pub fn process_migrate_pool(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let system_program_account = next_account_info(accounts_iter)?;
+        let rent_sysvar_account = next_account_info(accounts_iter)?;
+        let payer_account = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
+
+        check_pool_key(program_id, pool_account.key, &pool_seed)?;
+        if pool_account.owner != program_id {
+            msg!("Program should own pool account");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if !payer_account.is_signer {
+            msg!("The fee payer account should be a signer.");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // The unversioned data is copied out as the account is reallocated
+        let source = pool_account.data.borrow().to_vec();
+        let (number_of_markets, max_number_of_assets) =
+            match unpack_pool_v0_layout(&source, &pool_seed) {
+                Ok(layout) => layout,
+                Err(e) => {
+                    if source.get(..2) == Some(&[AccountType::Pool as u8, POOL_VERSION]) {
+                        msg!("The pool account is already up to date.");
+                    } else {
+                        msg!("The pool account has an unknown layout.");
+                    }
+                    return Err(e);
+                }
+            };
+        match unpack_pool_status(source[96])? {
+            PoolStatus::Uninitialized => (),
+            PoolStatus::PendingOrder(_) | PoolStatus::LockedPendingOrder(_) => {
+                msg!("The pool's orders have to be settled before it is migrated.");
+                return Err(ProgramError::InvalidArgument);
+            }
+            _ => {
+                if payer_account.key.to_bytes() != source[64..96] {
+                    msg!("A created pool can only be migrated by its signal provider.");
+                    return Err(ProgramError::InvalidArgument);
+                }
+            }
+        }
+
+        // The parameters of the markets were not stored in the unversioned layout
+        let serum_program_id = Pubkey::new(&source[..32]);
+        let mut markets: Vec<PoolMarket> = Vec::with_capacity(number_of_markets as usize);
+        for _ in 0..number_of_markets {
+            let market_account = next_account_info(accounts_iter)?;
+            markets.push(Self::unpack_authorized_market(
+                &serum_program_id,
+                market_account,
+            )?);
+        }
+
+        let rent = Rent::from_account_info(rent_sysvar_account)?;
+        grow_program_account(
+            system_program_account,
+            payer_account,
+            pool_account,
+            &rent,
+            get_pool_len(number_of_markets, max_number_of_assets),
+        )?;
+        let pool_header = migrate_pool_v0(
+            &source,
+            &pool_seed,
+            &markets,
+            payer_account.key,
+            &mut pool_account.data.borrow_mut(),
+        )
+        .map_err(|e| {
+            msg!("The given markets don't match the pool's markets.");
+            e
+        })?;
+
+        PoolEvent {
+            kind: EventKind::MigratePool,
+            pool: *pool_account.key,
+            actor: *payer_account.key,
+            amounts: vec![],
+            pool_token_supply: None,
+            status: pool_header.status,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    pub fn process_init_open_orders(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pool_seed: [u8; 32],
+        market_index: u16,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let system_program_account = next_account_info(accounts_iter)?;
+        let rent_sysvar_account = next_account_info(accounts_iter)?;
+        let payer_account = next_account_info(accounts_iter)?;
+        let pool_account = next_account_info(accounts_iter)?;
+        let openorders_account = next_account_info(accounts_iter)?;
+
+        check_pool_key(program_id, pool_account.key, &pool_seed)?;
+        if *pool_account.owner != *program_id {
+            msg!("Program should own pool account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+        if market_index >= pool_header.number_of_markets {
+            msg!("The pool has no market at the given index.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let open_orders_offset = pool_header.open_orders_offset as usize;
+        if unpack_open_orders(
+            &pool_account.data.borrow()[open_orders_offset..],
+            market_index,
+        )?
+        .is_initialized()
+        {
+            msg!("The pool's OpenOrders account for this market is already initialized.");
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        let (open_orders_key, bump) = get_open_orders_key(program_id, &pool_seed, market_index);
+        if openorders_account.key != &open_orders_key {
+            msg!("Provided OpenOrders account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if openorders_account.data_len() == 0 {
+            create_pda_account(
+                system_program_account,
+                payer_account,
+                openorders_account,
+                &Rent::from_account_info(rent_sysvar_account)?,
+                OPEN_ORDERS_LEN,
+                &pool_header.serum_program_id,
+                &[
+                    &pool_seed,
+                    OPEN_ORDERS_SEED,
+                    &market_index.to_le_bytes(),
+                    &[bump],
+                ],
+            )?;
+        } else if openorders_account.owner != &pool_header.serum_program_id {
+            // An unused account is kept when its market is replaced, in which case it is reused
+            // as is
+            msg!("The existing OpenOrders account should be owned by the serum program.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        PoolOpenOrders {
+            address: open_orders_key,
+            is_pending: false,
+        }
+        .pack_into_slice(get_open_orders_slice(
+            &mut pool_account.data.borrow_mut()[open_orders_offset..],
+            market_index,
+        )?);
+
+        PoolEvent {
+            kind: EventKind::InitOpenOrders,
+            pool: *pool_account.key,
+            actor: *payer_account.key,
+            amounts: vec![],
+            pool_token_supply: None,
+            status: pool_header.status,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    pub fn process_create_config(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        signal_provider_share: u16,
+        fee_recipients: Vec<FeeRecipient>,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let system_program_account = next_account_info(accounts_iter)?;
+        let rent_sysvar_account = next_account_info(accounts_iter)?;
+        let config_account = next_account_info(accounts_iter)?;
+        let program_data_account = next_account_info(accounts_iter)?;
+        let upgrade_authority_account = next_account_info(accounts_iter)?;
+        let payer_account = next_account_info(accounts_iter)?;
+
+        let rent = Rent::from_account_info(rent_sysvar_account)?;
+
+        check_upgrade_authority(program_id, program_data_account, upgrade_authority_account)?;
+
+        let (config_key, config_nonce) = get_config_key(program_id);
+        if &config_key != config_account.key {
+            msg!("Provided config account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if fee_recipients.len() > MAX_FEE_RECIPIENTS {
+            msg!("Too many fee recipients were given.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let total_shares = fee_recipients
+            .iter()
+            .fold(signal_provider_share as u64, |acc, r| acc + r.share as u64);
+        if total_shares == 0 {
+            msg!("Fee shares cannot be all zero.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let create_config_account = create_account(
+            &payer_account.key,
+            &config_key,
+            rent.minimum_balance(ProgramConfig::LEN),
+            ProgramConfig::LEN as u64,
+            &program_id,
+        );
+
+        invoke_signed(
+            &create_config_account,
+            &[
+                system_program_account.clone(),
+                payer_account.clone(),
+                config_account.clone(),
+            ],
+            &[&[CONFIG_SEED, &[config_nonce]]],
+        )?;
+
+        let config = ProgramConfig {
+            is_initialized: true,
+            signal_provider_share,
+            fee_recipients,
+        };
+        config.pack_into_slice(&mut config_account.data.borrow_mut());
+
+        Ok(())
+    }
+
+    // Private pools only accept deposits from the owners on their whitelist, or from holders of
+    // its gating mint
+    fn check_depositor_allowed<'a, 'b>(
+        program_id: &Pubkey,
+        pool_header: &PoolHeader,
+        pool_seed: &[u8; 32],
+        depositor_account: &AccountInfo<'b>,
+        accounts_iter: &mut Iter<'a, AccountInfo<'b>>,
+    ) -> ProgramResult {
+        if !pool_header.is_private {
+            return Ok(());
+        }
+        let whitelist_account = next_account_info(accounts_iter)?;
+        if &get_whitelist_key(program_id, pool_seed).0 != whitelist_account.key
+            || whitelist_account.owner != program_id
+        {
+            msg!("Provided whitelist account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let whitelist = Whitelist::unpack(&whitelist_account.data.borrow())?;
+
+        let mut is_allowed = whitelist.owners.contains(depositor_account.key);
+        if whitelist.gating_mint != Pubkey::default() {
+            let gating_token_account = next_account_info(accounts_iter)?;
+            if gating_token_account.owner == &spl_token::id() {
+                let gating_token = Account::unpack(&gating_token_account.data.borrow())?;
+                is_allowed |= (gating_token.mint == whitelist.gating_mint)
+                    & (&gating_token.owner == depositor_account.key)
+                    & (gating_token.amount != 0);
+            }
+        }
+        if !is_allowed {
+            msg!("The depositor is not allowed to buy into this private pool.");
+            return Err(BonfidaBotError::DepositorNotAllowed.into());
+        }
+        Ok(())
+    }
+
+    // Enforces the pool's minimum deposit and maximum pooltoken supply on the pooltokens bought
+    // by a deposit, fees included
+    fn check_deposit_amount(
+        pool_header: &PoolHeader,
+        pool_token_supply: u64,
+        pool_token_amount: u64,
+    ) -> ProgramResult {
+        if pool_token_amount < pool_header.min_deposit_pool_tokens {
+            msg!("The deposit is below the pool's minimum deposit.");
+            return Err(BonfidaBotError::DepositTooSmall.into());
+        }
+        if pool_header.max_pool_token_supply != 0
+            && pool_token_supply.saturating_add(pool_token_amount)
+                > pool_header.max_pool_token_supply
+        {
+            msg!("The deposit would bring the pool above its maximum pooltoken supply.");
+            return Err(BonfidaBotError::DepositCapExceeded.into());
+        }
+        Ok(())
+    }
+
+    // Adds a deposit into a pool with a lockup period to the depositor's receipt, which is created
+    // on first use, and returns the escrow account receiving the deposited pooltokens
+    fn lock_up_deposit<'a, 'b>(
+        program_id: &Pubkey,
+        pool_seed: &[u8; 32],
+        pool_account: &AccountInfo<'b>,
+        depositor_account: &AccountInfo<'b>,
+        accounts_iter: &mut Iter<'a, AccountInfo<'b>>,
+        pool_token_amount: u64,
+    ) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let system_program_account = next_account_info(accounts_iter)?;
+        let rent_sysvar_account = next_account_info(accounts_iter)?;
+        let clock_sysvar_account = next_account_info(accounts_iter)?;
+        let payer_account = next_account_info(accounts_iter)?;
+        let deposit_receipt_account = next_account_info(accounts_iter)?;
+        let escrow_account = next_account_info(accounts_iter)?;
+
+        Self::check_escrow_key(program_id, pool_seed, pool_account, escrow_account)?;
+        let (deposit_receipt_key, bump) =
+            get_deposit_receipt_key(program_id, pool_seed, depositor_account.key);
+        if deposit_receipt_account.key != &deposit_receipt_key {
+            msg!("Provided deposit receipt account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if deposit_receipt_account.data_len() == 0 {
+            create_pda_account(
+                system_program_account,
+                payer_account,
+                deposit_receipt_account,
+                &Rent::from_account_info(rent_sysvar_account)?,
+                DepositReceipt::LEN,
+                program_id,
+                &[
+                    pool_seed,
+                    DEPOSIT_RECEIPT_SEED,
+                    &depositor_account.key.to_bytes(),
+                    &[bump],
+                ],
+            )?;
+        } else if deposit_receipt_account.owner != program_id {
+            msg!("Program should own the deposit receipt account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // A new deposit locks up the whole receipt again
+        let mut receipt = DepositReceipt::unpack_unchecked(&deposit_receipt_account.data.borrow())?;
+        receipt.owner = *depositor_account.key;
+        receipt.pool_token_amount = receipt
+            .pool_token_amount
+            .checked_add(pool_token_amount)
+            .ok_or(BonfidaBotError::Overflow)?;
+        receipt.deposit_timestamp =
+            Clock::from_account_info(clock_sysvar_account)?.unix_timestamp as u64;
+        receipt.pack_into_slice(&mut deposit_receipt_account.data.borrow_mut());
+
+        Ok(escrow_account)
+    }
+
+    // Closes an account owned by the program, sending its lamports to the destination account
+    fn close_program_account(account: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+        **destination.lamports.borrow_mut() = destination
+            .lamports()
+            .checked_add(account.lamports())
+            .ok_or(BonfidaBotError::Overflow)?;
+        **account.lamports.borrow_mut() = 0;
+        fill_slice(&mut account.data.borrow_mut(), 0u8);
+        Ok(())
+    }
+
+    // Lists the pool in the signal provider's registry, creating the registry account if needed
+    fn register_pool<'a>(
+        program_id: &Pubkey,
+        system_program_account: &AccountInfo<'a>,
+        rent_sysvar_account: &AccountInfo<'a>,
+        payer_account: &AccountInfo<'a>,
+        signal_provider: &Pubkey,
+        registry_account: &AccountInfo<'a>,
+        pool_seed: &[u8; 32],
+    ) -> ProgramResult {
+        let (registry_key, bump) = get_registry_key(program_id, signal_provider);
+        if registry_account.key != &registry_key {
+            msg!("Provided registry account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if registry_account.data_len() == 0 {
+            create_pda_account(
+                system_program_account,
+                payer_account,
+                registry_account,
+                &Rent::from_account_info(rent_sysvar_account)?,
+                PoolRegistry::LEN,
+                program_id,
+                &[&signal_provider.to_bytes(), REGISTRY_SEED, &[bump]],
+            )?;
+        } else if registry_account.owner != program_id {
+            msg!("Program should own the registry account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut registry = PoolRegistry::unpack_unchecked(&registry_account.data.borrow())?;
+        registry.is_initialized = true;
+        if !registry.pool_seeds.contains(pool_seed) {
+            if registry.pool_seeds.len() == MAX_REGISTRY_POOLS {
+                msg!("The signal provider's pool registry is full.");
+                return Err(ProgramError::AccountDataTooSmall);
+            }
+            registry.pool_seeds.push(*pool_seed);
+        }
+        registry.pack_into_slice(&mut registry_account.data.borrow_mut());
+        Ok(())
+    }
+
+    // Removes the pool from the signal provider's registry. Pools created before registries were
+    // introduced aren't listed, in which case the registry may not exist.
+    fn unregister_pool(
+        program_id: &Pubkey,
+        signal_provider: &Pubkey,
+        registry_account: &AccountInfo,
+        pool_seed: &[u8; 32],
+    ) -> ProgramResult {
+        if registry_account.key != &get_registry_key(program_id, signal_provider).0 {
+            msg!("Provided registry account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if registry_account.data_len() == 0 {
+            return Ok(());
+        }
+        if registry_account.owner != program_id {
+            msg!("Program should own the registry account");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let mut registry = PoolRegistry::unpack(&registry_account.data.borrow())?;
+        registry.pool_seeds.retain(|seed| seed != pool_seed);
+        registry.pack_into_slice(&mut registry_account.data.borrow_mut());
+        Ok(())
+    }
+
+    // Queued redeem requests are escrowed in the pool's associated pooltoken account
+    fn check_escrow_key(
+        program_id: &Pubkey,
+        pool_seed: &[u8; 32],
+        pool_account: &AccountInfo,
+        escrow_account: &AccountInfo,
+    ) -> ProgramResult {
+        let pool_mint_key =
+            Pubkey::create_program_address(&[pool_seed, &[1]], &program_id).unwrap();
+        if escrow_account.key != &get_associated_token_address(pool_account.key, &pool_mint_key) {
+            msg!("Provided escrow account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    fn check_pool_asset_accounts(
+        pool_account: &AccountInfo,
+        pool_assets: &[PoolAsset],
+        pool_assets_accounts: &[&AccountInfo],
+    ) -> ProgramResult {
+        for (pool_asset, pool_asset_account) in pool_assets.iter().zip(pool_assets_accounts.iter())
+        {
+            if get_associated_token_address(&pool_account.key, &pool_asset.mint_address)
+                != *pool_asset_account.key
+            {
+                msg!("Provided pool asset account is invalid");
+                return Err(ProgramError::InvalidArgument);
+            }
+        }
+        Ok(())
+    }
+
+    // Checks a pending market's accounts against the pool's registry and returns the indices of
+    // its coin and pc pool assets
+    fn get_pending_market_assets(
+        pool_account: &AccountInfo,
+        pool_header: &PoolHeader,
+        pool_open_orders: &PoolOpenOrders,
+        market_index: u16,
+        market: &AccountInfo,
+        openorders_account: &AccountInfo,
+        pool_assets: &[PoolAsset],
+    ) -> Result<(usize, usize), ProgramError> {
+        let pool_market = unpack_pool_market(
+            &pool_account.data.borrow()[pool_header.market_offset as usize..],
+            market_index,
+        )?;
+        if market.key != &pool_market.address {
+            msg!("The given market account doesn't match the pool's pending market.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if openorders_account.key != &pool_open_orders.address {
+            msg!(
+                "The given OpenOrders account doesn't match the pool's OpenOrders for this market."
+            );
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let coin_index = pool_assets
+            .iter()
+            .position(|asset| asset.mint_address == pool_market.coin_mint)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let pc_index = pool_assets
+            .iter()
+            .position(|asset| asset.mint_address == pool_market.pc_mint)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        Ok((coin_index, pc_index))
+    }
+
+    fn cancel_pool_order<'a>(
+        dex_program: &AccountInfo<'a>,
+        market: &AccountInfo<'a>,
+        serum_market_bids: &AccountInfo<'a>,
+        serum_market_asks: &AccountInfo<'a>,
+        openorders_account: &AccountInfo<'a>,
+        pool_account: &AccountInfo<'a>,
+        event_queue: &AccountInfo<'a>,
+        side: Side,
+        order_id: u128,
+        pool_seed: [u8; 32],
+    ) -> ProgramResult {
+        let instruction = cancel_order(
+            &dex_program.key,
+            market.key,
+            serum_market_bids.key,
+            serum_market_asks.key,
+            openorders_account.key,
+            pool_account.key,
+            event_queue.key,
+            side,
+            order_id,
+        )?;
+
+        invoke_signed(
+            &instruction,
+            &vec![
+                dex_program.clone(),
+                market.clone(),
+                serum_market_bids.clone(),
+                serum_market_asks.clone(),
+                openorders_account.clone(),
+                pool_account.clone(),
+                event_queue.clone(),
+            ],
+            &[&[&pool_seed]],
+        )
+    }
+
+    // Settles the free funds of one of the pool's OpenOrders accounts into its asset accounts
+    fn settle_pool_open_orders<'a>(
+        spl_token_account: &AccountInfo<'a>,
+        dex_program: &AccountInfo<'a>,
+        market: &AccountInfo<'a>,
+        openorders_account: &AccountInfo<'a>,
+        coin_vault: &AccountInfo<'a>,
+        pc_vault: &AccountInfo<'a>,
+        vault_signer: &AccountInfo<'a>,
+        pool_account: &AccountInfo<'a>,
+        pool_coin_wallet: &AccountInfo<'a>,
+        pool_pc_wallet: &AccountInfo<'a>,
+        pool_seed: [u8; 32],
+    ) -> ProgramResult {
+        let instruction = settle_funds(
+            dex_program.key,
+            market.key,
+            spl_token_account.key,
+            openorders_account.key,
+            pool_account.key,
+            coin_vault.key,
+            pool_coin_wallet.key,
+            pc_vault.key,
+            pool_pc_wallet.key,
+            None,
+            vault_signer.key,
+        )?;
+        invoke_signed(
+            &instruction,
+            &[
+                dex_program.clone(),
+                market.clone(),
+                openorders_account.clone(),
+                pool_account.clone(),
+                coin_vault.clone(),
+                pc_vault.clone(),
+                pool_coin_wallet.clone(),
+                pool_pc_wallet.clone(),
+                vault_signer.clone(),
+                spl_token_account.clone(),
+            ],
+            &[&[&pool_seed]],
+        )
+    }
+
+    // Resets a pool whose pooltokens have all been redeemed, keeping the pool header mostly
+    // intact to preserve pool seeds. The markets and OpenOrders registry are kept as well since
+    // the pool's OpenOrders accounts still exist, bound to their markets.
+    fn reset_drained_pool(
+        pool_account: &AccountInfo,
+        pool_header: &mut PoolHeader,
+    ) -> ProgramResult {
+        fill_slice(
+            pool_account
+                .data
+                .borrow_mut()
+                .get_mut(pool_header.asset_offset as usize..)
+                .ok_or(ProgramError::InvalidAccountData)?,
+            0u8,
+        );
+        pool_header.status = PoolStatus::Uninitialized;
+        pool_header.pack_into_slice(&mut pool_account.data.borrow_mut()[..PoolHeader::LEN]);
+        Ok(())
+    }
+
+    // Whether a token account can still be credited, it may have been closed or frozen since
+    // the redeem request paid out to it was queued
+    fn can_receive_tokens(token_account: &AccountInfo) -> bool {
+        token_account.owner == &spl_token::id()
+            && Account::unpack(&token_account.data.borrow())
+                .map_or(false, |account| account.state == AccountState::Initialized)
+    }
+
+    // Whether a redeem of pool_token_amount pooltokens, once the redeem fee is taken out of it,
+    // pays out at least the given minimum amount of each pool asset. Assets past the end of
+    // min_amounts_out have no minimum.
+    fn meets_min_amounts_out(
+        mint_account: &AccountInfo,
+        pool_header: &PoolHeader,
+        pool_assets_accounts: &[&AccountInfo],
+        pool_token_amount: u64,
+        min_amounts_out: &[u64],
+    ) -> Result<bool, ProgramError> {
+        let total_pooltokens = Mint::unpack(&mint_account.data.borrow())?.supply as u128;
+        let fee = (pool_header.redeem_fee_ratio as u128 * pool_token_amount as u128) >> 16;
+        let redeemed_amount = pool_token_amount as u128 - fee;
+        for (pool_asset_account, min_amount_out) in pool_assets_accounts.iter().zip(min_amounts_out)
+        {
+            let pool_asset_amount = Account::unpack(&pool_asset_account.data.borrow())?.amount;
+            if redeemed_amount * (pool_asset_amount as u128) / total_pooltokens
+                < *min_amount_out as u128
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    // Settles the pool's pending OpenOrders accounts and cancels just enough of their orders to
+    // pay out a share of pool_token_amount pooltokens, taking the accounts of each pending market
+    // from accounts_iter. Returns the amounts of each pool asset which are still locked on Serum.
+    fn free_redeemed_share<'a, 'b>(
+        spl_token_account: &AccountInfo<'b>,
+        dex_program: &AccountInfo<'b>,
+        mint_account: &AccountInfo<'b>,
+        pool_account: &AccountInfo<'b>,
+        pool_header: &mut PoolHeader,
+        pool_assets: &[PoolAsset],
+        pool_assets_accounts: &[&AccountInfo<'b>],
+        accounts_iter: &mut Iter<'a, AccountInfo<'b>>,
+        pool_seed: [u8; 32],
+        pool_token_amount: u64,
+        mut pending_orders: u8,
+    ) -> Result<Vec<u64>, ProgramError> {
+        let nb_assets = pool_assets.len();
+        let mut locked_amounts = vec![0u64; nb_assets];
+        let total_pooltokens = Mint::unpack(&mint_account.data.borrow())?.supply;
+        if pool_token_amount > total_pooltokens {
+            msg!("Insufficient pool token funds");
+            return Err(ProgramError::InsufficientFunds);
+        }
+
+        // Settle the free funds of every pending OpenOrders account, in market order
+        let open_orders_offset = pool_header.open_orders_offset as usize;
+        let mut pending_markets = vec![];
+        for market_index in 0..pool_header.number_of_markets {
+            let pool_open_orders = unpack_open_orders(
+                &pool_account.data.borrow()[open_orders_offset..],
+                market_index,
+            )?;
+            if !pool_open_orders.is_pending {
+                continue;
+            }
+            // Market, OpenOrders, bids, asks, event queue, coin vault, pc vault, vault signer
+            let mut serum_accounts: Vec<&AccountInfo> = vec![];
+            for _ in 0..8 {
+                serum_accounts.push(next_account_info(accounts_iter)?)
+            }
+            let (coin_index, pc_index) = Self::get_pending_market_assets(
+                pool_account,
+                pool_header,
+                &pool_open_orders,
+                market_index,
+                serum_accounts[0],
+                serum_accounts[1],
+                pool_assets,
+            )?;
+            Self::settle_pool_open_orders(
+                spl_token_account,
+                dex_program,
+                serum_accounts[0],
+                serum_accounts[1],
+                serum_accounts[5],
+                serum_accounts[6],
+                serum_accounts[7],
+                pool_account,
+                pool_assets_accounts[coin_index],
+                pool_assets_accounts[pc_index],
+                pool_seed,
+            )?;
+            let balances = OpenOrdersBalances::unpack(&serum_accounts[1].data.borrow())?;
+            locked_amounts[coin_index] = locked_amounts[coin_index]
+                .checked_add(balances.native_coin_total)
+                .ok_or(BonfidaBotError::Overflow)?;
+            locked_amounts[pc_index] = locked_amounts[pc_index]
+                .checked_add(balances.native_pc_total)
+                .ok_or(BonfidaBotError::Overflow)?;
+            pending_markets.push((
+                market_index,
+                pool_open_orders,
+                serum_accounts,
+                coin_index,
+                pc_index,
+            ));
+        }
+
+        let mut amounts_out = vec![];
+        for i in 0..nb_assets {
+            let pool_asset_amount = Account::unpack(&pool_assets_accounts[i].data.borrow())?.amount;
+            let amount: u64 = (((pool_token_amount as u128)
+                * (pool_asset_amount as u128 + locked_amounts[i] as u128))
+                / (total_pooltokens as u128))
+                .try_into()
+                .map_err(|_| BonfidaBotError::Overflow)?;
+            amounts_out.push(amount);
+        }
+
+        // Cancel just enough of the pool's orders to pay out the locked part of the share
+        locked_amounts = vec![0u64; nb_assets];
+        for (market_index, mut pool_open_orders, serum_accounts, coin_index, pc_index) in
+            pending_markets
+        {
+            let coin_shortfall = amounts_out[coin_index].saturating_sub(
+                Account::unpack(&pool_assets_accounts[coin_index].data.borrow())?.amount,
+            );
+            let pc_shortfall = amounts_out[pc_index].saturating_sub(
+                Account::unpack(&pool_assets_accounts[pc_index].data.borrow())?.amount,
+            );
+            if (coin_shortfall != 0) | (pc_shortfall != 0) {
+                let orders = get_open_orders_ids(&serum_accounts[1].data.borrow())?;
+                for (side, order_id) in orders {
+                    let balances = OpenOrdersBalances::unpack(&serum_accounts[1].data.borrow())?;
+                    let coin_missing = balances.native_coin_free < coin_shortfall;
+                    let pc_missing = balances.native_pc_free < pc_shortfall;
+                    if !coin_missing & !pc_missing {
+                        break;
+                    }
+                    // Bids lock price currency and asks lock coin
+                    if (side == Side::Bid && !pc_missing) || (side == Side::Ask && !coin_missing) {
+                        continue;
+                    }
+                    Self::cancel_pool_order(
+                        dex_program,
+                        serum_accounts[0],
+                        serum_accounts[2],
+                        serum_accounts[3],
+                        serum_accounts[1],
+                        pool_account,
+                        serum_accounts[4],
+                        side,
+                        order_id,
+                        pool_seed,
+                    )?;
+                }
+                Self::settle_pool_open_orders(
+                    spl_token_account,
+                    dex_program,
+                    serum_accounts[0],
+                    serum_accounts[1],
+                    serum_accounts[5],
+                    serum_accounts[6],
+                    serum_accounts[7],
+                    pool_account,
+                    pool_assets_accounts[coin_index],
+                    pool_assets_accounts[pc_index],
+                    pool_seed,
+                )?;
+            }
+
+            let balances = OpenOrdersBalances::unpack(&serum_accounts[1].data.borrow())?;
+            if (balances.native_coin_total == 0) & (balances.native_pc_total == 0) {
+                pool_open_orders.is_pending = false;
+                pool_open_orders.pack_into_slice(get_open_orders_slice(
+                    &mut pool_account.data.borrow_mut()[open_orders_offset..],
+                    market_index,
+                )?);
+                pending_orders = pending_orders
+                    .checked_sub(1)
+                    .ok_or(ProgramError::InvalidAccountData)?;
+            }
+            locked_amounts[coin_index] += balances.native_coin_total;
+            locked_amounts[pc_index] += balances.native_pc_total;
+        }
+
+        pool_header.status = match NonZeroU8::new(pending_orders) {
+            Some(n) => PoolStatus::PendingOrder(n),
+            None => PoolStatus::Unlocked,
+        };
+        pool_header.pack_into_slice(&mut pool_account.data.borrow_mut()[..PoolHeader::LEN]);
+        Ok(locked_amounts)
+    }
+
+    // Burns the redeemed pooltokens and pays out their share of each pool asset
+    fn pay_out_redeem<'a>(
+        spl_token_account: &AccountInfo<'a>,
+        mint_account: &AccountInfo<'a>,
+        source_pool_token_owner_account: &AccountInfo<'a>,
+        source_pool_token_account: &AccountInfo<'a>,
+        pool_account: &AccountInfo<'a>,
+        pool_header: &mut PoolHeader,
+        pool_assets: &[PoolAsset],
+        pool_assets_accounts: &[&AccountInfo<'a>],
+        target_assets_accounts: &[&AccountInfo<'a>],
+        // The amounts of each pool asset still held by the pool's OpenOrders accounts
+        locked_amounts: &[u64],
+        pool_seed: [u8; 32],
+        pool_token_amount: u64,
+        min_amounts_out: &[u64],
+        // The kind and actor of the emitted event
+        kind: EventKind,
+        actor: &Pubkey,
+    ) -> ProgramResult {
+        let total_pooltokens = Mint::unpack(&mint_account.data.borrow())?.supply;
+        let total_user_pooltokens =
+            Account::unpack(&source_pool_token_account.data.borrow())?.amount;
+
+        if total_user_pooltokens < pool_token_amount {
+            msg!("Insufficient pool token funds");
+            return Err(ProgramError::InsufficientFunds);
+        }
+
+        // Execute buy out
+        let mut amounts_out = vec![0; pool_assets.len()];
+        for i in 0..pool_assets.len() {
+            let pool_asset_key =
+                get_associated_token_address(&pool_account.key, &pool_assets[i].mint_address);
+
+            if pool_asset_key != *pool_assets_accounts[i].key {
+                msg!("Provided pool asset account is invalid");
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            let pool_asset_amount = Account::unpack(&pool_assets_accounts[i].data.borrow())?.amount
+                as u128
+                + locked_amounts[i] as u128;
+
+            let amount: u64 = (((pool_token_amount as u128) * pool_asset_amount)
+                / (total_pooltokens as u128))
+                .try_into()
+                .map_err(|_| BonfidaBotError::Overflow)?;
+
+            if amount < min_amounts_out[i] {
+                msg!("The redeem would pay out less than the minimum amount of a pool asset.");
+                return Err(BonfidaBotError::MinAmountOutNotMet.into());
+            }
+            amounts_out[i] = amount;
+            if amount == 0 {
+                continue;
+            }
+            let instruction = transfer(
+                spl_token_account.key,
+                pool_assets_accounts[i].key,
+                target_assets_accounts[i].key,
+                pool_account.key,
+                &[],
+                amount,
+            )?;
+            invoke_signed(
+                &instruction,
+                &[
+                    spl_token_account.clone(),
+                    pool_assets_accounts[i].clone(),
+                    target_assets_accounts[i].clone(),
+                    pool_account.clone(),
+                ],
+                &[&[&pool_seed]],
+            )?;
+        }
+
+        // Burn the redeemed pooltokens
+        let instruction = burn(
+            spl_token_account.key,
+            &source_pool_token_account.key,
+            mint_account.key,
+            &source_pool_token_owner_account.key,
+            &[],
+            pool_token_amount,
+        )?;
+
+        // The pool signs for the pooltokens it escrows on behalf of queued redeem requests
+        invoke_signed(
+            &instruction,
+            &[
+                spl_token_account.clone(),
+                source_pool_token_account.clone(),
+                mint_account.clone(),
+                source_pool_token_owner_account.clone(),
+            ],
+            &[&[&pool_seed]],
+        )?;
+
+        if pool_token_amount == total_pooltokens {
+            Self::reset_drained_pool(pool_account, pool_header)?;
+        }
+
+        PoolEvent {
+            kind,
+            pool: *pool_account.key,
+            actor: *actor,
+            amounts: amounts_out,
+            pool_token_supply: Some((
+                total_pooltokens,
+                Mint::unpack(&mint_account.data.borrow())?.supply,
+            )),
+            status: pool_header.status,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // Checks the accounts shared by the cancel instructions. Expired orders can be cancelled by
+    // anyone so that they cannot block redemptions.
+    fn check_cancel_accounts(
+        program_id: &Pubkey,
+        pool_seed: &[u8; 32],
+        signal_provider: &AccountInfo,
+        market: &AccountInfo,
+        openorders_account: &AccountInfo,
+        pool_account: &AccountInfo,
+        dex_program: &AccountInfo,
+        clock_sysvar_account: &AccountInfo,
+    ) -> ProgramResult {
+        check_pool_key(program_id, pool_account.key, pool_seed)?;
+
+        let pool_header = PoolHeader::unpack(&pool_account.data.borrow()[..PoolHeader::LEN])?;
+        if &pool_header.serum_program_id != dex_program.key {
+            msg!("The provided serum program account is invalid for this pool.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        Self::get_open_orders_index(
+            pool_account,
+            &pool_header,
+            market.key,
+            openorders_account.key,
+        )?;
+
+        let current_timestamp =
+            Clock::from_account_info(clock_sysvar_account)?.unix_timestamp as u64;
+        if !pending_orders_expired(&pool_header, current_timestamp) {
+            check_signal_provider(&pool_header, signal_provider, true)?;
+        }
+        Ok(())
+    }
+
+    // Reads the parameters of a Serum market the pool is to be authorized to trade on
+    fn unpack_authorized_market(
+        serum_program_id: &Pubkey,
+        market_account: &AccountInfo,
+    ) -> Result<PoolMarket, ProgramError> {
+        if market_account.owner != serum_program_id {
+            msg!("The market account should be owned by the serum program.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let market_state = MarketState::unpack(&market_account.data.borrow())?;
+        if market_state.is_disabled {
+            msg!("The market has been disabled.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if market_state.coin_lot_size == 0 || market_state.pc_lot_size == 0 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(PoolMarket {
+            address: *market_account.key,
+            coin_mint: market_state.coin_mint,
+            pc_mint: market_state.pc_mint,
+            coin_lot_size: market_state.coin_lot_size,
+            pc_lot_size: market_state.pc_lot_size,
+        })
+    }
+
+    // Verifies that the pool holds at least one of the market's currencies
+    fn check_market_assets(
+        pool_market: &PoolMarket,
+        pool_assets: &Vec<PoolAsset>,
+    ) -> ProgramResult {
+        if !pool_assets.iter().any(|asset| {
+            asset.mint_address == pool_market.coin_mint || asset.mint_address == pool_market.pc_mint
+        }) {
+            msg!("The market doesn't trade any of the pool's assets.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    // Finds the market index the given OpenOrders account is registered at in the pool
+    fn get_open_orders_index(
+        pool_account: &AccountInfo,
+        pool_header: &PoolHeader,
+        market_key: &Pubkey,
+        openorders_key: &Pubkey,
+    ) -> Result<u16, ProgramError> {
+        let pool_data = pool_account.data.borrow();
+        let open_orders_offset = pool_header.open_orders_offset as usize;
+        for i in 0..pool_header.number_of_markets {
+            if &unpack_market(&pool_data[pool_header.market_offset as usize..], i) == market_key
+                && &unpack_open_orders(&pool_data[open_orders_offset..], i)?.address
+                    == openorders_key
+            {
+                return Ok(i);
+            }
+        }
+        msg!("The given OpenOrders account is not the pool's account for this market.");
+        Err(ProgramError::InvalidArgument)
+    }
+
+    // Reads the program config and the fee recipients' pooltoken accounts which follow
+    fn unpack_fee_recipients<'a, 'b>(
+        program_id: &Pubkey,
+        config_account: &AccountInfo,
+        pool_mint_key: &Pubkey,
+        accounts_iter: &mut Iter<'a, AccountInfo<'b>>,
+    ) -> Result<(ProgramConfig, Vec<&'a AccountInfo<'b>>), ProgramError> {
+        check_config_key(program_id, config_account.key)?;
+        if config_account.owner != program_id {
+            msg!("Program should own config account");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let config = ProgramConfig::unpack(&config_account.data.borrow())?;
+
+        let mut fee_recipient_pt_accounts = Vec::with_capacity(config.fee_recipients.len());
+        for recipient in config.fee_recipients.iter() {
+            let fee_recipient_pt_account = next_account_info(accounts_iter)?;
+            if fee_recipient_pt_account.key
+                != &get_associated_token_address(&recipient.owner, pool_mint_key)
+            {
+                msg!("The provided fee recipient pool token account is invalid.");
+                return Err(ProgramError::InvalidArgument);
+            }
+            fee_recipient_pt_accounts.push(fee_recipient_pt_account);
+        }
+        Ok((config, fee_recipient_pt_accounts))
+    }
+
+    // Values the pool's assets in the price currency of its first market, using the mid prices
+    // of its authorized Serum markets
+    fn get_pool_value<'a, 'b>(
+        pool_account: &AccountInfo,
+        pool_header: &PoolHeader,
+        accounts_iter: &mut Iter<'a, AccountInfo<'b>>,
+    ) -> Result<u128, ProgramError> {
+        let pool_data = pool_account.data.borrow();
+        let markets = (0..pool_header.number_of_markets)
+            .map(|i| unpack_pool_market(&pool_data[pool_header.market_offset as usize..], i))
+            .collect::<Result<Vec<PoolMarket>, ProgramError>>()?;
+        let asset_offset = pool_header.asset_offset as usize;
+        let assets_end = get_assets_end(pool_header);
+        let pool_assets = unpack_assets(
+            pool_data
+                .get(asset_offset..assets_end)
+                .ok_or(ProgramError::InvalidAccountData)?,
+        )?;
+
+        let mut pool_assets_accounts: Vec<&AccountInfo> = vec![];
+        for _ in 0..pool_assets.len() {
+            pool_assets_accounts.push(next_account_info(accounts_iter)?)
+        }
+
+        let quote_market_account = next_account_info(accounts_iter)?;
+        let quote_mint = match markets.first() {
+            Some(quote_market) if &quote_market.address == quote_market_account.key => {
+                quote_market.pc_mint
+            }
+            _ => {
+                msg!("The pool's first market should be given to value the pool.");
+                return Err(ProgramError::InvalidArgument);
+            }
+        };
+
+        let mut total_value: u128 = 0;
+        for (pool_asset, pool_asset_account) in pool_assets.iter().zip(pool_assets_accounts) {
+            if pool_asset_account.key
+                != &get_associated_token_address(pool_account.key, &pool_asset.mint_address)
+            {
+                msg!("Provided pool asset account is invalid");
+                return Err(ProgramError::InvalidArgument);
+            }
+            let amount = Account::unpack(&pool_asset_account.data.borrow())?.amount as u128;
+            if pool_asset.mint_address == quote_mint {
+                total_value += amount;
+                continue;
+            }
 
-        let tokens_to_mint = (collect_ratio * total_pooltokens / feeless_ratio) as u64;
+            let market_account = next_account_info(accounts_iter)?;
+            let bids_account = next_account_info(accounts_iter)?;
+            let asks_account = next_account_info(accounts_iter)?;
+            let pool_market = match markets
+                .iter()
+                .find(|pool_market| &pool_market.address == market_account.key)
+            {
+                Some(pool_market) if market_account.owner == &pool_header.serum_program_id => {
+                    pool_market
+                }
+                _ => {
+                    msg!("The given market is not authorized for this pool.");
+                    return Err(ProgramError::InvalidArgument);
+                }
+            };
+            let market = MarketState::unpack(&market_account.data.borrow())?;
+            if pool_market.coin_mint != pool_asset.mint_address
+                || pool_market.pc_mint != quote_mint
+                || &market.bids != bids_account.key
+                || &market.asks != asks_account.key
+            {
+                msg!("The given market cannot be used to value this pool asset.");
+                return Err(ProgramError::InvalidArgument);
+            }
+            let mid_price =
+                market.get_mid_price(&bids_account.data.borrow(), &asks_account.data.borrow())?;
+            total_value = amount
+                .checked_mul(mid_price)
+                .map(|value| value >> 32)
+                .and_then(|value| total_value.checked_add(value))
+                .ok_or(BonfidaBotError::Overflow)?;
+        }
+        Ok(total_value)
+    }
 
+    // Trades on behalf of the owner with an Immediate-or-Cancel order and settles the fills into
+    // the owner's wallets. The swap accounts are the market, the owner's OpenOrders, the request
+    // queue, the event queue, the bids, the asks, the coin vault, the pc vault and the vault signer.
+    fn swap_immediate<'a>(
+        spl_token_account: &AccountInfo<'a>,
+        rent_sysvar_account: &AccountInfo<'a>,
+        dex_program: &AccountInfo<'a>,
+        owner_account: &AccountInfo<'a>,
+        swap_accounts: &[&AccountInfo<'a>],
+        coin_wallet: &AccountInfo<'a>,
+        pc_wallet: &AccountInfo<'a>,
+        side: Side,
+        limit_price: u64,
+        max_coin_qty: u64,
+        max_native_pc_qty: u64,
+    ) -> ProgramResult {
+        let market = swap_accounts[0];
+        let openorders_account = swap_accounts[1];
+        let request_queue = swap_accounts[2];
+        let event_queue = swap_accounts[3];
+        let market_bids = swap_accounts[4];
+        let market_asks = swap_accounts[5];
+        let coin_vault = swap_accounts[6];
+        let pc_vault = swap_accounts[7];
+        let vault_signer = swap_accounts[8];
+        let payer_account = match side {
+            Side::Bid => pc_wallet,
+            Side::Ask => coin_wallet,
+        };
 
-        // Mint the required amount of pooltokens to the signal provider
-        //
-        // Like with deposit, these will often not be minted in the quantity
-        // expected, unless it's always divisible by 4
-        let signal_provider_fee = tokens_to_mint / 2;
-        let mint_to_sp_instruction = mint_to(
+        let new_order_instruction = new_order(
+            market.key,
+            openorders_account.key,
+            request_queue.key,
+            event_queue.key,
+            market_bids.key,
+            market_asks.key,
+            payer_account.key,
+            owner_account.key,
+            coin_vault.key,
+            pc_vault.key,
             spl_token_account.key,
-            &pool_mint_key,
-            signal_provider_pt_account.key,
-            &pool_account.key,
-            &[],
-            signal_provider_fee,
+            rent_sysvar_account.key,
+            None,
+            dex_program.key,
+            side,
+            NonZeroU64::new(limit_price).ok_or(ProgramError::InvalidArgument)?,
+            NonZeroU64::new(max_coin_qty).ok_or(ProgramError::InvalidArgument)?,
+            OrderType::ImmediateOrCancel,
+            0,
+            SelfTradeBehavior::DecrementTake,
+            // Maximum number of resting orders to match against
+            64,
+            NonZeroU64::new(max_native_pc_qty).ok_or(ProgramError::InsufficientFunds)?,
         )?;
-
-        invoke_signed(
-            &mint_to_sp_instruction,
+        invoke(
+            &new_order_instruction,
             &[
+                dex_program.clone(),
+                market.clone(),
+                openorders_account.clone(),
+                request_queue.clone(),
+                event_queue.clone(),
+                market_bids.clone(),
+                market_asks.clone(),
+                payer_account.clone(),
+                owner_account.clone(),
+                coin_vault.clone(),
+                pc_vault.clone(),
                 spl_token_account.clone(),
-                mint_account.clone(),
-                signal_provider_pt_account.clone(),
-                pool_account.clone(),
+                rent_sysvar_account.clone(),
             ],
-            &[&[&pool_seed]],
         )?;
 
-        // Mint the required amount of pooltokens to the bonfida fee account
-        let bonfida_fee = tokens_to_mint / 4;
-        let mint_to_bonfida_fee_instruction = mint_to(
+        // Immediate-or-Cancel fills are credited to the OpenOrders account right away
+        let settle_instruction = settle_funds(
+            dex_program.key,
+            market.key,
             spl_token_account.key,
-            &pool_mint_key,
-            &bonfida_fee_pt_key,
-            &pool_account.key,
-            &[],
-            bonfida_fee,
+            openorders_account.key,
+            owner_account.key,
+            coin_vault.key,
+            coin_wallet.key,
+            pc_vault.key,
+            pc_wallet.key,
+            None,
+            vault_signer.key,
         )?;
-
-        invoke_signed(
-            &mint_to_bonfida_fee_instruction,
+        invoke(
+            &settle_instruction,
             &[
+                dex_program.clone(),
+                market.clone(),
+                openorders_account.clone(),
+                owner_account.clone(),
+                coin_vault.clone(),
+                pc_vault.clone(),
+                coin_wallet.clone(),
+                pc_wallet.clone(),
+                vault_signer.clone(),
                 spl_token_account.clone(),
-                mint_account.clone(),
-                bonfida_fee_pt_account.clone(),
-                pool_account.clone(),
             ],
-            &[&[&pool_seed]],
-        )?;
-
-        // Mint the required amount of pooltokens to the bonfida fee account
-        let mint_to_bonfida_bnb_instruction = mint_to(
-            spl_token_account.key,
-            &pool_mint_key,
-            &bonfida_bnb_pt_key,
-            &pool_account.key,
-            &[],
-            tokens_to_mint - bonfida_fee - signal_provider_fee,
         )?;
+        Ok(())
+    }
 
-        invoke_signed(
-            &mint_to_bonfida_bnb_instruction,
-            &[
-                spl_token_account.clone(),
-                mint_account.clone(),
-                bonfida_bnb_pt_account.clone(),
-                pool_account.clone(),
-            ],
-            &[&[&pool_seed]],
-        )?;
+    fn mint_fees<'a>(
+        spl_token_account: &AccountInfo<'a>,
+        mint_account: &AccountInfo<'a>,
+        pool_account: &AccountInfo<'a>,
+        signal_provider_pt_account: &AccountInfo<'a>,
+        fee_recipient_pt_accounts: &[&AccountInfo<'a>],
+        config: &ProgramConfig,
+        pool_seed: [u8; 32],
+        fee: u64,
+    ) -> ProgramResult {
+        let (signal_provider_fee, recipient_fees) = split_fees(config, fee);
+        let mut fee_targets = vec![(signal_provider_pt_account, signal_provider_fee)];
+        fee_targets.extend(
+            fee_recipient_pt_accounts
+                .iter()
+                .cloned()
+                .zip(recipient_fees),
+        );
 
-        PoolHeader::pack(
-            pool_header,
-            &mut pool_account.data.borrow_mut()[..PoolHeader::LEN],
-        )?;
+        for (target_account, amount) in fee_targets {
+            if amount == 0 {
+                continue;
+            }
+            let instruction = mint_to(
+                spl_token_account.key,
+                mint_account.key,
+                target_account.key,
+                pool_account.key,
+                &[],
+                amount,
+            )?;
 
+            invoke_signed(
+                &instruction,
+                &[
+                    spl_token_account.clone(),
+                    mint_account.clone(),
+                    target_account.clone(),
+                    pool_account.clone(),
+                ],
+                &[&[&pool_seed]],
+            )?;
+        }
         Ok(())
     }
 
+    // Reads the accounts receiving the redeem fee, which are only expected when the pool charges one
+    fn unpack_redeem_fee_accounts<'a, 'b>(
+        program_id: &Pubkey,
+        pool_header: &PoolHeader,
+        pool_mint_key: &Pubkey,
+        accounts_iter: &mut Iter<'a, AccountInfo<'b>>,
+    ) -> Result<Option<(&'a AccountInfo<'b>, ProgramConfig, Vec<&'a AccountInfo<'b>>)>, ProgramError>
+    {
+        if pool_header.redeem_fee_ratio == 0 {
+            return Ok(None);
+        }
+        let signal_provider_pt_account = next_account_info(accounts_iter)?;
+        if signal_provider_pt_account.key
+            != &get_associated_token_address(&pool_header.signal_provider, pool_mint_key)
+        {
+            msg!("The provided signal provider pool token account is invalid.");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let config_account = next_account_info(accounts_iter)?;
+        let (config, fee_recipient_pt_accounts) =
+            Self::unpack_fee_recipients(program_id, config_account, pool_mint_key, accounts_iter)?;
+        Ok(Some((
+            signal_provider_pt_account,
+            config,
+            fee_recipient_pt_accounts,
+        )))
+    }
+
+    // Transfers the redeem fee out of the redeemed pooltokens and returns the amount left to redeem
+    fn transfer_redeem_fees<'a>(
+        spl_token_account: &AccountInfo<'a>,
+        source_pool_token_owner_account: &AccountInfo<'a>,
+        source_pool_token_account: &AccountInfo<'a>,
+        pool_header: &PoolHeader,
+        redeem_fee_accounts: &Option<(&AccountInfo<'a>, ProgramConfig, Vec<&AccountInfo<'a>>)>,
+        pool_seed: [u8; 32],
+        pool_token_amount: u64,
+    ) -> Result<u64, ProgramError> {
+        let (signal_provider_pt_account, config, fee_recipient_pt_accounts) =
+            match redeem_fee_accounts {
+                Some(accounts) => accounts,
+                None => return Ok(pool_token_amount),
+            };
+        let fee = ((pool_header.redeem_fee_ratio as u128 * pool_token_amount as u128) >> 16) as u64;
+        let (signal_provider_fee, recipient_fees) = split_fees(config, fee);
+        let mut fee_targets = vec![(*signal_provider_pt_account, signal_provider_fee)];
+        fee_targets.extend(
+            fee_recipient_pt_accounts
+                .iter()
+                .cloned()
+                .zip(recipient_fees),
+        );
+
+        for (target_account, amount) in fee_targets {
+            if amount == 0 {
+                continue;
+            }
+            let instruction = transfer(
+                spl_token_account.key,
+                source_pool_token_account.key,
+                target_account.key,
+                source_pool_token_owner_account.key,
+                &[],
+                amount,
+            )?;
+            // The pool signs for the pooltokens escrowed by queued redeem requests
+            invoke_signed(
+                &instruction,
+                &[
+                    spl_token_account.clone(),
+                    source_pool_token_account.clone(),
+                    target_account.clone(),
+                    source_pool_token_owner_account.clone(),
+                ],
+                &[&[&pool_seed]],
+            )?;
+        }
+        Ok(pool_token_amount - fee)
+    }
+
     pub fn process_instruction(
         program_id: &Pubkey,
         accounts: &[AccountInfo],
@@ -1389,6 +5269,13 @@ impl Processor {
                 pool_seed,
                 fee_collection_period,
                 fee_ratio,
+                max_order_lifetime,
+                timelock_period,
+                performance_fee_ratio,
+                redeem_fee_ratio,
+                lockup_period,
+                max_pool_token_supply,
+                min_deposit_pool_tokens,
                 deposit_amounts,
                 markets,
             } => {
@@ -1401,14 +5288,30 @@ impl Processor {
                     markets,
                     fee_collection_period,
                     fee_ratio,
+                    max_order_lifetime,
+                    timelock_period,
+                    performance_fee_ratio,
+                    redeem_fee_ratio,
+                    lockup_period,
+                    max_pool_token_supply,
+                    min_deposit_pool_tokens,
                 )
             }
             PoolInstruction::Deposit {
                 pool_seed,
                 pool_token_amount,
+                min_pool_tokens_out,
+                max_amounts_in,
             } => {
                 msg!("Instruction: Deposit into Pool");
-                Self::process_deposit(program_id, accounts, pool_seed, pool_token_amount)
+                Self::process_deposit(
+                    program_id,
+                    accounts,
+                    pool_seed,
+                    pool_token_amount,
+                    min_pool_tokens_out,
+                    max_amounts_in,
+                )
             }
             PoolInstruction::CreateOrder {
                 pool_seed,
@@ -1436,9 +5339,38 @@ impl Processor {
                     ratio_of_pool_assets_to_trade,
                     order_type,
                     market_index,
-                    coin_lot_size,
-                    pc_lot_size,
-                    target_mint,
+                    Some((coin_lot_size, pc_lot_size, target_mint)),
+                    client_id,
+                    self_trade_behavior,
+                    source_index as usize,
+                    target_index as usize,
+                    serum_limit,
+                )
+            }
+            PoolInstruction::CreateOrderV2 {
+                pool_seed,
+                side,
+                limit_price,
+                ratio_of_pool_assets_to_trade,
+                order_type,
+                client_id,
+                self_trade_behavior,
+                source_index,
+                target_index,
+                market_index,
+                serum_limit,
+            } => {
+                msg!("Instruction: Create Order for Pool");
+                Self::process_create_order(
+                    program_id,
+                    accounts,
+                    pool_seed,
+                    side,
+                    limit_price,
+                    ratio_of_pool_assets_to_trade,
+                    order_type,
+                    market_index,
+                    None,
                     client_id,
                     self_trade_behavior,
                     source_index as usize,
@@ -1471,14 +5403,218 @@ impl Processor {
             PoolInstruction::Redeem {
                 pool_seed,
                 pool_token_amount,
+                min_amounts_out,
             } => {
                 msg!("Instruction: Redeem out of Pool");
-                Self::process_redeem(program_id, accounts, pool_seed, pool_token_amount)
+                Self::process_redeem(
+                    program_id,
+                    accounts,
+                    pool_seed,
+                    pool_token_amount,
+                    min_amounts_out,
+                )
             }
             PoolInstruction::CollectFees { pool_seed } => {
                 msg!("Instruction: Collect Fees for Pool");
                 Self::process_collect_fees(program_id, accounts, pool_seed)
             }
+            PoolInstruction::Lock { pool_seed } => {
+                msg!("Instruction: Lock Pool");
+                Self::process_lock(program_id, accounts, pool_seed)
+            }
+            PoolInstruction::Unlock { pool_seed } => {
+                msg!("Instruction: Unlock Pool");
+                Self::process_unlock(program_id, accounts, pool_seed)
+            }
+            PoolInstruction::ProposeSignalProvider {
+                pool_seed,
+                new_signal_provider,
+            } => {
+                msg!("Instruction: Propose Signal Provider for Pool");
+                Self::process_propose_signal_provider(
+                    program_id,
+                    accounts,
+                    pool_seed,
+                    new_signal_provider,
+                )
+            }
+            PoolInstruction::AcceptSignalProvider { pool_seed } => {
+                msg!("Instruction: Accept Signal Provider for Pool");
+                Self::process_accept_signal_provider(program_id, accounts, pool_seed)
+            }
+            PoolInstruction::DepositSingleAsset {
+                pool_seed,
+                pool_token_amount,
+                min_pool_tokens_out,
+                limit_prices,
+            } => {
+                msg!("Instruction: Deposit Single Asset into Pool");
+                Self::process_deposit_single_asset(
+                    program_id,
+                    accounts,
+                    pool_seed,
+                    pool_token_amount,
+                    min_pool_tokens_out,
+                    limit_prices,
+                )
+            }
+            PoolInstruction::RedeemToSingleAsset {
+                pool_seed,
+                pool_token_amount,
+                min_amount_out,
+                limit_prices,
+            } => {
+                msg!("Instruction: Redeem out of Pool into a Single Asset");
+                Self::process_redeem_to_single_asset(
+                    program_id,
+                    accounts,
+                    pool_seed,
+                    pool_token_amount,
+                    min_amount_out,
+                    limit_prices,
+                )
+            }
+            PoolInstruction::CancelOrderByClientId {
+                pool_seed,
+                client_id,
+            } => {
+                msg!("Instruction: Cancel Order by Client Id");
+                Self::process_cancel_by_client_id(program_id, accounts, pool_seed, client_id)
+            }
+            PoolInstruction::CancelAllOrders { pool_seed } => {
+                msg!("Instruction: Cancel All Orders");
+                Self::process_cancel_all(program_id, accounts, pool_seed)
+            }
+            PoolInstruction::SettleAndRedeem {
+                pool_seed,
+                pool_token_amount,
+                min_amounts_out,
+            } => {
+                msg!("Instruction: Settle And Redeem");
+                Self::process_settle_and_redeem(
+                    program_id,
+                    accounts,
+                    pool_seed,
+                    pool_token_amount,
+                    min_amounts_out,
+                )
+            }
+            PoolInstruction::RequestRedeem {
+                pool_seed,
+                pool_token_amount,
+                min_amounts_out,
+            } => {
+                msg!("Instruction: Request Redeem");
+                Self::process_request_redeem(
+                    program_id,
+                    accounts,
+                    pool_seed,
+                    pool_token_amount,
+                    min_amounts_out,
+                )
+            }
+            PoolInstruction::ProcessRedeemQueue { pool_seed } => {
+                msg!("Instruction: Process Redeem Queue");
+                Self::process_redeem_queue(program_id, accounts, pool_seed)
+            }
+            PoolInstruction::CancelRedeemRequest {
+                pool_seed,
+                request_index,
+            } => {
+                msg!("Instruction: Cancel Redeem Request");
+                Self::process_cancel_redeem_request(program_id, accounts, pool_seed, request_index)
+            }
+            PoolInstruction::ReleaseDeposit { pool_seed } => {
+                msg!("Instruction: Release Deposit");
+                Self::process_release_deposit(program_id, accounts, pool_seed)
+            }
+            PoolInstruction::SetDepositLimits {
+                pool_seed,
+                max_pool_token_supply,
+                min_deposit_pool_tokens,
+            } => {
+                msg!("Instruction: Set Deposit Limits");
+                Self::process_set_deposit_limits(
+                    program_id,
+                    accounts,
+                    pool_seed,
+                    max_pool_token_supply,
+                    min_deposit_pool_tokens,
+                )
+            }
+            PoolInstruction::SetWhitelist {
+                pool_seed,
+                gating_mint,
+                owners,
+            } => {
+                msg!("Instruction: Set Whitelist");
+                Self::process_set_whitelist(program_id, accounts, pool_seed, gating_mint, owners)
+            }
+            PoolInstruction::RemoveWhitelist { pool_seed } => {
+                msg!("Instruction: Remove Whitelist");
+                Self::process_remove_whitelist(program_id, accounts, pool_seed)
+            }
+            PoolInstruction::ProposeMarketChange {
+                pool_seed,
+                market_index,
+                market,
+            } => {
+                msg!("Instruction: Propose Market Change for Pool");
+                Self::process_propose_market_change(
+                    program_id,
+                    accounts,
+                    pool_seed,
+                    market_index,
+                    market,
+                )
+            }
+            PoolInstruction::ExecuteMarketChange { pool_seed } => {
+                msg!("Instruction: Execute Market Change for Pool");
+                Self::process_execute_market_change(program_id, accounts, pool_seed)
+            }
+            PoolInstruction::ClosePool { pool_seed } => {
+                msg!("Instruction: Close Pool");
+                Self::process_close_pool(program_id, accounts, pool_seed)
+            }
+            PoolInstruction::ResizePool {
+                pool_seed,
+                max_number_of_assets,
+            } => {
+                msg!("Instruction: Resize Pool");
+                Self::process_resize_pool(program_id, accounts, pool_seed, max_number_of_assets)
+            }
+            PoolInstruction::MigratePool { pool_seed } => {
+                msg!("Instruction: Migrate Pool");
+                Self::process_migrate_pool(program_id, accounts, pool_seed)
+            }
+            PoolInstruction::SetMetadata {
+                pool_seed,
+                name,
+                description,
+                uri,
+            } => {
+                msg!("Instruction: Set Pool Metadata");
+                Self::process_set_metadata(program_id, accounts, pool_seed, name, description, uri)
+            }
+            PoolInstruction::InitOpenOrders {
+                pool_seed,
+                market_index,
+            } => {
+                msg!("Instruction: Init Pool OpenOrders");
+                Self::process_init_open_orders(program_id, accounts, pool_seed, market_index)
+            }
+            PoolInstruction::CreateConfig {
+                signal_provider_share,
+                fee_recipients,
+            } => {
+                msg!("Instruction: Create Program Config");
+                Self::process_create_config(
+                    program_id,
+                    accounts,
+                    signal_provider_share,
+                    fee_recipients,
+                )
+            }
         }
     }
 }
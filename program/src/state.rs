@@ -7,16 +7,42 @@ use std::{convert::TryInto, num::NonZeroU8};
 
 pub const PUBKEY_LENGTH: usize = 32;
 
-pub const BONFIDA_FEE: &str = "31LVSggbVz4VcwBSPdtK8HJ3Lt1cKTJUVQTRNNYMfqBq";
-pub const BONFIDA_BNB: &str = "3oQzjfjzUkJ5qHsERk2JPEpAKo34dxAQjUriBqursfxU";
+pub const CONFIG_SEED: &[u8] = b"config";
+pub const MAX_FEE_RECIPIENTS: usize = 8;
+pub const OPEN_ORDERS_SEED: &[u8] = b"open_orders";
+/// Size of a Serum OpenOrders account, including the 12 bytes of padding added by the dex
+pub const OPEN_ORDERS_LEN: usize = 3228;
+pub const REDEEM_QUEUE_SEED: &[u8] = b"redeem_queue";
+pub const DEPOSIT_RECEIPT_SEED: &[u8] = b"deposit_receipt";
+/// Maximum amount of time in seconds a pool can lock deposited pooltokens up for, a year
+pub const MAX_LOCKUP_PERIOD: u64 = 31_536_000;
+/// Maximum number of redeem requests waiting in a pool's queue
+pub const REDEEM_QUEUE_CAPACITY: usize = 64;
+/// Maximum number of pool assets a queued redeem request can store minimum amounts for
+pub const MAX_REDEEM_REQUEST_ASSETS: usize = 16;
+pub const WHITELIST_SEED: &[u8] = b"whitelist";
+/// Maximum number of owners on a private pool's whitelist
+pub const MAX_WHITELIST_OWNERS: usize = 64;
+pub const METADATA_SEED: &[u8] = b"metadata";
+/// Maximum lengths in bytes of the UTF-8 strings of a pool's metadata
+pub const MAX_METADATA_NAME_LEN: usize = 32;
+pub const MAX_METADATA_DESCRIPTION_LEN: usize = 200;
+pub const MAX_METADATA_URI_LEN: usize = 200;
+pub const REGISTRY_SEED: &[u8] = b"registry";
+/// Maximum number of pools listed in a signal provider's registry
+pub const MAX_REGISTRY_POOLS: usize = 64;
+/// Widest bid-ask spread, out of 2**16 of the best ask, at which a market's mid price can be used
+/// to value a pool. A thin order book can't move the high-water mark of the performance fee.
+pub const MAX_VALUATION_SPREAD_RATIO: u16 = 3277;
 
-// Pool state is composed of PoolHeader, Array of markets (pubkeys) and array of poolassets
+// Pool state is composed of PoolHeader, Array of PoolMarkets, array of PoolOpenOrders (one
+// per market) and array of poolassets
 
 #[derive(Debug, PartialEq)]
 pub struct PoolAsset {
     pub mint_address: Pubkey,
 }
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum PoolStatus {
     Uninitialized,
     Unlocked,
@@ -26,8 +52,29 @@ pub enum PoolStatus {
     LockedPendingOrder(NonZeroU8),
 }
 
+/// Type of the account, stored in the first byte of the pool header
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AccountType {
+    Uninitialized,
+    Pool,
+}
+
+/// Current version of the pool account layout. Accounts written before the header was
+/// versioned are upgraded by MigratePool.
+pub const POOL_VERSION: u8 = 1;
+/// Length of the header of unversioned pool accounts, which is followed by the addresses of the
+/// pool's markets and by its assets up to the end of the account
+pub const POOL_HEADER_V0_LEN: usize = 117;
+/// Timelock period and maximum order lifetime given to the pools migrated from the unversioned
+/// layout, which had neither
+pub const MIGRATED_POOL_TIMELOCK_PERIOD: u64 = 86400;
+pub const MIGRATED_POOL_MAX_ORDER_LIFETIME: u64 = 86400;
+
 #[derive(Debug, PartialEq)]
 pub struct PoolHeader {
+    pub account_type: AccountType,
+    /// Layout version of the pool account, POOL_VERSION once initialized
+    pub version: u8,
     pub serum_program_id: Pubkey,
     pub seed: [u8; 32],
     pub signal_provider: Pubkey,
@@ -36,6 +83,49 @@ pub struct PoolHeader {
     pub fee_ratio: u16,
     pub last_fee_collection_timestamp: u64,
     pub fee_collection_period: u64,
+    /// Maximum amount of time in seconds the pool can spend with pending orders before
+    /// anyone is allowed to cancel them.
+    pub max_order_lifetime: u64,
+    /// Timestamp at which the pool last went from no pending orders to at least one.
+    pub first_pending_order_timestamp: u64,
+    /// Delay in seconds before a proposed change to the pool can be executed.
+    pub timelock_period: u64,
+    /// Proposed new signal provider, the default pubkey when there is no pending proposal.
+    pub pending_signal_provider: Pubkey,
+    pub signal_provider_proposal_timestamp: u64,
+    /// Share of the gains above the high-water mark taken as a performance fee, out of 2**16.
+    pub performance_fee_ratio: u16,
+    /// Highest NAV per pooltoken on which performance fees were collected, as a 32.32 fixed
+    /// point amount of the price currency of the pool's first market. Zero until the first
+    /// fee collection.
+    pub high_water_mark: u64,
+    /// Share of the redeemed pooltokens taken as a fee, out of 2**16.
+    pub redeem_fee_ratio: u16,
+    /// Minimum amount of time in seconds deposited pooltokens are escrowed before being released
+    /// to their depositor. Zero when deposits aren't locked up.
+    pub lockup_period: u64,
+    /// Maximum supply of pooltokens deposits can bring the pool to. Zero for an uncapped pool.
+    pub max_pool_token_supply: u64,
+    /// Minimum amount of pooltokens a deposit has to buy, fees included.
+    pub min_deposit_pool_tokens: u64,
+    /// Private pools only accept deposits from the investors allowed by their whitelist account.
+    pub is_private: bool,
+    /// Market slot targeted by the pending market change, equal to the number of markets when
+    /// a new market is to be added.
+    pub pending_market_index: u16,
+    /// Proposed market for the pending market change, the default pubkey when there is no
+    /// pending proposal.
+    pub pending_market: Pubkey,
+    pub market_proposal_timestamp: u64,
+    /// Payer of the pool account's rent, to which ClosePool returns the lamports of the pool's
+    /// accounts. Recorded by Init.
+    pub creator: Pubkey,
+    /// Number of asset slots the pool account has room for
+    pub max_number_of_assets: u32,
+    /// Offsets in the pool account of the markets, OpenOrders registry and assets regions
+    pub market_offset: u32,
+    pub open_orders_offset: u32,
+    pub asset_offset: u32,
 }
 
 const STATUS_PENDING_ORDER_FLAG: u8 = 1 << 6;
@@ -43,63 +133,128 @@ const STATUS_PENDING_ORDER_MASK: u8 = 0x3f;
 const STATUS_LOCKED_FLAG: u8 = 2 << 6;
 const STATUS_UNLOCKED_FLAG: u8 = STATUS_PENDING_ORDER_MASK;
 
+pub fn pack_pool_status(status: &PoolStatus) -> u8 {
+    match status {
+        PoolStatus::Uninitialized => 0,
+        PoolStatus::Unlocked => STATUS_UNLOCKED_FLAG,
+        PoolStatus::Locked => STATUS_LOCKED_FLAG,
+        PoolStatus::PendingOrder(n) => {
+            STATUS_PENDING_ORDER_FLAG | (STATUS_PENDING_ORDER_MASK & (n.get() - 1))
+        }
+        PoolStatus::LockedPendingOrder(n) => {
+            STATUS_LOCKED_FLAG
+                | STATUS_PENDING_ORDER_FLAG
+                | (STATUS_PENDING_ORDER_MASK & (n.get() - 1))
+        }
+    }
+}
+
+pub fn unpack_pool_status(src: u8) -> Result<PoolStatus, ProgramError> {
+    if src == 0 {
+        return Ok(PoolStatus::Uninitialized);
+    }
+    Ok(match src >> 6 {
+        0 => PoolStatus::Unlocked,
+        1 => PoolStatus::PendingOrder(
+            NonZeroU8::new((src & STATUS_PENDING_ORDER_MASK) + 1)
+                .ok_or(ProgramError::InvalidArgument)?,
+        ),
+        2 => PoolStatus::Locked,
+        3 => PoolStatus::LockedPendingOrder(
+            NonZeroU8::new((src & STATUS_PENDING_ORDER_MASK) + 1)
+                .ok_or(ProgramError::InvalidArgument)?,
+        ),
+        _ => return Err(ProgramError::InvalidAccountData),
+    })
+}
+
 impl Sealed for PoolHeader {}
 
 impl Pack for PoolHeader {
-    const LEN: usize = 117;
+    const LEN: usize = 310;
 
     fn pack_into_slice(&self, target: &mut [u8]) {
+        target[0] = self.account_type as u8;
+        target[1] = self.version;
         let serum_program_id_bytes = self.serum_program_id.to_bytes();
-        target[0..32].copy_from_slice(&serum_program_id_bytes);
-        target[32..64].copy_from_slice(&self.seed);
+        target[2..34].copy_from_slice(&serum_program_id_bytes);
+        target[34..66].copy_from_slice(&self.seed);
         let signal_provider_bytes = self.signal_provider.to_bytes();
-        target[64..96].copy_from_slice(&signal_provider_bytes);
-        target[96] = match self.status {
-            PoolStatus::Uninitialized => 0,
-            PoolStatus::Unlocked => STATUS_UNLOCKED_FLAG,
-            PoolStatus::Locked => STATUS_LOCKED_FLAG,
-            PoolStatus::PendingOrder(n) => {
-                STATUS_PENDING_ORDER_FLAG | (STATUS_PENDING_ORDER_MASK & (n.get() - 1))
-            }
-            PoolStatus::LockedPendingOrder(n) => {
-                STATUS_LOCKED_FLAG
-                    | STATUS_PENDING_ORDER_FLAG
-                    | (STATUS_PENDING_ORDER_MASK & (n.get() - 1))
-            }
-        };
+        target[66..98].copy_from_slice(&signal_provider_bytes);
+        target[98] = pack_pool_status(&self.status);
         let number_of_markets_bytes = self.number_of_markets.to_le_bytes();
-        target[97..99].copy_from_slice(&number_of_markets_bytes);
-        target[99..101].copy_from_slice(&self.fee_ratio.to_le_bytes());
-        target[101..109].copy_from_slice(&self.last_fee_collection_timestamp.to_le_bytes());
-        target[109..117].copy_from_slice(&self.fee_collection_period.to_le_bytes());
+        target[99..101].copy_from_slice(&number_of_markets_bytes);
+        target[101..103].copy_from_slice(&self.fee_ratio.to_le_bytes());
+        target[103..111].copy_from_slice(&self.last_fee_collection_timestamp.to_le_bytes());
+        target[111..119].copy_from_slice(&self.fee_collection_period.to_le_bytes());
+        target[119..127].copy_from_slice(&self.max_order_lifetime.to_le_bytes());
+        target[127..135].copy_from_slice(&self.first_pending_order_timestamp.to_le_bytes());
+        target[135..143].copy_from_slice(&self.timelock_period.to_le_bytes());
+        target[143..175].copy_from_slice(&self.pending_signal_provider.to_bytes());
+        target[175..183].copy_from_slice(&self.signal_provider_proposal_timestamp.to_le_bytes());
+        target[183..185].copy_from_slice(&self.performance_fee_ratio.to_le_bytes());
+        target[185..193].copy_from_slice(&self.high_water_mark.to_le_bytes());
+        target[193..195].copy_from_slice(&self.redeem_fee_ratio.to_le_bytes());
+        target[195..203].copy_from_slice(&self.lockup_period.to_le_bytes());
+        target[203..211].copy_from_slice(&self.max_pool_token_supply.to_le_bytes());
+        target[211..219].copy_from_slice(&self.min_deposit_pool_tokens.to_le_bytes());
+        target[219] = self.is_private as u8;
+        target[220..222].copy_from_slice(&self.pending_market_index.to_le_bytes());
+        target[222..254].copy_from_slice(&self.pending_market.to_bytes());
+        target[254..262].copy_from_slice(&self.market_proposal_timestamp.to_le_bytes());
+        target[262..294].copy_from_slice(&self.creator.to_bytes());
+        target[294..298].copy_from_slice(&self.max_number_of_assets.to_le_bytes());
+        target[298..302].copy_from_slice(&self.market_offset.to_le_bytes());
+        target[302..306].copy_from_slice(&self.open_orders_offset.to_le_bytes());
+        target[306..310].copy_from_slice(&self.asset_offset.to_le_bytes());
     }
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        let serum_program_id = Pubkey::new(&src[..32]);
-        let seed: [u8; 32] = src[32..64].try_into().unwrap();
-        let signal_provider = Pubkey::new(&src[64..96]);
-        let status = if src[96] == 0 {
-            PoolStatus::Uninitialized
-        } else {
-            match src[96] >> 6 {
-                0 => PoolStatus::Unlocked,
-                1 => PoolStatus::PendingOrder(
-                    NonZeroU8::new((src[96] & STATUS_PENDING_ORDER_MASK) + 1)
-                        .ok_or(ProgramError::InvalidArgument)?,
-                ),
-                2 => PoolStatus::Locked,
-                3 => PoolStatus::LockedPendingOrder(
-                    NonZeroU8::new((src[96] & STATUS_PENDING_ORDER_MASK) + 1)
-                        .ok_or(ProgramError::InvalidArgument)?,
-                ),
-                _ => return Err(ProgramError::InvalidAccountData),
-            }
+        let account_type = match src[0] {
+            0 => AccountType::Uninitialized,
+            1 => AccountType::Pool,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let version = src[1];
+        if account_type == AccountType::Pool && version != POOL_VERSION {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let serum_program_id = Pubkey::new(&src[2..34]);
+        let seed: [u8; 32] = src[34..66].try_into().unwrap();
+        let signal_provider = Pubkey::new(&src[66..98]);
+        let status = unpack_pool_status(src[98])?;
+        let number_of_markets = u16::from_le_bytes(src[99..101].try_into().unwrap());
+        let fee_ratio = u16::from_le_bytes(src[101..103].try_into().unwrap());
+        let last_fee_collection_timestamp = u64::from_le_bytes(src[103..111].try_into().unwrap());
+        let fee_collection_period = u64::from_le_bytes(src[111..119].try_into().unwrap());
+        let max_order_lifetime = u64::from_le_bytes(src[119..127].try_into().unwrap());
+        let first_pending_order_timestamp = u64::from_le_bytes(src[127..135].try_into().unwrap());
+        let timelock_period = u64::from_le_bytes(src[135..143].try_into().unwrap());
+        let pending_signal_provider = Pubkey::new(&src[143..175]);
+        let signal_provider_proposal_timestamp =
+            u64::from_le_bytes(src[175..183].try_into().unwrap());
+        let performance_fee_ratio = u16::from_le_bytes(src[183..185].try_into().unwrap());
+        let high_water_mark = u64::from_le_bytes(src[185..193].try_into().unwrap());
+        let redeem_fee_ratio = u16::from_le_bytes(src[193..195].try_into().unwrap());
+        let lockup_period = u64::from_le_bytes(src[195..203].try_into().unwrap());
+        let max_pool_token_supply = u64::from_le_bytes(src[203..211].try_into().unwrap());
+        let min_deposit_pool_tokens = u64::from_le_bytes(src[211..219].try_into().unwrap());
+        let is_private = match src[219] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
         };
-        let number_of_markets = u16::from_le_bytes(src[97..99].try_into().unwrap());
-        let fee_ratio = u16::from_le_bytes(src[99..101].try_into().unwrap());
-        let last_fee_collection_timestamp = u64::from_le_bytes(src[101..109].try_into().unwrap());
-        let fee_collection_period = u64::from_le_bytes(src[109..117].try_into().unwrap());
+        let pending_market_index = u16::from_le_bytes(src[220..222].try_into().unwrap());
+        let pending_market = Pubkey::new(&src[222..254]);
+        let market_proposal_timestamp = u64::from_le_bytes(src[254..262].try_into().unwrap());
+        let creator = Pubkey::new(&src[262..294]);
+        let max_number_of_assets = u32::from_le_bytes(src[294..298].try_into().unwrap());
+        let market_offset = u32::from_le_bytes(src[298..302].try_into().unwrap());
+        let open_orders_offset = u32::from_le_bytes(src[302..306].try_into().unwrap());
+        let asset_offset = u32::from_le_bytes(src[306..310].try_into().unwrap());
         Ok(Self {
+            account_type,
+            version,
             serum_program_id,
             seed,
             signal_provider,
@@ -108,6 +263,26 @@ impl Pack for PoolHeader {
             fee_ratio,
             last_fee_collection_timestamp,
             fee_collection_period,
+            max_order_lifetime,
+            first_pending_order_timestamp,
+            timelock_period,
+            pending_signal_provider,
+            signal_provider_proposal_timestamp,
+            performance_fee_ratio,
+            high_water_mark,
+            redeem_fee_ratio,
+            lockup_period,
+            max_pool_token_supply,
+            min_deposit_pool_tokens,
+            is_private,
+            pending_market_index,
+            pending_market,
+            market_proposal_timestamp,
+            creator,
+            max_number_of_assets,
+            market_offset,
+            open_orders_offset,
+            asset_offset,
         })
     }
 
@@ -148,6 +323,80 @@ impl IsInitialized for PoolHeader {
     }
 }
 
+/// Serum market the pool is authorized to trade on, along with the parameters read from the
+/// market account when it was authorized
+#[derive(Debug, PartialEq)]
+pub struct PoolMarket {
+    pub address: Pubkey,
+    pub coin_mint: Pubkey,
+    pub pc_mint: Pubkey,
+    pub coin_lot_size: u64,
+    pub pc_lot_size: u64,
+}
+
+impl Sealed for PoolMarket {}
+
+impl Pack for PoolMarket {
+    const LEN: usize = 112;
+
+    fn pack_into_slice(&self, target: &mut [u8]) {
+        target[0..32].copy_from_slice(&self.address.to_bytes());
+        target[32..64].copy_from_slice(&self.coin_mint.to_bytes());
+        target[64..96].copy_from_slice(&self.pc_mint.to_bytes());
+        target[96..104].copy_from_slice(&self.coin_lot_size.to_le_bytes());
+        target[104..112].copy_from_slice(&self.pc_lot_size.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(Self {
+            address: Pubkey::new(&src[0..32]),
+            coin_mint: Pubkey::new(&src[32..64]),
+            pc_mint: Pubkey::new(&src[64..96]),
+            coin_lot_size: u64::from_le_bytes(src[96..104].try_into().unwrap()),
+            pc_lot_size: u64::from_le_bytes(src[104..112].try_into().unwrap()),
+        })
+    }
+}
+
+/// Serum OpenOrders account owned by the pool for the market with the same index
+#[derive(Debug, PartialEq)]
+pub struct PoolOpenOrders {
+    pub address: Pubkey,
+    /// Whether the account holds funds which haven't been settled back into the pool yet.
+    /// The pool's pending order count is the number of pending OpenOrders accounts.
+    pub is_pending: bool,
+}
+
+impl Sealed for PoolOpenOrders {}
+
+impl IsInitialized for PoolOpenOrders {
+    fn is_initialized(&self) -> bool {
+        self.address != Pubkey::default()
+    }
+}
+
+impl Pack for PoolOpenOrders {
+    const LEN: usize = 33;
+
+    fn pack_into_slice(&self, target: &mut [u8]) {
+        target[0..32].copy_from_slice(&self.address.to_bytes());
+        target[32] = self.is_pending as u8;
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let address = Pubkey::new(&src[..32]);
+        let is_pending = match src[32] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        Ok(Self {
+            address,
+            is_pending,
+        })
+    }
+}
+
 impl Sealed for PoolAsset {}
 
 impl IsInitialized for PoolAsset {
@@ -170,6 +419,431 @@ impl Pack for PoolAsset {
     }
 }
 
+// The program config is a single account derived from CONFIG_SEED which holds the fee split
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeeRecipient {
+    pub owner: Pubkey,
+    pub share: u16,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ProgramConfig {
+    pub is_initialized: bool,
+    /// Fees are split between the signal provider and the fee recipients pro-rata to their shares
+    pub signal_provider_share: u16,
+    pub fee_recipients: Vec<FeeRecipient>,
+}
+
+impl Sealed for ProgramConfig {}
+
+impl IsInitialized for ProgramConfig {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for ProgramConfig {
+    const LEN: usize = 4 + MAX_FEE_RECIPIENTS * (PUBKEY_LENGTH + 2);
+
+    fn pack_into_slice(&self, target: &mut [u8]) {
+        target[0] = self.is_initialized as u8;
+        target[1..3].copy_from_slice(&self.signal_provider_share.to_le_bytes());
+        target[3] = self.fee_recipients.len() as u8;
+        let mut offset = 4;
+        for recipient in self.fee_recipients.iter() {
+            target[offset..offset + 32].copy_from_slice(&recipient.owner.to_bytes());
+            target[offset + 32..offset + 34].copy_from_slice(&recipient.share.to_le_bytes());
+            offset += PUBKEY_LENGTH + 2;
+        }
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let is_initialized = match src[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let signal_provider_share = u16::from_le_bytes(src[1..3].try_into().unwrap());
+        let number_of_recipients = src[3] as usize;
+        if number_of_recipients > MAX_FEE_RECIPIENTS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let mut fee_recipients = Vec::with_capacity(number_of_recipients);
+        let mut offset = 4;
+        for _ in 0..number_of_recipients {
+            fee_recipients.push(FeeRecipient {
+                owner: Pubkey::new(&src[offset..offset + 32]),
+                share: u16::from_le_bytes(src[offset + 32..offset + 34].try_into().unwrap()),
+            });
+            offset += PUBKEY_LENGTH + 2;
+        }
+        Ok(Self {
+            is_initialized,
+            signal_provider_share,
+            fee_recipients,
+        })
+    }
+}
+
+/// Investors allowed to buy into a private pool, stored in an account derived from the pool
+/// seed and the WHITELIST_SEED
+#[derive(Debug, PartialEq)]
+pub struct Whitelist {
+    pub is_initialized: bool,
+    /// Holders of this mint are allowed to deposit, the default pubkey when there is none
+    pub gating_mint: Pubkey,
+    pub owners: Vec<Pubkey>,
+}
+
+impl Sealed for Whitelist {}
+
+impl IsInitialized for Whitelist {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Whitelist {
+    const LEN: usize = 34 + MAX_WHITELIST_OWNERS * PUBKEY_LENGTH;
+
+    fn pack_into_slice(&self, target: &mut [u8]) {
+        target[0] = self.is_initialized as u8;
+        target[1..33].copy_from_slice(&self.gating_mint.to_bytes());
+        target[33] = self.owners.len() as u8;
+        let mut offset = 34;
+        for owner in self.owners.iter() {
+            target[offset..offset + PUBKEY_LENGTH].copy_from_slice(&owner.to_bytes());
+            offset += PUBKEY_LENGTH;
+        }
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let is_initialized = match src[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let gating_mint = Pubkey::new(&src[1..33]);
+        let number_of_owners = src[33] as usize;
+        if number_of_owners > MAX_WHITELIST_OWNERS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let mut owners = Vec::with_capacity(number_of_owners);
+        let mut offset = 34;
+        for _ in 0..number_of_owners {
+            owners.push(Pubkey::new(&src[offset..offset + PUBKEY_LENGTH]));
+            offset += PUBKEY_LENGTH;
+        }
+        Ok(Self {
+            is_initialized,
+            gating_mint,
+            owners,
+        })
+    }
+}
+
+/// Human-readable description of a pool, stored in an account derived from the pool seed and
+/// the METADATA_SEED
+#[derive(Debug, PartialEq)]
+pub struct PoolMetadata {
+    pub is_initialized: bool,
+    /// Timestamp at which the metadata account was created
+    pub creation_timestamp: u64,
+    pub name: String,
+    pub description: String,
+    /// Link to the pool's strategy
+    pub uri: String,
+}
+
+impl Sealed for PoolMetadata {}
+
+impl IsInitialized for PoolMetadata {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+const METADATA_NAME_OFFSET: usize = 9;
+const METADATA_DESCRIPTION_OFFSET: usize = METADATA_NAME_OFFSET + 1 + MAX_METADATA_NAME_LEN;
+const METADATA_URI_OFFSET: usize = METADATA_DESCRIPTION_OFFSET + 1 + MAX_METADATA_DESCRIPTION_LEN;
+
+// Strings are stored as a length byte followed by a zero padded field of their maximum length
+fn pack_string(target: &mut [u8], string: &str) {
+    target[0] = string.len() as u8;
+    target[1..1 + string.len()].copy_from_slice(string.as_bytes());
+    for b in target[1 + string.len()..].iter_mut() {
+        *b = 0;
+    }
+}
+
+fn unpack_string(src: &[u8], max_len: usize) -> Result<String, ProgramError> {
+    let len = src[0] as usize;
+    if len > max_len {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    String::from_utf8(src[1..1 + len].to_vec()).map_err(|_| ProgramError::InvalidAccountData)
+}
+
+impl Pack for PoolMetadata {
+    const LEN: usize = METADATA_URI_OFFSET + 1 + MAX_METADATA_URI_LEN;
+
+    fn pack_into_slice(&self, target: &mut [u8]) {
+        target[0] = self.is_initialized as u8;
+        target[1..9].copy_from_slice(&self.creation_timestamp.to_le_bytes());
+        pack_string(
+            &mut target[METADATA_NAME_OFFSET..METADATA_DESCRIPTION_OFFSET],
+            &self.name,
+        );
+        pack_string(
+            &mut target[METADATA_DESCRIPTION_OFFSET..METADATA_URI_OFFSET],
+            &self.description,
+        );
+        pack_string(&mut target[METADATA_URI_OFFSET..Self::LEN], &self.uri);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let is_initialized = match src[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let creation_timestamp = u64::from_le_bytes(src[1..9].try_into().unwrap());
+        let name = unpack_string(&src[METADATA_NAME_OFFSET..], MAX_METADATA_NAME_LEN)?;
+        let description = unpack_string(
+            &src[METADATA_DESCRIPTION_OFFSET..],
+            MAX_METADATA_DESCRIPTION_LEN,
+        )?;
+        let uri = unpack_string(&src[METADATA_URI_OFFSET..], MAX_METADATA_URI_LEN)?;
+        Ok(Self {
+            is_initialized,
+            creation_timestamp,
+            name,
+            description,
+            uri,
+        })
+    }
+}
+
+/// Seeds of the pools run by a signal provider, stored in an account derived from the signal
+/// provider's key and the REGISTRY_SEED. A pool drained and created again by another signal
+/// provider stays listed in the registry of the previous one, so clients should check the
+/// signal provider in the header of the listed pools.
+#[derive(Debug, PartialEq)]
+pub struct PoolRegistry {
+    pub is_initialized: bool,
+    pub pool_seeds: Vec<[u8; 32]>,
+}
+
+impl Sealed for PoolRegistry {}
+
+impl IsInitialized for PoolRegistry {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for PoolRegistry {
+    const LEN: usize = 2 + MAX_REGISTRY_POOLS * 32;
+
+    fn pack_into_slice(&self, target: &mut [u8]) {
+        target[0] = self.is_initialized as u8;
+        target[1] = self.pool_seeds.len() as u8;
+        let mut offset = 2;
+        for pool_seed in self.pool_seeds.iter() {
+            target[offset..offset + 32].copy_from_slice(pool_seed);
+            offset += 32;
+        }
+        for b in target[offset..Self::LEN].iter_mut() {
+            *b = 0;
+        }
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let is_initialized = match src[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let number_of_pools = src[1] as usize;
+        if number_of_pools > MAX_REGISTRY_POOLS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let pool_seeds = src[2..2 + 32 * number_of_pools]
+            .chunks_exact(32)
+            .map(|seed| seed.try_into().unwrap())
+            .collect();
+        Ok(Self {
+            is_initialized,
+            pool_seeds,
+        })
+    }
+}
+
+// The redeem queue account of a pool is composed of a RedeemQueueHeader followed by a ring
+// buffer of REDEEM_QUEUE_CAPACITY RedeemRequests
+
+#[derive(Debug, PartialEq)]
+pub struct RedeemQueueHeader {
+    /// Index of the oldest request which hasn't been processed yet
+    pub head: u64,
+    /// Index of the next request to be queued
+    pub tail: u64,
+}
+
+impl Sealed for RedeemQueueHeader {}
+
+impl Pack for RedeemQueueHeader {
+    const LEN: usize = 16;
+
+    fn pack_into_slice(&self, target: &mut [u8]) {
+        target[0..8].copy_from_slice(&self.head.to_le_bytes());
+        target[8..16].copy_from_slice(&self.tail.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let head = u64::from_le_bytes(src[0..8].try_into().unwrap());
+        let tail = u64::from_le_bytes(src[8..16].try_into().unwrap());
+        if tail < head || tail - head > REDEEM_QUEUE_CAPACITY as u64 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self { head, tail })
+    }
+}
+
+/// Pooltokens escrowed by the pool until they can be redeemed on behalf of their owner
+#[derive(Debug, PartialEq)]
+pub struct RedeemRequest {
+    pub owner: Pubkey,
+    /// Zero once the request has been cancelled
+    pub pool_token_amount: u64,
+    /// Minimum amount of each pool asset to pay out, at most MAX_REDEEM_REQUEST_ASSETS of them
+    pub min_amounts_out: Vec<u64>,
+}
+
+impl Sealed for RedeemRequest {}
+
+impl Pack for RedeemRequest {
+    const LEN: usize = 41 + 8 * MAX_REDEEM_REQUEST_ASSETS;
+
+    fn pack_into_slice(&self, target: &mut [u8]) {
+        target[0..32].copy_from_slice(&self.owner.to_bytes());
+        target[32..40].copy_from_slice(&self.pool_token_amount.to_le_bytes());
+        target[40] = self.min_amounts_out.len() as u8;
+        for (i, amount) in self.min_amounts_out.iter().enumerate() {
+            target[41 + 8 * i..49 + 8 * i].copy_from_slice(&amount.to_le_bytes());
+        }
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let number_of_amounts = src[40] as usize;
+        if number_of_amounts > MAX_REDEEM_REQUEST_ASSETS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let min_amounts_out = (0..number_of_amounts)
+            .map(|i| u64::from_le_bytes(src[41 + 8 * i..49 + 8 * i].try_into().unwrap()))
+            .collect();
+        Ok(Self {
+            owner: Pubkey::new(&src[0..32]),
+            pool_token_amount: u64::from_le_bytes(src[32..40].try_into().unwrap()),
+            min_amounts_out,
+        })
+    }
+}
+
+/// Pooltokens of a depositor escrowed by the pool for the duration of its lockup period
+#[derive(Debug, PartialEq)]
+pub struct DepositReceipt {
+    pub owner: Pubkey,
+    pub pool_token_amount: u64,
+    /// Timestamp of the owner's latest deposit, from which the whole amount is locked up
+    pub deposit_timestamp: u64,
+}
+
+impl Sealed for DepositReceipt {}
+
+impl IsInitialized for DepositReceipt {
+    fn is_initialized(&self) -> bool {
+        self.owner != Pubkey::default()
+    }
+}
+
+impl Pack for DepositReceipt {
+    const LEN: usize = 48;
+
+    fn pack_into_slice(&self, target: &mut [u8]) {
+        target[0..32].copy_from_slice(&self.owner.to_bytes());
+        target[32..40].copy_from_slice(&self.pool_token_amount.to_le_bytes());
+        target[40..48].copy_from_slice(&self.deposit_timestamp.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(Self {
+            owner: Pubkey::new(&src[0..32]),
+            pool_token_amount: u64::from_le_bytes(src[32..40].try_into().unwrap()),
+            deposit_timestamp: u64::from_le_bytes(src[40..48].try_into().unwrap()),
+        })
+    }
+}
+
+pub const REDEEM_QUEUE_LEN: usize =
+    RedeemQueueHeader::LEN + REDEEM_QUEUE_CAPACITY * RedeemRequest::LEN;
+
+pub fn unpack_redeem_request(
+    input: &[u8],
+    request_index: u64,
+) -> Result<RedeemRequest, ProgramError> {
+    let offset = RedeemQueueHeader::LEN
+        + RedeemRequest::LEN * (request_index % REDEEM_QUEUE_CAPACITY as u64) as usize;
+    input
+        .get(offset..offset + RedeemRequest::LEN)
+        .ok_or(ProgramError::InvalidAccountData)
+        .and_then(|slice| RedeemRequest::unpack_from_slice(slice))
+}
+
+pub fn get_redeem_request_slice(
+    target: &mut [u8],
+    request_index: u64,
+) -> Result<&mut [u8], ProgramError> {
+    let offset = RedeemQueueHeader::LEN
+        + RedeemRequest::LEN * (request_index % REDEEM_QUEUE_CAPACITY as u64) as usize;
+    target
+        .get_mut(offset..offset + RedeemRequest::LEN)
+        .ok_or(ProgramError::InvalidAccountData)
+}
+
+pub fn get_open_orders_offset(number_of_markets: u16) -> usize {
+    PoolHeader::LEN + PoolMarket::LEN * number_of_markets as usize
+}
+
+pub fn get_asset_offset(number_of_markets: u16) -> usize {
+    get_open_orders_offset(number_of_markets) + PoolOpenOrders::LEN * number_of_markets as usize
+}
+
+// Lays the markets, OpenOrders registry and assets regions out right after the header, the
+// assets taking the rest of an account of the given length
+pub fn set_regions(pool_header: &mut PoolHeader, number_of_markets: u16, data_len: usize) {
+    pool_header.number_of_markets = number_of_markets;
+    pool_header.market_offset = PoolHeader::LEN as u32;
+    pool_header.open_orders_offset = get_open_orders_offset(number_of_markets) as u32;
+    pool_header.asset_offset = get_asset_offset(number_of_markets) as u32;
+    pool_header.max_number_of_assets =
+        (data_len.saturating_sub(get_asset_offset(number_of_markets)) / PoolAsset::LEN) as u32;
+}
+
+// End of the asset region, which has room for the pool's max_number_of_assets
+pub fn get_assets_end(pool_header: &PoolHeader) -> usize {
+    pool_header.asset_offset as usize + PoolAsset::LEN * pool_header.max_number_of_assets as usize
+}
+
+// Number of leading asset slots up to the last one in use
+fn count_used_asset_slots(assets: &[u8]) -> usize {
+    assets
+        .chunks_exact(PoolAsset::LEN)
+        .rposition(|slot| slot.iter().any(|b| *b != 0))
+        .map_or(0, |i| i + 1)
+}
+
 pub fn unpack_assets(input: &[u8]) -> Result<Vec<PoolAsset>, ProgramError> {
     let number_of_assets = input.len() / PoolAsset::LEN;
     let mut output: Vec<PoolAsset> = Vec::with_capacity(number_of_assets);
@@ -199,22 +873,204 @@ pub fn get_asset_slice(target: &mut [u8], index: usize) -> Result<&mut [u8], Pro
 }
 
 pub fn unpack_market(input: &[u8], market_index: u16) -> Pubkey {
-    let offset = 32 * (market_index as usize);
+    let offset = PoolMarket::LEN * (market_index as usize);
     return Pubkey::new(&input[offset..offset + 32]);
 }
 
-pub fn pack_markets(target: &mut [u8], markets: &Vec<Pubkey>) -> Result<(), ProgramError> {
+pub fn unpack_pool_market(input: &[u8], market_index: u16) -> Result<PoolMarket, ProgramError> {
+    let offset = PoolMarket::LEN * (market_index as usize);
+    input
+        .get(offset..offset + PoolMarket::LEN)
+        .ok_or(ProgramError::InvalidArgument)
+        .and_then(|slice| PoolMarket::unpack_from_slice(slice))
+}
+
+pub fn unpack_open_orders(input: &[u8], market_index: u16) -> Result<PoolOpenOrders, ProgramError> {
+    let offset = PoolOpenOrders::LEN * (market_index as usize);
+    input
+        .get(offset..offset + PoolOpenOrders::LEN)
+        .ok_or(ProgramError::InvalidArgument)
+        .and_then(|slice| PoolOpenOrders::unpack_unchecked(slice))
+}
+
+pub fn get_open_orders_slice(
+    target: &mut [u8],
+    market_index: u16,
+) -> Result<&mut [u8], ProgramError> {
+    let offset = PoolOpenOrders::LEN * (market_index as usize);
+    target
+        .get_mut(offset..offset + PoolOpenOrders::LEN)
+        .ok_or(ProgramError::InvalidArgument)
+}
+
+pub fn pack_markets(target: &mut [u8], markets: &Vec<PoolMarket>) -> Result<(), ProgramError> {
     for i in 0..markets.len() {
-        target[32 * i..32 * (i + 1)].copy_from_slice(&markets[i].to_bytes());
+        markets[i].pack_into_slice(
+            target
+                .get_mut(PoolMarket::LEN * i..PoolMarket::LEN * (i + 1))
+                .ok_or(ProgramError::InvalidArgument)?,
+        );
+    }
+    Ok(())
+}
+
+// Appends a market to the pool state. The OpenOrders registry and the assets are moved to make
+// room for the new market and its registry entry, which is taken from the unused asset slots at
+// the end of the asset region. The header's regions and number of markets are updated.
+pub fn push_market(
+    target: &mut [u8],
+    pool_header: &mut PoolHeader,
+    market: &PoolMarket,
+) -> Result<(), ProgramError> {
+    let open_orders_offset = pool_header.open_orders_offset as usize;
+    let asset_offset = pool_header.asset_offset as usize;
+    let region_end = get_assets_end(pool_header);
+    let shift = PoolMarket::LEN + PoolOpenOrders::LEN;
+    let used_asset_slots = count_used_asset_slots(
+        target
+            .get(asset_offset..region_end)
+            .ok_or(ProgramError::InvalidAccountData)?,
+    );
+    let assets_end = asset_offset + used_asset_slots * PoolAsset::LEN;
+    if assets_end + shift > region_end {
+        return Err(ProgramError::AccountDataTooSmall);
     }
+    target.copy_within(asset_offset..assets_end, asset_offset + shift);
+    target.copy_within(
+        open_orders_offset..asset_offset,
+        open_orders_offset + PoolMarket::LEN,
+    );
+    market.pack_into_slice(&mut target[open_orders_offset..open_orders_offset + PoolMarket::LEN]);
+    for b in target[asset_offset + PoolMarket::LEN..asset_offset + shift].iter_mut() {
+        *b = 0;
+    }
+    pool_header.number_of_markets = pool_header
+        .number_of_markets
+        .checked_add(1)
+        .ok_or(ProgramError::InvalidArgument)?;
+    pool_header.open_orders_offset += PoolMarket::LEN as u32;
+    pool_header.asset_offset += shift as u32;
+    pool_header.max_number_of_assets =
+        ((region_end - asset_offset - shift) / PoolAsset::LEN) as u32;
     Ok(())
 }
 
+// Reads the number of markets and of asset slots of an unversioned pool account. The account has
+// to match the unversioned layout exactly: either a created pool, its header followed by the
+// addresses of its markets and by its assets up to the end of the account, or an account left
+// blank by Init.
+pub fn unpack_pool_v0_layout(
+    input: &[u8],
+    pool_seed: &[u8; 32],
+) -> Result<(u16, usize), ProgramError> {
+    let regions_len = input
+        .len()
+        .checked_sub(POOL_HEADER_V0_LEN)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    if unpack_pool_status(input[96])? == PoolStatus::Uninitialized {
+        // Create lays the markets and assets out again, all of the room goes to the assets
+        if input.iter().any(|b| *b != 0) || regions_len % PoolAsset::LEN != 0 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        return Ok((0, regions_len / PoolAsset::LEN));
+    }
+    let number_of_markets = u16::from_le_bytes(input[97..99].try_into().unwrap());
+    let assets_len = regions_len
+        .checked_sub(PUBKEY_LENGTH * number_of_markets as usize)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    if input[32..64] != pool_seed[..] || assets_len % PoolAsset::LEN != 0 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok((number_of_markets, assets_len / PoolAsset::LEN))
+}
+
+// Length of the pool account holding the given number of markets and asset slots
+pub fn get_pool_len(number_of_markets: u16, max_number_of_assets: usize) -> usize {
+    get_asset_offset(number_of_markets) + PoolAsset::LEN * max_number_of_assets
+}
+
+// Writes an unversioned pool account into the current layout, field by field. The given markets
+// hold the parameters of the pool's markets, in order, and the target is the grown pool account,
+// with room for the current header, the markets, an empty OpenOrders registry and the same number
+// of asset slots. The header fields which didn't exist are given their default values.
+pub fn migrate_pool_v0(
+    source: &[u8],
+    pool_seed: &[u8; 32],
+    markets: &Vec<PoolMarket>,
+    creator: &Pubkey,
+    target: &mut [u8],
+) -> Result<PoolHeader, ProgramError> {
+    let (number_of_markets, max_number_of_assets) = unpack_pool_v0_layout(source, pool_seed)?;
+    if markets.len() != number_of_markets as usize
+        || target.len() != get_pool_len(number_of_markets, max_number_of_assets)
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+    for (market_index, market) in markets.iter().enumerate() {
+        let offset = POOL_HEADER_V0_LEN + PUBKEY_LENGTH * market_index;
+        if market.address != Pubkey::new(&source[offset..offset + PUBKEY_LENGTH]) {
+            return Err(ProgramError::InvalidArgument);
+        }
+    }
+
+    let pool_header = PoolHeader {
+        account_type: AccountType::Pool,
+        version: POOL_VERSION,
+        serum_program_id: Pubkey::new(&source[..32]),
+        seed: source[32..64].try_into().unwrap(),
+        signal_provider: Pubkey::new(&source[64..96]),
+        status: unpack_pool_status(source[96])?,
+        number_of_markets,
+        fee_ratio: u16::from_le_bytes(source[99..101].try_into().unwrap()),
+        last_fee_collection_timestamp: u64::from_le_bytes(source[101..109].try_into().unwrap()),
+        fee_collection_period: u64::from_le_bytes(source[109..117].try_into().unwrap()),
+        max_order_lifetime: MIGRATED_POOL_MAX_ORDER_LIFETIME,
+        first_pending_order_timestamp: 0,
+        timelock_period: MIGRATED_POOL_TIMELOCK_PERIOD,
+        pending_signal_provider: Pubkey::default(),
+        signal_provider_proposal_timestamp: 0,
+        performance_fee_ratio: 0,
+        high_water_mark: 0,
+        redeem_fee_ratio: 0,
+        lockup_period: 0,
+        max_pool_token_supply: 0,
+        min_deposit_pool_tokens: 0,
+        is_private: false,
+        pending_market_index: 0,
+        pending_market: Pubkey::default(),
+        market_proposal_timestamp: 0,
+        creator: *creator,
+        max_number_of_assets: max_number_of_assets as u32,
+        market_offset: PoolHeader::LEN as u32,
+        open_orders_offset: get_open_orders_offset(number_of_markets) as u32,
+        asset_offset: get_asset_offset(number_of_markets) as u32,
+    };
+    for b in target.iter_mut() {
+        *b = 0;
+    }
+    pool_header.pack_into_slice(&mut target[..PoolHeader::LEN]);
+    pack_markets(&mut target[PoolHeader::LEN..], markets)?;
+    let v0_asset_offset = POOL_HEADER_V0_LEN + PUBKEY_LENGTH * number_of_markets as usize;
+    target[pool_header.asset_offset as usize..].copy_from_slice(&source[v0_asset_offset..]);
+    Ok(pool_header)
+}
+
 #[cfg(test)]
 mod tests {
     use std::num::NonZeroU8;
 
-    use super::{pack_markets, unpack_assets, unpack_market, PoolAsset, PoolHeader, PoolStatus};
+    use super::{
+        get_asset_offset, get_assets_end, get_open_orders_offset, get_open_orders_slice,
+        get_pool_len, get_redeem_request_slice, migrate_pool_v0, pack_markets, pack_pool_status,
+        push_market, set_regions, unpack_assets, unpack_market, unpack_open_orders,
+        unpack_pool_market, unpack_pool_v0_layout, unpack_redeem_request, AccountType,
+        DepositReceipt, FeeRecipient, PoolAsset, PoolHeader, PoolMarket, PoolMetadata,
+        PoolOpenOrders, PoolRegistry, PoolStatus, ProgramConfig, RedeemQueueHeader, RedeemRequest,
+        Whitelist, MAX_METADATA_NAME_LEN, MAX_METADATA_URI_LEN, MAX_REDEEM_REQUEST_ASSETS,
+        MAX_REGISTRY_POOLS, MAX_WHITELIST_OWNERS, MIGRATED_POOL_MAX_ORDER_LIFETIME,
+        MIGRATED_POOL_TIMELOCK_PERIOD, POOL_HEADER_V0_LEN, POOL_VERSION, PUBKEY_LENGTH,
+        REDEEM_QUEUE_CAPACITY, REDEEM_QUEUE_LEN,
+    };
     use solana_program::{
         program_pack::{IsInitialized, Pack},
         pubkey::Pubkey,
@@ -223,6 +1079,8 @@ mod tests {
     #[test]
     fn test_state_packing() {
         let header_state = PoolHeader {
+            account_type: AccountType::Pool,
+            version: POOL_VERSION,
             serum_program_id: Pubkey::new_unique(),
             seed: [0u8; 32],
             signal_provider: Pubkey::new_unique(),
@@ -231,6 +1089,26 @@ mod tests {
             fee_ratio: 15,
             last_fee_collection_timestamp: 1_000_000_000,
             fee_collection_period: 10_000,
+            max_order_lifetime: 86_400,
+            first_pending_order_timestamp: 1_000_000_100,
+            timelock_period: 604_800,
+            pending_signal_provider: Pubkey::new_unique(),
+            signal_provider_proposal_timestamp: 1_000_000_200,
+            performance_fee_ratio: 6553,
+            high_water_mark: 5 << 32,
+            redeem_fee_ratio: 300,
+            lockup_period: 86400,
+            max_pool_token_supply: 50_000_000,
+            min_deposit_pool_tokens: 1_000,
+            is_private: true,
+            pending_market_index: 3,
+            pending_market: Pubkey::new_unique(),
+            market_proposal_timestamp: 1_000_000_300,
+            creator: Pubkey::new_unique(),
+            max_number_of_assets: 100,
+            market_offset: PoolHeader::LEN as u32,
+            open_orders_offset: get_open_orders_offset(234) as u32,
+            asset_offset: get_asset_offset(234) as u32,
         };
 
         let header_size = PoolHeader::LEN;
@@ -257,6 +1135,8 @@ mod tests {
     #[test]
     fn test_header_packing() {
         let mut header_state = PoolHeader {
+            account_type: AccountType::Pool,
+            version: POOL_VERSION,
             serum_program_id: Pubkey::new_unique(),
             seed: [0u8; 32],
             signal_provider: Pubkey::new_unique(),
@@ -265,6 +1145,26 @@ mod tests {
             fee_ratio: 15,
             last_fee_collection_timestamp: 1_000_000_000,
             fee_collection_period: 10_000,
+            max_order_lifetime: 86_400,
+            first_pending_order_timestamp: 1_000_000_100,
+            timelock_period: 604_800,
+            pending_signal_provider: Pubkey::new_unique(),
+            signal_provider_proposal_timestamp: 1_000_000_200,
+            performance_fee_ratio: 6553,
+            high_water_mark: 5 << 32,
+            redeem_fee_ratio: 300,
+            lockup_period: 86400,
+            max_pool_token_supply: 50_000_000,
+            min_deposit_pool_tokens: 1_000,
+            is_private: true,
+            pending_market_index: 3,
+            pending_market: Pubkey::new_unique(),
+            market_proposal_timestamp: 1_000_000_300,
+            creator: Pubkey::new_unique(),
+            max_number_of_assets: 100,
+            market_offset: PoolHeader::LEN as u32,
+            open_orders_offset: get_open_orders_offset(234) as u32,
+            asset_offset: get_asset_offset(234) as u32,
         };
         assert_eq!(
             header_state,
@@ -272,6 +1172,8 @@ mod tests {
         );
 
         header_state = PoolHeader {
+            account_type: AccountType::Pool,
+            version: POOL_VERSION,
             serum_program_id: Pubkey::new_unique(),
             seed: [0u8; 32],
             signal_provider: Pubkey::new_unique(),
@@ -280,6 +1182,26 @@ mod tests {
             fee_ratio: 15,
             last_fee_collection_timestamp: 1_000_000_000,
             fee_collection_period: 10_000,
+            max_order_lifetime: 86_400,
+            first_pending_order_timestamp: 1_000_000_100,
+            timelock_period: 604_800,
+            pending_signal_provider: Pubkey::new_unique(),
+            signal_provider_proposal_timestamp: 1_000_000_200,
+            performance_fee_ratio: 6553,
+            high_water_mark: 5 << 32,
+            redeem_fee_ratio: 300,
+            lockup_period: 86400,
+            max_pool_token_supply: 50_000_000,
+            min_deposit_pool_tokens: 1_000,
+            is_private: true,
+            pending_market_index: 3,
+            pending_market: Pubkey::new_unique(),
+            market_proposal_timestamp: 1_000_000_300,
+            creator: Pubkey::new_unique(),
+            max_number_of_assets: 100,
+            market_offset: PoolHeader::LEN as u32,
+            open_orders_offset: get_open_orders_offset(234) as u32,
+            asset_offset: get_asset_offset(234) as u32,
         };
         assert_eq!(
             header_state,
@@ -287,6 +1209,8 @@ mod tests {
         );
 
         header_state = PoolHeader {
+            account_type: AccountType::Pool,
+            version: POOL_VERSION,
             serum_program_id: Pubkey::new_unique(),
             seed: [0u8; 32],
             signal_provider: Pubkey::new_unique(),
@@ -295,6 +1219,26 @@ mod tests {
             fee_ratio: 15,
             last_fee_collection_timestamp: 1_000_000_000,
             fee_collection_period: 10_000,
+            max_order_lifetime: 86_400,
+            first_pending_order_timestamp: 1_000_000_100,
+            timelock_period: 604_800,
+            pending_signal_provider: Pubkey::new_unique(),
+            signal_provider_proposal_timestamp: 1_000_000_200,
+            performance_fee_ratio: 6553,
+            high_water_mark: 5 << 32,
+            redeem_fee_ratio: 300,
+            lockup_period: 86400,
+            max_pool_token_supply: 50_000_000,
+            min_deposit_pool_tokens: 1_000,
+            is_private: true,
+            pending_market_index: 3,
+            pending_market: Pubkey::new_unique(),
+            market_proposal_timestamp: 1_000_000_300,
+            creator: Pubkey::new_unique(),
+            max_number_of_assets: 100,
+            market_offset: PoolHeader::LEN as u32,
+            open_orders_offset: get_open_orders_offset(234) as u32,
+            asset_offset: get_asset_offset(234) as u32,
         };
         assert_eq!(
             header_state,
@@ -302,6 +1246,8 @@ mod tests {
         );
 
         header_state = PoolHeader {
+            account_type: AccountType::Pool,
+            version: POOL_VERSION,
             serum_program_id: Pubkey::new_unique(),
             seed: [0u8; 32],
             signal_provider: Pubkey::new_unique(),
@@ -310,6 +1256,26 @@ mod tests {
             fee_ratio: 15,
             last_fee_collection_timestamp: 1_000_000_000,
             fee_collection_period: 10_000,
+            max_order_lifetime: 86_400,
+            first_pending_order_timestamp: 1_000_000_100,
+            timelock_period: 604_800,
+            pending_signal_provider: Pubkey::new_unique(),
+            signal_provider_proposal_timestamp: 1_000_000_200,
+            performance_fee_ratio: 6553,
+            high_water_mark: 5 << 32,
+            redeem_fee_ratio: 300,
+            lockup_period: 86400,
+            max_pool_token_supply: 50_000_000,
+            min_deposit_pool_tokens: 1_000,
+            is_private: true,
+            pending_market_index: 3,
+            pending_market: Pubkey::new_unique(),
+            market_proposal_timestamp: 1_000_000_300,
+            creator: Pubkey::new_unique(),
+            max_number_of_assets: 100,
+            market_offset: PoolHeader::LEN as u32,
+            open_orders_offset: get_open_orders_offset(234) as u32,
+            asset_offset: get_asset_offset(234) as u32,
         };
         assert_eq!(
             header_state,
@@ -317,6 +1283,8 @@ mod tests {
         );
 
         header_state = PoolHeader {
+            account_type: AccountType::Pool,
+            version: POOL_VERSION,
             serum_program_id: Pubkey::new_unique(),
             seed: [0u8; 32],
             signal_provider: Pubkey::new_unique(),
@@ -325,6 +1293,26 @@ mod tests {
             fee_ratio: 15,
             last_fee_collection_timestamp: 1_000_000_000,
             fee_collection_period: 10_000,
+            max_order_lifetime: 86_400,
+            first_pending_order_timestamp: 1_000_000_100,
+            timelock_period: 604_800,
+            pending_signal_provider: Pubkey::new_unique(),
+            signal_provider_proposal_timestamp: 1_000_000_200,
+            performance_fee_ratio: 6553,
+            high_water_mark: 5 << 32,
+            redeem_fee_ratio: 300,
+            lockup_period: 86400,
+            max_pool_token_supply: 50_000_000,
+            min_deposit_pool_tokens: 1_000,
+            is_private: true,
+            pending_market_index: 3,
+            pending_market: Pubkey::new_unique(),
+            market_proposal_timestamp: 1_000_000_300,
+            creator: Pubkey::new_unique(),
+            max_number_of_assets: 100,
+            market_offset: PoolHeader::LEN as u32,
+            open_orders_offset: get_open_orders_offset(234) as u32,
+            asset_offset: get_asset_offset(234) as u32,
         };
         assert!(PoolHeader::unpack(&get_packed(&header_state)).is_err());
     }
@@ -335,6 +1323,26 @@ mod tests {
         output_vec
     }
 
+    #[test]
+    fn test_config_packing() {
+        let config = ProgramConfig {
+            is_initialized: true,
+            signal_provider_share: 2,
+            fee_recipients: vec![
+                FeeRecipient {
+                    owner: Pubkey::new_unique(),
+                    share: 1,
+                },
+                FeeRecipient {
+                    owner: Pubkey::new_unique(),
+                    share: 1,
+                },
+            ],
+        };
+        assert_eq!(config, ProgramConfig::unpack(&get_packed(&config)).unwrap());
+        assert!(ProgramConfig::unpack(&[0u8; ProgramConfig::LEN]).is_err());
+    }
+
     #[test]
     fn test_state_init() {
         let pool_asset = PoolAsset::unpack_unchecked(&[0u8; PoolAsset::LEN]).unwrap();
@@ -342,17 +1350,317 @@ mod tests {
     }
 
     #[test]
-    fn test_market_packing() {
-        let markets = vec![
-            Pubkey::new_unique(),
-            Pubkey::new_unique(),
-            Pubkey::new_unique(),
-            Pubkey::new_unique(),
+    fn test_open_orders_packing() {
+        let open_orders = vec![
+            PoolOpenOrders {
+                address: Pubkey::new_unique(),
+                is_pending: true,
+            },
+            PoolOpenOrders {
+                address: Pubkey::new_unique(),
+                is_pending: false,
+            },
         ];
-        let mut output_array = [0u8; 4 * 32];
+        let mut output_array = [0u8; 3 * PoolOpenOrders::LEN];
+        for (i, slot) in open_orders.iter().enumerate() {
+            slot.pack_into_slice(get_open_orders_slice(&mut output_array, i as u16).unwrap());
+        }
+        for i in 0..2 {
+            assert_eq!(
+                open_orders[i],
+                unpack_open_orders(&output_array, i as u16).unwrap()
+            );
+        }
+        assert!(!unpack_open_orders(&output_array, 2)
+            .unwrap()
+            .is_initialized());
+        assert!(unpack_open_orders(&output_array, 3).is_err());
+    }
+
+    #[test]
+    fn test_redeem_queue_packing() {
+        let mut queue = vec![0u8; REDEEM_QUEUE_LEN];
+        let header = RedeemQueueHeader { head: 3, tail: 5 };
+        header.pack_into_slice(&mut queue[..RedeemQueueHeader::LEN]);
+        assert_eq!(
+            header,
+            RedeemQueueHeader::unpack_from_slice(&queue[..RedeemQueueHeader::LEN]).unwrap()
+        );
+
+        // The requests wrap around the end of the ring buffer
+        let request = RedeemRequest {
+            owner: Pubkey::new_unique(),
+            pool_token_amount: 1_000,
+            min_amounts_out: vec![10, 0, 20],
+        };
+        let index = REDEEM_QUEUE_CAPACITY as u64 + 2;
+        request.pack_into_slice(get_redeem_request_slice(&mut queue, index).unwrap());
+        assert_eq!(request, unpack_redeem_request(&queue, index).unwrap());
+        assert_eq!(request, unpack_redeem_request(&queue, 2).unwrap());
+
+        // A request can't hold more minimum amounts than MAX_REDEEM_REQUEST_ASSETS
+        let mut corrupted_request = [0u8; RedeemRequest::LEN];
+        corrupted_request[40] = MAX_REDEEM_REQUEST_ASSETS as u8 + 1;
+        assert!(RedeemRequest::unpack_from_slice(&corrupted_request).is_err());
+
+        let overflowing_header = RedeemQueueHeader {
+            head: 0,
+            tail: REDEEM_QUEUE_CAPACITY as u64 + 1,
+        };
+        overflowing_header.pack_into_slice(&mut queue[..RedeemQueueHeader::LEN]);
+        assert!(RedeemQueueHeader::unpack_from_slice(&queue[..RedeemQueueHeader::LEN]).is_err());
+    }
+
+    #[test]
+    fn test_deposit_receipt_packing() {
+        let receipt = DepositReceipt {
+            owner: Pubkey::new_unique(),
+            pool_token_amount: 2_000,
+            deposit_timestamp: 1_614_000_000,
+        };
+        let mut output = [0u8; DepositReceipt::LEN];
+        receipt.pack_into_slice(&mut output);
+        assert_eq!(receipt, DepositReceipt::unpack(&output).unwrap());
+        assert!(
+            !DepositReceipt::unpack_unchecked(&[0u8; DepositReceipt::LEN])
+                .unwrap()
+                .is_initialized()
+        );
+    }
+
+    #[test]
+    fn test_whitelist_packing() {
+        let whitelist = Whitelist {
+            is_initialized: true,
+            gating_mint: Pubkey::new_unique(),
+            owners: (0..MAX_WHITELIST_OWNERS)
+                .map(|_| Pubkey::new_unique())
+                .collect(),
+        };
+        let mut output = [0u8; Whitelist::LEN];
+        whitelist.pack_into_slice(&mut output);
+        assert_eq!(whitelist, Whitelist::unpack(&output).unwrap());
+
+        output[33] = MAX_WHITELIST_OWNERS as u8 + 1;
+        assert!(Whitelist::unpack_unchecked(&output).is_err());
+    }
+
+    #[test]
+    fn test_registry_packing() {
+        let mut registry = PoolRegistry {
+            is_initialized: true,
+            pool_seeds: (0..MAX_REGISTRY_POOLS).map(|i| [i as u8; 32]).collect(),
+        };
+        let mut output = [0u8; PoolRegistry::LEN];
+        registry.pack_into_slice(&mut output);
+        assert_eq!(registry, PoolRegistry::unpack(&output).unwrap());
+
+        // Removed seeds are cleared from the account
+        registry.pool_seeds.truncate(1);
+        registry.pack_into_slice(&mut output);
+        assert_eq!(registry, PoolRegistry::unpack(&output).unwrap());
+        assert!(output[34..].iter().all(|b| *b == 0));
+
+        output[1] = MAX_REGISTRY_POOLS as u8 + 1;
+        assert!(PoolRegistry::unpack_unchecked(&output).is_err());
+    }
+
+    #[test]
+    fn test_metadata_packing() {
+        let metadata = PoolMetadata {
+            is_initialized: true,
+            creation_timestamp: 1_000_000_000,
+            name: "a".repeat(MAX_METADATA_NAME_LEN),
+            description: String::from("Momentum strategy on the SRM/USDC market ⚡"),
+            uri: "u".repeat(MAX_METADATA_URI_LEN),
+        };
+        let mut output = [0u8; PoolMetadata::LEN];
+        metadata.pack_into_slice(&mut output);
+        assert_eq!(metadata, PoolMetadata::unpack(&output).unwrap());
+
+        // Shorter strings don't leave the end of the previous ones behind
+        let updated_metadata = PoolMetadata {
+            name: String::from("b"),
+            ..metadata
+        };
+        updated_metadata.pack_into_slice(&mut output);
+        assert_eq!(updated_metadata, PoolMetadata::unpack(&output).unwrap());
+        assert!(output[11..11 + MAX_METADATA_NAME_LEN - 1]
+            .iter()
+            .all(|b| *b == 0));
+
+        output[9] = MAX_METADATA_NAME_LEN as u8 + 1;
+        assert!(PoolMetadata::unpack_unchecked(&output).is_err());
+        // The strings have to be valid UTF-8
+        output[9] = 1;
+        output[10] = 0xff;
+        assert!(PoolMetadata::unpack_unchecked(&output).is_err());
+    }
+
+    fn new_pool_market() -> PoolMarket {
+        PoolMarket {
+            address: Pubkey::new_unique(),
+            coin_mint: Pubkey::new_unique(),
+            pc_mint: Pubkey::new_unique(),
+            coin_lot_size: 1000,
+            pc_lot_size: 1,
+        }
+    }
+
+    #[test]
+    fn test_market_packing() {
+        let markets: Vec<PoolMarket> = (0..4).map(|_| new_pool_market()).collect();
+        let mut output_array = [0u8; 4 * PoolMarket::LEN];
         pack_markets(&mut output_array, &markets).unwrap();
         for i in 0..4 {
-            assert_eq!(markets[i], unpack_market(&output_array, i as u16));
+            assert_eq!(markets[i].address, unpack_market(&output_array, i as u16));
+            assert_eq!(
+                markets[i],
+                unpack_pool_market(&output_array, i as u16).unwrap()
+            );
         }
+        assert!(unpack_pool_market(&output_array, 4).is_err());
+    }
+
+    #[test]
+    fn test_push_market() {
+        let markets = vec![new_pool_market()];
+        let open_orders = PoolOpenOrders {
+            address: Pubkey::new_unique(),
+            is_pending: true,
+        };
+        let assets = vec![
+            PoolAsset {
+                mint_address: Pubkey::new_unique(),
+            },
+            PoolAsset {
+                mint_address: Pubkey::new_unique(),
+            },
+        ];
+        let mut data = vec![0u8; get_asset_offset(1) + 7 * PoolAsset::LEN];
+        let mut header = PoolHeader::unpack_from_slice(&[0u8; PoolHeader::LEN]).unwrap();
+        set_regions(&mut header, 1, data.len());
+        assert_eq!(header.max_number_of_assets, 7);
+        pack_markets(&mut data[PoolHeader::LEN..], &markets).unwrap();
+        open_orders.pack_into_slice(&mut data[get_open_orders_offset(1)..]);
+        assets[0].pack_into_slice(&mut data[get_asset_offset(1)..]);
+        assets[1].pack_into_slice(&mut data[get_asset_offset(1) + PoolAsset::LEN..]);
+
+        let new_market = new_pool_market();
+        push_market(&mut data, &mut header, &new_market).unwrap();
+        assert_eq!(header.number_of_markets, 2);
+        assert_eq!(
+            header.open_orders_offset as usize,
+            get_open_orders_offset(2)
+        );
+        assert_eq!(header.asset_offset as usize, get_asset_offset(2));
+        assert_eq!(header.max_number_of_assets, 2);
+        assert_eq!(
+            markets[0],
+            unpack_pool_market(&data[PoolHeader::LEN..], 0).unwrap()
+        );
+        assert_eq!(
+            new_market,
+            unpack_pool_market(&data[PoolHeader::LEN..], 1).unwrap()
+        );
+        let open_orders_data = &data[get_open_orders_offset(2)..];
+        assert_eq!(
+            open_orders,
+            unpack_open_orders(open_orders_data, 0).unwrap()
+        );
+        assert!(!unpack_open_orders(open_orders_data, 1)
+            .unwrap()
+            .is_initialized());
+        let assets_end = get_assets_end(&header);
+        assert!(assets_end <= data.len());
+        assert_eq!(
+            assets,
+            unpack_assets(&data[get_asset_offset(2)..assets_end]).unwrap()
+        );
+
+        // The remaining free asset slots can't hold another market
+        assert!(push_market(&mut data, &mut header, &new_pool_market()).is_err());
+    }
+
+    #[test]
+    fn test_migrate_pool_v0() {
+        let markets = vec![new_pool_market()];
+        let asset = PoolAsset {
+            mint_address: Pubkey::new_unique(),
+        };
+        let seed = [7u8; 32];
+        let serum_program_id = Pubkey::new_unique();
+        let signal_provider = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+
+        // A created pool in the unversioned layout, with a market and three asset slots
+        let mut source = vec![0u8; POOL_HEADER_V0_LEN + PUBKEY_LENGTH + 3 * PoolAsset::LEN];
+        source[..32].copy_from_slice(&serum_program_id.to_bytes());
+        source[32..64].copy_from_slice(&seed);
+        source[64..96].copy_from_slice(&signal_provider.to_bytes());
+        source[96] = pack_pool_status(&PoolStatus::Locked);
+        source[97..99].copy_from_slice(&1u16.to_le_bytes());
+        source[99..101].copy_from_slice(&15u16.to_le_bytes());
+        source[101..109].copy_from_slice(&1_000_000_000u64.to_le_bytes());
+        source[109..117].copy_from_slice(&604_800u64.to_le_bytes());
+        source[117..149].copy_from_slice(&markets[0].address.to_bytes());
+        asset.pack_into_slice(&mut source[149..181]);
+        assert_eq!(unpack_pool_v0_layout(&source, &seed).unwrap(), (1, 3));
+
+        let mut target = vec![0u8; get_pool_len(1, 3)];
+        let header = migrate_pool_v0(&source, &seed, &markets, &creator, &mut target).unwrap();
+        assert_eq!(
+            header,
+            PoolHeader::unpack(&target[..PoolHeader::LEN]).unwrap()
+        );
+        assert_eq!(header.account_type, AccountType::Pool);
+        assert_eq!(header.version, POOL_VERSION);
+        assert_eq!(header.serum_program_id, serum_program_id);
+        assert_eq!(header.seed, seed);
+        assert_eq!(header.signal_provider, signal_provider);
+        assert_eq!(header.status, PoolStatus::Locked);
+        assert_eq!(header.number_of_markets, 1);
+        assert_eq!(header.fee_ratio, 15);
+        assert_eq!(header.last_fee_collection_timestamp, 1_000_000_000);
+        assert_eq!(header.fee_collection_period, 604_800);
+        assert_eq!(header.timelock_period, MIGRATED_POOL_TIMELOCK_PERIOD);
+        assert_eq!(header.max_order_lifetime, MIGRATED_POOL_MAX_ORDER_LIFETIME);
+        assert_eq!(header.creator, creator);
+        assert_eq!(header.max_number_of_assets, 3);
+        assert_eq!(get_assets_end(&header), target.len());
+        let market_data = &target[header.market_offset as usize..];
+        assert_eq!(markets[0], unpack_pool_market(market_data, 0).unwrap());
+        let open_orders_data = &target[header.open_orders_offset as usize..];
+        assert!(!unpack_open_orders(open_orders_data, 0)
+            .unwrap()
+            .is_initialized());
+        let assets_data = &target[header.asset_offset as usize..get_assets_end(&header)];
+        assert_eq!(vec![asset], unpack_assets(assets_data).unwrap());
+
+        // Only the exact unversioned layout is migrated
+        assert!(unpack_pool_v0_layout(&source[..source.len() - 1], &seed).is_err());
+        assert!(unpack_pool_v0_layout(&source[..POOL_HEADER_V0_LEN], &seed).is_err());
+        assert!(unpack_pool_v0_layout(&source, &[8u8; 32]).is_err());
+        let mut extra_markets = source.clone();
+        extra_markets[97..99].copy_from_slice(&5u16.to_le_bytes());
+        assert!(unpack_pool_v0_layout(&extra_markets, &seed).is_err());
+        let mut wrong_target = vec![0u8; get_pool_len(1, 3) + 1];
+        assert!(migrate_pool_v0(&source, &seed, &markets, &creator, &mut wrong_target).is_err());
+        // The markets' parameters have to be given for the pool's markets
+        let other_markets = vec![new_pool_market()];
+        assert!(migrate_pool_v0(&source, &seed, &other_markets, &creator, &mut target).is_err());
+        assert!(migrate_pool_v0(&source, &seed, &vec![], &creator, &mut target).is_err());
+
+        // An account left blank by Init only holds asset slots
+        let blank = vec![0u8; POOL_HEADER_V0_LEN + 4 * PoolAsset::LEN];
+        assert_eq!(unpack_pool_v0_layout(&blank, &seed).unwrap(), (0, 4));
+        let mut blank_target = vec![0u8; get_pool_len(0, 4)];
+        let header = migrate_pool_v0(&blank, &seed, &vec![], &creator, &mut blank_target).unwrap();
+        assert_eq!(header.account_type, AccountType::Pool);
+        assert_eq!(header.status, PoolStatus::Uninitialized);
+        assert_eq!(header.max_number_of_assets, 4);
+        let mut written = blank.clone();
+        written[200] = 1;
+        assert!(unpack_pool_v0_layout(&written, &seed).is_err());
     }
 }
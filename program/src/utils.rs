@@ -1,9 +1,26 @@
+use std::convert::TryInto;
+
+use serum_dex::matching::Side;
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
+    account_info::AccountInfo,
+    bpf_loader_upgradeable,
+    entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE},
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
     pubkey::Pubkey,
+    rent::Rent,
+    system_instruction::{allocate, assign, create_account, transfer},
 };
 
-use crate::state::PoolHeader;
+use crate::{
+    error::BonfidaBotError,
+    state::{
+        PoolHeader, PoolStatus, ProgramConfig, CONFIG_SEED, DEPOSIT_RECEIPT_SEED,
+        MAX_VALUATION_SPREAD_RATIO, METADATA_SEED, OPEN_ORDERS_SEED, REDEEM_QUEUE_SEED,
+        REGISTRY_SEED, WHITELIST_SEED,
+    },
+};
 
 pub fn check_pool_key(program_id: &Pubkey, key: &Pubkey, pool_seed: &[u8; 32]) -> ProgramResult {
     let expected_key = Pubkey::create_program_address(&[pool_seed], program_id)?;
@@ -32,6 +49,430 @@ pub fn check_signal_provider(
     Ok(())
 }
 
+pub fn get_config_key(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONFIG_SEED], program_id)
+}
+
+pub fn check_config_key(program_id: &Pubkey, key: &Pubkey) -> ProgramResult {
+    if &get_config_key(program_id).0 != key {
+        msg!("Provided config account is invalid");
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+// Each of the pool's markets is traded from a single OpenOrders account owned by the pool
+pub fn get_open_orders_key(
+    program_id: &Pubkey,
+    pool_seed: &[u8; 32],
+    market_index: u16,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[pool_seed, OPEN_ORDERS_SEED, &market_index.to_le_bytes()],
+        program_id,
+    )
+}
+
+pub fn get_redeem_queue_key(program_id: &Pubkey, pool_seed: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[pool_seed, REDEEM_QUEUE_SEED], program_id)
+}
+
+pub fn get_deposit_receipt_key(
+    program_id: &Pubkey,
+    pool_seed: &[u8; 32],
+    owner: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[pool_seed, DEPOSIT_RECEIPT_SEED, &owner.to_bytes()],
+        program_id,
+    )
+}
+
+pub fn get_whitelist_key(program_id: &Pubkey, pool_seed: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[pool_seed, WHITELIST_SEED], program_id)
+}
+
+pub fn get_metadata_key(program_id: &Pubkey, pool_seed: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[pool_seed, METADATA_SEED], program_id)
+}
+
+pub fn get_registry_key(program_id: &Pubkey, signal_provider: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[&signal_provider.to_bytes(), REGISTRY_SEED], program_id)
+}
+
+// Creates a program derived account of the given size. The account may already hold lamports,
+// in which case create_account would fail: it is then topped up to rent exemption, allocated
+// and assigned instead.
+pub fn create_pda_account<'a>(
+    system_program_account: &AccountInfo<'a>,
+    payer_account: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    rent: &Rent,
+    space: usize,
+    owner: &Pubkey,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    let required_lamports = rent.minimum_balance(space);
+    if account.lamports() == 0 {
+        let instruction = create_account(
+            payer_account.key,
+            account.key,
+            required_lamports,
+            space as u64,
+            owner,
+        );
+        return invoke_signed(
+            &instruction,
+            &[
+                system_program_account.clone(),
+                payer_account.clone(),
+                account.clone(),
+            ],
+            &[signer_seeds],
+        );
+    }
+
+    let missing_lamports = required_lamports.saturating_sub(account.lamports());
+    if missing_lamports > 0 {
+        invoke(
+            &transfer(payer_account.key, account.key, missing_lamports),
+            &[
+                system_program_account.clone(),
+                payer_account.clone(),
+                account.clone(),
+            ],
+        )?;
+    }
+    invoke_signed(
+        &allocate(account.key, space as u64),
+        &[system_program_account.clone(), account.clone()],
+        &[signer_seeds],
+    )?;
+    invoke_signed(
+        &assign(account.key, owner),
+        &[system_program_account.clone(), account.clone()],
+        &[signer_seeds],
+    )
+}
+
+// Grows an account owned by the program to the given size, the payer topping it up to rent
+// exemption. The new bytes are zeroed.
+pub fn grow_program_account<'a>(
+    system_program_account: &AccountInfo<'a>,
+    payer_account: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    rent: &Rent,
+    space: usize,
+) -> ProgramResult {
+    if space > account.data_len() + MAX_PERMITTED_DATA_INCREASE {
+        msg!(
+            "An account can grow by at most {} bytes at a time.",
+            MAX_PERMITTED_DATA_INCREASE
+        );
+        return Err(ProgramError::InvalidArgument);
+    }
+    let missing_lamports = rent
+        .minimum_balance(space)
+        .saturating_sub(account.lamports());
+    if missing_lamports > 0 {
+        invoke(
+            &transfer(payer_account.key, account.key, missing_lamports),
+            &[
+                system_program_account.clone(),
+                payer_account.clone(),
+                account.clone(),
+            ],
+        )?;
+    }
+    account.realloc(space, true)
+}
+
+// The upgrade authority is read from the program's ProgramData account, which is a
+// bincode serialized UpgradeableLoaderState::ProgramData
+pub fn check_upgrade_authority(
+    program_id: &Pubkey,
+    program_data_account: &AccountInfo,
+    upgrade_authority_account: &AccountInfo,
+) -> ProgramResult {
+    let (program_data_key, _) =
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    if &program_data_key != program_data_account.key
+        || program_data_account.owner != &bpf_loader_upgradeable::id()
+    {
+        msg!("Provided program data account is invalid");
+        return Err(ProgramError::InvalidArgument);
+    }
+    let data = program_data_account.data.borrow();
+    if data.get(0..4) != Some(&3u32.to_le_bytes()[..]) || data.get(12) != Some(&1) {
+        msg!("The program has no upgrade authority");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let upgrade_authority = data
+        .get(13..45)
+        .map(Pubkey::new)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    if &upgrade_authority != upgrade_authority_account.key {
+        msg!("A wrong upgrade authority account was provided.");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !upgrade_authority_account.is_signer {
+        msg!("The upgrade authority's signature is required.");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+// Returns the signal provider's part of the fee followed by each fee recipient's part.
+// Rounding leftovers go to the last fee recipient.
+pub fn split_fees(config: &ProgramConfig, fee: u64) -> (u64, Vec<u64>) {
+    let total_shares = config
+        .fee_recipients
+        .iter()
+        .fold(config.signal_provider_share as u128, |acc, r| {
+            acc + r.share as u128
+        });
+    if total_shares == 0 {
+        return (fee, vec![0; config.fee_recipients.len()]);
+    }
+    let signal_provider_fee =
+        ((fee as u128) * (config.signal_provider_share as u128) / total_shares) as u64;
+    let mut remaining_fee = fee - signal_provider_fee;
+    let mut recipient_fees = Vec::with_capacity(config.fee_recipients.len());
+    for (i, recipient) in config.fee_recipients.iter().enumerate() {
+        let recipient_fee = if i == config.fee_recipients.len() - 1 {
+            remaining_fee
+        } else {
+            ((fee as u128) * (recipient.share as u128) / total_shares) as u64
+        };
+        remaining_fee -= recipient_fee;
+        recipient_fees.push(recipient_fee);
+    }
+    (signal_provider_fee + remaining_fee, recipient_fees)
+}
+
+// Resting orders are only allowed to block the pool for max_order_lifetime seconds
+pub fn pending_orders_expired(pool_header: &PoolHeader, current_timestamp: u64) -> bool {
+    match pool_header.status {
+        PoolStatus::PendingOrder(_) | PoolStatus::LockedPendingOrder(_) => {
+            current_timestamp.saturating_sub(pool_header.first_pending_order_timestamp)
+                > pool_header.max_order_lifetime
+        }
+        _ => false,
+    }
+}
+
+// A capped pool can't be capped below its current supply, nor require deposits above its cap
+pub fn check_deposit_limits(
+    max_pool_token_supply: u64,
+    min_deposit_pool_tokens: u64,
+    pool_token_supply: u64,
+) -> ProgramResult {
+    if max_pool_token_supply == 0 {
+        return Ok(());
+    }
+    if max_pool_token_supply < pool_token_supply {
+        msg!("The maximum pooltoken supply cannot be below the current supply.");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if min_deposit_pool_tokens > max_pool_token_supply {
+        msg!("The minimum deposit cannot exceed the maximum pooltoken supply.");
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+// The fields of a Serum market account which are needed to price its coin and trade on it
+pub struct MarketState {
+    pub is_disabled: bool,
+    pub coin_mint: Pubkey,
+    pub pc_mint: Pubkey,
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub coin_lot_size: u64,
+    pub pc_lot_size: u64,
+}
+
+impl MarketState {
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < 365 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        // Serum accounts start with a 5 byte padding followed by the account flags
+        let account_flags = u64::from_le_bytes(data[5..13].try_into().unwrap());
+        if account_flags & 3 != 3 {
+            msg!("The given account is not an initialized Serum market.");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self {
+            is_disabled: account_flags & (1 << 7) != 0,
+            coin_mint: Pubkey::new(&data[53..85]),
+            pc_mint: Pubkey::new(&data[85..117]),
+            bids: Pubkey::new(&data[285..317]),
+            asks: Pubkey::new(&data[317..349]),
+            coin_lot_size: u64::from_le_bytes(data[349..357].try_into().unwrap()),
+            pc_lot_size: u64::from_le_bytes(data[357..365].try_into().unwrap()),
+        })
+    }
+
+    // Mid price in native price currency per native coin, as a 32.32 fixed point number. The
+    // spread has to be at most MAX_VALUATION_SPREAD_RATIO of the best ask.
+    pub fn get_mid_price(&self, bids_data: &[u8], asks_data: &[u8]) -> Result<u128, ProgramError> {
+        let (best_bid, best_ask) = match (
+            get_best_slab_price(bids_data, true),
+            get_best_slab_price(asks_data, false),
+        ) {
+            (Some(bid), Some(ask)) => (bid as u128, ask as u128),
+            _ => {
+                msg!("The market's order book is empty.");
+                return Err(ProgramError::InvalidArgument);
+            }
+        };
+        if best_ask.saturating_sub(best_bid) << 16 > MAX_VALUATION_SPREAD_RATIO as u128 * best_ask {
+            msg!("The market's bid-ask spread is too wide to value the pool.");
+            return Err(BonfidaBotError::SpreadTooWide.into());
+        }
+        if self.coin_lot_size == 0 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok((((best_bid + best_ask) * self.pc_lot_size as u128) << 31) / self.coin_lot_size as u128)
+    }
+}
+
+// Native token amounts held by a Serum OpenOrders account. The free amounts can be settled,
+// the rest of the totals is locked in resting orders.
+#[derive(Debug, PartialEq)]
+pub struct OpenOrdersBalances {
+    pub native_coin_free: u64,
+    pub native_coin_total: u64,
+    pub native_pc_free: u64,
+    pub native_pc_total: u64,
+}
+
+impl OpenOrdersBalances {
+    pub fn unpack(open_orders_data: &[u8]) -> Result<Self, ProgramError> {
+        let data = open_orders_data
+            .get(77..109)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        Ok(Self {
+            native_coin_free: u64::from_le_bytes(data[..8].try_into().unwrap()),
+            native_coin_total: u64::from_le_bytes(data[8..16].try_into().unwrap()),
+            native_pc_free: u64::from_le_bytes(data[16..24].try_into().unwrap()),
+            native_pc_total: u64::from_le_bytes(data[24..32].try_into().unwrap()),
+        })
+    }
+}
+
+// Lists the side and order id of the orders held by a Serum OpenOrders account. After the 5
+// byte padding, the account has 128 order slots described by a free slot bitmask at 109..125,
+// a bid bitmask at 125..141 and the order ids from 141. An account which Serum hasn't
+// initialized yet holds no orders.
+pub fn get_open_orders_ids(open_orders_data: &[u8]) -> Result<Vec<(Side, u128)>, ProgramError> {
+    let slot_data = open_orders_data
+        .get(109..141 + 16 * 128)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    // Initialized and OpenOrders account flags
+    let account_flags = u64::from_le_bytes(open_orders_data[5..13].try_into().unwrap());
+    if account_flags & 5 != 5 {
+        return Ok(vec![]);
+    }
+    let free_slot_bits = u128::from_le_bytes(slot_data[..16].try_into().unwrap());
+    let is_bid_bits = u128::from_le_bytes(slot_data[16..32].try_into().unwrap());
+    let mut orders = vec![];
+    for slot in 0..128 {
+        if free_slot_bits & (1 << slot) != 0 {
+            continue;
+        }
+        let offset = 32 + 16 * slot;
+        let order_id = u128::from_le_bytes(slot_data[offset..offset + 16].try_into().unwrap());
+        let side = if is_bid_bits & (1 << slot) != 0 {
+            Side::Bid
+        } else {
+            Side::Ask
+        };
+        orders.push((side, order_id));
+    }
+    Ok(orders)
+}
+
+// Walks down a Serum order book critbit tree to its highest or lowest order, returning its
+// price in lots. Nodes are 72 bytes long and start after the 13 byte account prefix and the
+// 32 byte slab header.
+pub fn get_best_slab_price(slab_data: &[u8], highest: bool) -> Option<u64> {
+    let leaf_count = u64::from_le_bytes(slab_data.get(37..45)?.try_into().ok()?);
+    if leaf_count == 0 {
+        return None;
+    }
+    let mut node_index = u32::from_le_bytes(slab_data.get(33..37)?.try_into().ok()?) as usize;
+    // A tree can't be deeper than its number of leaves
+    for _ in 0..leaf_count {
+        let offset = 45 + 72 * node_index;
+        let node = slab_data.get(offset..offset + 72)?;
+        match u32::from_le_bytes(node[..4].try_into().ok()?) {
+            // Inner node
+            1 => {
+                let child_offset = 24 + 4 * (highest as usize);
+                node_index =
+                    u32::from_le_bytes(node[child_offset..child_offset + 4].try_into().ok()?)
+                        as usize;
+            }
+            // Leaf node, the price is in the upper half of the order key
+            2 => return Some((u128::from_le_bytes(node[8..24].try_into().ok()?) >> 64) as u64),
+            _ => return None,
+        }
+    }
+    None
+}
+
+// Amount of a pool asset a deposit of pool_token_amount pooltokens has to bring in. It is
+// rounded up so that no deposit can mint pooltokens without its share of every held asset.
+pub fn compute_deposit_amount(
+    pool_token_amount: u64,
+    pool_asset_amount: u64,
+    total_pooltokens: u64,
+) -> Result<u64, ProgramError> {
+    if total_pooltokens == 0 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let total_pooltokens = total_pooltokens as u128;
+    ((pool_token_amount as u128 * pool_asset_amount as u128 + total_pooltokens - 1)
+        / total_pooltokens)
+        .try_into()
+        .map_err(|_| BonfidaBotError::Overflow.into())
+}
+
+// Returns the amount of pooltokens to mint as a performance fee along with the new high-water
+// mark. The NAV per pooltoken and high-water mark are 32.32 fixed point numbers, the fee is
+// a share of the gains above the high-water mark out of 2**16.
+pub fn compute_performance_fee(
+    total_value: u128,
+    pool_token_supply: u64,
+    high_water_mark: u64,
+    performance_fee_ratio: u16,
+) -> Result<(u64, u64), ProgramError> {
+    if pool_token_supply == 0 {
+        return Ok((0, high_water_mark));
+    }
+    let supply = pool_token_supply as u128;
+    let nav: u64 = ((total_value << 32) / supply)
+        .try_into()
+        .map_err(|_| BonfidaBotError::Overflow)?;
+    if high_water_mark == 0 {
+        // The first collection sets the reference
+        return Ok((0, nav));
+    }
+    if nav <= high_water_mark {
+        return Ok((0, high_water_mark));
+    }
+    let fee_value =
+        ((((nav - high_water_mark) as u128 * supply) >> 32) * performance_fee_ratio as u128) >> 16;
+    // Minting t pooltokens gives away t / (supply + t) of the pool's value
+    let tokens_to_mint: u64 = (fee_value * supply / (total_value - fee_value))
+        .try_into()
+        .map_err(|_| BonfidaBotError::Overflow)?;
+    let new_high_water_mark = ((total_value << 32) / (supply + tokens_to_mint as u128)) as u64;
+    Ok((tokens_to_mint, new_high_water_mark.max(high_water_mark)))
+}
+
 pub fn fill_slice(target: &mut [u8], val: u8) {
     for i in 0..target.len() {
         target[i] = val;
@@ -58,13 +499,187 @@ pub fn pow_fixedpoint_u16(x: u32, n: u64) -> u32 {
 
 #[cfg(test)]
 mod tests {
-    use super::pow_fixedpoint_u16;
+    use super::{
+        check_deposit_limits, compute_deposit_amount, compute_performance_fee, get_best_slab_price,
+        get_open_orders_ids, pow_fixedpoint_u16, split_fees, MarketState, OpenOrdersBalances,
+    };
+    use crate::state::{FeeRecipient, ProgramConfig, OPEN_ORDERS_LEN};
+    use serum_dex::matching::Side;
+    use solana_program::pubkey::Pubkey;
 
     #[test]
-    fn test_exp(){
-        let half:u16 = 1<<15;
+    fn test_exp() {
+        let half: u16 = 1 << 15;
         for i in 1..16 {
-            assert_eq!(pow_fixedpoint_u16(half as u32, i), 1<<(16 - i));
+            assert_eq!(pow_fixedpoint_u16(half as u32, i), 1 << (16 - i));
         }
     }
+
+    #[test]
+    fn test_check_deposit_limits() {
+        assert!(check_deposit_limits(0, u64::MAX, 1_000_000).is_ok());
+        assert!(check_deposit_limits(1_000_000, 1_000, 1_000_000).is_ok());
+        assert!(check_deposit_limits(999_999, 1_000, 1_000_000).is_err());
+        assert!(check_deposit_limits(2_000_000, 2_000_001, 1_000_000).is_err());
+    }
+
+    #[test]
+    fn test_deposit_amount() {
+        assert_eq!(
+            compute_deposit_amount(10_000, 2_000_000, 1_000_000).unwrap(),
+            20_000
+        );
+        // A deposit too small for a whole token of an asset still has to bring one in
+        assert_eq!(compute_deposit_amount(1, 1_000, 1_000_000).unwrap(), 1);
+        assert_eq!(compute_deposit_amount(1_001, 1_000, 1_000_000).unwrap(), 2);
+        assert_eq!(compute_deposit_amount(1, 0, 1_000_000).unwrap(), 0);
+        assert!(compute_deposit_amount(u64::MAX, u64::MAX, 1).is_err());
+        assert!(compute_deposit_amount(1, 1, 0).is_err());
+    }
+
+    #[test]
+    fn test_split_fees() {
+        let mut config = ProgramConfig {
+            is_initialized: true,
+            signal_provider_share: 2,
+            fee_recipients: vec![
+                FeeRecipient {
+                    owner: Pubkey::new_unique(),
+                    share: 1,
+                },
+                FeeRecipient {
+                    owner: Pubkey::new_unique(),
+                    share: 1,
+                },
+            ],
+        };
+        assert_eq!(split_fees(&config, 1000), (500, vec![250, 250]));
+        assert_eq!(split_fees(&config, 1003), (501, vec![250, 252]));
+
+        config.fee_recipients = vec![];
+        assert_eq!(split_fees(&config, 1003), (1003, vec![]));
+    }
+
+    #[test]
+    fn test_performance_fee() {
+        // The first collection only sets the high-water mark
+        assert_eq!(
+            compute_performance_fee(2_000_000, 1_000_000, 0, 1 << 15).unwrap(),
+            (0, 2 << 32)
+        );
+        // No fee below the high-water mark
+        assert_eq!(
+            compute_performance_fee(2_000_000, 1_000_000, 3 << 32, 1 << 15).unwrap(),
+            (0, 3 << 32)
+        );
+        // Half of a doubling is taken as fees
+        let (tokens_to_mint, high_water_mark) =
+            compute_performance_fee(2_000_000, 1_000_000, 1 << 32, 1 << 15).unwrap();
+        assert_eq!(tokens_to_mint, 333_333);
+        assert_eq!(high_water_mark, 6_442_452_554);
+        assert_eq!(
+            2_000_000 * (tokens_to_mint as u128) / (1_000_000 + tokens_to_mint as u128),
+            499_999
+        );
+    }
+
+    #[test]
+    fn test_best_slab_price() {
+        let mut slab = vec![0u8; 45 + 3 * 72];
+        assert_eq!(get_best_slab_price(&slab, true), None);
+
+        // An inner node at the root with two leaves
+        slab[37..45].copy_from_slice(&2u64.to_le_bytes());
+        slab[45..49].copy_from_slice(&1u32.to_le_bytes());
+        slab[69..73].copy_from_slice(&1u32.to_le_bytes());
+        slab[73..77].copy_from_slice(&2u32.to_le_bytes());
+        for (i, price) in [(1usize, 90u128), (2, 110)].iter() {
+            let offset = 45 + 72 * i;
+            slab[offset..offset + 4].copy_from_slice(&2u32.to_le_bytes());
+            slab[offset + 8..offset + 24].copy_from_slice(&((price << 64) | 7).to_le_bytes());
+        }
+        assert_eq!(get_best_slab_price(&slab, true), Some(110));
+        assert_eq!(get_best_slab_price(&slab, false), Some(90));
+    }
+
+    #[test]
+    fn test_mid_price() {
+        // An order book holding a single order at the root of the tree
+        let single_order_slab = |price: u128| {
+            let mut slab = vec![0u8; 45 + 72];
+            slab[37..45].copy_from_slice(&1u64.to_le_bytes());
+            slab[45..49].copy_from_slice(&2u32.to_le_bytes());
+            slab[53..69].copy_from_slice(&(price << 64).to_le_bytes());
+            slab
+        };
+        let market = MarketState {
+            is_disabled: false,
+            coin_mint: Pubkey::new_unique(),
+            pc_mint: Pubkey::new_unique(),
+            bids: Pubkey::new_unique(),
+            asks: Pubkey::new_unique(),
+            coin_lot_size: 10,
+            pc_lot_size: 1,
+        };
+        assert_eq!(
+            market
+                .get_mid_price(&single_order_slab(196), &single_order_slab(204))
+                .unwrap(),
+            20 << 32
+        );
+        assert!(market
+            .get_mid_price(&single_order_slab(95), &single_order_slab(100))
+            .is_ok());
+        // A thin book with a wide spread can't be used to value a pool
+        assert!(market
+            .get_mid_price(&single_order_slab(94), &single_order_slab(100))
+            .is_err());
+        assert!(market
+            .get_mid_price(&single_order_slab(100), &single_order_slab(300))
+            .is_err());
+        assert!(market
+            .get_mid_price(&vec![0u8; 45 + 72], &single_order_slab(100))
+            .is_err());
+    }
+
+    #[test]
+    fn test_open_orders_ids() {
+        // Serum hasn't initialized the account yet, all of its slots look used
+        let mut open_orders = vec![0u8; OPEN_ORDERS_LEN];
+        assert!(get_open_orders_ids(&open_orders).unwrap().is_empty());
+
+        open_orders[5..13].copy_from_slice(&5u64.to_le_bytes());
+        open_orders[109..125].copy_from_slice(&u128::MAX.to_le_bytes());
+        assert!(get_open_orders_ids(&open_orders).unwrap().is_empty());
+
+        // Slot 0 holds a bid and slot 5 an ask
+        open_orders[109..125].copy_from_slice(&(u128::MAX & !1 & !(1 << 5)).to_le_bytes());
+        open_orders[125..141].copy_from_slice(&1u128.to_le_bytes());
+        open_orders[141..157].copy_from_slice(&42u128.to_le_bytes());
+        open_orders[221..237].copy_from_slice(&(7u128 << 64).to_le_bytes());
+        assert_eq!(
+            get_open_orders_ids(&open_orders).unwrap(),
+            vec![(Side::Bid, 42), (Side::Ask, 7 << 64)]
+        );
+        assert!(get_open_orders_ids(&open_orders[..200]).is_err());
+    }
+
+    #[test]
+    fn test_open_orders_balances() {
+        let mut open_orders = vec![0u8; OPEN_ORDERS_LEN];
+        open_orders[77..85].copy_from_slice(&1u64.to_le_bytes());
+        open_orders[85..93].copy_from_slice(&2u64.to_le_bytes());
+        open_orders[93..101].copy_from_slice(&3u64.to_le_bytes());
+        open_orders[101..109].copy_from_slice(&4u64.to_le_bytes());
+        assert_eq!(
+            OpenOrdersBalances::unpack(&open_orders).unwrap(),
+            OpenOrdersBalances {
+                native_coin_free: 1,
+                native_coin_total: 2,
+                native_pc_free: 3,
+                native_pc_total: 4,
+            }
+        );
+        assert!(OpenOrdersBalances::unpack(&open_orders[..100]).is_err());
+    }
 }
@@ -6,7 +6,7 @@ use solana_program::{
     sysvar,
 };
 
-use solana_sdk::{signature::{Keypair, Signer}, transport::TransportError};
+use solana_sdk::{account::AccountSharedData, signature::{Keypair, Signer}, transport::TransportError};
 use spl_token::instruction::mint_to;
 
 use super::utils::{Context, OpenOrderView, create_token_account, wrap_process_transaction};
@@ -293,6 +293,79 @@ impl SerumMarket {
         matching_open_order.pubkey()
     }
 
+    // Places an order from an outside trader which rests on the book
+    pub async fn place_resting_order(
+        &self,
+        ctx: &Context,
+        side: Side,
+        limit_price: NonZeroU64,
+        max_qty: NonZeroU64,
+        asset_mint_authority: &Keypair,
+    ) -> Pubkey {
+        let source = Keypair::new();
+        let source_owner = Keypair::new();
+        let source_mint = match side {
+            Side::Bid => &self.pc_mint,
+            Side::Ask => &self.coin_mint,
+        };
+        let create_source = create_token_account(&ctx, source_mint, &source, &source_owner.pubkey());
+        ctx.test_state.banks_client
+            .to_owned()
+            .process_transaction(create_source)
+            .await
+            .unwrap();
+        let mint_source_instruction = mint_to(
+            &spl_token::id(),
+            source_mint,
+            &source.pubkey(),
+            &asset_mint_authority.pubkey(),
+            &[],
+            (u64::MAX as u64) >> 1,
+        )
+        .unwrap();
+
+        let (open_order, create_open_order_instruction) =
+            SerumMarket::create_dex_account(&ctx, 3216).unwrap();
+        wrap_process_transaction(
+            &ctx,
+            vec![mint_source_instruction, create_open_order_instruction],
+            vec![&asset_mint_authority, &open_order],
+        )
+        .await
+        .unwrap();
+
+        let order_instruction = serum_dex::instruction::new_order(
+            &self.market_key.pubkey(),
+            &open_order.pubkey(),
+            &self.req_q_key.pubkey(),
+            &self.event_q_key.pubkey(),
+            &self.bids_key.pubkey(),
+            &self.asks_key.pubkey(),
+            &source.pubkey(),
+            &source_owner.pubkey(),
+            &self.coin_vault,
+            &self.pc_vault,
+            &spl_token::id(),
+            &sysvar::rent::id(),
+            None,
+            &ctx.serum_program_id,
+            side,
+            limit_price,
+            max_qty,
+            OrderType::Limit,
+            0,
+            SelfTradeBehavior::DecrementTake,
+            1000,
+            NonZeroU64::new(2 * limit_price.get() * max_qty.get() * self.pc_lot_size).unwrap(),
+        )
+        .unwrap();
+        wrap_process_transaction(&ctx, vec![order_instruction], vec![&source_owner])
+            .await
+            .unwrap();
+
+        open_order.pubkey()
+    }
+
     pub async fn crank(
         &self,
         ctx: &Context,
@@ -338,4 +411,21 @@ impl SerumMarket {
         .await
         .unwrap();
     }
+
+    // Only the dex's disable authority can disable a market, so its account flags are
+    // overwritten instead
+    pub async fn disable(&self, ctx: &mut Context) {
+        let mut market_account = ctx
+            .test_state
+            .banks_client
+            .get_account(self.market_key.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        market_account.data[5] |= 1 << 7;
+        ctx.test_state.set_account(
+            &self.market_key.pubkey(),
+            &AccountSharedData::from(market_account),
+        );
+    }
 }
@@ -1,22 +1,38 @@
-use std::{
-    num::{NonZeroU16, NonZeroU64},
-    str::FromStr,
-};
+use std::num::{NonZeroU16, NonZeroU64};
 
 #[cfg(not(feature = "fuzz"))]
 use bonfida_bot::{
-    instruction::{cancel_order, create, create_order, deposit, init, redeem, settle_funds, collect_fees},
-    state::{BONFIDA_BNB, BONFIDA_FEE},
+    instruction::{
+        accept_signal_provider, cancel_all_orders, cancel_order, cancel_order_by_client_id,
+        cancel_redeem_request, close_pool, collect_fees, create, create_order, create_order_v2, deposit,
+        deposit_single_asset, execute_market_change, init, init_open_orders, lock, migrate_pool,
+        process_redeem_queue, propose_market_change, propose_signal_provider, redeem,
+        redeem_to_single_asset, release_deposit, remove_whitelist, request_redeem, resize_pool,
+        set_deposit_limits, set_metadata, set_whitelist, settle_and_redeem, settle_funds, unlock,
+    },
+    state::{PoolHeader, PoolMetadata, PoolRegistry, Whitelist},
+    utils::{get_metadata_key, get_open_orders_key, get_registry_key, get_whitelist_key},
 };
 
 #[cfg(feature = "fuzz")]
 use crate::{
-    instruction::{cancel_order, create, create_order, deposit, init, redeem, settle_funds, collect_fees},
-    state::{BONFIDA_BNB, BONFIDA_FEE},
+    instruction::{
+        accept_signal_provider, cancel_all_orders, cancel_order, cancel_order_by_client_id,
+        cancel_redeem_request, close_pool, collect_fees, create, create_order, create_order_v2, deposit,
+        deposit_single_asset, execute_market_change, init, init_open_orders, lock, migrate_pool,
+        process_redeem_queue, propose_market_change, propose_signal_provider, redeem,
+        redeem_to_single_asset, release_deposit, remove_whitelist, request_redeem, resize_pool,
+        set_deposit_limits, set_metadata, set_whitelist, settle_and_redeem, settle_funds, unlock,
+    },
+    state::{PoolHeader, PoolMetadata, PoolRegistry, Whitelist},
+    utils::{get_metadata_key, get_open_orders_key, get_registry_key, get_whitelist_key},
 };
 use rand::{distributions::Alphanumeric, Rng};
-use serum_dex::{instruction::SelfTradeBehavior, matching::Side};
-use solana_program::{pubkey::Pubkey, system_program, sysvar};
+use serum_dex::{
+    instruction::SelfTradeBehavior,
+    matching::{OrderType, Side},
+};
+use solana_program::{program_pack::Pack, pubkey::Pubkey, system_program, sysvar};
 use solana_sdk::{
     signature::{Keypair, Signer},
     transport::TransportError,
@@ -114,10 +130,9 @@ impl TestPool {
             .unwrap();
 
         // Initialize fee accounts
-        self.get_pt_account(ctx, &Pubkey::from_str(BONFIDA_FEE).unwrap())
-            .await;
-        self.get_pt_account(ctx, &Pubkey::from_str(BONFIDA_BNB).unwrap())
-            .await;
+        for fee_recipient in ctx.fee_recipients.iter() {
+            self.get_pt_account(ctx, fee_recipient).await;
+        }
     }
 
     pub async fn get_pt_account(&self, ctx: &Context, owner: &Pubkey) -> Pubkey {
@@ -175,6 +190,13 @@ impl TestPool {
         market: &Pubkey,
         fee_collection_period: u64,
         fee_ratio: u16,
+        max_order_lifetime: u64,
+        timelock_period: u64,
+        performance_fee_ratio: u16,
+        redeem_fee_ratio: u16,
+        lockup_period: u64,
+        max_pool_token_supply: u64,
+        min_deposit_pool_tokens: u64,
     ) -> Result<(), TransportError> {
         println!("Deposit amounts length {:#?}", deposit_amounts.len());
         let create_instruction = create(
@@ -190,13 +212,28 @@ impl TestPool {
             &source_asset_keys,
             &ctx.serum_program_id,
             &self.signal_provider.pubkey(),
+            &system_program::id(),
+            &sysvar::rent::id(),
+            &ctx.test_state.payer.pubkey(),
             fee_collection_period,
             fee_ratio,
+            max_order_lifetime,
+            timelock_period,
+            performance_fee_ratio,
+            redeem_fee_ratio,
+            lockup_period,
+            max_pool_token_supply,
+            min_deposit_pool_tokens,
             deposit_amounts,
             vec![market.clone()],
         )
         .unwrap();
-        wrap_process_transaction(&ctx, vec![create_instruction], vec![&source_owner]).await
+        wrap_process_transaction(
+            &ctx,
+            vec![create_instruction],
+            vec![&source_owner, &self.signal_provider],
+        )
+        .await
     }
 
     pub async fn deposit(
@@ -207,6 +244,36 @@ impl TestPool {
         source_owner: &Keypair,
         source_asset_keys: &Vec<Pubkey>,
     ) -> Result<(), TransportError> {
+        self.deposit_with_bounds(
+            ctx,
+            amount,
+            pooltoken_target_key,
+            source_owner,
+            source_asset_keys,
+            0,
+            vec![u64::MAX; self.mints.len()],
+        )
+        .await
+    }
+
+    pub async fn deposit_with_bounds(
+        &self,
+        ctx: &Context,
+        amount: u64,
+        pooltoken_target_key: &Pubkey,
+        source_owner: &Keypair,
+        source_asset_keys: &Vec<Pubkey>,
+        min_pool_tokens_out: u64,
+        max_amounts_in: Vec<u64>,
+    ) -> Result<(), TransportError> {
+        let lockup_account_keys = [
+            system_program::id(),
+            sysvar::rent::id(),
+            sysvar::clock::id(),
+            ctx.test_state.payer.pubkey(),
+        ];
+        let lockup_accounts = self.get_lockup_accounts(ctx, &lockup_account_keys).await;
+        let allow_list_keys = self.get_allow_list_keys(ctx, &source_owner.pubkey()).await;
         let deposit_instruction = deposit(
             &spl_token::id(),
             &self.program_id,
@@ -215,23 +282,96 @@ impl TestPool {
             &self.mints.iter().map(|m| m.pool_asset_key).collect(),
             &pooltoken_target_key,
             &get_associated_token_address(&self.signal_provider.pubkey(), &self.mint_key),
+            &ctx.config_key,
+            &ctx.fee_recipients,
             &source_owner.pubkey(),
             &source_asset_keys,
+            &allow_list_keys,
+            lockup_accounts,
             self.seeds,
             amount,
+            min_pool_tokens_out,
+            max_amounts_in,
         )
         .unwrap();
         wrap_process_transaction(&ctx, vec![deposit_instruction], vec![&source_owner]).await
     }
 
-    pub async fn initialize_new_order(&self, ctx: &Context) -> Result<Order, TransportError> {
-        let (open_order, create_open_order_instruction) =
-            SerumMarket::create_dex_account(&ctx, 3216).unwrap();
+    // Buys into the pool with a single token, every other asset being bought on its market
+    pub async fn deposit_single_asset(
+        &self,
+        ctx: &Context,
+        amount: u64,
+        pooltoken_target_key: &Pubkey,
+        source_owner: &Keypair,
+        source_key: &Pubkey,
+        // One (market, user OpenOrders account, user coin account) triplet per bought asset
+        swaps: &[(&SerumMarket, &Pubkey, &Pubkey)],
+        min_pool_tokens_out: u64,
+        limit_prices: Vec<u64>,
+    ) -> Result<(), TransportError> {
+        let lockup_account_keys = [
+            system_program::id(),
+            sysvar::rent::id(),
+            sysvar::clock::id(),
+            ctx.test_state.payer.pubkey(),
+        ];
+        let lockup_accounts = self.get_lockup_accounts(ctx, &lockup_account_keys).await;
+        let allow_list_keys = self.get_allow_list_keys(ctx, &source_owner.pubkey()).await;
+        let deposit_instruction = deposit_single_asset(
+            &spl_token::id(),
+            &sysvar::rent::id(),
+            &ctx.serum_program_id,
+            &self.program_id,
+            &self.mint_key,
+            &self.key,
+            &self.mints.iter().map(|m| m.pool_asset_key).collect(),
+            &pooltoken_target_key,
+            &get_associated_token_address(&self.signal_provider.pubkey(), &self.mint_key),
+            &ctx.config_key,
+            &ctx.fee_recipients,
+            &source_owner.pubkey(),
+            source_key,
+            &allow_list_keys,
+            lockup_accounts,
+            &get_swap_account_keys(swaps),
+            self.seeds,
+            amount,
+            min_pool_tokens_out,
+            limit_prices,
+        )
+        .unwrap();
+        wrap_process_transaction(&ctx, vec![deposit_instruction], vec![&source_owner]).await
+    }
 
-        wrap_process_transaction(&ctx, vec![create_open_order_instruction], vec![&open_order])
+    // Returns the pool's OpenOrders account for the market, initializing it on first use
+    pub async fn initialize_new_order(
+        &self,
+        ctx: &Context,
+        market_index: u16,
+    ) -> Result<Order, TransportError> {
+        let (open_orders_key, _) = get_open_orders_key(&self.program_id, &self.seeds, market_index);
+        let existing_account = ctx
+            .test_state
+            .banks_client
+            .to_owned()
+            .get_account(open_orders_key)
             .await?;
+        if existing_account.is_none() {
+            let init_open_orders_instruction = init_open_orders(
+                &system_program::id(),
+                &sysvar::rent::id(),
+                &self.program_id,
+                &ctx.test_state.payer.pubkey(),
+                &self.key,
+                self.seeds,
+                market_index,
+            )
+            .unwrap();
+            wrap_process_transaction(&ctx, vec![init_open_orders_instruction], vec![]).await?;
+        }
         Ok(Order {
-            open_orders_account: open_order.pubkey(),
+            open_orders_account: open_orders_key,
         })
     }
 
@@ -245,6 +385,35 @@ impl TestPool {
         side: Side,
         limit_price: NonZeroU64,
         max_qty: NonZeroU16,
+        order_type: OrderType,
+    ) -> Result<(), TransportError> {
+        self.create_new_order_with_client_id(
+            ctx,
+            serum_market,
+            source_asset_index,
+            target_asset_index,
+            order,
+            side,
+            limit_price,
+            max_qty,
+            order_type,
+            0,
+        )
+        .await
+    }
+
+    pub async fn create_new_order_with_client_id(
+        &self,
+        ctx: &mut Context,
+        serum_market: &SerumMarket,
+        source_asset_index: u64,
+        target_asset_index: u64,
+        order: &Order,
+        side: Side,
+        limit_price: NonZeroU64,
+        max_qty: NonZeroU16,
+        order_type: OrderType,
+        client_id: u64,
     ) -> Result<(), TransportError> {
         println!("{:?}", vec![
             &order.open_orders_account,
@@ -255,6 +424,59 @@ impl TestPool {
         ]);
         let acc = ctx.test_state.banks_client.get_account(serum_market.event_q_key.pubkey()).await.unwrap();
         println!("{:?}", acc.is_some());
+        let create_order_instruction = create_order_v2(
+            &self.program_id,
+            &self.signal_provider.pubkey(),
+            &serum_market.market_key.pubkey(),
+            &self.mints[source_asset_index as usize].pool_asset_key,
+            source_asset_index,
+            target_asset_index,
+            &order.open_orders_account,
+            &serum_market.event_q_key.pubkey(),
+            &serum_market.req_q_key.pubkey(),
+            &serum_market.bids_key.pubkey(),
+            &serum_market.asks_key.pubkey(),
+            &self.key,
+            &serum_market.coin_vault,
+            &serum_market.pc_vault,
+            &spl_token::id(),
+            &ctx.serum_program_id,
+            &sysvar::rent::id(),
+            &sysvar::clock::id(),
+            None,
+            self.seeds,
+            side,
+            limit_price,
+            0,
+            max_qty,
+            order_type,
+            client_id,
+            SelfTradeBehavior::DecrementTake,
+            1000,
+        )
+        .unwrap();
+        wrap_process_transaction(
+            &ctx,
+            vec![create_order_instruction],
+            vec![&self.signal_provider],
+        )
+        .await
+    }
+
+    // Creates an order with the deprecated instruction, which takes the lot sizes as arguments
+    pub async fn create_legacy_order(
+        &self,
+        ctx: &Context,
+        serum_market: &SerumMarket,
+        source_asset_index: u64,
+        target_asset_index: u64,
+        order: &Order,
+        side: Side,
+        limit_price: NonZeroU64,
+        max_qty: NonZeroU16,
+        coin_lot_size: u64,
+        pc_lot_size: u64,
+    ) -> Result<(), TransportError> {
         let create_order_instruction = create_order(
             &self.program_id,
             &self.signal_provider.pubkey(),
@@ -273,19 +495,20 @@ impl TestPool {
             &spl_token::id(),
             &ctx.serum_program_id,
             &sysvar::rent::id(),
+            &sysvar::clock::id(),
             None,
             self.seeds,
             side,
             limit_price,
             0,
-            serum_market.coin_lot_size,
-            serum_market.pc_lot_size,
+            coin_lot_size,
+            pc_lot_size,
             &self.mints[target_asset_index as usize].key,
             max_qty,
-            serum_dex::matching::OrderType::ImmediateOrCancel,
+            OrderType::Limit,
             0,
             SelfTradeBehavior::DecrementTake,
-            1000
+            1000,
         )
         .unwrap();
         wrap_process_transaction(
@@ -331,12 +554,13 @@ impl TestPool {
         ctx: &Context,
         serum_market: &SerumMarket,
         order: &Order,
+        signer: &Keypair,
     ) -> Result<(), TransportError> {
         let openorder_view =
             OpenOrderView::get(order.open_orders_account, &ctx.test_state.banks_client).await?;
         let cancel_instruction = cancel_order(
             &self.program_id,
-            &self.signal_provider.pubkey(),
+            &signer.pubkey(),
             &serum_market.market_key.pubkey(),
             &order.open_orders_account,
             &serum_market.bids_key.pubkey(),
@@ -344,12 +568,63 @@ impl TestPool {
             &serum_market.event_q_key.pubkey(),
             &self.key,
             &ctx.serum_program_id,
+            &sysvar::clock::id(),
             self.seeds,
             Side::Bid,
             openorder_view.orders[0],
         )
         .unwrap();
-        wrap_process_transaction(&ctx, vec![cancel_instruction], vec![&self.signal_provider]).await
+        wrap_process_transaction(&ctx, vec![cancel_instruction], vec![signer]).await
+    }
+
+    pub async fn cancel_order_by_client_id(
+        &self,
+        ctx: &Context,
+        serum_market: &SerumMarket,
+        order: &Order,
+        signer: &Keypair,
+        client_id: u64,
+    ) -> Result<(), TransportError> {
+        let cancel_instruction = cancel_order_by_client_id(
+            &self.program_id,
+            &signer.pubkey(),
+            &serum_market.market_key.pubkey(),
+            &order.open_orders_account,
+            &serum_market.bids_key.pubkey(),
+            &serum_market.asks_key.pubkey(),
+            &serum_market.event_q_key.pubkey(),
+            &self.key,
+            &ctx.serum_program_id,
+            &sysvar::clock::id(),
+            self.seeds,
+            client_id,
+        )
+        .unwrap();
+        wrap_process_transaction(&ctx, vec![cancel_instruction], vec![signer]).await
+    }
+
+    pub async fn cancel_all_orders(
+        &self,
+        ctx: &Context,
+        serum_market: &SerumMarket,
+        order: &Order,
+        signer: &Keypair,
+    ) -> Result<(), TransportError> {
+        let cancel_instruction = cancel_all_orders(
+            &self.program_id,
+            &signer.pubkey(),
+            &serum_market.market_key.pubkey(),
+            &order.open_orders_account,
+            &serum_market.bids_key.pubkey(),
+            &serum_market.asks_key.pubkey(),
+            &serum_market.event_q_key.pubkey(),
+            &self.key,
+            &ctx.serum_program_id,
+            &sysvar::clock::id(),
+            self.seeds,
+        )
+        .unwrap();
+        wrap_process_transaction(&ctx, vec![cancel_instruction], vec![signer]).await
     }
 
     pub async fn redeem(
@@ -360,6 +635,65 @@ impl TestPool {
         pooltoken_target_key: &Pubkey,
         source_asset_keys: &Vec<Pubkey>,
     ) -> Result<(), TransportError> {
+        self.redeem_with_bounds(
+            ctx,
+            amount,
+            source_owner,
+            pooltoken_target_key,
+            source_asset_keys,
+            vec![0; self.mints.len()],
+        )
+        .await
+    }
+
+    pub async fn redeem_with_bounds(
+        &self,
+        ctx: &Context,
+        amount: u64,
+        source_owner: &Keypair,
+        pooltoken_target_key: &Pubkey,
+        source_asset_keys: &Vec<Pubkey>,
+        min_amounts_out: Vec<u64>,
+    ) -> Result<(), TransportError> {
+        self.redeem_with_pending_orders(
+            ctx,
+            amount,
+            source_owner,
+            pooltoken_target_key,
+            source_asset_keys,
+            min_amounts_out,
+            &[],
+        )
+        .await
+    }
+
+    // Redeems while the pool has pending orders on the given markets
+    pub async fn redeem_with_pending_orders(
+        &self,
+        ctx: &Context,
+        amount: u64,
+        source_owner: &Keypair,
+        pooltoken_target_key: &Pubkey,
+        source_asset_keys: &Vec<Pubkey>,
+        min_amounts_out: Vec<u64>,
+        pending_orders: &[(&SerumMarket, &Order)],
+    ) -> Result<(), TransportError> {
+        let mut pending_order_account_keys = vec![];
+        for (serum_market, order) in pending_orders.iter() {
+            pending_order_account_keys.extend_from_slice(&[
+                serum_market.market_key.pubkey(),
+                order.open_orders_account,
+                serum_market.bids_key.pubkey(),
+                serum_market.asks_key.pubkey(),
+                serum_market.event_q_key.pubkey(),
+                serum_market.coin_vault,
+                serum_market.pc_vault,
+                serum_market.vault_signer_pk,
+            ]);
+        }
+        let signal_provider_pt_key =
+            get_associated_token_address(&self.signal_provider.pubkey(), &self.mint_key);
+        let redeem_fee_accounts = self.get_redeem_fee_accounts(ctx, &signal_provider_pt_key).await;
         let redeem_instruction = redeem(
             &spl_token::id(),
             &sysvar::clock::id(),
@@ -370,14 +704,222 @@ impl TestPool {
             &source_owner.pubkey(),
             &pooltoken_target_key,
             &source_asset_keys,
+            redeem_fee_accounts,
+            &ctx.serum_program_id,
+            &pending_order_account_keys,
             self.seeds,
             amount,
+            min_amounts_out,
         )
         .unwrap();
         wrap_process_transaction(&ctx, vec![redeem_instruction], vec![&source_owner]).await
     }
 
-    pub async fn collect_fees(&self, ctx: &Context) -> Result<(), TransportError> {
+    // Escrows pooltokens in the pool's redeem queue
+    pub async fn request_redeem(
+        &self,
+        ctx: &Context,
+        amount: u64,
+        source_owner: &Keypair,
+        pooltoken_source_key: &Pubkey,
+    ) -> Result<(), TransportError> {
+        self.request_redeem_with_bounds(
+            ctx,
+            amount,
+            source_owner,
+            pooltoken_source_key,
+            vec![0; self.mints.len()],
+        )
+        .await
+    }
+
+    pub async fn request_redeem_with_bounds(
+        &self,
+        ctx: &Context,
+        amount: u64,
+        source_owner: &Keypair,
+        pooltoken_source_key: &Pubkey,
+        min_amounts_out: Vec<u64>,
+    ) -> Result<(), TransportError> {
+        let request_instruction = request_redeem(
+            &system_program::id(),
+            &spl_token::id(),
+            &sysvar::rent::id(),
+            &self.program_id,
+            &ctx.test_state.payer.pubkey(),
+            &self.mint_key,
+            &self.key,
+            &source_owner.pubkey(),
+            pooltoken_source_key,
+            self.seeds,
+            amount,
+            min_amounts_out,
+        )
+        .unwrap();
+        wrap_process_transaction(&ctx, vec![request_instruction], vec![&source_owner]).await
+    }
+
+    // Processes the queued requests of the given owners, paying them out to their associated
+    // token accounts
+    pub async fn process_redeem_queue(
+        &self,
+        ctx: &Context,
+        request_owners: &[Pubkey],
+    ) -> Result<(), TransportError> {
+        let mut target_asset_keys = vec![];
+        for owner in request_owners.iter() {
+            for m in self.mints.iter() {
+                target_asset_keys.push(get_associated_token_address(owner, &m.key));
+            }
+            target_asset_keys.push(get_associated_token_address(owner, &self.mint_key));
+        }
+        let signal_provider_pt_key =
+            get_associated_token_address(&self.signal_provider.pubkey(), &self.mint_key);
+        let redeem_fee_accounts = self.get_redeem_fee_accounts(ctx, &signal_provider_pt_key).await;
+        let process_instruction = process_redeem_queue(
+            &spl_token::id(),
+            &sysvar::clock::id(),
+            &self.program_id,
+            &self.mint_key,
+            &self.key,
+            &self.mints.iter().map(|m| m.pool_asset_key).collect(),
+            redeem_fee_accounts,
+            &target_asset_keys,
+            self.seeds,
+        )
+        .unwrap();
+        wrap_process_transaction(&ctx, vec![process_instruction], vec![]).await
+    }
+
+    pub async fn cancel_redeem_request(
+        &self,
+        ctx: &Context,
+        owner: &Keypair,
+        pooltoken_target_key: &Pubkey,
+        request_index: u64,
+    ) -> Result<(), TransportError> {
+        let cancel_instruction = cancel_redeem_request(
+            &spl_token::id(),
+            &self.program_id,
+            &self.mint_key,
+            &self.key,
+            &owner.pubkey(),
+            pooltoken_target_key,
+            self.seeds,
+            request_index,
+        )
+        .unwrap();
+        wrap_process_transaction(&ctx, vec![cancel_instruction], vec![&owner]).await
+    }
+
+    // Buys out of the pool after settling the given pending orders in the same instruction
+    pub async fn settle_and_redeem(
+        &self,
+        ctx: &Context,
+        amount: u64,
+        source_owner: &Keypair,
+        pooltoken_source_key: &Pubkey,
+        target_asset_keys: &Vec<Pubkey>,
+        pending_orders: &[(&SerumMarket, &Order)],
+    ) -> Result<(), TransportError> {
+        let mut settle_account_keys = vec![];
+        for (serum_market, order) in pending_orders.iter() {
+            settle_account_keys.extend_from_slice(&[
+                serum_market.market_key.pubkey(),
+                order.open_orders_account,
+                serum_market.bids_key.pubkey(),
+                serum_market.asks_key.pubkey(),
+                serum_market.event_q_key.pubkey(),
+                serum_market.coin_vault,
+                serum_market.pc_vault,
+                serum_market.vault_signer_pk,
+            ]);
+        }
+        let signal_provider_pt_key =
+            get_associated_token_address(&self.signal_provider.pubkey(), &self.mint_key);
+        let redeem_fee_accounts = self.get_redeem_fee_accounts(ctx, &signal_provider_pt_key).await;
+        let redeem_instruction = settle_and_redeem(
+            &spl_token::id(),
+            &sysvar::clock::id(),
+            &ctx.serum_program_id,
+            &self.program_id,
+            &self.mint_key,
+            &self.key,
+            &self.mints.iter().map(|m| m.pool_asset_key).collect(),
+            &source_owner.pubkey(),
+            pooltoken_source_key,
+            target_asset_keys,
+            redeem_fee_accounts,
+            &settle_account_keys,
+            self.seeds,
+            amount,
+            vec![0; self.mints.len()],
+        )
+        .unwrap();
+        wrap_process_transaction(&ctx, vec![redeem_instruction], vec![&source_owner]).await
+    }
+
+    // Buys out of the pool into a single token, every other asset being sold on its market
+    pub async fn redeem_to_single_asset(
+        &self,
+        ctx: &Context,
+        amount: u64,
+        source_owner: &Keypair,
+        pooltoken_source_key: &Pubkey,
+        target_key: &Pubkey,
+        // One (market, user OpenOrders account, user coin account) triplet per sold asset
+        swaps: &[(&SerumMarket, &Pubkey, &Pubkey)],
+        min_amount_out: u64,
+        limit_prices: Vec<u64>,
+    ) -> Result<(), TransportError> {
+        let signal_provider_pt_key =
+            get_associated_token_address(&self.signal_provider.pubkey(), &self.mint_key);
+        let redeem_fee_accounts = self.get_redeem_fee_accounts(ctx, &signal_provider_pt_key).await;
+        let redeem_instruction = redeem_to_single_asset(
+            &spl_token::id(),
+            &sysvar::clock::id(),
+            &sysvar::rent::id(),
+            &ctx.serum_program_id,
+            &self.program_id,
+            &self.mint_key,
+            &self.key,
+            &self.mints.iter().map(|m| m.pool_asset_key).collect(),
+            &source_owner.pubkey(),
+            pooltoken_source_key,
+            target_key,
+            redeem_fee_accounts,
+            &get_swap_account_keys(swaps),
+            self.seeds,
+            amount,
+            min_amount_out,
+            limit_prices,
+        )
+        .unwrap();
+        wrap_process_transaction(&ctx, vec![redeem_instruction], vec![&source_owner]).await
+    }
+
+    // The first price market should be the pool's first market, the others are matched to the
+    // pool assets by their coin mint
+    pub async fn collect_fees(
+        &self,
+        ctx: &Context,
+        price_markets: &[&SerumMarket],
+    ) -> Result<(), TransportError> {
+        let mut pool_asset_keys = vec![];
+        let mut price_account_keys = vec![];
+        if let Some(quote_market) = price_markets.first() {
+            pool_asset_keys = self.mints.iter().map(|m| m.pool_asset_key).collect();
+            price_account_keys.push(quote_market.market_key.pubkey());
+            for m in self.mints.iter().filter(|m| m.key != quote_market.pc_mint) {
+                let market = price_markets
+                    .iter()
+                    .find(|market| market.coin_mint == m.key)
+                    .unwrap();
+                price_account_keys.push(market.market_key.pubkey());
+                price_account_keys.push(market.bids_key.pubkey());
+                price_account_keys.push(market.asks_key.pubkey());
+            }
+        }
         let instruction = collect_fees(
             &spl_token::id(),
             &sysvar::clock::id(),
@@ -385,11 +927,402 @@ impl TestPool {
             &self.key,
             &self.mint_key,
             &get_associated_token_address(&self.signal_provider.pubkey(), &self.mint_key),
+            &ctx.config_key,
+            &ctx.fee_recipients,
+            &pool_asset_keys,
+            &price_account_keys,
             self.seeds.clone(),
         )
         .unwrap();
         wrap_process_transaction(&ctx, vec![instruction], vec![]).await
     }
+
+    pub async fn lock(&self, ctx: &Context, signer: &Keypair) -> Result<(), TransportError> {
+        let instruction = lock(&self.program_id, &signer.pubkey(), &self.key, self.seeds).unwrap();
+        wrap_process_transaction(&ctx, vec![instruction], vec![signer]).await
+    }
+
+    pub async fn unlock(&self, ctx: &Context) -> Result<(), TransportError> {
+        let instruction = unlock(&self.program_id, &self.key, self.seeds).unwrap();
+        wrap_process_transaction(&ctx, vec![instruction], vec![]).await
+    }
+
+    pub async fn propose_signal_provider(
+        &self,
+        ctx: &Context,
+        signer: &Keypair,
+        new_signal_provider: &Pubkey,
+    ) -> Result<(), TransportError> {
+        let instruction = propose_signal_provider(
+            &self.program_id,
+            &signer.pubkey(),
+            &sysvar::clock::id(),
+            &self.key,
+            self.seeds,
+            new_signal_provider,
+        )
+        .unwrap();
+        wrap_process_transaction(&ctx, vec![instruction], vec![signer]).await
+    }
+
+    pub async fn accept_signal_provider(
+        &self,
+        ctx: &Context,
+        new_signal_provider: &Keypair,
+    ) -> Result<(), TransportError> {
+        let signal_provider = self.get_header(ctx).await.signal_provider;
+        let instruction = accept_signal_provider(
+            &self.program_id,
+            &new_signal_provider.pubkey(),
+            &sysvar::clock::id(),
+            &system_program::id(),
+            &sysvar::rent::id(),
+            &ctx.test_state.payer.pubkey(),
+            &signal_provider,
+            &self.key,
+            self.seeds,
+        )
+        .unwrap();
+        wrap_process_transaction(&ctx, vec![instruction], vec![new_signal_provider]).await
+    }
+
+    pub async fn propose_market_change(
+        &self,
+        ctx: &Context,
+        signer: &Keypair,
+        market_index: u16,
+        market: &Pubkey,
+    ) -> Result<(), TransportError> {
+        let instruction = propose_market_change(
+            &self.program_id,
+            &signer.pubkey(),
+            &sysvar::clock::id(),
+            &self.key,
+            self.seeds,
+            market_index,
+            market,
+        )
+        .unwrap();
+        wrap_process_transaction(&ctx, vec![instruction], vec![signer]).await
+    }
+
+    pub async fn execute_market_change(
+        &self,
+        ctx: &Context,
+        signer: &Keypair,
+        market: &Pubkey,
+        replaced_open_orders: Option<(&Pubkey, &Pubkey)>,
+    ) -> Result<(), TransportError> {
+        let instruction = execute_market_change(
+            &self.program_id,
+            &signer.pubkey(),
+            &sysvar::clock::id(),
+            &ctx.serum_program_id,
+            &self.key,
+            self.seeds,
+            market,
+            replaced_open_orders,
+        )
+        .unwrap();
+        wrap_process_transaction(&ctx, vec![instruction], vec![signer]).await
+    }
+
+    pub async fn set_deposit_limits(
+        &self,
+        ctx: &Context,
+        signal_provider: &Keypair,
+        max_pool_token_supply: u64,
+        min_deposit_pool_tokens: u64,
+    ) -> Result<(), TransportError> {
+        let instruction = set_deposit_limits(
+            &self.program_id,
+            &signal_provider.pubkey(),
+            &self.mint_key,
+            &self.key,
+            self.seeds,
+            max_pool_token_supply,
+            min_deposit_pool_tokens,
+        )
+        .unwrap();
+        wrap_process_transaction(&ctx, vec![instruction], vec![signal_provider]).await
+    }
+
+    // The accounts allowing the owner to buy into a private pool
+    async fn get_allow_list_keys(&self, ctx: &Context, owner: &Pubkey) -> Vec<Pubkey> {
+        if !self.get_header(ctx).await.is_private {
+            return vec![];
+        }
+        let (whitelist_key, _) = get_whitelist_key(&self.program_id, &self.seeds);
+        let data = ctx
+            .test_state
+            .banks_client
+            .to_owned()
+            .get_account(whitelist_key)
+            .await
+            .unwrap()
+            .unwrap()
+            .data;
+        let whitelist = Whitelist::unpack(&data).unwrap();
+        let mut allow_list_keys = vec![whitelist_key];
+        if whitelist.gating_mint != Pubkey::default() {
+            allow_list_keys.push(get_associated_token_address(owner, &whitelist.gating_mint));
+        }
+        allow_list_keys
+    }
+
+    pub async fn set_whitelist(
+        &self,
+        ctx: &Context,
+        signal_provider: &Keypair,
+        gating_mint: Pubkey,
+        owners: Vec<Pubkey>,
+    ) -> Result<(), TransportError> {
+        let instruction = set_whitelist(
+            &system_program::id(),
+            &sysvar::rent::id(),
+            &self.program_id,
+            &ctx.test_state.payer.pubkey(),
+            &signal_provider.pubkey(),
+            &self.key,
+            self.seeds,
+            gating_mint,
+            owners,
+        )
+        .unwrap();
+        wrap_process_transaction(&ctx, vec![instruction], vec![signal_provider]).await
+    }
+
+    pub async fn set_metadata(
+        &self,
+        ctx: &Context,
+        signal_provider: &Keypair,
+        name: &str,
+        description: &str,
+        uri: &str,
+    ) -> Result<(), TransportError> {
+        let instruction = set_metadata(
+            &system_program::id(),
+            &sysvar::rent::id(),
+            &sysvar::clock::id(),
+            &self.program_id,
+            &ctx.test_state.payer.pubkey(),
+            &signal_provider.pubkey(),
+            &self.key,
+            self.seeds,
+            name.to_string(),
+            description.to_string(),
+            uri.to_string(),
+        )
+        .unwrap();
+        wrap_process_transaction(&ctx, vec![instruction], vec![signal_provider]).await
+    }
+
+    pub async fn get_registry(&self, ctx: &Context, signal_provider: &Pubkey) -> PoolRegistry {
+        let (registry_key, _) = get_registry_key(&self.program_id, signal_provider);
+        let data = ctx
+            .test_state
+            .banks_client
+            .to_owned()
+            .get_account(registry_key)
+            .await
+            .unwrap()
+            .unwrap()
+            .data;
+        PoolRegistry::unpack(&data).unwrap()
+    }
+
+    pub async fn get_metadata(&self, ctx: &Context) -> PoolMetadata {
+        let (metadata_key, _) = get_metadata_key(&self.program_id, &self.seeds);
+        let data = ctx
+            .test_state
+            .banks_client
+            .to_owned()
+            .get_account(metadata_key)
+            .await
+            .unwrap()
+            .unwrap()
+            .data;
+        PoolMetadata::unpack(&data).unwrap()
+    }
+
+    pub async fn remove_whitelist(
+        &self,
+        ctx: &Context,
+        signal_provider: &Keypair,
+    ) -> Result<(), TransportError> {
+        let instruction =
+            remove_whitelist(&self.program_id, &signal_provider.pubkey(), &self.key, self.seeds)
+                .unwrap();
+        wrap_process_transaction(&ctx, vec![instruction], vec![signal_provider]).await
+    }
+
+    // The accounts escrowing a deposit, which are only passed when the pool has a lockup period
+    async fn get_lockup_accounts<'a>(
+        &self,
+        ctx: &Context,
+        lockup_account_keys: &'a [Pubkey; 4],
+    ) -> Option<(&'a Pubkey, &'a Pubkey, &'a Pubkey, &'a Pubkey)> {
+        if self.get_header(ctx).await.lockup_period == 0 {
+            return None;
+        }
+        Some((
+            &lockup_account_keys[0],
+            &lockup_account_keys[1],
+            &lockup_account_keys[2],
+            &lockup_account_keys[3],
+        ))
+    }
+
+    // Releases the pooltokens escrowed for the owner's deposits
+    pub async fn release_deposit(
+        &self,
+        ctx: &Context,
+        owner: &Keypair,
+        pooltoken_target_key: &Pubkey,
+    ) -> Result<(), TransportError> {
+        let release_instruction = release_deposit(
+            &spl_token::id(),
+            &sysvar::clock::id(),
+            &self.program_id,
+            &self.mint_key,
+            &self.key,
+            &owner.pubkey(),
+            pooltoken_target_key,
+            self.seeds,
+        )
+        .unwrap();
+        wrap_process_transaction(&ctx, vec![release_instruction], vec![&owner]).await
+    }
+
+    // The accounts receiving the redeem fee, which are only passed when the pool charges one
+    async fn get_redeem_fee_accounts<'a>(
+        &self,
+        ctx: &'a Context,
+        signal_provider_pt_key: &'a Pubkey,
+    ) -> Option<(&'a Pubkey, &'a Pubkey, &'a Vec<Pubkey>)> {
+        if self.get_header(ctx).await.redeem_fee_ratio == 0 {
+            return None;
+        }
+        Some((signal_provider_pt_key, &ctx.config_key, &ctx.fee_recipients))
+    }
+
+    // Closes the drained pool, along with its associated asset accounts
+    pub async fn close_pool(
+        &self,
+        ctx: &Context,
+        markets: Vec<Pubkey>,
+    ) -> Result<(), TransportError> {
+        // Closed pools are drained, their header has an uninitialized status
+        let data = ctx
+            .test_state
+            .banks_client
+            .to_owned()
+            .get_account(self.key)
+            .await
+            .unwrap()
+            .unwrap()
+            .data;
+        let signal_provider = PoolHeader::unpack_unchecked(&data[..PoolHeader::LEN])
+            .unwrap()
+            .signal_provider;
+        let instruction = close_pool(
+            &spl_token::id(),
+            &sysvar::rent::id(),
+            &ctx.serum_program_id,
+            &self.program_id,
+            &self.key,
+            &self.mint_key,
+            &ctx.test_state.payer.pubkey(),
+            &signal_provider,
+            self.seeds,
+            &markets,
+            &self.mints.iter().map(|m| m.pool_asset_key).collect(),
+        )
+        .unwrap();
+        wrap_process_transaction(&ctx, vec![instruction], vec![]).await
+    }
+
+    pub async fn resize_pool(
+        &self,
+        ctx: &Context,
+        max_number_of_assets: u32,
+    ) -> Result<(), TransportError> {
+        let instruction = resize_pool(
+            &system_program::id(),
+            &sysvar::rent::id(),
+            &self.program_id,
+            &ctx.test_state.payer.pubkey(),
+            &self.key,
+            self.seeds,
+            max_number_of_assets,
+        )
+        .unwrap();
+        wrap_process_transaction(&ctx, vec![instruction], vec![]).await
+    }
+
+    pub async fn migrate_pool(
+        &self,
+        ctx: &Context,
+        payer: &Keypair,
+        markets: Vec<Pubkey>,
+    ) -> Result<(), TransportError> {
+        let instruction = migrate_pool(
+            &system_program::id(),
+            &sysvar::rent::id(),
+            &self.program_id,
+            &payer.pubkey(),
+            &self.key,
+            self.seeds,
+            &markets,
+        )
+        .unwrap();
+        wrap_process_transaction(&ctx, vec![instruction], vec![payer]).await
+    }
+
+    pub async fn get_header(&self, ctx: &Context) -> PoolHeader {
+        let data = ctx
+            .test_state
+            .banks_client
+            .to_owned()
+            .get_account(self.key)
+            .await
+            .unwrap()
+            .unwrap()
+            .data;
+        PoolHeader::unpack(&data[..PoolHeader::LEN]).unwrap()
+    }
+
+    pub async fn get_pool_token_supply(&self, ctx: &Context) -> u64 {
+        let data = ctx
+            .test_state
+            .banks_client
+            .to_owned()
+            .get_account(self.mint_key)
+            .await
+            .unwrap()
+            .unwrap()
+            .data;
+        Mint::unpack(&data).unwrap().supply
+    }
+}
+
+fn get_swap_account_keys(swaps: &[(&SerumMarket, &Pubkey, &Pubkey)]) -> Vec<Pubkey> {
+    let mut swap_account_keys = Vec::with_capacity(10 * swaps.len());
+    for (serum_market, open_orders_key, coin_account_key) in swaps {
+        swap_account_keys.extend(vec![
+            serum_market.market_key.pubkey(),
+            **open_orders_key,
+            serum_market.req_q_key.pubkey(),
+            serum_market.event_q_key.pubkey(),
+            serum_market.bids_key.pubkey(),
+            serum_market.asks_key.pubkey(),
+            serum_market.coin_vault,
+            serum_market.pc_vault,
+            serum_market.vault_signer_pk,
+            **coin_account_key,
+        ]);
+    }
+    swap_account_keys
 }
 
 pub struct TestMint {
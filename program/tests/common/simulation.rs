@@ -3,7 +3,7 @@ use std::num::{NonZeroU16, NonZeroU64};
 #[cfg(feature = "fuzz")]
 use arbitrary::{Arbitrary, Unstructured};
 
-use serum_dex::matching::Side;
+use serum_dex::matching::{OrderType, Side};
 use solana_program::{instruction::InstructionError, program_pack::Pack, pubkey::Pubkey};
 use solana_sdk::{
     signature::{Keypair, Signer},
@@ -255,6 +255,13 @@ impl Universe {
                 &self.serum_market.as_ref().unwrap().market_key.pubkey(),
                 604800,
                 15,
+                86400,
+                604800,
+                0,
+                0,
+                0,
+                0,
+                0,
             )
             .await?;
         self.pool_token_supply = 1_000_000;
@@ -274,7 +281,7 @@ impl Universe {
             cancel_after,
         } = turn.signal_intention
         {
-            let order = self.pool.initialize_new_order(ctx).await?;
+            let order = self.pool.initialize_new_order(ctx, 0).await?;
             let (source_asset_index, target_asset_index) = match side {
                 Side::Bid => (1, 2),
                 Side::Ask => (2, 1),
@@ -290,6 +297,7 @@ impl Universe {
                     side,
                     limit_price,
                     max_qty,
+                    OrderType::ImmediateOrCancel,
                 )
                 .await;
             self.known_accounts.push(order.open_orders_account);
@@ -426,6 +434,13 @@ impl Universe {
                                     &self.serum_market.as_ref().unwrap().market_key.pubkey(),
                                     700_000,
                                     15,
+                                    86400,
+                                    604800,
+                                    0,
+                                    0,
+                                    0,
+                                    0,
+                                    0,
                                 )
                                 .await;
                             result_err_filter(result)?;
@@ -479,7 +494,8 @@ impl Universe {
                                 &order, 
                                 side, 
                                 NonZeroU64::new((((seed >> 19) & 0x3f) << 4) as u64 + 1).unwrap(), 
-                                NonZeroU16::new((((seed >> 25) & 0x3f) << 4) as u16 + 1).unwrap()
+                                NonZeroU16::new((((seed >> 25) & 0x3f) << 4) as u16 + 1).unwrap(),
+                                OrderType::ImmediateOrCancel,
                             ).await;
                             result_err_filter(result)?;
                         }
@@ -503,7 +519,8 @@ impl Universe {
                             // let result = self.pool.cancel_order(
                             //     ctx, 
                             //     self.serum_market.as_ref().unwrap(), 
-                            //     &order
+                            //     &order,
+                            //     &actor.key,
                             // ).await;
                             // result_err_filter(result)?;
                         }
@@ -538,7 +555,7 @@ impl Universe {
                             result_err_filter(result)?;
                         }
                         6 => {
-                            let result = self.pool.collect_fees(ctx).await;
+                            let result = self.pool.collect_fees(ctx, &[]).await;
                             result_err_filter(result)?;
                         }
                         7 => {}
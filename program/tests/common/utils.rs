@@ -1,17 +1,25 @@
-use std::{convert::TryInto, num::NonZeroU8, str::FromStr};
+use std::{cell::RefCell, convert::TryInto, num::NonZeroU8, str::FromStr};
 
 #[cfg(feature = "fuzz")]
 use arbitrary::Unstructured;
 
 #[cfg(not(feature = "fuzz"))]
 use bonfida_bot::{
-    state::{unpack_assets, PoolHeader},
+    event::PoolEvent,
+    instruction::create_config,
+    state::{get_assets_end, unpack_assets, FeeRecipient, PoolHeader},
+    utils::get_config_key,
 };
 
 #[cfg(feature = "fuzz")]
-use crate::state::{unpack_assets, PoolHeader};
+use crate::{
+    event::PoolEvent,
+    instruction::create_config,
+    state::{get_assets_end, unpack_assets, FeeRecipient, PoolHeader},
+    utils::get_config_key,
+};
 
-use solana_program::{instruction::{Instruction, InstructionError}, program_error::ProgramError, program_option::COption, program_pack::Pack, pubkey::Pubkey, rent::Rent, system_instruction};
+use solana_program::{bpf_loader_upgradeable, clock::Clock, instruction::{Instruction, InstructionError}, program_error::ProgramError, program_option::COption, program_pack::Pack, pubkey::Pubkey, rent::Rent, system_instruction, system_program, sysvar};
 use solana_program_test::{BanksClient, ProgramTest, ProgramTestBanksClientExt, ProgramTestContext, find_file, read_file};
 use solana_sdk::{account::Account, signature::{Keypair, Signer}, transaction::{Transaction, TransactionError}, transport::TransportError};
 use spl_associated_token_account::{create_associated_token_account, get_associated_token_address};
@@ -24,18 +32,28 @@ pub struct Context {
     pub bonfidabot_program_id: Pubkey,
     pub serum_program_id: Pubkey,
     pub test_state: ProgramTestContext,
+    pub config_key: Pubkey,
+    pub fee_recipients: Vec<Pubkey>,
     pub mint_authority: Keypair,
     pub fida_mint: MintInfo,
     pub srm_mint: MintInfo,
     pub pc_mint: MintInfo,
     pub coin_mint: MintInfo,
+    /// Pool events logged by the last transaction sent through wrap_process_transaction
+    pub pool_events: RefCell<Vec<PoolEvent>>,
 }
 
 pub type MintInfo = (Pubkey, Mint);
 
 impl Context {
     pub async fn refresh_blockhash(&mut self){
-        self.test_state.last_blockhash = self.test_state.banks_client.get_new_blockhash(&self.test_state.last_blockhash).await.unwrap().0;
+        self.test_state.last_blockhash = self.test_state.banks_client.get_new_latest_blockhash(&self.test_state.last_blockhash).await.unwrap();
+    }
+
+    pub async fn warp_forward(&mut self, seconds: i64) {
+        let mut clock: Clock = self.test_state.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp += seconds;
+        self.test_state.set_sysvar(&clock);
     }
 
     pub async fn init() -> Context {
@@ -60,6 +78,27 @@ impl Context {
                 ..Account::default()
             },
         );
+        // Fake the program data account so that the config can be created
+        let upgrade_authority = Keypair::new();
+        let (program_data_key, _) = Pubkey::find_program_address(
+            &[&bonfidabot_program_id.to_bytes()],
+            &bpf_loader_upgradeable::id(),
+        );
+        let mut program_data = vec![0; 45];
+        program_data[0] = 3;
+        program_data[12] = 1;
+        program_data[13..45].copy_from_slice(&upgrade_authority.pubkey().to_bytes());
+        program_test.add_account(
+            program_data_key,
+            Account {
+                lamports: u32::MAX.into(),
+                data: program_data,
+                owner: bpf_loader_upgradeable::id(),
+                executable: false,
+                ..Account::default()
+            },
+        );
+
         let payer = Keypair::new();
         program_test.add_account(
             payer.pubkey(),
@@ -78,16 +117,44 @@ impl Context {
         let mut test_state = program_test.start_with_context().await;
         test_state.payer = payer;
 
-        Context {
+        let (config_key, _) = get_config_key(&bonfidabot_program_id);
+        let fee_recipients = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let ctx = Context {
             bonfidabot_program_id,
             serum_program_id,
             test_state,
+            config_key,
+            fee_recipients,
             mint_authority,
             fida_mint,
             srm_mint,
             pc_mint,
             coin_mint,
-        }
+            pool_events: RefCell::new(vec![]),
+        };
+
+        // Half of the fees go to the signal provider, the rest is split between the recipients
+        let create_config_instruction = create_config(
+            &system_program::id(),
+            &sysvar::rent::id(),
+            &bonfidabot_program_id,
+            &config_key,
+            &program_data_key,
+            &upgrade_authority.pubkey(),
+            &ctx.test_state.payer.pubkey(),
+            2,
+            ctx.fee_recipients
+                .iter()
+                .map(|owner| FeeRecipient { owner: *owner, share: 1 })
+                .collect(),
+        )
+        .unwrap();
+        wrap_process_transaction(&ctx, vec![create_config_instruction], vec![&upgrade_authority])
+            .await
+            .unwrap();
+
+        ctx
     }
 
     pub fn get_mints(&self) -> Vec<MintInfo> {
@@ -193,8 +260,9 @@ pub async fn print_pool_data(
         .unwrap()
         .data;
     let pool_header = PoolHeader::unpack(&data[..PoolHeader::LEN]).unwrap();
-    let pool_asset_offset = PoolHeader::LEN + 32 * (pool_header.number_of_markets as usize);
-    let pool_assets = unpack_assets(&data[pool_asset_offset..])?;
+    let pool_asset_offset = pool_header.asset_offset as usize;
+    let pool_assets_end = get_assets_end(&pool_header);
+    let pool_assets = unpack_assets(&data[pool_asset_offset..pool_assets_end])?;
     for asset in pool_assets {
         print!("{:?}", asset);
         let pool_asset_key = get_associated_token_address(&pool_key, &asset.mint_address);
@@ -231,10 +299,24 @@ pub async fn wrap_process_transaction(
     let mut setup_transaction = Transaction::new_with_payer(&instructions, Some(&ctx.test_state.payer.pubkey()));
     &signers.push(&ctx.test_state.payer);
     setup_transaction.partial_sign(&signers, ctx.test_state.last_blockhash);
-    ctx.test_state.banks_client
-        .to_owned()
+    let mut banks_client = ctx.test_state.banks_client.to_owned();
+    let simulation = banks_client
+        .simulate_transaction(setup_transaction.clone())
+        .await?;
+    *ctx.pool_events.borrow_mut() = simulation
+        .simulation_details
+        .map(|details| {
+            details
+                .logs
+                .iter()
+                .filter_map(|log| PoolEvent::from_log(log))
+                .collect()
+        })
+        .unwrap_or_default();
+    banks_client
         .process_transaction(setup_transaction)
         .await
+        .map_err(|e| e.into())
 }
 
 pub fn add_token_account(
@@ -1,27 +1,50 @@
 #![cfg(feature = "test-bpf")]
-use serum_dex::{instruction::SelfTradeBehavior, matching::Side};
-use solana_program::{program_pack::Pack, pubkey::Pubkey};
+use serum_dex::{
+    instruction::SelfTradeBehavior,
+    matching::{OrderType, Side},
+};
+use solana_program::{
+    entrypoint::MAX_PERMITTED_DATA_INCREASE, program_pack::Pack, pubkey::Pubkey,
+    system_instruction, system_program, sysvar,
+};
 
-use solana_sdk::{signature::Keypair, signature::Signer};
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    instruction::InstructionError,
+    signature::Keypair,
+    signature::Signer,
+    transaction::TransactionError,
+};
 
 use spl_token;
 use std::{
     convert::TryInto,
-    num::{NonZeroU16, NonZeroU64},
+    num::{NonZeroU16, NonZeroU64, NonZeroU8},
     str::FromStr,
 };
 
+use bonfida_bot::{
+    event::{EventKind, PoolEvent},
+    instruction::init_open_orders,
+    state::{
+        get_asset_offset, get_assets_end, unpack_open_orders, unpack_pool_market, PoolAsset,
+        PoolHeader, PoolStatus, MAX_LOCKUP_PERIOD, MIGRATED_POOL_MAX_ORDER_LIFETIME,
+        MIGRATED_POOL_TIMELOCK_PERIOD, POOL_HEADER_V0_LEN, POOL_VERSION,
+    },
+    utils::{get_deposit_receipt_key, get_metadata_key, get_redeem_queue_key, get_whitelist_key},
+};
+
 mod common;
 
 use common::{
     simulation::Actor,
     utils::{
         clone_keypair,
-        print_pool_data, Context, OpenOrderView,
+        print_pool_data, wrap_process_transaction, Context, OpenOrderView,
     },
 };
 
-use common::pool::TestPool;
+use common::pool::{Order, TestPool};
 
 use common::market::SerumMarket;
 
@@ -98,7 +121,14 @@ async fn test_bonfida_bot() {
         deposit_amounts,
         &serum_market.market_key.pubkey(),
         604800,
-        100
+        100,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
     )
     .await
     .unwrap();
@@ -121,7 +151,7 @@ async fn test_bonfida_bot() {
         .await
         .unwrap();
 
-    let order = pool.initialize_new_order(&ctx).await.unwrap();
+    let order = pool.initialize_new_order(&ctx, 0).await.unwrap();
 
     // Execute a CreateOrder instruction
     pool.create_new_order(
@@ -133,6 +163,7 @@ async fn test_bonfida_bot() {
         Side::Bid,
         NonZeroU64::new(1).unwrap(),
         NonZeroU16::new(1 << 14).unwrap(),
+        OrderType::ImmediateOrCancel,
     )
     .await
     .unwrap();
@@ -196,8 +227,8 @@ async fn test_bonfida_bot() {
     ctx.refresh_blockhash().await;
 
     // Execute a Cancel order instruction on the original, partially settled, order
-    // For now cancelling cannot work since we are forced to use IOC orders
-    // pool.cancel_order(&ctx, &serum_market, &order)
+    // For now cancelling cannot work since the order is an IOC order
+    // pool.cancel_order(&ctx, &serum_market, &order, &pool.signal_provider)
     //     .await
     //     .unwrap();
 
@@ -229,9 +260,3082 @@ async fn test_bonfida_bot() {
     .await
     .unwrap();
 
-    // pool.collect_fees(&ctx).await.unwrap();
+    // pool.collect_fees(&ctx, &[]).await.unwrap();
 
     print_pool_data(&pool.key, &ctx.test_state.banks_client)
         .await
         .unwrap();
 }
+
+#[tokio::test]
+async fn test_lock_unlock() {
+    let mut ctx = Context::init().await;
+    let mints = ctx.get_mints();
+
+    let mut pool = TestPool::new(&ctx);
+
+    for mint_info in &mints {
+        pool.add_mint(None, mint_info)
+    }
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    source_actor.asset_accounts = pool
+        .get_funded_token_accounts(&ctx, &source_actor.key.pubkey())
+        .await;
+    source_actor.pool_token_account =
+        Some(pool.get_pt_account(&ctx, &source_actor.key.pubkey()).await);
+    pool.get_pt_account(&ctx, &pool.signal_provider.pubkey())
+        .await;
+
+    let pc_mint = pool.mints[2].key;
+    let coin_mint = pool.mints[3].key;
+
+    let serum_market = SerumMarket::initialize_market_accounts(&ctx, &coin_mint, &pc_mint)
+        .await
+        .unwrap();
+
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![3_238_385, 4_000_000, 1_000_001, 20_000_000],
+        &serum_market.market_key.pubkey(),
+        604800,
+        100,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+
+    // Only the signal provider can lock the pool
+    assert!(pool.lock(&ctx, &source_actor.key).await.is_err());
+
+    // An unlocked pool cannot be unlocked
+    assert!(pool.unlock(&ctx).await.is_err());
+
+    // Unlocked -> Locked
+    pool.lock(&ctx, &pool.signal_provider).await.unwrap();
+    assert_eq!(pool.get_header(&ctx).await.status, PoolStatus::Locked);
+
+    ctx.refresh_blockhash().await;
+    assert!(pool.lock(&ctx, &pool.signal_provider).await.is_err());
+
+    // No buy-ins or buy-outs while locked
+    assert!(pool
+        .deposit(
+            &ctx,
+            5000,
+            &source_actor.pool_token_account.as_ref().unwrap(),
+            &source_actor.key,
+            &source_actor.asset_accounts,
+        )
+        .await
+        .is_err());
+    assert!(pool
+        .redeem(
+            &ctx,
+            100,
+            &source_actor.key,
+            source_actor.pool_token_account.as_ref().unwrap(),
+            &source_actor.asset_accounts,
+        )
+        .await
+        .is_err());
+
+    // Locked -> Unlocked, by anyone
+    pool.unlock(&ctx).await.unwrap();
+    assert_eq!(pool.get_header(&ctx).await.status, PoolStatus::Unlocked);
+
+    pool.deposit(
+        &ctx,
+        5000,
+        &source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+    )
+    .await
+    .unwrap();
+
+    // Unlocked -> PendingOrder(1)
+    let order = pool.initialize_new_order(&ctx, 0).await.unwrap();
+    pool.create_new_order(
+        &mut ctx,
+        &serum_market,
+        2,
+        3,
+        &order,
+        Side::Bid,
+        NonZeroU64::new(1).unwrap(),
+        NonZeroU16::new(1 << 14).unwrap(),
+        OrderType::ImmediateOrCancel,
+    )
+    .await
+    .unwrap();
+    let one = NonZeroU8::new(1).unwrap();
+    assert_eq!(pool.get_header(&ctx).await.status, PoolStatus::PendingOrder(one));
+
+    // PendingOrder(1) -> LockedPendingOrder(1)
+    ctx.refresh_blockhash().await;
+    pool.lock(&ctx, &pool.signal_provider).await.unwrap();
+    assert_eq!(
+        pool.get_header(&ctx).await.status,
+        PoolStatus::LockedPendingOrder(one)
+    );
+
+    // LockedPendingOrder(1) -> PendingOrder(1)
+    ctx.refresh_blockhash().await;
+    pool.unlock(&ctx).await.unwrap();
+    assert_eq!(pool.get_header(&ctx).await.status, PoolStatus::PendingOrder(one));
+
+    ctx.refresh_blockhash().await;
+    pool.lock(&ctx, &pool.signal_provider).await.unwrap();
+
+    // Settling the last pending order keeps the pool locked: LockedPendingOrder(1) -> Locked
+    let matching_amount_token = spl_token::state::Account::unpack(
+        &ctx.test_state
+            .banks_client
+            .get_account(pool.mints[1].pool_asset_key)
+            .await
+            .unwrap()
+            .unwrap()
+            .data,
+    )
+    .unwrap()
+    .amount;
+    let lots_to_trade =
+        serum_market.coin_lot_size * matching_amount_token / (serum_market.pc_lot_size * 1);
+    serum_market
+        .match_and_crank_order(
+            &ctx,
+            Side::Bid,
+            NonZeroU64::new(2).unwrap(),
+            NonZeroU64::new(lots_to_trade).unwrap(),
+            0,
+            SelfTradeBehavior::DecrementTake,
+            &ctx.mint_authority,
+            &order.open_orders_account,
+        )
+        .await;
+    pool.settle(&ctx, &serum_market, 3, 2, &order)
+        .await
+        .unwrap();
+    assert_eq!(pool.get_header(&ctx).await.status, PoolStatus::Locked);
+
+    ctx.refresh_blockhash().await;
+    pool.unlock(&ctx).await.unwrap();
+    assert_eq!(pool.get_header(&ctx).await.status, PoolStatus::Unlocked);
+
+    pool.redeem(
+        &ctx,
+        100,
+        &source_actor.key,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.asset_accounts,
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_settle_unfilled_order() {
+    let mut ctx = Context::init().await;
+    let mints = ctx.get_mints();
+
+    let mut pool = TestPool::new(&ctx);
+
+    for mint_info in &mints {
+        pool.add_mint(None, mint_info)
+    }
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    source_actor.asset_accounts = pool
+        .get_funded_token_accounts(&ctx, &source_actor.key.pubkey())
+        .await;
+    source_actor.pool_token_account =
+        Some(pool.get_pt_account(&ctx, &source_actor.key.pubkey()).await);
+    pool.get_pt_account(&ctx, &pool.signal_provider.pubkey())
+        .await;
+
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[3].key, &pool.mints[2].key)
+            .await
+            .unwrap();
+
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![3_238_385, 4_000_000, 1_000_001, 20_000_000],
+        &serum_market.market_key.pubkey(),
+        604800,
+        100,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+
+    // An IOC order on an empty book is never filled
+    let balances_before = get_pool_asset_balances(&ctx, &pool).await;
+    let order = pool.initialize_new_order(&ctx, 0).await.unwrap();
+    pool.create_new_order(
+        &mut ctx,
+        &serum_market,
+        2,
+        3,
+        &order,
+        Side::Bid,
+        NonZeroU64::new(1).unwrap(),
+        NonZeroU16::new(1 << 14).unwrap(),
+        OrderType::ImmediateOrCancel,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        pool.get_header(&ctx).await.status,
+        PoolStatus::PendingOrder(NonZeroU8::new(1).unwrap())
+    );
+
+    // Settling it releases the pool even when there is nothing left to transfer
+    pool.settle(&ctx, &serum_market, 3, 2, &order)
+        .await
+        .unwrap();
+    assert_eq!(pool.get_header(&ctx).await.status, PoolStatus::Unlocked);
+    assert_eq!(get_pool_asset_balances(&ctx, &pool).await, balances_before);
+
+    // The order is no longer pending, so there is nothing more to settle
+    ctx.refresh_blockhash().await;
+    assert!(pool
+        .settle(&ctx, &serum_market, 3, 2, &order)
+        .await
+        .is_err());
+
+    pool.deposit(
+        &ctx,
+        5000,
+        &source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_resting_orders() {
+    let mut ctx = Context::init().await;
+    let mints = ctx.get_mints();
+
+    let mut pool = TestPool::new(&ctx);
+
+    for mint_info in &mints {
+        pool.add_mint(None, mint_info)
+    }
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    source_actor.asset_accounts = pool
+        .get_funded_token_accounts(&ctx, &source_actor.key.pubkey())
+        .await;
+    source_actor.pool_token_account =
+        Some(pool.get_pt_account(&ctx, &source_actor.key.pubkey()).await);
+
+    let pc_mint = pool.mints[2].key;
+    let coin_mint = pool.mints[3].key;
+
+    let serum_market = SerumMarket::initialize_market_accounts(&ctx, &coin_mint, &pc_mint)
+        .await
+        .unwrap();
+
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![3_238_385, 4_000_000, 1_000_001, 20_000_000],
+        &serum_market.market_key.pubkey(),
+        604800,
+        100,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+
+    // A limit order on an empty book rests on Serum
+    let order = pool.initialize_new_order(&ctx, 0).await.unwrap();
+    // The deprecated instruction's lot sizes have to match the market's
+    assert!(pool
+        .create_legacy_order(
+            &ctx,
+            &serum_market,
+            2,
+            3,
+            &order,
+            Side::Bid,
+            NonZeroU64::new(1).unwrap(),
+            NonZeroU16::new(1 << 14).unwrap(),
+            serum_market.coin_lot_size,
+            serum_market.pc_lot_size * 10,
+        )
+        .await
+        .is_err());
+
+    pool.create_new_order(
+        &mut ctx,
+        &serum_market,
+        2,
+        3,
+        &order,
+        Side::Bid,
+        NonZeroU64::new(1).unwrap(),
+        NonZeroU16::new(1 << 14).unwrap(),
+        OrderType::Limit,
+    )
+    .await
+    .unwrap();
+    let pool_header = pool.get_header(&ctx).await;
+    assert_eq!(
+        pool_header.status,
+        PoolStatus::PendingOrder(NonZeroU8::new(1).unwrap())
+    );
+    assert_ne!(pool_header.first_pending_order_timestamp, 0);
+
+    let openorder_view =
+        OpenOrderView::get(order.open_orders_account, &ctx.test_state.banks_client)
+            .await
+            .unwrap();
+    assert_ne!(openorder_view.native_pc_free, openorder_view.native_pc_total);
+
+    // The pool's OpenOrders accounts are needed to redeem while orders are pending
+    assert!(pool
+        .redeem(
+            &ctx,
+            100,
+            &source_actor.key,
+            source_actor.pool_token_account.as_ref().unwrap(),
+            &source_actor.asset_accounts,
+        )
+        .await
+        .is_err());
+
+    // The order hasn't expired yet, only the signal provider can cancel it
+    assert!(pool
+        .cancel_order(&ctx, &serum_market, &order, &source_actor.key)
+        .await
+        .is_err());
+    pool.cancel_order(&ctx, &serum_market, &order, &pool.signal_provider)
+        .await
+        .unwrap();
+
+    pool.settle(&ctx, &serum_market, 3, 2, &order)
+        .await
+        .unwrap();
+    assert_eq!(pool.get_header(&ctx).await.status, PoolStatus::Unlocked);
+
+    pool.redeem(
+        &ctx,
+        100,
+        &source_actor.key,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.asset_accounts,
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_open_orders_registry() {
+    let mut ctx = Context::init().await;
+    let mints = ctx.get_mints();
+
+    let mut pool = TestPool::new(&ctx);
+
+    for mint_info in &mints {
+        pool.add_mint(None, mint_info)
+    }
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    source_actor.asset_accounts = pool
+        .get_funded_token_accounts(&ctx, &source_actor.key.pubkey())
+        .await;
+    source_actor.pool_token_account =
+        Some(pool.get_pt_account(&ctx, &source_actor.key.pubkey()).await);
+
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[3].key, &pool.mints[2].key)
+            .await
+            .unwrap();
+
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![3_238_385, 4_000_000, 1_000_001, 20_000_000],
+        &serum_market.market_key.pubkey(),
+        604800,
+        100,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+
+    // Orders can only be placed from the pool's registered OpenOrders account
+    let (foreign_open_order, create_open_order_instruction) =
+        SerumMarket::create_dex_account(&ctx, 3216).unwrap();
+    wrap_process_transaction(
+        &ctx,
+        vec![create_open_order_instruction],
+        vec![&foreign_open_order],
+    )
+    .await
+    .unwrap();
+    let result = pool
+        .create_new_order(
+            &mut ctx,
+            &serum_market,
+            2,
+            3,
+            &Order {
+                open_orders_account: foreign_open_order.pubkey(),
+            },
+            Side::Bid,
+            NonZeroU64::new(1).unwrap(),
+            NonZeroU16::new(1 << 14).unwrap(),
+            OrderType::Limit,
+        )
+        .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+
+    let order = pool.initialize_new_order(&ctx, 0).await.unwrap();
+    let init_open_orders_instruction = init_open_orders(
+        &system_program::id(),
+        &sysvar::rent::id(),
+        &ctx.bonfidabot_program_id,
+        &ctx.test_state.payer.pubkey(),
+        &pool.key,
+        pool.seeds,
+        0,
+    )
+    .unwrap();
+    ctx.refresh_blockhash().await;
+    let result =
+        wrap_process_transaction(&ctx, vec![init_open_orders_instruction], vec![]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+
+    // Several orders from the same OpenOrders account count as a single pending order
+    for limit_price in 1..3 {
+        pool.create_new_order(
+            &mut ctx,
+            &serum_market,
+            2,
+            3,
+            &order,
+            Side::Bid,
+            NonZeroU64::new(limit_price).unwrap(),
+            NonZeroU16::new(1 << 14).unwrap(),
+            OrderType::Limit,
+        )
+        .await
+        .unwrap();
+    }
+    assert_eq!(
+        pool.get_header(&ctx).await.status,
+        PoolStatus::PendingOrder(NonZeroU8::new(1).unwrap())
+    );
+}
+
+#[tokio::test]
+async fn test_cancel_by_client_id_and_cancel_all() {
+    let mut ctx = Context::init().await;
+    let mints = ctx.get_mints();
+
+    let mut pool = TestPool::new(&ctx);
+
+    for mint_info in &mints {
+        pool.add_mint(None, mint_info)
+    }
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    source_actor.asset_accounts = pool
+        .get_funded_token_accounts(&ctx, &source_actor.key.pubkey())
+        .await;
+    source_actor.pool_token_account =
+        Some(pool.get_pt_account(&ctx, &source_actor.key.pubkey()).await);
+
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[3].key, &pool.mints[2].key)
+            .await
+            .unwrap();
+
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![3_238_385, 4_000_000, 1_000_001, 20_000_000],
+        &serum_market.market_key.pubkey(),
+        604800,
+        100,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+
+    // Three resting bids with client ids 1, 2 and 3
+    let order = pool.initialize_new_order(&ctx, 0).await.unwrap();
+    for client_id in 1..4 {
+        pool.create_new_order_with_client_id(
+            &mut ctx,
+            &serum_market,
+            2,
+            3,
+            &order,
+            Side::Bid,
+            NonZeroU64::new(client_id).unwrap(),
+            NonZeroU16::new(1 << 12).unwrap(),
+            OrderType::Limit,
+            client_id,
+        )
+        .await
+        .unwrap();
+    }
+    let locked_pc = |view: &OpenOrderView| view.native_pc_total - view.native_pc_free;
+    let openorder_view =
+        OpenOrderView::get(order.open_orders_account, &ctx.test_state.banks_client)
+            .await
+            .unwrap();
+    let locked_before_cancel = locked_pc(&openorder_view);
+
+    assert!(pool
+        .cancel_order_by_client_id(&ctx, &serum_market, &order, &source_actor.key, 2)
+        .await
+        .is_err());
+    pool.cancel_order_by_client_id(&ctx, &serum_market, &order, &pool.signal_provider, 2)
+        .await
+        .unwrap();
+    let openorder_view =
+        OpenOrderView::get(order.open_orders_account, &ctx.test_state.banks_client)
+            .await
+            .unwrap();
+    assert!(locked_pc(&openorder_view) < locked_before_cancel);
+    assert_ne!(locked_pc(&openorder_view), 0);
+
+    assert!(pool
+        .cancel_all_orders(&ctx, &serum_market, &order, &source_actor.key)
+        .await
+        .is_err());
+    pool.cancel_all_orders(&ctx, &serum_market, &order, &pool.signal_provider)
+        .await
+        .unwrap();
+    let openorder_view =
+        OpenOrderView::get(order.open_orders_account, &ctx.test_state.banks_client)
+            .await
+            .unwrap();
+    assert_eq!(locked_pc(&openorder_view), 0);
+
+    pool.settle(&ctx, &serum_market, 3, 2, &order)
+        .await
+        .unwrap();
+    assert_eq!(pool.get_header(&ctx).await.status, PoolStatus::Unlocked);
+}
+
+#[tokio::test]
+async fn test_settle_and_redeem() {
+    let mut ctx = Context::init().await;
+    let mints = ctx.get_mints();
+
+    let mut pool = TestPool::new(&ctx);
+
+    for mint_info in &mints {
+        pool.add_mint(None, mint_info)
+    }
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    source_actor.asset_accounts = pool
+        .get_funded_token_accounts(&ctx, &source_actor.key.pubkey())
+        .await;
+    source_actor.pool_token_account =
+        Some(pool.get_pt_account(&ctx, &source_actor.key.pubkey()).await);
+
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[3].key, &pool.mints[2].key)
+            .await
+            .unwrap();
+
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![3_238_385, 4_000_000, 1_000_001, 20_000_000],
+        &serum_market.market_key.pubkey(),
+        604800,
+        100,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+
+    // Two resting bids, leaving the pool with a pending order
+    let order = pool.initialize_new_order(&ctx, 0).await.unwrap();
+    for client_id in 1..3 {
+        pool.create_new_order_with_client_id(
+            &mut ctx,
+            &serum_market,
+            2,
+            3,
+            &order,
+            Side::Bid,
+            NonZeroU64::new(client_id).unwrap(),
+            NonZeroU16::new(1 << 12).unwrap(),
+            OrderType::Limit,
+            client_id,
+        )
+        .await
+        .unwrap();
+    }
+    pool.cancel_order_by_client_id(&ctx, &serum_market, &order, &pool.signal_provider, 1)
+        .await
+        .unwrap();
+
+    let pooltoken_source = source_actor.pool_token_account.unwrap();
+    let pc_target = source_actor.asset_accounts[2];
+    let pc_balance_before = get_token_balance(&ctx, pc_target).await;
+
+    // A small share is paid out of the pool asset accounts, so the bid still resting on the
+    // book is left alone
+    pool.settle_and_redeem(
+        &ctx,
+        100_000,
+        &source_actor.key,
+        &pooltoken_source,
+        &source_actor.asset_accounts,
+        &[(&serum_market, &order)],
+    )
+    .await
+    .unwrap();
+
+    let one = NonZeroU8::new(1).unwrap();
+    assert_eq!(
+        pool.get_header(&ctx).await.status,
+        PoolStatus::PendingOrder(one)
+    );
+    let openorder_view =
+        OpenOrderView::get(order.open_orders_account, &ctx.test_state.banks_client)
+            .await
+            .unwrap();
+    assert_ne!(openorder_view.native_pc_total, 0);
+    assert!(get_token_balance(&ctx, pc_target).await > pc_balance_before);
+    assert_eq!(get_token_balance(&ctx, pooltoken_source).await, 900_000);
+
+    // Draining the pool cancels and settles the bid without a separate CancelAllOrders or
+    // SettleFunds, and keeps track of its OpenOrders account, which still exists
+    ctx.refresh_blockhash().await;
+    pool.settle_and_redeem(
+        &ctx,
+        900_000,
+        &source_actor.key,
+        &pooltoken_source,
+        &source_actor.asset_accounts,
+        &[(&serum_market, &order)],
+    )
+    .await
+    .unwrap();
+    let header = pool.get_header(&ctx).await;
+    assert_eq!(header.status, PoolStatus::Uninitialized);
+    let openorder_view =
+        OpenOrderView::get(order.open_orders_account, &ctx.test_state.banks_client)
+            .await
+            .unwrap();
+    assert_eq!(openorder_view.native_pc_total, 0);
+    let pool_data = ctx
+        .test_state
+        .banks_client
+        .to_owned()
+        .get_account(pool.key)
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+    let pool_open_orders =
+        unpack_open_orders(&pool_data[header.open_orders_offset as usize..], 0).unwrap();
+    assert_eq!(pool_open_orders.address, order.open_orders_account);
+}
+
+#[tokio::test]
+async fn test_redeem_with_pending_orders() {
+    let mut ctx = Context::init().await;
+    let mints = ctx.get_mints();
+
+    let mut pool = TestPool::new(&ctx);
+
+    for mint_info in &mints {
+        pool.add_mint(None, mint_info)
+    }
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    source_actor.asset_accounts = pool
+        .get_funded_token_accounts(&ctx, &source_actor.key.pubkey())
+        .await;
+    source_actor.pool_token_account =
+        Some(pool.get_pt_account(&ctx, &source_actor.key.pubkey()).await);
+
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[3].key, &pool.mints[2].key)
+            .await
+            .unwrap();
+
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![3_238_385, 4_000_000, 1_000_001, 20_000_000],
+        &serum_market.market_key.pubkey(),
+        604800,
+        100,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+
+    let order = pool.initialize_new_order(&ctx, 0).await.unwrap();
+    pool.create_new_order(
+        &mut ctx,
+        &serum_market,
+        2,
+        3,
+        &order,
+        Side::Bid,
+        NonZeroU64::new(1).unwrap(),
+        NonZeroU16::new(1 << 14).unwrap(),
+        OrderType::Limit,
+    )
+    .await
+    .unwrap();
+    let one = NonZeroU8::new(1).unwrap();
+    assert_eq!(pool.get_header(&ctx).await.status, PoolStatus::PendingOrder(one));
+
+    let pooltoken_source = source_actor.pool_token_account.unwrap();
+    let pool_pc_key = pool.mints[2].pool_asset_key;
+
+    // A small share is covered by the pool asset accounts, the order keeps resting
+    pool.redeem_with_pending_orders(
+        &ctx,
+        100,
+        &source_actor.key,
+        &pooltoken_source,
+        &source_actor.asset_accounts,
+        vec![0; 4],
+        &[(&serum_market, &order)],
+    )
+    .await
+    .unwrap();
+    assert_eq!(pool.get_header(&ctx).await.status, PoolStatus::PendingOrder(one));
+    let openorder_view =
+        OpenOrderView::get(order.open_orders_account, &ctx.test_state.banks_client)
+            .await
+            .unwrap();
+    assert_ne!(openorder_view.native_pc_total, 0);
+
+    // Redeeming everything includes the funds locked in the order, which gets cancelled
+    pool.redeem_with_pending_orders(
+        &ctx,
+        999_900,
+        &source_actor.key,
+        &pooltoken_source,
+        &source_actor.asset_accounts,
+        vec![0; 4],
+        &[(&serum_market, &order)],
+    )
+    .await
+    .unwrap();
+    assert_eq!(pool.get_header(&ctx).await.status, PoolStatus::Uninitialized);
+    let openorder_view =
+        OpenOrderView::get(order.open_orders_account, &ctx.test_state.banks_client)
+            .await
+            .unwrap();
+    assert_eq!(openorder_view.native_pc_total, 0);
+    assert_eq!(get_token_balance(&ctx, pool_pc_key).await, 0);
+    assert_eq!(get_token_balance(&ctx, pooltoken_source).await, 0);
+}
+
+#[tokio::test]
+async fn test_redeem_queue() {
+    let mut ctx = Context::init().await;
+    let mints = ctx.get_mints();
+
+    let mut pool = TestPool::new(&ctx);
+
+    for mint_info in &mints {
+        pool.add_mint(None, mint_info)
+    }
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    source_actor.asset_accounts = pool
+        .get_funded_token_accounts(&ctx, &source_actor.key.pubkey())
+        .await;
+    source_actor.pool_token_account =
+        Some(pool.get_pt_account(&ctx, &source_actor.key.pubkey()).await);
+
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[3].key, &pool.mints[2].key)
+            .await
+            .unwrap();
+
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![3_238_385, 4_000_000, 1_000_001, 20_000_000],
+        &serum_market.market_key.pubkey(),
+        604800,
+        100,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+
+    // The pool's own pooltoken account escrows the queued requests
+    let escrow_key = pool.get_pt_account(&ctx, &pool.key).await;
+    let pooltoken_source = source_actor.pool_token_account.unwrap();
+    let asset_balance_before = get_token_balance(&ctx, source_actor.asset_accounts[0]).await;
+
+    pool.lock(&ctx, &pool.signal_provider).await.unwrap();
+    pool.request_redeem(&ctx, 100_000, &source_actor.key, &pooltoken_source)
+        .await
+        .unwrap();
+    pool.request_redeem(&ctx, 50_000, &source_actor.key, &pooltoken_source)
+        .await
+        .unwrap();
+    assert_eq!(get_token_balance(&ctx, escrow_key).await, 150_000);
+    assert_eq!(get_token_balance(&ctx, pooltoken_source).await, 850_000);
+
+    // Only the owner can cancel a request
+    assert!(pool
+        .cancel_redeem_request(&ctx, &pool.signal_provider, &pooltoken_source, 1)
+        .await
+        .is_err());
+    pool.cancel_redeem_request(&ctx, &source_actor.key, &pooltoken_source, 1)
+        .await
+        .unwrap();
+    assert_eq!(get_token_balance(&ctx, escrow_key).await, 100_000);
+    assert_eq!(get_token_balance(&ctx, pooltoken_source).await, 900_000);
+
+    // The queue is only processed once the pool is unlocked
+    let result = pool
+        .process_redeem_queue(&ctx, &[source_actor.key.pubkey()])
+        .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(2))
+    );
+    pool.unlock(&ctx).await.unwrap();
+    ctx.refresh_blockhash().await;
+    pool.process_redeem_queue(&ctx, &[source_actor.key.pubkey()])
+        .await
+        .unwrap();
+
+    assert_eq!(get_token_balance(&ctx, escrow_key).await, 0);
+    assert!(get_token_balance(&ctx, source_actor.asset_accounts[0]).await > asset_balance_before);
+
+    // Nothing is left in the queue
+    ctx.refresh_blockhash().await;
+    assert!(pool
+        .process_redeem_queue(&ctx, &[source_actor.key.pubkey()])
+        .await
+        .is_err());
+
+    // A request whose owner has no account for the pool assets is refunded instead of blocking
+    // the requests queued after it
+    let other_owner = Keypair::new();
+    let other_pooltoken_account = pool.get_pt_account(&ctx, &other_owner.pubkey()).await;
+    let transfer_instruction = spl_token::instruction::transfer(
+        &spl_token::id(),
+        &pooltoken_source,
+        &other_pooltoken_account,
+        &source_actor.key.pubkey(),
+        &[],
+        10_000,
+    )
+    .unwrap();
+    wrap_process_transaction(&ctx, vec![transfer_instruction], vec![&source_actor.key])
+        .await
+        .unwrap();
+    pool.request_redeem(&ctx, 10_000, &other_owner, &other_pooltoken_account)
+        .await
+        .unwrap();
+    pool.request_redeem(&ctx, 10_000, &source_actor.key, &pooltoken_source)
+        .await
+        .unwrap();
+    let asset_balance_before = get_token_balance(&ctx, source_actor.asset_accounts[0]).await;
+    ctx.refresh_blockhash().await;
+    pool.process_redeem_queue(&ctx, &[other_owner.pubkey(), source_actor.key.pubkey()])
+        .await
+        .unwrap();
+    assert_eq!(get_token_balance(&ctx, other_pooltoken_account).await, 10_000);
+    assert_eq!(get_token_balance(&ctx, escrow_key).await, 0);
+    assert_eq!(get_token_balance(&ctx, pooltoken_source).await, 880_000);
+    assert!(get_token_balance(&ctx, source_actor.asset_accounts[0]).await > asset_balance_before);
+
+    // A request which would pay out less than its minimum amounts is refunded as well
+    pool.request_redeem_with_bounds(
+        &ctx,
+        10_000,
+        &source_actor.key,
+        &pooltoken_source,
+        vec![1_000_000, 0, 0, 0],
+    )
+    .await
+    .unwrap();
+    assert_eq!(get_token_balance(&ctx, pooltoken_source).await, 870_000);
+    let asset_balance_before = get_token_balance(&ctx, source_actor.asset_accounts[0]).await;
+    ctx.refresh_blockhash().await;
+    pool.process_redeem_queue(&ctx, &[source_actor.key.pubkey()])
+        .await
+        .unwrap();
+    assert_eq!(get_token_balance(&ctx, escrow_key).await, 0);
+    assert_eq!(get_token_balance(&ctx, pooltoken_source).await, 880_000);
+    assert_eq!(
+        get_token_balance(&ctx, source_actor.asset_accounts[0]).await,
+        asset_balance_before
+    );
+
+    // The minimum amounts are given for each pool asset
+    assert!(pool
+        .request_redeem_with_bounds(&ctx, 10_000, &source_actor.key, &pooltoken_source, vec![0])
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_redeem_fee_and_lockup() {
+    let mut ctx = Context::init().await;
+
+    let mut pool = TestPool::new(&ctx);
+    pool.add_mint(None, &ctx.pc_mint);
+    pool.add_mint(None, &ctx.coin_mint);
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+    let mut depositor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    for actor in [&mut source_actor, &mut depositor].iter_mut() {
+        actor.asset_accounts = pool
+            .get_funded_token_accounts(&ctx, &actor.key.pubkey())
+            .await;
+        actor.pool_token_account = Some(pool.get_pt_account(&ctx, &actor.key.pubkey()).await);
+    }
+    let signal_provider_pt_key = pool
+        .get_pt_account(&ctx, &pool.signal_provider.pubkey())
+        .await;
+
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[1].key, &pool.mints[0].key)
+            .await
+            .unwrap();
+
+    // Deposits can't be locked up for more than a year
+    assert!(pool
+        .create(
+            &ctx,
+            source_actor.pool_token_account.as_ref().unwrap(),
+            &source_actor.key,
+            &source_actor.asset_accounts,
+            vec![10_000_000, 10_000_000],
+            &serum_market.market_key.pubkey(),
+            604800,
+            0,
+            86400,
+            604800,
+            0,
+            1 << 12,
+            MAX_LOCKUP_PERIOD + 1,
+            0,
+            0,
+        )
+        .await
+        .is_err());
+
+    // A sixteenth of redeemed pooltokens is taken as fees, deposits are locked up for a day
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![10_000_000, 10_000_000],
+        &serum_market.market_key.pubkey(),
+        604800,
+        0,
+        86400,
+        604800,
+        0,
+        1 << 12,
+        86400,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+
+    // Deposited pooltokens are escrowed by the pool's own pooltoken account
+    let escrow_key = pool.get_pt_account(&ctx, &pool.key).await;
+    let depositor_pt_key = depositor.pool_token_account.unwrap();
+    pool.deposit(
+        &ctx,
+        100_000,
+        &depositor_pt_key,
+        &depositor.key,
+        &depositor.asset_accounts,
+    )
+    .await
+    .unwrap();
+    assert_eq!(get_token_balance(&ctx, escrow_key).await, 100_000);
+    assert_eq!(get_token_balance(&ctx, depositor_pt_key).await, 0);
+
+    let result = pool
+        .release_deposit(&ctx, &depositor, &depositor_pt_key)
+        .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(2))
+    );
+
+    ctx.warp_forward(86401).await;
+    ctx.refresh_blockhash().await;
+    pool.release_deposit(&ctx, &depositor, &depositor_pt_key)
+        .await
+        .unwrap();
+    assert_eq!(get_token_balance(&ctx, escrow_key).await, 0);
+    assert_eq!(get_token_balance(&ctx, depositor_pt_key).await, 100_000);
+    let (receipt_key, _) = get_deposit_receipt_key(
+        &ctx.bonfidabot_program_id,
+        &pool.seeds,
+        &depositor.key.pubkey(),
+    );
+    assert!(ctx
+        .test_state
+        .banks_client
+        .to_owned()
+        .get_account(receipt_key)
+        .await
+        .unwrap()
+        .is_none());
+
+    // Nothing is left to release
+    ctx.refresh_blockhash().await;
+    assert!(pool
+        .release_deposit(&ctx, &depositor, &depositor_pt_key)
+        .await
+        .is_err());
+
+    // The redeem fee is transferred out of the redeemed pooltokens, only the rest is burnt
+    let fee_pt_keys: Vec<Pubkey> = ctx
+        .fee_recipients
+        .iter()
+        .map(|r| spl_associated_token_account::get_associated_token_address(r, &pool.mint_key))
+        .chain(std::iter::once(signal_provider_pt_key))
+        .collect();
+    let pooltoken_source = source_actor.pool_token_account.unwrap();
+    let supply_before = pool.get_pool_token_supply(&ctx).await;
+    pool.redeem(
+        &ctx,
+        160_000,
+        &source_actor.key,
+        &pooltoken_source,
+        &source_actor.asset_accounts,
+    )
+    .await
+    .unwrap();
+
+    let mut fees = 0;
+    for fee_pt_key in fee_pt_keys {
+        fees += get_token_balance(&ctx, fee_pt_key).await;
+    }
+    assert_eq!(fees, 10_000);
+    assert_eq!(get_token_balance(&ctx, pooltoken_source).await, 840_000);
+    assert_eq!(
+        pool.get_pool_token_supply(&ctx).await,
+        supply_before - 150_000
+    );
+}
+
+#[tokio::test]
+async fn test_deposit_limits() {
+    let mut ctx = Context::init().await;
+
+    let mut pool = TestPool::new(&ctx);
+    pool.add_mint(None, &ctx.pc_mint);
+    pool.add_mint(None, &ctx.coin_mint);
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    source_actor.asset_accounts = pool
+        .get_funded_token_accounts(&ctx, &source_actor.key.pubkey())
+        .await;
+    source_actor.pool_token_account =
+        Some(pool.get_pt_account(&ctx, &source_actor.key.pubkey()).await);
+
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[1].key, &pool.mints[0].key)
+            .await
+            .unwrap();
+
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![10_000_000, 10_000_000],
+        &serum_market.market_key.pubkey(),
+        604800,
+        0,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        1_500_000,
+        10_000,
+    )
+    .await
+    .unwrap();
+
+    let pooltoken_target = source_actor.pool_token_account.unwrap();
+    let result = pool
+        .deposit(
+            &ctx,
+            5_000,
+            &pooltoken_target,
+            &source_actor.key,
+            &source_actor.asset_accounts,
+        )
+        .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(11))
+    );
+    let result = pool
+        .deposit(
+            &ctx,
+            600_000,
+            &pooltoken_target,
+            &source_actor.key,
+            &source_actor.asset_accounts,
+        )
+        .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(10))
+    );
+    pool.deposit(
+        &ctx,
+        400_000,
+        &pooltoken_target,
+        &source_actor.key,
+        &source_actor.asset_accounts,
+    )
+    .await
+    .unwrap();
+    assert_eq!(pool.get_pool_token_supply(&ctx).await, 1_400_000);
+
+    // Only the signal provider can update the limits, and not below the current supply
+    assert!(pool
+        .set_deposit_limits(&ctx, &source_actor.key, 0, 0)
+        .await
+        .is_err());
+    assert!(pool
+        .set_deposit_limits(&ctx, &pool.signal_provider, 1_000_000, 0)
+        .await
+        .is_err());
+    pool.set_deposit_limits(&ctx, &pool.signal_provider, 0, 0)
+        .await
+        .unwrap();
+    let header = pool.get_header(&ctx).await;
+    assert_eq!(header.max_pool_token_supply, 0);
+    assert_eq!(header.min_deposit_pool_tokens, 0);
+
+    ctx.refresh_blockhash().await;
+    pool.deposit(
+        &ctx,
+        600_000,
+        &pooltoken_target,
+        &source_actor.key,
+        &source_actor.asset_accounts,
+    )
+    .await
+    .unwrap();
+    assert_eq!(pool.get_pool_token_supply(&ctx).await, 2_000_000);
+}
+
+#[tokio::test]
+async fn test_private_pool() {
+    let mut ctx = Context::init().await;
+
+    let mut pool = TestPool::new(&ctx);
+    pool.add_mint(None, &ctx.pc_mint);
+    pool.add_mint(None, &ctx.coin_mint);
+
+    let mut actors: Vec<Actor> = (0..3)
+        .map(|_| Actor {
+            key: Keypair::new(),
+            asset_accounts: vec![],
+            pool_token_balance: 0,
+            pool_token_account: None,
+            signal_provider: false,
+        })
+        .collect();
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    for actor in actors.iter_mut() {
+        actor.asset_accounts = pool
+            .get_funded_token_accounts(&ctx, &actor.key.pubkey())
+            .await;
+        actor.pool_token_account = Some(pool.get_pt_account(&ctx, &actor.key.pubkey()).await);
+    }
+    let (source_actor, investor, outsider) = (&actors[0], &actors[1], &actors[2]);
+
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[1].key, &pool.mints[0].key)
+            .await
+            .unwrap();
+
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![10_000_000, 10_000_000],
+        &serum_market.market_key.pubkey(),
+        604800,
+        0,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+
+    // Only the signal provider manages the whitelist
+    assert!(pool
+        .set_whitelist(&ctx, &source_actor.key, Pubkey::default(), vec![investor.key.pubkey()])
+        .await
+        .is_err());
+    pool.set_whitelist(
+        &ctx,
+        &pool.signal_provider,
+        Pubkey::default(),
+        vec![investor.key.pubkey()],
+    )
+    .await
+    .unwrap();
+    assert!(pool.get_header(&ctx).await.is_private);
+
+    let result = pool
+        .deposit(
+            &ctx,
+            10_000,
+            outsider.pool_token_account.as_ref().unwrap(),
+            &outsider.key,
+            &outsider.asset_accounts,
+        )
+        .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(12))
+    );
+    pool.deposit(
+        &ctx,
+        10_000,
+        investor.pool_token_account.as_ref().unwrap(),
+        &investor.key,
+        &investor.asset_accounts,
+    )
+    .await
+    .unwrap();
+
+    // Holders of the gating mint are allowed in as well
+    pool.set_whitelist(&ctx, &pool.signal_provider, pool.mints[0].key, vec![])
+        .await
+        .unwrap();
+    ctx.refresh_blockhash().await;
+    pool.deposit(
+        &ctx,
+        10_000,
+        outsider.pool_token_account.as_ref().unwrap(),
+        &outsider.key,
+        &outsider.asset_accounts,
+    )
+    .await
+    .unwrap();
+
+    // Redemptions are never restricted
+    pool.redeem(
+        &ctx,
+        10_000,
+        &investor.key,
+        investor.pool_token_account.as_ref().unwrap(),
+        &investor.asset_accounts,
+    )
+    .await
+    .unwrap();
+
+    pool.remove_whitelist(&ctx, &pool.signal_provider)
+        .await
+        .unwrap();
+    assert!(!pool.get_header(&ctx).await.is_private);
+}
+
+#[tokio::test]
+async fn test_signal_provider_rotation() {
+    let mut ctx = Context::init().await;
+    let mints = ctx.get_mints();
+
+    let mut pool = TestPool::new(&ctx);
+
+    for mint_info in &mints {
+        pool.add_mint(None, mint_info)
+    }
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    source_actor.asset_accounts = pool
+        .get_funded_token_accounts(&ctx, &source_actor.key.pubkey())
+        .await;
+    source_actor.pool_token_account =
+        Some(pool.get_pt_account(&ctx, &source_actor.key.pubkey()).await);
+
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[3].key, &pool.mints[2].key)
+            .await
+            .unwrap();
+
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![3_238_385, 4_000_000, 1_000_001, 20_000_000],
+        &serum_market.market_key.pubkey(),
+        604800,
+        100,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+
+    let new_signal_provider = Keypair::new();
+
+    // Nothing to accept yet
+    assert!(pool
+        .accept_signal_provider(&ctx, &new_signal_provider)
+        .await
+        .is_err());
+
+    // Only the current signal provider can propose a new one
+    assert!(pool
+        .propose_signal_provider(&ctx, &source_actor.key, &source_actor.key.pubkey())
+        .await
+        .is_err());
+
+    pool.propose_signal_provider(&ctx, &pool.signal_provider, &new_signal_provider.pubkey())
+        .await
+        .unwrap();
+    let pool_header = pool.get_header(&ctx).await;
+    assert_eq!(
+        pool_header.pending_signal_provider,
+        new_signal_provider.pubkey()
+    );
+    assert_eq!(pool_header.signal_provider, pool.signal_provider.pubkey());
+
+    // The proposal is timelocked
+    ctx.refresh_blockhash().await;
+    assert!(pool
+        .accept_signal_provider(&ctx, &new_signal_provider)
+        .await
+        .is_err());
+    assert!(pool
+        .accept_signal_provider(&ctx, &source_actor.key)
+        .await
+        .is_err());
+
+    // The proposal can be withdrawn
+    pool.propose_signal_provider(&ctx, &pool.signal_provider, &Pubkey::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        pool.get_header(&ctx).await.pending_signal_provider,
+        Pubkey::default()
+    );
+
+    // The pool moves to the registry of its new signal provider
+    pool.propose_signal_provider(&ctx, &pool.signal_provider, &new_signal_provider.pubkey())
+        .await
+        .unwrap();
+    ctx.warp_forward(604800).await;
+    ctx.refresh_blockhash().await;
+    pool.accept_signal_provider(&ctx, &new_signal_provider)
+        .await
+        .unwrap();
+    assert!(pool
+        .get_registry(&ctx, &pool.signal_provider.pubkey())
+        .await
+        .pool_seeds
+        .is_empty());
+    assert_eq!(
+        pool.get_registry(&ctx, &new_signal_provider.pubkey())
+            .await
+            .pool_seeds,
+        vec![pool.seeds]
+    );
+}
+
+#[tokio::test]
+async fn test_create_unauthorized_market() {
+    let mut ctx = Context::init().await;
+    let mints = ctx.get_mints();
+
+    let mut pool = TestPool::new(&ctx);
+
+    for mint_info in &mints {
+        pool.add_mint(None, mint_info)
+    }
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    source_actor.asset_accounts = pool
+        .get_funded_token_accounts(&ctx, &source_actor.key.pubkey())
+        .await;
+    source_actor.pool_token_account =
+        Some(pool.get_pt_account(&ctx, &source_actor.key.pubkey()).await);
+
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[3].key, &pool.mints[2].key)
+            .await
+            .unwrap();
+
+    // Markets have to be owned by the pool's Serum program
+    let result = pool
+        .create(
+            &ctx,
+            source_actor.pool_token_account.as_ref().unwrap(),
+            &source_actor.key,
+            &source_actor.asset_accounts,
+            vec![3_238_385, 4_000_000, 1_000_001, 20_000_000],
+            &source_actor.asset_accounts[0],
+            604800,
+            100,
+            86400,
+            604800,
+            0,
+            0,
+            0,
+            0,
+            0,
+        )
+        .await;
+    assert!(result.is_err());
+
+    // Nor can they be disabled
+    serum_market.disable(&mut ctx).await;
+    ctx.refresh_blockhash().await;
+    let result = pool
+        .create(
+            &ctx,
+            source_actor.pool_token_account.as_ref().unwrap(),
+            &source_actor.key,
+            &source_actor.asset_accounts,
+            vec![3_238_385, 4_000_000, 1_000_001, 20_000_000],
+            &serum_market.market_key.pubkey(),
+            604800,
+            100,
+            86400,
+            604800,
+            0,
+            0,
+            0,
+            0,
+            0,
+        )
+        .await;
+    assert!(result.is_err());
+
+    // The pool can still be created on a live market
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[3].key, &pool.mints[2].key)
+            .await
+            .unwrap();
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![3_238_385, 4_000_000, 1_000_001, 20_000_000],
+        &serum_market.market_key.pubkey(),
+        604800,
+        100,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_market_change() {
+    let mut ctx = Context::init().await;
+    let mints = ctx.get_mints();
+
+    let mut pool = TestPool::new(&ctx);
+
+    for mint_info in &mints {
+        pool.add_mint(None, mint_info)
+    }
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    source_actor.asset_accounts = pool
+        .get_funded_token_accounts(&ctx, &source_actor.key.pubkey())
+        .await;
+    source_actor.pool_token_account =
+        Some(pool.get_pt_account(&ctx, &source_actor.key.pubkey()).await);
+
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[3].key, &pool.mints[2].key)
+            .await
+            .unwrap();
+
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![3_238_385, 4_000_000, 1_000_001, 20_000_000],
+        &serum_market.market_key.pubkey(),
+        604800,
+        100,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+
+    let new_serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[1].key, &pool.mints[0].key)
+            .await
+            .unwrap();
+    let new_market = new_serum_market.market_key.pubkey();
+
+    // Nothing to execute yet
+    assert!(pool
+        .execute_market_change(&ctx, &pool.signal_provider, &new_market, None)
+        .await
+        .is_err());
+
+    // Only the signal provider can propose a market change
+    assert!(pool
+        .propose_market_change(&ctx, &source_actor.key, 1, &new_market)
+        .await
+        .is_err());
+    // Markets can only be replaced or appended
+    assert!(pool
+        .propose_market_change(&ctx, &pool.signal_provider, 2, &new_market)
+        .await
+        .is_err());
+    assert!(pool
+        .propose_market_change(
+            &ctx,
+            &pool.signal_provider,
+            1,
+            &serum_market.market_key.pubkey()
+        )
+        .await
+        .is_err());
+
+    // The proposal can be withdrawn
+    pool.propose_market_change(&ctx, &pool.signal_provider, 1, &new_market)
+        .await
+        .unwrap();
+    pool.propose_market_change(&ctx, &pool.signal_provider, 0, &Pubkey::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        pool.get_header(&ctx).await.pending_market,
+        Pubkey::default()
+    );
+
+    ctx.refresh_blockhash().await;
+    pool.propose_market_change(&ctx, &pool.signal_provider, 1, &new_market)
+        .await
+        .unwrap();
+    let pool_header = pool.get_header(&ctx).await;
+    assert_eq!(pool_header.pending_market, new_market);
+    assert_eq!(pool_header.pending_market_index, 1);
+    assert_eq!(pool_header.number_of_markets, 1);
+    assert_eq!(pool_header.max_number_of_assets, 100);
+    assert_eq!(pool_header.version, POOL_VERSION);
+    assert_eq!(pool_header.asset_offset as usize, get_asset_offset(1));
+
+    // Pools created with the current layout have nothing to migrate
+    assert!(pool
+        .migrate_pool(&ctx, &pool.signal_provider, vec![])
+        .await
+        .is_err());
+
+    // The proposal is timelocked
+    assert!(pool
+        .execute_market_change(&ctx, &pool.signal_provider, &new_market, None)
+        .await
+        .is_err());
+
+    ctx.warp_forward(604800).await;
+    ctx.refresh_blockhash().await;
+
+    // The proposed market account has to be provided
+    assert!(pool
+        .execute_market_change(
+            &ctx,
+            &pool.signal_provider,
+            &serum_market.market_key.pubkey(),
+            None
+        )
+        .await
+        .is_err());
+    pool.execute_market_change(&ctx, &pool.signal_provider, &new_market, None)
+        .await
+        .unwrap();
+    let pool_header = pool.get_header(&ctx).await;
+    assert_eq!(pool_header.pending_market, Pubkey::default());
+    assert_eq!(pool_header.number_of_markets, 2);
+    // The new market takes its room from the asset slots
+    assert_eq!(pool_header.max_number_of_assets, 95);
+
+    // The pool can trade on the new market
+    pool.initialize_new_order(&ctx, 1).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_close_pool() {
+    let mut ctx = Context::init().await;
+
+    let mut pool = TestPool::new(&ctx);
+    pool.add_mint(None, &ctx.pc_mint);
+    pool.add_mint(None, &ctx.coin_mint);
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    source_actor.asset_accounts = pool
+        .get_funded_token_accounts(&ctx, &source_actor.key.pubkey())
+        .await;
+    source_actor.pool_token_account =
+        Some(pool.get_pt_account(&ctx, &source_actor.key.pubkey()).await);
+
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[1].key, &pool.mints[0].key)
+            .await
+            .unwrap();
+    let market_key = serum_market.market_key.pubkey();
+
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![10_000_000, 10_000_000],
+        &market_key,
+        604800,
+        0,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        pool.get_header(&ctx).await.creator,
+        ctx.test_state.payer.pubkey()
+    );
+    let signal_provider = pool.signal_provider.pubkey();
+    assert_eq!(
+        pool.get_registry(&ctx, &signal_provider).await.pool_seeds,
+        vec![pool.seeds]
+    );
+
+    // The pool still has pooltokens
+    assert!(pool.close_pool(&ctx, vec![market_key]).await.is_err());
+
+    pool.redeem(
+        &ctx,
+        1_000_000,
+        &source_actor.key,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.asset_accounts,
+    )
+    .await
+    .unwrap();
+
+    pool.close_pool(&ctx, vec![market_key]).await.unwrap();
+    assert!(pool
+        .get_registry(&ctx, &signal_provider)
+        .await
+        .pool_seeds
+        .is_empty());
+    let banks_client = &mut ctx.test_state.banks_client;
+    assert!(banks_client.get_account(pool.key).await.unwrap().is_none());
+    for mint in pool.mints.iter() {
+        assert!(banks_client
+            .get_account(mint.pool_asset_key)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    // The pool's derived accounts are closed along with it
+    for key in [
+        get_redeem_queue_key(&ctx.bonfidabot_program_id, &pool.seeds).0,
+        get_whitelist_key(&ctx.bonfidabot_program_id, &pool.seeds).0,
+        get_metadata_key(&ctx.bonfidabot_program_id, &pool.seeds).0,
+    ]
+    .iter()
+    {
+        assert!(banks_client.get_account(*key).await.unwrap().is_none());
+    }
+
+    // The seed can be used again, the pooltoken mint being reused
+    ctx.refresh_blockhash().await;
+    pool.setup(&ctx).await;
+
+    // A pool which was only initialized can't be closed
+    ctx.refresh_blockhash().await;
+    assert!(pool.close_pool(&ctx, vec![market_key]).await.is_err());
+
+    ctx.refresh_blockhash().await;
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![10_000_000, 10_000_000],
+        &market_key,
+        604800,
+        0,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+    assert_eq!(pool.get_pool_token_supply(&ctx).await, 1_000_000);
+    assert_eq!(
+        pool.get_registry(&ctx, &signal_provider).await.pool_seeds,
+        vec![pool.seeds]
+    );
+}
+
+#[tokio::test]
+async fn test_migrate_pool_v0() {
+    let mut ctx = Context::init().await;
+
+    let mut pool = TestPool::new(&ctx);
+    pool.add_mint(None, &ctx.pc_mint);
+    pool.add_mint(None, &ctx.coin_mint);
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    source_actor.asset_accounts = pool
+        .get_funded_token_accounts(&ctx, &source_actor.key.pubkey())
+        .await;
+    source_actor.pool_token_account =
+        Some(pool.get_pt_account(&ctx, &source_actor.key.pubkey()).await);
+
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[1].key, &pool.mints[0].key)
+            .await
+            .unwrap();
+    let market_key = serum_market.market_key.pubkey();
+
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![10_000_000, 10_000_000],
+        &market_key,
+        604800,
+        0,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+
+    // Write the pool back in the baseline layout: the 117 byte header, the addresses of the
+    // markets and the asset slots
+    let header = pool.get_header(&ctx).await;
+    let banks_client = &mut ctx.test_state.banks_client;
+    let data = banks_client
+        .get_account(pool.key)
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+    let market_data = &data[header.market_offset as usize..];
+    let pool_market = unpack_pool_market(market_data, 0).unwrap();
+    let assets_data = &data[header.asset_offset as usize..get_assets_end(&header)];
+    let mut v0_data = vec![0u8; POOL_HEADER_V0_LEN];
+    v0_data.copy_from_slice(&data[2..POOL_HEADER_V0_LEN + 2]);
+    v0_data.extend_from_slice(&pool_market.address.to_bytes());
+    v0_data.extend_from_slice(assets_data);
+    let rent = banks_client.get_rent().await.unwrap();
+    let v0_account = Account {
+        lamports: rent.minimum_balance(v0_data.len()),
+        data: v0_data,
+        owner: ctx.bonfidabot_program_id,
+        ..Account::default()
+    };
+    ctx.test_state
+        .set_account(&pool.key, &AccountSharedData::from(v0_account));
+
+    // The pool can't be used before it is migrated
+    ctx.refresh_blockhash().await;
+    assert!(pool
+        .deposit(
+            &ctx,
+            1_000_000,
+            source_actor.pool_token_account.as_ref().unwrap(),
+            &source_actor.key,
+            &source_actor.asset_accounts,
+        )
+        .await
+        .is_err());
+
+    // A created pool is migrated by its signal provider, which gives the pool's markets
+    wrap_process_transaction(
+        &ctx,
+        vec![system_instruction::transfer(
+            &ctx.test_state.payer.pubkey(),
+            &pool.signal_provider.pubkey(),
+            1_000_000_000,
+        )],
+        vec![],
+    )
+    .await
+    .unwrap();
+    assert!(pool
+        .migrate_pool(&ctx, &ctx.test_state.payer, vec![market_key])
+        .await
+        .is_err());
+    assert!(pool
+        .migrate_pool(&ctx, &pool.signal_provider, vec![])
+        .await
+        .is_err());
+    pool.migrate_pool(&ctx, &pool.signal_provider, vec![market_key])
+        .await
+        .unwrap();
+
+    let migrated_header = pool.get_header(&ctx).await;
+    assert_eq!(
+        migrated_header,
+        PoolHeader {
+            max_order_lifetime: MIGRATED_POOL_MAX_ORDER_LIFETIME,
+            timelock_period: MIGRATED_POOL_TIMELOCK_PERIOD,
+            creator: pool.signal_provider.pubkey(),
+            ..header
+        }
+    );
+    let banks_client = &mut ctx.test_state.banks_client;
+    let account = banks_client.get_account(pool.key).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), get_assets_end(&migrated_header));
+    assert!(rent.is_exempt(account.lamports, account.data.len()));
+    let market_data = &account.data[migrated_header.market_offset as usize..];
+    assert_eq!(unpack_pool_market(market_data, 0).unwrap(), pool_market);
+
+    // The migrated pool is usable, and up to date
+    ctx.refresh_blockhash().await;
+    pool.deposit(
+        &ctx,
+        1_000_000,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+    )
+    .await
+    .unwrap();
+    assert_eq!(pool.get_pool_token_supply(&ctx).await, 2_000_000);
+    assert!(pool
+        .migrate_pool(&ctx, &pool.signal_provider, vec![market_key])
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_pool_metadata() {
+    let mut ctx = Context::init().await;
+    let mints = ctx.get_mints();
+
+    let mut pool = TestPool::new(&ctx);
+
+    for mint_info in &mints {
+        pool.add_mint(None, mint_info)
+    }
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    source_actor.asset_accounts = pool
+        .get_funded_token_accounts(&ctx, &source_actor.key.pubkey())
+        .await;
+    source_actor.pool_token_account =
+        Some(pool.get_pt_account(&ctx, &source_actor.key.pubkey()).await);
+
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[3].key, &pool.mints[2].key)
+            .await
+            .unwrap();
+
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![3_238_385, 4_000_000, 1_000_001, 20_000_000],
+        &serum_market.market_key.pubkey(),
+        604800,
+        100,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+
+    // Only the signal provider can describe the pool
+    assert!(pool
+        .set_metadata(&ctx, &source_actor.key, "Momentum", "", "")
+        .await
+        .is_err());
+    assert!(pool
+        .set_metadata(&ctx, &pool.signal_provider, &"a".repeat(33), "", "")
+        .await
+        .is_err());
+
+    // Lamports sent to the metadata address beforehand don't prevent its creation
+    let (metadata_key, _) = get_metadata_key(&ctx.bonfidabot_program_id, &pool.seeds);
+    wrap_process_transaction(
+        &ctx,
+        vec![system_instruction::transfer(
+            &ctx.test_state.payer.pubkey(),
+            &metadata_key,
+            1_000,
+        )],
+        vec![],
+    )
+    .await
+    .unwrap();
+
+    pool.set_metadata(
+        &ctx,
+        &pool.signal_provider,
+        "Momentum",
+        "Follows the trend on the SRM/USDC market",
+        "https://bonfida.com/bots/momentum",
+    )
+    .await
+    .unwrap();
+    let metadata = pool.get_metadata(&ctx).await;
+    assert_eq!(metadata.name, "Momentum");
+    assert_eq!(metadata.description, "Follows the trend on the SRM/USDC market");
+    assert_eq!(metadata.uri, "https://bonfida.com/bots/momentum");
+    assert!(metadata.creation_timestamp > 0);
+
+    // Updates keep the creation timestamp
+    ctx.warp_forward(86400).await;
+    ctx.refresh_blockhash().await;
+    pool.set_metadata(&ctx, &pool.signal_provider, "Momentum v2", "", "")
+        .await
+        .unwrap();
+    let updated_metadata = pool.get_metadata(&ctx).await;
+    assert_eq!(updated_metadata.name, "Momentum v2");
+    assert_eq!(updated_metadata.description, "");
+    assert_eq!(updated_metadata.creation_timestamp, metadata.creation_timestamp);
+}
+
+#[tokio::test]
+async fn test_performance_fee() {
+    let mut ctx = Context::init().await;
+
+    let mut pool = TestPool::new(&ctx);
+    pool.add_mint(None, &ctx.pc_mint);
+    pool.add_mint(None, &ctx.coin_mint);
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    source_actor.asset_accounts = pool
+        .get_funded_token_accounts(&ctx, &source_actor.key.pubkey())
+        .await;
+    source_actor.pool_token_account =
+        Some(pool.get_pt_account(&ctx, &source_actor.key.pubkey()).await);
+    pool.get_pt_account(&ctx, &pool.signal_provider.pubkey())
+        .await;
+
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[1].key, &pool.mints[0].key)
+            .await
+            .unwrap();
+
+    // A quarter of the gains above the high-water mark are taken as fees
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![10_000_000, 10_000_000],
+        &serum_market.market_key.pubkey(),
+        604800,
+        100,
+        86400,
+        604800,
+        1 << 14,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+    assert_eq!(pool.get_header(&ctx).await.high_water_mark, 0);
+
+    // Mid price of 200 price lots per coin lot
+    serum_market
+        .place_resting_order(
+            &ctx,
+            Side::Bid,
+            NonZeroU64::new(196).unwrap(),
+            NonZeroU64::new(10).unwrap(),
+            &ctx.mint_authority,
+        )
+        .await;
+    serum_market
+        .place_resting_order(
+            &ctx,
+            Side::Ask,
+            NonZeroU64::new(204).unwrap(),
+            NonZeroU64::new(10).unwrap(),
+            &ctx.mint_authority,
+        )
+        .await;
+
+    // The first collection only sets the high-water mark
+    ctx.warp_forward(604801).await;
+    ctx.refresh_blockhash().await;
+    let supply = pool.get_pool_token_supply(&ctx).await;
+    pool.collect_fees(&ctx, &[&serum_market]).await.unwrap();
+    let management_fee = pool.get_pool_token_supply(&ctx).await - supply;
+    let first_high_water_mark = pool.get_header(&ctx).await.high_water_mark;
+    assert_ne!(first_high_water_mark, 0);
+
+    // A bid at 290 takes the whole ask side and a single lot is offered far above it
+    serum_market
+        .place_resting_order(
+            &ctx,
+            Side::Bid,
+            NonZeroU64::new(290).unwrap(),
+            NonZeroU64::new(20).unwrap(),
+            &ctx.mint_authority,
+        )
+        .await;
+    serum_market
+        .place_resting_order(
+            &ctx,
+            Side::Ask,
+            NonZeroU64::new(600).unwrap(),
+            NonZeroU64::new(1).unwrap(),
+            &ctx.mint_authority,
+        )
+        .await;
+
+    ctx.warp_forward(604801).await;
+    ctx.refresh_blockhash().await;
+
+    // The pool can't be valued without its markets
+    assert!(pool.collect_fees(&ctx, &[]).await.is_err());
+
+    // Nor from a book this thin, so the high-water mark can't be pushed up
+    assert!(pool.collect_fees(&ctx, &[&serum_market]).await.is_err());
+    assert_eq!(
+        pool.get_header(&ctx).await.high_water_mark,
+        first_high_water_mark
+    );
+
+    // Mid price goes up to 295
+    serum_market
+        .place_resting_order(
+            &ctx,
+            Side::Ask,
+            NonZeroU64::new(300).unwrap(),
+            NonZeroU64::new(10).unwrap(),
+            &ctx.mint_authority,
+        )
+        .await;
+    ctx.refresh_blockhash().await;
+
+    let supply = pool.get_pool_token_supply(&ctx).await;
+    pool.collect_fees(&ctx, &[&serum_market]).await.unwrap();
+    let fees = pool.get_pool_token_supply(&ctx).await - supply;
+    let second_high_water_mark = pool.get_header(&ctx).await.high_water_mark;
+    assert!(second_high_water_mark > first_high_water_mark);
+    assert!(fees > 10 * management_fee);
+
+    // The NAV is now below the high-water mark because of the management fee
+    ctx.warp_forward(604801).await;
+    ctx.refresh_blockhash().await;
+    let supply = pool.get_pool_token_supply(&ctx).await;
+    pool.collect_fees(&ctx, &[&serum_market]).await.unwrap();
+    let fees = pool.get_pool_token_supply(&ctx).await - supply;
+    assert_eq!(
+        pool.get_header(&ctx).await.high_water_mark,
+        second_high_water_mark
+    );
+    assert!(fees < 2 * management_fee);
+}
+
+#[tokio::test]
+async fn test_slippage_bounds() {
+    let mut ctx = Context::init().await;
+    let mints = ctx.get_mints();
+
+    let mut pool = TestPool::new(&ctx);
+
+    for mint_info in &mints {
+        pool.add_mint(None, mint_info)
+    }
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    source_actor.asset_accounts = pool
+        .get_funded_token_accounts(&ctx, &source_actor.key.pubkey())
+        .await;
+    source_actor.pool_token_account =
+        Some(pool.get_pt_account(&ctx, &source_actor.key.pubkey()).await);
+    pool.get_pt_account(&ctx, &pool.signal_provider.pubkey())
+        .await;
+
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[3].key, &pool.mints[2].key)
+            .await
+            .unwrap();
+
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![3_238_385, 4_000_000, 1_000_001, 20_000_000],
+        &serum_market.market_key.pubkey(),
+        604800,
+        100,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+
+    // Buying 1% of the pool takes 1% of each asset rounded up, 15 pooltokens are taken as fees
+    let result = pool
+        .deposit_with_bounds(
+            &ctx,
+            10_000,
+            &source_actor.pool_token_account.as_ref().unwrap(),
+            &source_actor.key,
+            &source_actor.asset_accounts,
+            0,
+            vec![32_383, 40_000, 10_001, 200_000],
+        )
+        .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(5))
+    );
+    let result = pool
+        .deposit_with_bounds(
+            &ctx,
+            10_000,
+            &source_actor.pool_token_account.as_ref().unwrap(),
+            &source_actor.key,
+            &source_actor.asset_accounts,
+            9_986,
+            vec![32_384, 40_000, 10_001, 200_000],
+        )
+        .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(6))
+    );
+    pool.deposit_with_bounds(
+        &ctx,
+        10_000,
+        &source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        9_985,
+        vec![32_384, 40_000, 10_001, 200_000],
+    )
+    .await
+    .unwrap();
+
+    let result = pool
+        .redeem_with_bounds(
+            &ctx,
+            10_000,
+            &source_actor.key,
+            source_actor.pool_token_account.as_ref().unwrap(),
+            &source_actor.asset_accounts,
+            vec![32_383, 40_000, 10_001, 200_000],
+        )
+        .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(7))
+    );
+    pool.redeem_with_bounds(
+        &ctx,
+        10_000,
+        &source_actor.key,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.asset_accounts,
+        vec![32_383, 40_000, 10_000, 200_000],
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_deposit_single_asset() {
+    let mut ctx = Context::init().await;
+    let mints = ctx.get_mints();
+
+    let mut pool = TestPool::new(&ctx);
+
+    // The pool holds the quote and the base token of a single market
+    pool.add_mint(None, &mints[2]);
+    pool.add_mint(None, &mints[3]);
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    source_actor.asset_accounts = pool
+        .get_funded_token_accounts(&ctx, &source_actor.key.pubkey())
+        .await;
+    source_actor.pool_token_account =
+        Some(pool.get_pt_account(&ctx, &source_actor.key.pubkey()).await);
+    pool.get_pt_account(&ctx, &pool.signal_provider.pubkey())
+        .await;
+
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[1].key, &pool.mints[0].key)
+            .await
+            .unwrap();
+
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![1_000_000, 2_000_000],
+        &serum_market.market_key.pubkey(),
+        604800,
+        100,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+
+    let (open_order, create_open_order_instruction) =
+        SerumMarket::create_dex_account(&ctx, 3216).unwrap();
+    wrap_process_transaction(
+        &ctx,
+        vec![create_open_order_instruction],
+        vec![&open_order],
+    )
+    .await
+    .unwrap();
+
+    // Buying 1% of the pool with the quote token only requires 20_000 base tokens on an empty book
+    let result = pool
+        .deposit_single_asset(
+            &ctx,
+            10_000,
+            source_actor.pool_token_account.as_ref().unwrap(),
+            &source_actor.key,
+            &source_actor.asset_accounts[0],
+            &[(&serum_market, &open_order.pubkey(), &source_actor.asset_accounts[1])],
+            9_985,
+            vec![0, 400],
+        )
+        .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(8))
+    );
+
+    serum_market
+        .place_resting_order(
+            &ctx,
+            Side::Ask,
+            NonZeroU64::new(300).unwrap(),
+            NonZeroU64::new(100).unwrap(),
+            &ctx.mint_authority,
+        )
+        .await;
+    ctx.refresh_blockhash().await;
+
+    let pt_balance_before = get_token_balance(&ctx, source_actor.pool_token_account.unwrap()).await;
+    let base_balance_before = get_token_balance(&ctx, pool.mints[1].pool_asset_key).await;
+    pool.deposit_single_asset(
+        &ctx,
+        10_000,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts[0],
+        &[(&serum_market, &open_order.pubkey(), &source_actor.asset_accounts[1])],
+        9_985,
+        vec![0, 400],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        get_token_balance(&ctx, source_actor.pool_token_account.unwrap()).await,
+        pt_balance_before + 9_985
+    );
+    assert_eq!(
+        get_token_balance(&ctx, pool.mints[1].pool_asset_key).await,
+        base_balance_before + 20_000
+    );
+}
+
+#[tokio::test]
+async fn test_redeem_to_single_asset() {
+    let mut ctx = Context::init().await;
+    let mints = ctx.get_mints();
+
+    let mut pool = TestPool::new(&ctx);
+
+    // The pool holds the quote and the base token of a single market
+    pool.add_mint(None, &mints[2]);
+    pool.add_mint(None, &mints[3]);
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    source_actor.asset_accounts = pool
+        .get_funded_token_accounts(&ctx, &source_actor.key.pubkey())
+        .await;
+    source_actor.pool_token_account =
+        Some(pool.get_pt_account(&ctx, &source_actor.key.pubkey()).await);
+
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[1].key, &pool.mints[0].key)
+            .await
+            .unwrap();
+
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![1_000_000, 2_000_000],
+        &serum_market.market_key.pubkey(),
+        604800,
+        100,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+
+    let (open_order, create_open_order_instruction) =
+        SerumMarket::create_dex_account(&ctx, 3216).unwrap();
+    wrap_process_transaction(
+        &ctx,
+        vec![create_open_order_instruction],
+        vec![&open_order],
+    )
+    .await
+    .unwrap();
+
+    // Redeeming 1% of the pool pays out 10_000 quote tokens and sells 20_000 base tokens
+    let result = pool
+        .redeem_to_single_asset(
+            &ctx,
+            10_000,
+            &source_actor.key,
+            source_actor.pool_token_account.as_ref().unwrap(),
+            &source_actor.asset_accounts[0],
+            &[(&serum_market, &open_order.pubkey(), &source_actor.asset_accounts[1])],
+            15_900,
+            vec![0, 250],
+        )
+        .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(8))
+    );
+
+    serum_market
+        .place_resting_order(
+            &ctx,
+            Side::Bid,
+            NonZeroU64::new(300).unwrap(),
+            NonZeroU64::new(100).unwrap(),
+            &ctx.mint_authority,
+        )
+        .await;
+
+    let result = pool
+        .redeem_to_single_asset(
+            &ctx,
+            10_000,
+            &source_actor.key,
+            source_actor.pool_token_account.as_ref().unwrap(),
+            &source_actor.asset_accounts[0],
+            &[(&serum_market, &open_order.pubkey(), &source_actor.asset_accounts[1])],
+            16_001,
+            vec![0, 250],
+        )
+        .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(7))
+    );
+    ctx.refresh_blockhash().await;
+
+    let pt_balance_before = get_token_balance(&ctx, source_actor.pool_token_account.unwrap()).await;
+    let quote_balance_before = get_token_balance(&ctx, source_actor.asset_accounts[0]).await;
+    pool.redeem_to_single_asset(
+        &ctx,
+        10_000,
+        &source_actor.key,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.asset_accounts[0],
+        &[(&serum_market, &open_order.pubkey(), &source_actor.asset_accounts[1])],
+        15_900,
+        vec![0, 250],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        get_token_balance(&ctx, source_actor.pool_token_account.unwrap()).await,
+        pt_balance_before - 10_000
+    );
+    assert!(get_token_balance(&ctx, source_actor.asset_accounts[0]).await >= quote_balance_before + 15_900);
+    assert_eq!(get_token_balance(&ctx, pool.mints[1].pool_asset_key).await, 1_980_000);
+}
+
+#[tokio::test]
+async fn test_resize_pool() {
+    let mut ctx = Context::init().await;
+
+    let mut pool = TestPool::new(&ctx);
+    pool.add_mint(None, &ctx.pc_mint);
+    pool.add_mint(None, &ctx.coin_mint);
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    source_actor.asset_accounts = pool
+        .get_funded_token_accounts(&ctx, &source_actor.key.pubkey())
+        .await;
+    source_actor.pool_token_account =
+        Some(pool.get_pt_account(&ctx, &source_actor.key.pubkey()).await);
+
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[1].key, &pool.mints[0].key)
+            .await
+            .unwrap();
+
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![10_000_000, 10_000_000],
+        &serum_market.market_key.pubkey(),
+        604800,
+        0,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+    assert_eq!(pool.get_header(&ctx).await.max_number_of_assets, 100);
+
+    // The pool account can only grow
+    assert!(pool.resize_pool(&ctx, 100).await.is_err());
+
+    pool.resize_pool(&ctx, 300).await.unwrap();
+    let header = pool.get_header(&ctx).await;
+    assert_eq!(header.max_number_of_assets, 300);
+    let banks_client = &mut ctx.test_state.banks_client;
+    let pool_account = banks_client.get_account(pool.key).await.unwrap().unwrap();
+    assert_eq!(pool_account.data.len(), get_assets_end(&header));
+    let rent = banks_client.get_rent().await.unwrap();
+    assert!(rent.is_exempt(pool_account.lamports, pool_account.data.len()));
+
+    // A single instruction can't grow the account by more than MAX_PERMITTED_DATA_INCREASE bytes
+    ctx.refresh_blockhash().await;
+    let max_increase = (MAX_PERMITTED_DATA_INCREASE / PoolAsset::LEN) as u32;
+    assert!(pool
+        .resize_pool(&ctx, 300 + max_increase + 1)
+        .await
+        .is_err());
+
+    // The existing assets are kept
+    pool.deposit(
+        &ctx,
+        1_000_000,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+    )
+    .await
+    .unwrap();
+    assert_eq!(pool.get_pool_token_supply(&ctx).await, 2_000_000);
+}
+
+#[tokio::test]
+async fn test_pool_events() {
+    let mut ctx = Context::init().await;
+    let mints = ctx.get_mints();
+
+    let mut pool = TestPool::new(&ctx);
+
+    for mint_info in &mints {
+        pool.add_mint(None, mint_info)
+    }
+
+    let mut source_actor = Actor {
+        key: Keypair::new(),
+        asset_accounts: vec![],
+        pool_token_balance: 0,
+        pool_token_account: None,
+        signal_provider: false,
+    };
+
+    ctx.refresh_blockhash().await;
+
+    pool.setup(&ctx).await;
+
+    source_actor.asset_accounts = pool
+        .get_funded_token_accounts(&ctx, &source_actor.key.pubkey())
+        .await;
+    source_actor.pool_token_account =
+        Some(pool.get_pt_account(&ctx, &source_actor.key.pubkey()).await);
+
+    let serum_market =
+        SerumMarket::initialize_market_accounts(&ctx, &pool.mints[3].key, &pool.mints[2].key)
+            .await
+            .unwrap();
+
+    pool.create(
+        &ctx,
+        source_actor.pool_token_account.as_ref().unwrap(),
+        &source_actor.key,
+        &source_actor.asset_accounts,
+        vec![3_238_385, 4_000_000, 1_000_001, 20_000_000],
+        &serum_market.market_key.pubkey(),
+        604800,
+        100,
+        86400,
+        604800,
+        0,
+        0,
+        0,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+
+    let pooltoken_account = source_actor.pool_token_account.unwrap();
+
+    // A deposit logs the assets it moved into the pool and the minted pooltokens
+    let balances_before = get_pool_asset_balances(&ctx, &pool).await;
+    let supply_before = pool.get_pool_token_supply(&ctx).await;
+    pool.deposit(
+        &ctx,
+        100_000,
+        &pooltoken_account,
+        &source_actor.key,
+        &source_actor.asset_accounts,
+    )
+    .await
+    .unwrap();
+    let balances_after = get_pool_asset_balances(&ctx, &pool).await;
+    let expected_event = PoolEvent {
+        kind: EventKind::Deposit,
+        pool: pool.key,
+        actor: source_actor.key.pubkey(),
+        amounts: balances_after
+            .iter()
+            .zip(balances_before.iter())
+            .map(|(after, before)| after - before)
+            .collect(),
+        pool_token_supply: Some((supply_before, pool.get_pool_token_supply(&ctx).await)),
+        status: pool.get_header(&ctx).await.status,
+    };
+    assert_eq!(*ctx.pool_events.borrow(), vec![expected_event]);
+
+    // A redeem logs the assets paid out of the pool and the burnt pooltokens
+    let balances_before = balances_after;
+    let supply_before = pool.get_pool_token_supply(&ctx).await;
+    pool.redeem(
+        &ctx,
+        50_000,
+        &source_actor.key,
+        &pooltoken_account,
+        &source_actor.asset_accounts,
+    )
+    .await
+    .unwrap();
+    let balances_after = get_pool_asset_balances(&ctx, &pool).await;
+    let expected_event = PoolEvent {
+        kind: EventKind::Redeem,
+        pool: pool.key,
+        actor: source_actor.key.pubkey(),
+        amounts: balances_before
+            .iter()
+            .zip(balances_after.iter())
+            .map(|(before, after)| before - after)
+            .collect(),
+        pool_token_supply: Some((supply_before, pool.get_pool_token_supply(&ctx).await)),
+        status: pool.get_header(&ctx).await.status,
+    };
+    assert_eq!(*ctx.pool_events.borrow(), vec![expected_event]);
+
+    // A queued redeem is logged on behalf of the request's owner when the queue is processed
+    pool.lock(&ctx, &pool.signal_provider).await.unwrap();
+    pool.request_redeem(&ctx, 20_000, &source_actor.key, &pooltoken_account)
+        .await
+        .unwrap();
+    let expected_event = PoolEvent {
+        kind: EventKind::RequestRedeem,
+        pool: pool.key,
+        actor: source_actor.key.pubkey(),
+        amounts: vec![20_000],
+        pool_token_supply: None,
+        status: pool.get_header(&ctx).await.status,
+    };
+    assert_eq!(*ctx.pool_events.borrow(), vec![expected_event]);
+
+    pool.unlock(&ctx).await.unwrap();
+    ctx.refresh_blockhash().await;
+    let balances_before = get_pool_asset_balances(&ctx, &pool).await;
+    let supply_before = pool.get_pool_token_supply(&ctx).await;
+    pool.process_redeem_queue(&ctx, &[source_actor.key.pubkey()])
+        .await
+        .unwrap();
+    let balances_after = get_pool_asset_balances(&ctx, &pool).await;
+    let supply_after = pool.get_pool_token_supply(&ctx).await;
+    assert_eq!(supply_before - supply_after, 20_000);
+    let expected_event = PoolEvent {
+        kind: EventKind::ProcessRedeemQueue,
+        pool: pool.key,
+        actor: source_actor.key.pubkey(),
+        amounts: balances_before
+            .iter()
+            .zip(balances_after.iter())
+            .map(|(before, after)| before - after)
+            .collect(),
+        pool_token_supply: Some((supply_before, supply_after)),
+        status: pool.get_header(&ctx).await.status,
+    };
+    assert_eq!(*ctx.pool_events.borrow(), vec![expected_event]);
+
+    // An order logs the amount of the source asset it trades
+    let order = pool.initialize_new_order(&ctx, 0).await.unwrap();
+    let source_balance = get_token_balance(&ctx, pool.mints[2].pool_asset_key).await;
+    let max_ratio_of_pool_to_sell = 1 << 14;
+    pool.create_new_order(
+        &mut ctx,
+        &serum_market,
+        2,
+        3,
+        &order,
+        Side::Bid,
+        NonZeroU64::new(1).unwrap(),
+        NonZeroU16::new(max_ratio_of_pool_to_sell).unwrap(),
+        OrderType::ImmediateOrCancel,
+    )
+    .await
+    .unwrap();
+    let expected_event = PoolEvent {
+        kind: EventKind::CreateOrder,
+        pool: pool.key,
+        actor: pool.signal_provider.pubkey(),
+        amounts: vec![((source_balance as u128 * max_ratio_of_pool_to_sell as u128) >> 16) as u64],
+        pool_token_supply: None,
+        status: pool.get_header(&ctx).await.status,
+    };
+    assert_eq!(*ctx.pool_events.borrow(), vec![expected_event]);
+}
+
+async fn get_token_balance(ctx: &Context, key: Pubkey) -> u64 {
+    spl_token::state::Account::unpack(
+        &ctx.test_state
+            .banks_client
+            .to_owned()
+            .get_account(key)
+            .await
+            .unwrap()
+            .unwrap()
+            .data,
+    )
+    .unwrap()
+    .amount
+}
+
+async fn get_pool_asset_balances(ctx: &Context, pool: &TestPool) -> Vec<u64> {
+    let mut balances = vec![];
+    for mint in pool.mints.iter() {
+        balances.push(get_token_balance(ctx, mint.pool_asset_key).await);
+    }
+    balances
+}